@@ -155,7 +155,7 @@ impl bc::group::BloomGroupDatabase for BlockChain {
 pub struct BlockChain {
 	// All locks must be captured in the order declared here.
 	blooms_config: bc::Config,
-	first_block: H256,
+	first_block: RwLock<H256>,
 
 	best_block: RwLock<BestBlock>,
 
@@ -187,7 +187,7 @@ impl BlockProvider for BlockChain {
 	}
 
 	fn first_block(&self) -> H256 {
-		self.first_block
+		*self.first_block.read()
 	}
 
 	/// Get raw block data
@@ -346,7 +346,7 @@ impl BlockChain {
 				levels: LOG_BLOOMS_LEVELS,
 				elements_per_index: LOG_BLOOMS_ELEMENTS_PER_INDEX,
 			},
-			first_block: H256::zero(),
+			first_block: RwLock::new(H256::zero()),
 			best_block: RwLock::new(BestBlock::default()),
 			block_headers: RwLock::new(HashMap::new()),
 			block_bodies: RwLock::new(HashMap::new()),
@@ -424,9 +424,9 @@ impl BlockChain {
 				batch.put(db::COL_EXTRA, b"first", &hash);
 				db.write(batch).expect("Low level database error.");
 
-				bc.first_block = hash;
+				*bc.first_block.write() = hash;
 			} else {
-				bc.first_block = H256::from_slice(&raw_first);
+				*bc.first_block.write() = H256::from_slice(&raw_first);
 			}
 
 			// and write them
@@ -593,6 +593,7 @@ impl BlockChain {
 	/// This is used by snapshot restoration.
 	///
 	/// Supply a dummy parent total difficulty when the parent block may not be in the chain.
+	/// The block is buffered in the database, which must be flushed to persist it.
 	/// Returns true if the block is disconnected.
 	pub fn insert_snapshot_block(&self, bytes: &[u8], receipts: Vec<Receipt>, parent_td: Option<U256>, is_best: bool) -> bool {
 		let block = BlockView::new(bytes);
@@ -635,7 +636,7 @@ impl BlockChain {
 				info: info,
 				block: bytes
 			}, is_best);
-			self.db.write(batch).unwrap();
+			self.db.write_buffered(batch);
 
 			false
 		} else {
@@ -669,12 +670,105 @@ impl BlockChain {
 				info: info,
 				block: bytes,
 			}, is_best);
-			self.db.write(batch).unwrap();
+			self.db.write_buffered(batch);
 
 			true
 		}
 	}
 
+	/// Set the first block of the chain: the earliest block from which on
+	/// the chain is complete up to the best block.
+	///
+	/// Used after snapshot restoration, where older blocks are missing.
+	pub fn set_first_block(&self, hash: H256) {
+		let mut batch = self.db.transaction();
+		batch.put(db::COL_EXTRA, b"first", &hash);
+		self.db.write(batch).expect("Low level database error. Some issue with disk?");
+
+		*self.first_block.write() = hash;
+	}
+
+	/// Inserts an ancient block, which must be the parent of the current first block.
+	/// The block is assumed to be verified and is inserted without being executed;
+	/// it becomes the new first block.
+	///
+	/// Used to fill in the blocks missing before a restored snapshot.
+	/// Returns false if the block isn't the parent of the first block
+	/// or there is an uncommitted update pending.
+	pub fn insert_ancient_block(&self, bytes: &[u8], receipts: Vec<Receipt>) -> bool {
+		let block = BlockView::new(bytes);
+		let header = block.header_view();
+		let hash = header.sha3();
+
+		let first = self.first_block();
+		let (first_details, first_difficulty) = match (self.block_details(&first), self.block_header(&first)) {
+			(Some(details), Some(header)) => (details, *header.difficulty()),
+			_ => return false,
+		};
+
+		if first_details.parent != hash || self.is_known(&hash) {
+			return false;
+		}
+
+		// an uncommitted update would be overwritten by ours.
+		if self.pending_best_block.read().is_some() {
+			return false;
+		}
+
+		let mut batch = self.db.transaction();
+
+		let block_rlp = UntrustedRlp::new(bytes);
+		let compressed_header = block_rlp.at(0)
+			.expect("ancient blocks are verified before insertion; a verified block has a header; qed")
+			.compress(RlpType::Blocks);
+		let compressed_body = UntrustedRlp::new(&Self::block_to_body(bytes)).compress(RlpType::Blocks);
+
+		batch.put(db::COL_HEADERS, &hash, &compressed_header);
+		batch.put(db::COL_BODIES, &hash, &compressed_body);
+
+		let info = BlockInfo {
+			hash: hash,
+			number: header.number(),
+			total_difficulty: first_details.total_difficulty - first_difficulty,
+			location: BlockLocation::CanonChain,
+		};
+
+		let mut block_details = HashMap::new();
+		block_details.insert(hash, BlockDetails {
+			number: header.number(),
+			total_difficulty: info.total_difficulty,
+			parent: header.parent_hash(),
+			children: vec![first],
+		});
+
+		// the chain is complete once we reach a block whose parent we already have.
+		let new_first = match self.block_details(&header.parent_hash()) {
+			Some(mut parent_details) => {
+				parent_details.children.push(hash);
+				block_details.insert(header.parent_hash(), parent_details);
+				self.genesis_hash()
+			}
+			None => hash,
+		};
+
+		self.prepare_update(&mut batch, ExtrasUpdate {
+			block_hashes: self.prepare_block_hashes_update(bytes, &info),
+			block_details: block_details,
+			block_receipts: self.prepare_block_receipts_update(receipts, &info),
+			transactions_addresses: self.prepare_transaction_addresses_update(bytes, &info),
+			blocks_blooms: self.prepare_block_blooms_update(bytes, &info),
+			info: info,
+			block: bytes,
+		}, false);
+
+		batch.put(db::COL_EXTRA, b"first", &new_first);
+		self.db.write(batch).expect("Low level database error. Some issue with disk?");
+
+		*self.first_block.write() = new_first;
+
+		true
+	}
+
 	/// Add a child to a given block. Assumes that the block hash is in
 	/// the chain and the child's parent is this block.
 	///
@@ -861,6 +955,98 @@ impl BlockChain {
 		}
 	}
 
+	/// Reverts the canon chain to the given block, which must be in the canon chain.
+	/// Reverted blocks and their side branches are removed from the database, so they can be imported again.
+	/// Returns hashes of the reverted blocks or `None` if the block is not in the canon chain.
+	/// The best block is updated once `commit` is called.
+	pub fn revert_to(&self, batch: &mut DBTransaction, hash: &H256) -> Option<Vec<H256>> {
+		assert!(self.pending_best_block.read().is_none());
+
+		let mut details = match self.block_details(hash) {
+			Some(details) => details,
+			None => return None,
+		};
+		if self.block_hash(details.number).as_ref() != Some(hash) {
+			return None;
+		}
+
+		let best_number = self.best_block_number();
+		let retracted = (details.number + 1..best_number + 1)
+			.map(|n| self.block_hash(n).expect("blocks up to the best one are in the canon chain; qed"))
+			.collect::<Vec<_>>();
+
+		if !retracted.is_empty() {
+			let range = (details.number + 1) as bc::Number..best_number as bc::Number;
+			let chain = bc::group::BloomGroupChain::new(self.blooms_config, self);
+			let blooms = chain.replace(&range, vec![]).into_iter()
+				.map(|p| (From::from(p.0), From::from(p.1)))
+				.collect::<HashMap<LogGroupPosition, BloomGroup>>();
+
+			let mut write_blocks_blooms = self.blocks_blooms.write();
+			batch.extend_with_cache(db::COL_EXTRA, &mut *write_blocks_blooms, blooms, CacheUpdatePolicy::Remove);
+		}
+
+		{
+			let mut write_hashes = self.block_hashes.write();
+			for n in details.number + 1..best_number + 1 {
+				batch.delete_with_cache(db::COL_EXTRA, &mut *write_hashes, &n);
+			}
+		}
+
+		{
+			let mut write_txs = self.transaction_addresses.write();
+			for block_hash in &retracted {
+				let body = self.block_body(block_hash).expect("Retracted block must be in database.");
+				for tx_hash in BodyView::new(&body).transaction_hashes() {
+					batch.delete_with_cache(db::COL_EXTRA, &mut *write_txs, &tx_hash);
+				}
+			}
+		}
+
+		// forget all descendants, including side branches of reverted blocks.
+		let mut removed = Vec::new();
+		let mut pending = details.children.clone();
+		while let Some(block_hash) = pending.pop() {
+			if let Some(block_details) = self.block_details(&block_hash) {
+				pending.extend(block_details.children);
+			}
+			removed.push(block_hash);
+		}
+
+		{
+			let mut write_details = self.block_details.write();
+			let mut write_receipts = self.block_receipts.write();
+			let mut write_headers = self.block_headers.write();
+			let mut write_bodies = self.block_bodies.write();
+			for block_hash in &removed {
+				batch.delete_with_cache(db::COL_EXTRA, &mut *write_details, block_hash);
+				batch.delete_with_cache(db::COL_EXTRA, &mut *write_receipts, block_hash);
+				batch.delete(db::COL_HEADERS, block_hash);
+				batch.delete(db::COL_BODIES, block_hash);
+				write_headers.remove(block_hash);
+				write_bodies.remove(block_hash);
+			}
+		}
+
+		details.children.clear();
+		{
+			let mut write_details = self.block_details.write();
+			batch.write_with_cache(db::COL_EXTRA, &mut *write_details, hash.clone(), details.clone(), CacheUpdatePolicy::Overwrite);
+			self.cache_man.lock().note_used(CacheID::BlockDetails(hash.clone()));
+		}
+
+		let block = self.block(hash).expect("Block details are in database, so is the block; qed");
+		batch.put(db::COL_EXTRA, b"best", hash);
+		*self.pending_best_block.write() = Some(BestBlock {
+			hash: hash.clone(),
+			number: details.number,
+			total_difficulty: details.total_difficulty,
+			block: block,
+		});
+
+		Some(retracted)
+	}
+
 	/// Iterator that lists `first` and then all of `first`'s ancestors, by hash.
 	pub fn ancestry_iter(&self, first: H256) -> Option<AncestryIter> {
 		if self.is_known(&first) {
@@ -1751,4 +1937,61 @@ mod tests {
 		assert_eq!(bc.rewind(), Some(genesis_hash.clone()));
 		assert_eq!(bc.rewind(), None);
 	}
+
+	#[test]
+	fn should_revert_to_canon_block() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+		let first = canon_chain.generate(&mut finalizer).unwrap();
+
+		let t1 = Transaction {
+			nonce: 0.into(),
+			gas_price: 0.into(),
+			gas: 100_000.into(),
+			action: Action::Create,
+			value: 100.into(),
+			data: "601080600c6000396000f3006000355415600957005b60203560003555".from_hex().unwrap(),
+		}.sign(&"".sha3());
+
+		let second = canon_chain.with_transaction(t1.clone()).generate(&mut finalizer).unwrap();
+
+		let first_hash = BlockView::new(&first).header_view().sha3();
+		let second_hash = BlockView::new(&second).header_view().sha3();
+
+		let temp = RandomTempPath::new();
+		let db = new_db(temp.as_str());
+		let bc = BlockChain::new(Config::default(), &genesis, db.clone());
+
+		let mut batch = db.transaction();
+		bc.insert_block(&mut batch, &first, vec![]);
+		bc.commit();
+		bc.insert_block(&mut batch, &second, vec![]);
+		bc.commit();
+		db.write(batch).unwrap();
+		assert!(bc.transaction_address(&t1.hash()).is_some());
+
+		let mut batch = db.transaction();
+		assert_eq!(bc.revert_to(&mut batch, &first_hash), Some(vec![second_hash.clone()]));
+		bc.commit();
+		db.write(batch).unwrap();
+
+		assert_eq!(bc.best_block_hash(), first_hash);
+		assert_eq!(bc.best_block_number(), 1);
+		assert_eq!(bc.block_hash(2), None);
+		assert_eq!(bc.transaction_address(&t1.hash()), None);
+		assert!(bc.is_known(&second_hash));
+
+		// reverted block can be imported again
+		let mut batch = db.transaction();
+		bc.insert_block(&mut batch, &second, vec![]);
+		bc.commit();
+		db.write(batch).unwrap();
+
+		assert_eq!(bc.best_block_hash(), second_hash);
+		assert_eq!(bc.block_hash(2), Some(second_hash));
+
+		let mut batch = db.transaction();
+		assert_eq!(bc.revert_to(&mut batch, &H256::default()), None);
+	}
 }
@@ -0,0 +1,32 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Chain checkpoints used to revert test chains.
+
+use util::H256;
+use header::BlockNumber;
+
+/// Best block of the chain, recorded so that the chain can be reverted to it later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainCheckpoint {
+	/// Block number.
+	pub number: BlockNumber,
+	/// Block hash.
+	pub hash: H256,
+	/// State root of the block.
+	pub state_root: H256,
+}
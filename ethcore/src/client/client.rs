@@ -23,7 +23,7 @@ use time::precise_time_ns;
 
 // util
 use util::{Bytes, PerfTimer, Itertools, Mutex, RwLock, TrieFactory};
-use util::{U256, H256, Address, H2048, Uint, FixedHash};
+use util::{U256, H256, Address, H2048, Uint, FixedHash, Mismatch, ordered_trie_root};
 use util::journaldb::{self, JournalDB};
 use util::trie::{TrieSpec, Trie};
 use util::sha3::*;
@@ -32,18 +32,18 @@ use util::kvdb::*;
 // other
 use io::*;
 use views::{BlockView, HeaderView, BodyView};
-use error::{ImportError, ExecutionError, CallError, BlockError, ImportResult};
-use header::BlockNumber;
+use error::{Error, ImportError, ExecutionError, CallError, BlockError, ImportResult};
+use header::{BlockNumber, Header};
 use state::State;
 use spec::Spec;
 use basic_types::Seal;
-use engines::Engine;
+use engines::{Engine, SealMode};
 use service::ClientIoMessage;
 use env_info::LastHashes;
 use verification;
 use verification::{PreverifiedBlock, Verifier};
 use block::*;
-use transaction::{LocalizedTransaction, SignedTransaction, Action};
+use transaction::{LocalizedTransaction, SignedTransaction, PendingTransaction, Action};
 use blockchain::extras::TransactionAddress;
 use types::filter::Filter;
 use log_entry::LocalizedLogEntry;
@@ -52,12 +52,12 @@ use blockchain::{BlockChain, BlockProvider, TreeRoute, ImportRoute};
 use client::{
 	BlockID, TransactionID, UncleID, TraceId, ClientConfig, BlockChainClient,
 	MiningBlockChainClient, TraceFilter, CallAnalytics, BlockImportError, Mode,
-	ChainNotify, Switch
+	ChainNotify, Switch, ChainCheckpoint
 };
 use client::Error as ClientError;
 use env_info::EnvInfo;
 use executive::{Executive, Executed, TransactOptions, contract_address};
-use receipt::LocalizedReceipt;
+use receipt::{Receipt, LocalizedReceipt};
 use trace::{TraceDB, ImportRequest as TraceImportRequest, LocalizedTrace, Database as TraceDatabase};
 use trace;
 use trace::FlatTransactionTraces;
@@ -65,7 +65,7 @@ use evm::Factory as EvmFactory;
 use miner::{Miner, MinerService};
 use snapshot::{self, io as snapshot_io};
 use factory::Factories;
-use rlp::{View, UntrustedRlp};
+use rlp::{View, UntrustedRlp, Encodable};
 
 
 // re-export
@@ -75,6 +75,7 @@ pub use blockchain::CacheSize as BlockChainCacheSize;
 
 const MAX_TX_QUEUE_SIZE: usize = 4096;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
+const LOCAL_TRANSACTIONS_KEY: &'static [u8] = b"local_transactions";
 
 impl fmt::Display for BlockChainInfo {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -222,7 +223,9 @@ impl Client {
 			last_hashes: RwLock::new(VecDeque::new()),
 			factories: factories,
 		};
-		Ok(Arc::new(client))
+		let client = Arc::new(client);
+		client.miner.restore_local_transactions(&*client);
+		Ok(client)
 	}
 
 	/// Adds an actor to be notified on certain events
@@ -585,6 +588,31 @@ impl Client {
 		}
 	}
 
+	/// Seconds between blocks if the miner seals them on interval.
+	pub fn seal_interval(&self) -> Option<u64> {
+		match self.miner.seal_mode() {
+			SealMode::Interval(interval) => Some(interval),
+			_ => None,
+		}
+	}
+
+	/// Seals a new block on sealing interval.
+	pub fn seal_on_interval(&self) {
+		if let Err(e) = self.miner.seal_blocks(self, 1) {
+			warn!(target: "client", "Failed to seal block on interval: {}", e);
+		}
+	}
+
+	/// Whether the chain may be reverted to an earlier checkpoint.
+	pub fn allows_revert(&self) -> bool {
+		self.engine.allows_revert()
+	}
+
+	/// Whether traces of imported blocks are recorded.
+	pub fn tracing_enabled(&self) -> bool {
+		self.tracedb.tracing_enabled()
+	}
+
 	/// Look up the block number for the given block ID.
 	pub fn block_number(&self, id: BlockID) -> Option<BlockNumber> {
 		match id {
@@ -595,6 +623,26 @@ impl Client {
 		}
 	}
 
+	// decode block receipts, checking them against the receipts root in the header.
+	fn decode_receipts(header: &Header, receipts_bytes: &[u8]) -> Result<Vec<Receipt>, BlockImportError> {
+		let receipts: Vec<Receipt> = try!(UntrustedRlp::new(receipts_bytes).as_val()
+			.map_err(|e| BlockImportError::Other(format!("Invalid receipts: {}", e))));
+		let receipts_root = ordered_trie_root(receipts.iter().map(|r| r.rlp_bytes().to_vec()).collect());
+		if &receipts_root != header.receipts_root() {
+			return Err(BlockImportError::Block(BlockError::InvalidReceiptsRoot(Mismatch { expected: header.receipts_root().clone(), found: receipts_root })));
+		}
+		Ok(receipts)
+	}
+
+	// the first block of the chain, if blocks before it are missing.
+	fn first_block_hash(&self) -> Option<H256> {
+		let first = self.chain.first_block();
+		match first == self.chain.genesis_hash() {
+			true => None,
+			false => Some(first),
+		}
+	}
+
 	/// Take a snapshot at the given block.
 	/// If the ID given is "latest", this will default to 1000 blocks behind.
 	pub fn take_snapshot<W: snapshot_io::SnapshotWriter + Send>(&self, writer: W, at: BlockID, p: &snapshot::Progress) -> Result<(), ::error::Error> {
@@ -914,6 +962,89 @@ impl BlockChainClient for Client {
 		Ok(try!(self.block_queue.import_block(bytes)))
 	}
 
+	fn import_ancient_block(&self, block_bytes: Bytes, receipts_bytes: Bytes) -> Result<H256, BlockImportError> {
+		let _import_lock = self.import_lock.lock();
+		let header = BlockView::new(&block_bytes).header();
+		let hash = header.hash();
+		if self.chain.is_known(&hash) {
+			return Err(BlockImportError::Import(ImportError::AlreadyInChain));
+		}
+
+		// only the parent of the first block may be imported, which makes
+		// the restored hash chain vouch for the block.
+		let first = self.chain.first_block();
+		let expected = try!(self.chain.block_details(&first)
+			.map(|details| details.parent)
+			.ok_or_else(|| BlockImportError::Other("First block not found in chain".into())));
+		if expected != hash {
+			return Err(BlockImportError::Block(BlockError::InvalidParentHash(Mismatch { expected: expected, found: hash })));
+		}
+
+		try!(verification::verify_block_basic(&header, &block_bytes, &*self.engine));
+		try!(self.engine.verify_block_unordered(&header, Some(&block_bytes)));
+		let receipts = try!(Self::decode_receipts(&header, &receipts_bytes));
+
+		if !self.chain.insert_ancient_block(&block_bytes, receipts) {
+			return Err(BlockImportError::Other(format!("Ancient block {} could not be inserted", hash)));
+		}
+		self.chain.commit();
+		Ok(hash)
+	}
+
+	fn import_block_with_receipts(&self, block_bytes: Bytes, receipts_bytes: Bytes, is_best: bool) -> Result<H256, BlockImportError> {
+		// blocks imported without execution have no traces, which would leave gaps in the trace database.
+		if self.tracedb.tracing_enabled() {
+			return Err(BlockImportError::Other("Blocks can't be imported without execution when tracing is enabled".into()));
+		}
+
+		let _import_lock = self.import_lock.lock();
+		let header = BlockView::new(&block_bytes).header();
+		let hash = header.hash();
+		if self.chain.is_known(&hash) {
+			return Err(BlockImportError::Import(ImportError::AlreadyInChain));
+		}
+		if !self.chain.is_known(header.parent_hash()) {
+			return Err(BlockImportError::Block(BlockError::UnknownParent(header.parent_hash().clone())));
+		}
+		if is_best && !self.state_db.lock().contains(header.state_root()) {
+			return Err(BlockImportError::Other(format!("State of block {} is missing", hash)));
+		}
+
+		try!(verification::verify_block_basic(&header, &block_bytes, &*self.engine));
+		try!(self.engine.verify_block_unordered(&header, Some(&block_bytes)));
+		try!(self.verifier.verify_block_family(&header, &block_bytes, &*self.engine, &*self.chain));
+		let receipts = try!(Self::decode_receipts(&header, &receipts_bytes));
+
+		self.chain.insert_snapshot_block(&block_bytes, receipts, None, is_best);
+		self.chain.add_child(header.parent_hash().clone(), hash);
+		self.chain.commit();
+		try!(self.db.flush().map_err(BlockImportError::Other));
+		Ok(hash)
+	}
+
+	fn import_state_nodes(&self, address_hash: Option<H256>, nodes: Vec<Bytes>) -> Result<(), String> {
+		let mut state_db = self.state_db.lock();
+		for node in nodes {
+			let hash = node.sha3();
+			match address_hash {
+				Some(address_hash) => self.factories.accountdb.create(state_db.as_hashdb_mut(), address_hash).emplace(hash, node),
+				None => state_db.as_hashdb_mut().emplace(hash, node),
+			}
+		}
+
+		let mut batch = DBTransaction::new(&self.db);
+		try!(state_db.inject(&mut batch).map_err(|e| format!("{}", e)));
+		self.db.write(batch)
+	}
+
+	fn has_state_node(&self, address_hash: Option<H256>, hash: H256) -> bool {
+		let state_db = self.state_db.lock();
+		match address_hash {
+			Some(address_hash) => self.factories.accountdb.readonly(state_db.as_hashdb(), address_hash).contains(&hash),
+			None => state_db.contains(&hash),
+		}
+	}
+
 	fn queue_info(&self) -> BlockQueueInfo {
 		self.block_queue.queue_info()
 	}
@@ -923,12 +1054,15 @@ impl BlockChainClient for Client {
 	}
 
 	fn chain_info(&self) -> BlockChainInfo {
+		let first_block_hash = self.first_block_hash();
 		BlockChainInfo {
 			total_difficulty: self.chain.best_block_total_difficulty(),
 			pending_total_difficulty: self.chain.best_block_total_difficulty(),
 			genesis_hash: self.chain.genesis_hash(),
 			best_block_hash: self.chain.best_block_hash(),
-			best_block_number: From::from(self.chain.best_block_number())
+			best_block_number: From::from(self.chain.best_block_number()),
+			first_block_hash: first_block_hash,
+			first_block_number: first_block_hash.and_then(|h| self.chain.block_number(&h)),
 		}
 	}
 
@@ -1111,6 +1245,76 @@ impl MiningBlockChainClient for Client {
 		self.db.flush().expect("DB flush failed.");
 		Ok(h)
 	}
+
+	fn store_local_transactions(&self, transactions: &[PendingTransaction]) {
+		let mut batch = DBTransaction::new(&self.db);
+		if transactions.is_empty() {
+			batch.delete(::db::COL_NODE_INFO, LOCAL_TRANSACTIONS_KEY);
+		} else {
+			batch.put(::db::COL_NODE_INFO, LOCAL_TRANSACTIONS_KEY, &::rlp::encode(&transactions));
+		}
+		self.db.write(batch).expect("DB write failed.");
+	}
+
+	fn stored_local_transactions(&self) -> Vec<PendingTransaction> {
+		match self.db.get(::db::COL_NODE_INFO, LOCAL_TRANSACTIONS_KEY) {
+			Ok(Some(raw)) => UntrustedRlp::new(&raw).as_val().unwrap_or_else(|e| {
+				warn!("Error decoding stored local transactions: {:?}", e);
+				Vec::new()
+			}),
+			Ok(None) => Vec::new(),
+			Err(e) => {
+				warn!("Error reading stored local transactions: {}", e);
+				Vec::new()
+			},
+		}
+	}
+
+	fn take_checkpoint(&self) -> Result<ChainCheckpoint, Error> {
+		if !self.engine.allows_revert() {
+			return Err(Error::RevertUnavailable);
+		}
+
+		let header = self.best_block_header();
+		let header = HeaderView::new(&header);
+		Ok(ChainCheckpoint {
+			number: header.number(),
+			hash: header.sha3(),
+			state_root: header.state_root(),
+		})
+	}
+
+	fn revert_to_checkpoint(&self, checkpoint: &ChainCheckpoint) -> Result<(), Error> {
+		if !self.engine.allows_revert() {
+			return Err(Error::RevertUnavailable);
+		}
+
+		{
+			let _import_lock = self.import_lock.lock();
+			let best_number = self.chain.best_block_number();
+			let state_available = {
+				let state_db = self.state_db.lock();
+				!(state_db.is_pruned() && best_number >= checkpoint.number + HISTORY) && state_db.contains(&checkpoint.state_root)
+			};
+			let is_checkpoint_block = self.chain.block_header(&checkpoint.hash)
+				.map_or(false, |h| h.number() == checkpoint.number && h.state_root() == &checkpoint.state_root);
+			if !state_available || !is_checkpoint_block {
+				return Err(Error::InvalidCheckpoint);
+			}
+
+			let mut batch = DBTransaction::new(&self.db);
+			let retracted = try!(self.chain.revert_to(&mut batch, &checkpoint.hash).ok_or(Error::InvalidCheckpoint));
+			self.tracedb.revert(&mut batch, checkpoint.number + 1, best_number);
+			self.db.write_buffered(batch);
+			self.chain.commit();
+			self.last_hashes.write().clear();
+			self.db.flush().expect("DB flush failed.");
+			info!(target: "client", "Reverted {} blocks to #{} ({})", retracted.len(), checkpoint.number, checkpoint.hash);
+		}
+
+		self.miner.chain_reverted(self);
+		Ok(())
+	}
 }
 
 impl MayPanic for Client {
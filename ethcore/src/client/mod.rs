@@ -16,6 +16,7 @@
 
 //! Blockchain database client.
 
+mod checkpoint;
 mod config;
 mod error;
 mod test_client;
@@ -23,6 +24,7 @@ mod trace;
 mod client;
 
 pub use self::client::*;
+pub use self::checkpoint::ChainCheckpoint;
 pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockQueueConfig, BlockChainConfig, Switch, VMType};
 pub use self::error::Error;
 pub use types::ids::*;
@@ -21,21 +21,23 @@ use util::*;
 use rlp::*;
 use ethkey::{Generator, Random};
 use devtools::*;
-use transaction::{Transaction, LocalizedTransaction, SignedTransaction, Action};
+use transaction::{Transaction, LocalizedTransaction, SignedTransaction, PendingTransaction, Action};
 use blockchain::TreeRoute;
 use client::{
 	BlockChainClient, MiningBlockChainClient, BlockChainInfo, BlockStatus, BlockID,
-	TransactionID, UncleID, TraceId, TraceFilter, LastHashes, CallAnalytics, BlockImportError
+	TransactionID, UncleID, TraceId, TraceFilter, LastHashes, CallAnalytics, BlockImportError,
+	ChainCheckpoint
 };
 use header::{Header as BlockHeader, BlockNumber};
 use filter::Filter;
 use log_entry::LocalizedLogEntry;
 use receipt::{Receipt, LocalizedReceipt};
 use blockchain::extras::BlockReceipts;
-use error::{ImportResult};
+use error::{Error, ImportResult, ImportError};
 use evm::{Factory as EvmFactory, VMType};
 use miner::{Miner, MinerService, TransactionImportResult};
 use spec::Spec;
+use views::HeaderView;
 
 use block_queue::BlockQueueInfo;
 use block::{OpenBlock, SealedBlock};
@@ -67,6 +69,12 @@ pub struct TestBlockChainClient {
 	pub execution_result: RwLock<Option<Result<Executed, CallError>>>,
 	/// Transaction receipts.
 	pub receipts: RwLock<HashMap<TransactionID, LocalizedReceipt>>,
+	/// Receipts of blocks imported along with them, by block hash.
+	pub block_receipts: RwLock<HashMap<H256, Bytes>>,
+	/// State trie nodes and code, by hash.
+	pub state_nodes: RwLock<HashMap<H256, Bytes>>,
+	/// State root of blocks added by `add_blocks`.
+	pub state_root: RwLock<H256>,
 	/// Block queue size.
 	pub queue_size: AtomicUsize,
 	/// Miner
@@ -77,6 +85,8 @@ pub struct TestBlockChainClient {
 	pub vm_factory: EvmFactory,
 	/// Timestamp assigned to latest sealed block
 	pub latest_block_timestamp: RwLock<u64>,
+	/// Stored local transactions.
+	pub local_transactions: RwLock<Vec<PendingTransaction>>,
 }
 
 #[derive(Clone)]
@@ -101,7 +111,11 @@ impl Default for TestBlockChainClient {
 impl TestBlockChainClient {
 	/// Creates new test client.
 	pub fn new() -> Self {
-		let spec = Spec::new_test();
+		Self::new_with_spec(Spec::new_test())
+	}
+
+	/// Creates new test client with specified spec.
+	pub fn new_with_spec(spec: Spec) -> Self {
 		let mut client = TestBlockChainClient {
 			blocks: RwLock::new(HashMap::new()),
 			numbers: RwLock::new(HashMap::new()),
@@ -114,11 +128,15 @@ impl TestBlockChainClient {
 			code: RwLock::new(HashMap::new()),
 			execution_result: RwLock::new(None),
 			receipts: RwLock::new(HashMap::new()),
+			block_receipts: RwLock::new(HashMap::new()),
+			state_nodes: RwLock::new(HashMap::new()),
+			state_root: RwLock::new(SHA3_NULL_RLP),
 			queue_size: AtomicUsize::new(0),
 			miner: Arc::new(Miner::with_spec(&spec)),
 			spec: spec,
 			vm_factory: EvmFactory::new(VMType::Interpreter),
 			latest_block_timestamp: RwLock::new(10_000_000),
+			local_transactions: RwLock::new(Vec::new()),
 		};
 		client.add_blocks(1, EachBlockWith::Nothing); // add genesis block
 		client.genesis_hash = client.last_hash.read().clone();
@@ -155,6 +173,15 @@ impl TestBlockChainClient {
 		self.storage.write().insert((address, position), value);
 	}
 
+	/// Add state trie nodes or code and set the state root of blocks added from now on.
+	pub fn set_state(&self, root: H256, nodes: Vec<Bytes>) {
+		let mut state_nodes = self.state_nodes.write();
+		for node in nodes {
+			state_nodes.insert(node.sha3(), node);
+		}
+		*self.state_root.write() = root;
+	}
+
 	/// Set block queue size for testing
 	pub fn set_queue_size(&self, size: usize) {
 		self.queue_size.store(size, AtomicOrder::Relaxed);
@@ -174,6 +201,7 @@ impl TestBlockChainClient {
 			header.set_parent_hash(self.last_hash.read().clone());
 			header.set_number(n as BlockNumber);
 			header.set_gas_limit(U256::from(1_000_000));
+			header.set_state_root(self.state_root.read().clone());
 			let uncles = match with {
 				EachBlockWith::Uncle | EachBlockWith::UncleAndTransaction => {
 					let mut uncles = RlpStream::new_list(1);
@@ -317,6 +345,48 @@ impl MiningBlockChainClient for TestBlockChainClient {
 	fn import_sealed_block(&self, _block: SealedBlock) -> ImportResult {
 		Ok(H256::default())
 	}
+
+	fn store_local_transactions(&self, transactions: &[PendingTransaction]) {
+		*self.local_transactions.write() = transactions.to_vec();
+	}
+
+	fn stored_local_transactions(&self) -> Vec<PendingTransaction> {
+		self.local_transactions.read().clone()
+	}
+
+	fn take_checkpoint(&self) -> Result<ChainCheckpoint, Error> {
+		if !self.spec.engine.allows_revert() {
+			return Err(Error::RevertUnavailable);
+		}
+		let info = self.chain_info();
+		let header = self.best_block_header();
+		Ok(ChainCheckpoint {
+			number: info.best_block_number,
+			hash: info.best_block_hash,
+			state_root: HeaderView::new(&header).state_root(),
+		})
+	}
+
+	fn revert_to_checkpoint(&self, checkpoint: &ChainCheckpoint) -> Result<(), Error> {
+		if !self.spec.engine.allows_revert() {
+			return Err(Error::RevertUnavailable);
+		}
+		if self.block_hash(BlockID::Number(checkpoint.number)).as_ref() != Some(&checkpoint.hash) {
+			return Err(Error::InvalidCheckpoint);
+		}
+
+		let mut blocks = self.blocks.write();
+		let mut numbers = self.numbers.write();
+		let mut difficulty = self.difficulty.write();
+		let len = numbers.len();
+		for n in (checkpoint.number as usize + 1)..len {
+			if let Some(block) = numbers.remove(&n).and_then(|hash| blocks.remove(&hash)) {
+				*difficulty = *difficulty - *Rlp::new(&block).val_at::<BlockHeader>(0).difficulty();
+			}
+		}
+		*self.last_hash.write() = checkpoint.hash.clone();
+		Ok(())
+	}
 }
 
 impl BlockChainClient for TestBlockChainClient {
@@ -467,6 +537,9 @@ impl BlockChainClient for TestBlockChainClient {
 
 	// TODO: returns just hashes instead of node state rlp(?)
 	fn state_data(&self, hash: &H256) -> Option<Bytes> {
+		if let Some(node) = self.state_nodes.read().get(hash) {
+			return Some(node.clone());
+		}
 		// starts with 'f' ?
 		if *hash > H256::from("f000000000000000000000000000000000000000000000000000000000000000") {
 			let mut rlp = RlpStream::new();
@@ -477,6 +550,13 @@ impl BlockChainClient for TestBlockChainClient {
 	}
 
 	fn block_receipts(&self, hash: &H256) -> Option<Bytes> {
+		if let Some(receipts) = self.block_receipts.read().get(hash) {
+			return Some(receipts.clone());
+		}
+		// blocks are added without receipts, which matches their empty receipts root.
+		if self.blocks.read().contains_key(hash) {
+			return Some(::rlp::EMPTY_LIST_RLP.to_vec());
+		}
 		// starts with 'f' ?
 		if *hash > H256::from("f000000000000000000000000000000000000000000000000000000000000000") {
 			let receipt = BlockReceipts::new(vec![Receipt::new(
@@ -535,6 +615,40 @@ impl BlockChainClient for TestBlockChainClient {
 		Ok(h)
 	}
 
+	fn import_ancient_block(&self, block_bytes: Bytes, receipts_bytes: Bytes) -> Result<H256, BlockImportError> {
+		let header = Rlp::new(&block_bytes).val_at::<BlockHeader>(0);
+		let h = header.hash();
+		if self.blocks.read().contains_key(&h) {
+			return Err(BlockImportError::Import(ImportError::AlreadyInChain));
+		}
+		self.numbers.write().entry(header.number() as usize).or_insert_with(|| h.clone());
+		self.blocks.write().insert(h.clone(), block_bytes);
+		self.block_receipts.write().insert(h.clone(), receipts_bytes);
+		Ok(h)
+	}
+
+	fn import_block_with_receipts(&self, block_bytes: Bytes, receipts_bytes: Bytes, is_best: bool) -> Result<H256, BlockImportError> {
+		let header = Rlp::new(&block_bytes).val_at::<BlockHeader>(0);
+		if is_best && !self.has_state_node(None, header.state_root().clone()) && header.state_root() != &SHA3_NULL_RLP {
+			return Err(BlockImportError::Other(format!("State of block {} is missing", header.hash())));
+		}
+		let h = try!(self.import_block(block_bytes));
+		self.block_receipts.write().insert(h.clone(), receipts_bytes);
+		Ok(h)
+	}
+
+	fn import_state_nodes(&self, _address_hash: Option<H256>, nodes: Vec<Bytes>) -> Result<(), String> {
+		let mut state_nodes = self.state_nodes.write();
+		for node in nodes {
+			state_nodes.insert(node.sha3(), node);
+		}
+		Ok(())
+	}
+
+	fn has_state_node(&self, _address_hash: Option<H256>, hash: H256) -> bool {
+		self.state_nodes.read().contains_key(&hash)
+	}
+
 	fn queue_info(&self) -> BlockQueueInfo {
 		BlockQueueInfo {
 			verified_queue_size: self.queue_size.load(AtomicOrder::Relaxed),
@@ -560,6 +674,8 @@ impl BlockChainClient for TestBlockChainClient {
 			genesis_hash: self.genesis_hash.clone(),
 			best_block_hash: self.last_hash.read().clone(),
 			best_block_number: self.blocks.read().len() as BlockNumber - 1,
+			first_block_hash: None,
+			first_block_number: None,
 		}
 	}
 
@@ -20,11 +20,11 @@ use blockchain::TreeRoute;
 use block_queue::BlockQueueInfo;
 use block::{OpenBlock, SealedBlock};
 use header::{BlockNumber};
-use transaction::{LocalizedTransaction, SignedTransaction};
+use transaction::{LocalizedTransaction, SignedTransaction, PendingTransaction};
 use log_entry::LocalizedLogEntry;
 use filter::Filter;
 use views::{BlockView};
-use error::{ImportResult, CallError};
+use error::{Error, ImportResult, CallError};
 use receipt::LocalizedReceipt;
 use trace::LocalizedTrace;
 use evm::Factory as EvmFactory;
@@ -37,6 +37,7 @@ use block_import_error::BlockImportError;
 use ipc::IpcConfig;
 use types::blockchain_info::BlockChainInfo;
 use types::block_status::BlockStatus;
+use client::ChainCheckpoint;
 
 #[derive(Ipc)]
 #[ipc(client_ident="RemoteClient")]
@@ -140,6 +141,23 @@ pub trait BlockChainClient : Sync + Send {
 	/// Import a block into the blockchain.
 	fn import_block(&self, bytes: Bytes) -> Result<H256, BlockImportError>;
 
+	/// Import an ancient block along with its receipts, without executing it.
+	/// The block must be the parent of the first block in the chain.
+	fn import_ancient_block(&self, block_bytes: Bytes, receipts_bytes: Bytes) -> Result<H256, BlockImportError>;
+
+	/// Import a block along with its receipts, without executing it. The parent must be
+	/// in the chain. The block becomes the best block only if `is_best` is set, which
+	/// requires its state to be present in the state database.
+	fn import_block_with_receipts(&self, block_bytes: Bytes, receipts_bytes: Bytes, is_best: bool) -> Result<H256, BlockImportError>;
+
+	/// Write downloaded state trie nodes or code into the state database.
+	/// Storage trie nodes and code belong to the account with given address hash.
+	fn import_state_nodes(&self, address_hash: Option<H256>, nodes: Vec<Bytes>) -> Result<(), String>;
+
+	/// Check if the state database contains a node, belonging to the account
+	/// with given address hash if any.
+	fn has_state_node(&self, address_hash: Option<H256>, hash: H256) -> bool;
+
 	/// Get block queue information.
 	fn queue_info(&self) -> BlockQueueInfo;
 
@@ -226,6 +244,19 @@ pub trait MiningBlockChainClient : BlockChainClient {
 
 	/// Import sealed block. Skips all verifications.
 	fn import_sealed_block(&self, block: SealedBlock) -> ImportResult;
+
+	/// Persist transactions originating from this node, replacing previously stored ones.
+	fn store_local_transactions(&self, transactions: &[PendingTransaction]);
+
+	/// Transactions originating from this node persisted with `store_local_transactions`.
+	fn stored_local_transactions(&self) -> Vec<PendingTransaction>;
+
+	/// Records the best block and its state root, so that the chain can be reverted to it later.
+	/// Fails unless the engine allows reverting the chain.
+	fn take_checkpoint(&self) -> Result<ChainCheckpoint, Error>;
+
+	/// Reverts the chain to the checkpoint, dropping the miner's pending block and queued transactions.
+	fn revert_to_checkpoint(&self, checkpoint: &ChainCheckpoint) -> Result<(), Error>;
 }
 
 impl IpcConfig for BlockChainClient { }
@@ -34,8 +34,10 @@ pub const COL_BODIES: Option<u32> = Some(2);
 pub const COL_EXTRA: Option<u32> = Some(3);
 /// Column for Traces
 pub const COL_TRACE: Option<u32> = Some(4);
+/// Column for node info, e.g. local transactions
+pub const COL_NODE_INFO: Option<u32> = Some(5);
 /// Number of columns in DB
-pub const NUM_COLUMNS: Option<u32> = Some(5);
+pub const NUM_COLUMNS: Option<u32> = Some(6);
 
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
@@ -102,6 +104,11 @@ pub trait Writable {
 		}
 	}
 
+	/// Deletes the value from the database and removes it from the cache.
+	fn delete_with_cache<K, T, R>(&mut self, col: Option<u32>, cache: &mut Cache<K, T>, key: &K) where
+	K: Key<T, Target = R> + Hash + Eq,
+	R: Deref<Target = [u8]>;
+
 	/// Writes the values into the database and updates the cache.
 	fn extend_with_cache<K, T, R>(&mut self, col: Option<u32>, cache: &mut Cache<K, T>, values: HashMap<K, T>, policy: CacheUpdatePolicy) where
 	K: Key<T, Target = R> + Hash + Eq,
@@ -173,6 +180,13 @@ impl Writable for DBTransaction {
 	fn write<T, R>(&mut self, col: Option<u32>, key: &Key<T, Target = R>, value: &T) where T: rlp::Encodable, R: Deref<Target = [u8]> {
 		self.put(col, &key.key(), &rlp::encode(value));
 	}
+
+	fn delete_with_cache<K, T, R>(&mut self, col: Option<u32>, cache: &mut Cache<K, T>, key: &K) where
+	K: Key<T, Target = R> + Hash + Eq,
+	R: Deref<Target = [u8]> {
+		self.delete(col, &key.key());
+		cache.remove(key);
+	}
 }
 
 impl Readable for Database {
@@ -17,25 +17,62 @@
 use std::collections::BTreeMap;
 use util::Address;
 use builtin::Builtin;
-use engines::Engine;
+use engines::{Engine, SealMode};
 use spec::CommonParams;
 use evm::Schedule;
 use env_info::EnvInfo;
 use block::ExecutedBlock;
 use common::Bytes;
 use account_provider::AccountProvider;
+use ethjson;
+
+/// Seconds between blocks in interval mode if not given.
+const DEFAULT_SEAL_INTERVAL: u64 = 5;
+
+/// `InstantSeal` params.
+#[derive(Debug, PartialEq)]
+pub struct InstantSealParams {
+	/// When blocks are sealed.
+	pub seal_mode: SealMode,
+}
+
+impl Default for InstantSealParams {
+	fn default() -> Self {
+		InstantSealParams {
+			seal_mode: SealMode::Instant,
+		}
+	}
+}
+
+impl From<ethjson::spec::InstantSealParams> for InstantSealParams {
+	fn from(p: ethjson::spec::InstantSealParams) -> Self {
+		let seal_mode = match p.mode {
+			None | Some(ethjson::spec::SealMode::Instant) => SealMode::Instant,
+			Some(ethjson::spec::SealMode::Interval) => {
+				let interval = p.interval.map_or(DEFAULT_SEAL_INTERVAL, Into::into);
+				SealMode::Interval(::std::cmp::max(interval, 1))
+			},
+			Some(ethjson::spec::SealMode::Manual) => SealMode::Manual,
+		};
+		InstantSealParams {
+			seal_mode: seal_mode,
+		}
+	}
+}
 
 /// An engine which does not provide any consensus mechanism, just seals blocks internally.
 pub struct InstantSeal {
 	params: CommonParams,
+	our_params: InstantSealParams,
 	builtins: BTreeMap<Address, Builtin>,
 }
 
 impl InstantSeal {
 	/// Returns new instance of InstantSeal with default VM Factory
-	pub fn new(params: CommonParams, builtins: BTreeMap<Address, Builtin>) -> Self {
+	pub fn new(params: CommonParams, our_params: InstantSealParams, builtins: BTreeMap<Address, Builtin>) -> Self {
 		InstantSeal {
 			params: params,
+			our_params: our_params,
 			builtins: builtins,
 		}
 	}
@@ -61,6 +98,14 @@ impl Engine for InstantSeal {
 	fn generate_seal(&self, _block: &ExecutedBlock, _accounts: Option<&AccountProvider>) -> Option<Vec<Bytes>> {
 		Some(Vec::new())
 	}
+
+	fn seal_mode(&self) -> SealMode {
+		self.our_params.seal_mode
+	}
+
+	fn allows_revert(&self) -> bool {
+		true
+	}
 }
 
 #[cfg(test)]
@@ -70,6 +115,7 @@ mod tests {
 	use account_provider::AccountProvider;
 	use spec::Spec;
 	use block::*;
+	use engines::SealMode;
 
 	/// Create a new test chain spec with `BasicAuthority` consensus engine.
 	fn new_test_instant() -> Spec { Spec::load(include_bytes!("../../res/instant_seal.json")) }
@@ -93,6 +139,25 @@ mod tests {
 		assert!(b.try_seal(engine, seal).is_ok());
 	}
 
+	#[test]
+	fn instant_seals_on_transactions_by_default() {
+		assert_eq!(new_test_instant().engine.seal_mode(), SealMode::Instant);
+	}
+
+	#[test]
+	fn should_read_seal_mode_from_params() {
+		use ethjson;
+		use engines::instant_seal::InstantSealParams;
+
+		let params = |mode, interval: Option<u64>| InstantSealParams::from(ethjson::spec::InstantSealParams {
+			mode: Some(mode),
+			interval: interval.map(|i| ethjson::uint::Uint(i.into())),
+		});
+		assert_eq!(params(ethjson::spec::SealMode::Manual, None).seal_mode, SealMode::Manual);
+		assert_eq!(params(ethjson::spec::SealMode::Interval, Some(3)).seal_mode, SealMode::Interval(3));
+		assert_eq!(params(ethjson::spec::SealMode::Interval, None).seal_mode, SealMode::Interval(5));
+	}
+
 	#[test]
 	fn instant_cant_verify() {
 		let engine = new_test_instant().engine;
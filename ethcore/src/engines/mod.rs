@@ -21,7 +21,7 @@ mod instant_seal;
 mod basic_authority;
 
 pub use self::null_engine::NullEngine;
-pub use self::instant_seal::InstantSeal;
+pub use self::instant_seal::{InstantSeal, InstantSealParams};
 pub use self::basic_authority::BasicAuthority;
 
 use common::*;
@@ -30,6 +30,17 @@ use block::ExecutedBlock;
 use spec::CommonParams;
 use evm::Schedule;
 
+/// When an internally sealing engine seals new blocks.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SealMode {
+	/// Seal a block as soon as there are transactions to include.
+	Instant,
+	/// Seal a block every given number of seconds, even if empty.
+	Interval(u64),
+	/// Seal blocks only on request.
+	Manual,
+}
+
 /// A consensus mechanism for the chain. Generally either proof-of-work or proof-of-stake-based.
 /// Provides hooks into each of the major parts of block import.
 pub trait Engine : Sync + Send {
@@ -79,6 +90,13 @@ pub trait Engine : Sync + Send {
 	/// be returned.
 	fn generate_seal(&self, _block: &ExecutedBlock, _accounts: Option<&AccountProvider>) -> Option<Vec<Bytes>> { None }
 
+	/// When blocks are sealed internally. Only meaningful for engines able to `generate_seal`.
+	fn seal_mode(&self) -> SealMode { SealMode::Instant }
+
+	/// Whether the chain may be reverted to one of its earlier blocks on request.
+	/// Only engines used for test chains should allow it.
+	fn allows_revert(&self) -> bool { false }
+
 	/// Phase 1 quick block verification. Only does checks that are cheap. `block` (the header's full block)
 	/// may be provided for additional checks. Returns either a null `Ok` or a general error detailing the problem with import.
 	fn verify_block_basic(&self, _header: &Header,  _block: Option<&[u8]>) -> Result<(), Error> { Ok(()) }
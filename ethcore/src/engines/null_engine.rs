@@ -54,4 +54,8 @@ impl Engine for NullEngine {
 	fn schedule(&self, _env_info: &EnvInfo) -> Schedule {
 		Schedule::new_homestead()
 	}
+
+	fn allows_revert(&self) -> bool {
+		true
+	}
 }
@@ -63,6 +63,8 @@ pub enum TransactionError {
 	},
 	/// Transaction's gas limit (aka gas) is invalid.
 	InvalidGasLimit(OutOfBounds<U256>),
+	/// Transaction is not permitted by the transaction filter.
+	NotAllowed,
 }
 
 impl fmt::Display for TransactionError {
@@ -81,6 +83,7 @@ impl fmt::Display for TransactionError {
 			GasLimitExceeded { limit, got } =>
 				format!("Gas limit exceeded. Limit={}, Given={}", limit, got),
 			InvalidGasLimit(ref err) => format!("Invalid gas limit. {}", err),
+			NotAllowed => "Not permitted by transaction filter".into(),
 		};
 
 		f.write_fmt(format_args!("Transaction error ({})", msg))
@@ -229,6 +232,12 @@ pub enum Error {
 	PowHashInvalid,
 	/// The value of the nonce or mishash is invalid.
 	PowInvalid,
+	/// Engine is unable to seal blocks internally.
+	SealingUnavailable,
+	/// Engine does not allow reverting the chain.
+	RevertUnavailable,
+	/// Checkpoint is not in the canon chain or its state has been pruned.
+	InvalidCheckpoint,
 	/// Error concerning TrieDBs
 	Trie(TrieError),
 	/// Io crate error.
@@ -257,6 +266,9 @@ impl fmt::Display for Error {
 				f.write_fmt(format_args!("Unknown engine name ({})", name)),
 			Error::PowHashInvalid => f.write_str("Invalid or out of date PoW hash."),
 			Error::PowInvalid => f.write_str("Invalid nonce or mishash"),
+			Error::SealingUnavailable => f.write_str("Engine is unable to seal blocks internally"),
+			Error::RevertUnavailable => f.write_str("Engine does not allow reverting the chain"),
+			Error::InvalidCheckpoint => f.write_str("Checkpoint is not in the canon chain or its state has been pruned"),
 			Error::Trie(ref err) => err.fmt(f),
 			Error::StdIo(ref err) => err.fmt(f),
 			Error::Snappy(ref err) => err.fmt(f),
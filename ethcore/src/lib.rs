@@ -95,10 +95,12 @@ extern crate ethash;
 extern crate ethkey;
 extern crate semver;
 extern crate ethcore_ipc_nano as nanoipc;
+extern crate ethcore_stratum;
 extern crate ethcore_devtools as devtools;
 extern crate rand;
 extern crate bit_set;
 extern crate rlp;
+extern crate ws;
 
 #[macro_use]
 extern crate log;
@@ -16,6 +16,8 @@
 
 //! Database migrations.
 
+use util::migration::ChangeColumns;
+
 pub mod state;
 pub mod blocks;
 pub mod extras;
@@ -23,3 +25,11 @@ pub mod extras;
 mod v9;
 pub use self::v9::ToV9;
 pub use self::v9::Extract;
+
+/// The migration from v9 to v10.
+/// Adds a column for node info.
+pub const TO_V10: ChangeColumns = ChangeColumns {
+	pre_columns: Some(5),
+	post_columns: Some(6),
+	version: 10,
+};
@@ -0,0 +1,272 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! In-process CPU miner searching ethash solutions for the miner's sealing work.
+//! Hashes are computed with the light cache, so it's only suitable for low-difficulty development chains.
+
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use std::sync::{Arc, Weak, Condvar as SCondvar, Mutex as SMutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
+use rand;
+use ethash::EthashManager;
+use ethereum::ethash::Ethash;
+use util::{H256, H64, U256, Hashable};
+use rlp;
+use block::IsBlock;
+use client::MiningBlockChainClient;
+use miner::{Miner, MinerService, ExternalMinerService};
+use miner::work_notify::NotifyWork;
+
+/// Number of hashes computed between checks for new work.
+const HASHES_PER_ROUND: u64 = 32;
+/// Interval of hashrate reports in milliseconds. Has to be shorter than the external miner's entry timeout.
+const HASHRATE_REPORT_INTERVAL: u64 = 1000;
+
+/// CPU miner options.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CpuMinerOptions {
+	/// Number of mining threads.
+	pub threads: usize,
+}
+
+/// Work package: pow hash, difficulty and block number.
+type Work = (H256, U256, u64);
+
+struct State {
+	/// Current work package, if any.
+	work: SMutex<Option<Work>>,
+	/// Signalled when work package changes or the miner is stopped.
+	work_changed: SCondvar,
+	/// Incremented with every work package change.
+	generation: AtomicUsize,
+	exit: AtomicBool,
+	ethash: EthashManager,
+	miner: Weak<Miner>,
+	client: Weak<MiningBlockChainClient>,
+	external_miner: Arc<ExternalMinerService>,
+}
+
+impl State {
+	fn set_work(&self, work: Option<Work>) {
+		let mut current = self.work.lock().unwrap();
+		*current = work;
+		self.generation.fetch_add(1, AtomicOrdering::SeqCst);
+		self.work_changed.notify_all();
+	}
+
+	/// Clears the work package unless it changed since `generation`.
+	fn clear_work(&self, generation: usize) {
+		let mut current = self.work.lock().unwrap();
+		if self.generation.load(AtomicOrdering::SeqCst) == generation {
+			*current = None;
+			self.generation.fetch_add(1, AtomicOrdering::SeqCst);
+			self.work_changed.notify_all();
+		}
+	}
+
+	/// Blocks until there is work to do. Returns `None` if the miner is stopped.
+	fn wait_for_work(&self) -> Option<(Work, usize)> {
+		let mut current = self.work.lock().unwrap();
+		loop {
+			if self.exit.load(AtomicOrdering::SeqCst) {
+				return None;
+			}
+			if let Some(work) = *current {
+				return Some((work, self.generation.load(AtomicOrdering::SeqCst)));
+			}
+			current = self.work_changed.wait(current).unwrap();
+		}
+	}
+
+	fn is_current(&self, generation: usize) -> bool {
+		!self.exit.load(AtomicOrdering::SeqCst) && self.generation.load(AtomicOrdering::SeqCst) == generation
+	}
+
+	fn submit(&self, pow_hash: H256, nonce: u64, mix_hash: H256) {
+		let seal = vec![rlp::encode(&mix_hash).to_vec(), rlp::encode(&H64::from(nonce)).to_vec()];
+		match (self.client.upgrade(), self.miner.upgrade()) {
+			(Some(client), Some(miner)) => if let Err(e) = miner.submit_seal(&*client, pow_hash, seal) {
+				warn!(target: "miner", "CPU miner solution rejected: {}", e);
+			},
+			_ => trace!(target: "miner", "CPU miner solution dropped, miner is not available"),
+		}
+	}
+}
+
+/// Searches for a nonce of `work` starting at `nonce`. Returns the nonce and mix hash
+/// of the solution or `None` after `rounds` unsuccessful hashes.
+fn search(ethash: &EthashManager, work: &Work, nonce: u64, rounds: u64) -> Option<(u64, H256)> {
+	let (pow_hash, difficulty, number) = *work;
+	(0..rounds).map(|i| nonce.wrapping_add(i)).filter_map(|nonce| {
+		let pow = ethash.compute_light(number, &pow_hash.0, nonce);
+		if Ethash::boundary_to_difficulty(&H256(pow.value)) >= difficulty {
+			Some((nonce, H256(pow.mix_hash)))
+		} else {
+			None
+		}
+	}).next()
+}
+
+fn mine(state: Arc<State>, hashrate_id: H256) {
+	let mut nonce = rand::random::<u64>();
+	// hashes are counted across work packages, new work usually comes more often than reports are due
+	let mut hashes = 0u64;
+	let mut last_report = Instant::now();
+	while let Some((work, generation)) = state.wait_for_work() {
+		trace!(target: "miner", "CPU miner working on {}", work.0);
+		while state.is_current(generation) {
+			let solution = search(&state.ethash, &work, nonce, HASHES_PER_ROUND);
+			if let Some((solution, mix_hash)) = solution {
+				trace!(target: "miner", "CPU miner found solution for {}: {}", work.0, solution);
+				state.clear_work(generation);
+				state.submit(work.0, solution, mix_hash);
+				break;
+			}
+			nonce = nonce.wrapping_add(HASHES_PER_ROUND);
+			hashes += HASHES_PER_ROUND;
+
+			let elapsed = last_report.elapsed();
+			let elapsed = elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000;
+			if elapsed >= HASHRATE_REPORT_INTERVAL {
+				state.external_miner.submit_hashrate(U256::from(hashes * 1000 / elapsed), hashrate_id);
+				hashes = 0;
+				last_report = Instant::now();
+			}
+		}
+	}
+}
+
+/// Feeds new sealing work to CPU miner threads.
+struct CpuMinerNotifier {
+	state: Arc<State>,
+}
+
+impl NotifyWork for CpuMinerNotifier {
+	fn notify(&self, pow_hash: H256, difficulty: U256, number: u64) {
+		self.state.set_work(Some((pow_hash, difficulty, number)));
+	}
+}
+
+/// Built-in CPU miner. Mining threads are stopped when dropped.
+pub struct CpuMiner {
+	state: Arc<State>,
+	threads: Vec<JoinHandle<()>>,
+}
+
+impl CpuMiner {
+	/// Starts mining threads and registers them for new work notifications with the miner.
+	/// Solutions are submitted to the miner and hashrate is reported to `external_miner`.
+	pub fn start(
+		options: &CpuMinerOptions,
+		miner: Arc<Miner>,
+		client: Arc<MiningBlockChainClient>,
+		external_miner: Arc<ExternalMinerService>,
+	) -> Result<CpuMiner, String> {
+		let state = Arc::new(State {
+			work: SMutex::new(None),
+			work_changed: SCondvar::new(),
+			generation: AtomicUsize::new(0),
+			exit: AtomicBool::new(false),
+			ethash: EthashManager::new(),
+			miner: Arc::downgrade(&miner),
+			client: Arc::downgrade(&client),
+			external_miner: external_miner,
+		});
+
+		let mut threads = Vec::with_capacity(options.threads);
+		for i in 0..options.threads {
+			let state = state.clone();
+			let hashrate_id = format!("cpu-miner-{}", i).sha3();
+			threads.push(try!(thread::Builder::new()
+				.name(format!("CPU Miner #{}", i))
+				.spawn(move || mine(state, hashrate_id))
+				.map_err(|e| format!("Error starting CPU miner thread: {}", e))));
+		}
+
+		miner.add_work_listener(Box::new(CpuMinerNotifier { state: state.clone() }));
+		let work = miner.map_sealing_work(&*client, |b| (b.hash(), *b.header().difficulty(), b.header().number()));
+		if work.is_some() {
+			state.set_work(work);
+		}
+		info!("CPU miner started with {} threads", options.threads);
+
+		Ok(CpuMiner {
+			state: state,
+			threads: threads,
+		})
+	}
+
+	/// Pow hash of the work package currently mined.
+	pub fn work(&self) -> Option<H256> {
+		self.state.work.lock().unwrap().map(|(pow_hash, _, _)| pow_hash)
+	}
+}
+
+impl Drop for CpuMiner {
+	fn drop(&mut self) {
+		trace!(target: "shutdown", "[CpuMiner] Closing...");
+		{
+			let _work = self.state.work.lock().unwrap();
+			self.state.exit.store(true, AtomicOrdering::SeqCst);
+			self.state.work_changed.notify_all();
+		}
+		for t in self.threads.drain(..) {
+			t.join().unwrap();
+		}
+		trace!(target: "shutdown", "[CpuMiner] Closed.");
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use ethash::EthashManager;
+	use util::{H256, U256, Uint};
+	use client::{TestBlockChainClient, MiningBlockChainClient, EachBlockWith};
+	use miner::{Miner, MinerService, ExternalMiner};
+	use spec::Spec;
+	use super::{CpuMiner, CpuMinerOptions, search};
+
+	#[test]
+	fn should_find_solution_meeting_difficulty() {
+		let ethash = EthashManager::new();
+		let work = (H256::from(1), U256::from(1), 0);
+		// any nonce satisfies difficulty of 1
+		assert_eq!(search(&ethash, &work, 5, 1).map(|(nonce, _)| nonce), Some(5));
+		let work = (H256::from(1), U256::max_value(), 0);
+		assert_eq!(search(&ethash, &work, 5, 1), None);
+	}
+
+	#[test]
+	fn should_follow_sealing_work() {
+		let test_client = Arc::new(TestBlockChainClient::default());
+		let client: Arc<MiningBlockChainClient> = test_client.clone();
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let options = CpuMinerOptions { threads: 0 };
+		let cpu_miner = CpuMiner::start(&options, miner.clone(), client.clone(), Arc::new(ExternalMiner::default())).unwrap();
+
+		let pow_hash = miner.map_sealing_work(&*client, |b| b.hash());
+		assert_eq!(cpu_miner.work(), pow_hash);
+
+		test_client.add_blocks(1, EachBlockWith::Nothing);
+		miner.update_sealing(&*client);
+		let new_pow_hash = miner.map_sealing_work(&*client, |b| b.hash());
+		assert!(new_pow_hash != pow_hash);
+		assert_eq!(cpu_miner.work(), new_pow_hash);
+	}
+}
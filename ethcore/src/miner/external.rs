@@ -14,29 +14,86 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap, VecDeque};
 use std::sync::Arc;
 use std::time::{Instant, Duration};
 use util::{Mutex, U256, H256};
 
+/// Share statistics of a single worker.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WorkerStats {
+	/// Number of accepted shares.
+	pub accepted_shares: u64,
+	/// Number of rejected shares.
+	pub rejected_shares: u64,
+	/// Hashrate estimated from recently accepted shares.
+	pub hashrate: U256,
+}
+
 /// External miner interface.
 pub trait ExternalMinerService: Send + Sync {
 	/// Submit hashrate for given miner.
 	fn submit_hashrate(&self, hashrate: U256, id: H256);
 
+	/// Record share submitted by given worker. Accepted shares add `difficulty` to the worker's hashrate.
+	fn submit_share(&self, worker: String, difficulty: U256, accepted: bool);
+
+	/// Share statistics of workers.
+	fn workers(&self) -> BTreeMap<String, WorkerStats>;
+
 	/// Total hashrate.
 	fn hashrate(&self) -> U256;
 }
 
+struct WorkerShares {
+	accepted: u64,
+	rejected: u64,
+	first_share: Instant,
+	last_share: Instant,
+	recent: VecDeque<(Instant, U256)>,
+}
+
+impl WorkerShares {
+	fn new() -> Self {
+		WorkerShares {
+			accepted: 0,
+			rejected: 0,
+			first_share: Instant::now(),
+			last_share: Instant::now(),
+			recent: VecDeque::new(),
+		}
+	}
+
+	fn prune(&mut self) {
+		let window = Duration::from_secs(SHARES_WINDOW);
+		while self.recent.front().map_or(false, |&(t, _)| t.elapsed() > window) {
+			self.recent.pop_front();
+		}
+	}
+
+	/// Whether the worker submitted no share within the shares window.
+	fn is_stale(&self) -> bool {
+		self.last_share.elapsed() > Duration::from_secs(SHARES_WINDOW)
+	}
+
+	fn hashrate(&self) -> U256 {
+		let elapsed = ::std::cmp::min(self.first_share.elapsed().as_secs(), SHARES_WINDOW);
+		let work = self.recent.iter().fold(U256::from(0), |sum, &(_, d)| sum + d);
+		work / U256::from(::std::cmp::max(elapsed, 1))
+	}
+}
+
 /// External Miner.
 pub struct ExternalMiner {
 	hashrates: Arc<Mutex<HashMap<H256, (Instant, U256)>>>,
+	workers: Mutex<HashMap<String, WorkerShares>>,
 }
 
 impl Default for ExternalMiner {
 	fn default() -> Self {
 		ExternalMiner {
 			hashrates: Arc::new(Mutex::new(HashMap::new())),
+			workers: Mutex::new(HashMap::new()),
 		}
 	}
 }
@@ -46,30 +103,70 @@ impl ExternalMiner {
 	pub fn new(hashrates: Arc<Mutex<HashMap<H256, (Instant, U256)>>>) -> Self {
 		ExternalMiner {
 			hashrates: hashrates,
+			workers: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Forgets workers which submitted no share within the shares window.
+	fn prune_workers(workers: &mut HashMap<String, WorkerShares>) {
+		let stale: Vec<String> = workers.iter().filter(|&(_, shares)| shares.is_stale()).map(|(worker, _)| worker.clone()).collect();
+		for worker in stale {
+			workers.remove(&worker);
 		}
 	}
 }
 
 const ENTRY_TIMEOUT: u64 = 2;
+/// Shares accepted within this number of seconds count towards the hashrate.
+const SHARES_WINDOW: u64 = 600;
 
 impl ExternalMinerService for ExternalMiner {
 	fn submit_hashrate(&self, hashrate: U256, id: H256) {
 		self.hashrates.lock().insert(id, (Instant::now() + Duration::from_secs(ENTRY_TIMEOUT), hashrate));
 	}
 
+	fn submit_share(&self, worker: String, difficulty: U256, accepted: bool) {
+		let mut workers = self.workers.lock();
+		ExternalMiner::prune_workers(&mut workers);
+		let shares = workers.entry(worker).or_insert_with(WorkerShares::new);
+		shares.last_share = Instant::now();
+		if accepted {
+			shares.accepted += 1;
+			shares.recent.push_back((Instant::now(), difficulty));
+		} else {
+			shares.rejected += 1;
+		}
+		shares.prune();
+	}
+
+	fn workers(&self) -> BTreeMap<String, WorkerStats> {
+		let mut workers = self.workers.lock();
+		ExternalMiner::prune_workers(&mut workers);
+		workers.iter_mut().map(|(worker, shares)| {
+			shares.prune();
+			(worker.clone(), WorkerStats {
+				accepted_shares: shares.accepted,
+				rejected_shares: shares.rejected,
+				hashrate: shares.hashrate(),
+			})
+		}).collect()
+	}
+
 	fn hashrate(&self) -> U256 {
 		let mut hashrates = self.hashrates.lock();
 		let h = hashrates.drain().filter(|&(_, (t, _))| t > Instant::now()).collect();
 		*hashrates = h;
-		hashrates.iter().fold(U256::from(0), |sum, (_, &(_, v))| sum + v)
+		let submitted = hashrates.iter().fold(U256::from(0), |sum, (_, &(_, v))| sum + v);
+		self.workers().values().fold(submitted, |sum, stats| sum + stats.hashrate)
 	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use super::SHARES_WINDOW;
 	use std::thread::sleep;
-	use std::time::Duration;
+	use std::time::{Instant, Duration};
 	use util::{H256, U256};
 
 	fn miner() -> ExternalMiner {
@@ -107,4 +204,41 @@ mod tests {
 		// then
 		assert_eq!(m.hashrate(), U256::from(35));
 	}
+
+	#[test]
+	fn should_count_shares_of_workers() {
+		// given
+		let m = miner();
+
+		// when
+		m.submit_share("worker1".into(), U256::from(1000), true);
+		m.submit_share("worker1".into(), U256::from(1000), true);
+		m.submit_share("worker1".into(), U256::from(1000), false);
+		m.submit_share("worker2".into(), U256::from(1000), false);
+
+		// then
+		let workers = m.workers();
+		assert_eq!(workers.len(), 2);
+		assert_eq!(workers["worker1"].accepted_shares, 2);
+		assert_eq!(workers["worker1"].rejected_shares, 1);
+		assert_eq!(workers["worker1"].hashrate, U256::from(2000));
+		assert_eq!(workers["worker2"], WorkerStats { accepted_shares: 0, rejected_shares: 1, hashrate: U256::from(0) });
+		assert_eq!(m.hashrate(), U256::from(2000));
+	}
+
+	#[test]
+	fn should_forget_idle_workers() {
+		// given
+		let m = miner();
+		m.submit_share("worker1".into(), U256::from(1000), true);
+		m.submit_share("worker2".into(), U256::from(1000), true);
+
+		// when
+		m.workers.lock().get_mut("worker1").unwrap().last_share = Instant::now() - Duration::from_secs(SHARES_WINDOW + 1);
+
+		// then
+		let workers = m.workers();
+		assert_eq!(workers.len(), 1);
+		assert!(workers.contains_key("worker2"));
+	}
 }
@@ -0,0 +1,127 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Gas price suggestions based on transactions in recent blocks.
+
+use util::{H256, U256, Mutex};
+use client::{BlockChainClient, BlockID};
+use header::BlockNumber;
+use views::BlockView;
+
+/// Configures the gas price oracle.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GasPriceOracleOptions {
+	/// Number of recent blocks to sample transactions from.
+	pub blocks: usize,
+	/// Percentile (0 - 100) of sampled gas prices to suggest.
+	pub percentile: usize,
+}
+
+impl Default for GasPriceOracleOptions {
+	fn default() -> Self {
+		GasPriceOracleOptions {
+			blocks: 100,
+			percentile: 50,
+		}
+	}
+}
+
+/// Distribution of gas prices of transactions in recent blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasPriceHistogram {
+	/// Best block at the time the histogram was computed.
+	pub block_number: BlockNumber,
+	/// Number of sampled transactions.
+	pub sample_size: usize,
+	/// Gas prices at percentiles 0, 1, ..., 100.
+	pub percentiles: Vec<U256>,
+}
+
+impl GasPriceHistogram {
+	/// Gas price at given percentile.
+	pub fn percentile(&self, percentile: usize) -> U256 {
+		self.percentiles[::std::cmp::min(percentile, 100)]
+	}
+}
+
+/// Suggests gas prices given the gas prices of transactions in recent blocks.
+/// The distribution is recomputed at most once per block.
+pub struct GasPriceOracle {
+	options: GasPriceOracleOptions,
+	cache: Mutex<Option<(H256, Option<GasPriceHistogram>)>>,
+}
+
+impl GasPriceOracle {
+	/// Creates new oracle.
+	pub fn new(options: GasPriceOracleOptions) -> Self {
+		GasPriceOracle {
+			options: options,
+			cache: Mutex::new(None),
+		}
+	}
+
+	/// Returns gas price distribution at the current best block.
+	/// `None` if there were no transactions in sampled blocks.
+	pub fn histogram<C: BlockChainClient + ?Sized>(&self, chain: &C) -> Option<GasPriceHistogram> {
+		let chain_info = chain.chain_info();
+		let mut cache = self.cache.lock();
+		if let Some((ref hash, ref histogram)) = *cache {
+			if *hash == chain_info.best_block_hash {
+				return histogram.clone();
+			}
+		}
+
+		let histogram = Self::compute(chain, chain_info.best_block_hash, chain_info.best_block_number, self.options.blocks);
+		*cache = Some((chain_info.best_block_hash, histogram.clone()));
+		histogram
+	}
+
+	/// Returns suggested gas price, never lower than `floor`.
+	/// `None` if there were no transactions in sampled blocks.
+	pub fn gas_price<C: BlockChainClient + ?Sized>(&self, chain: &C, floor: U256) -> Option<U256> {
+		self.histogram(chain).map(|h| ::std::cmp::max(h.percentile(self.options.percentile), floor))
+	}
+
+	fn compute<C: BlockChainClient + ?Sized>(chain: &C, best_hash: H256, best_number: BlockNumber, blocks: usize) -> Option<GasPriceHistogram> {
+		let mut hash = best_hash;
+		let mut corpus = Vec::new();
+		for _ in 0..blocks {
+			let block_bytes = match chain.block(BlockID::Hash(hash)) {
+				Some(bytes) => bytes,
+				None => break,
+			};
+			let block = BlockView::new(&block_bytes);
+			let header = block.header_view();
+			if header.number() == 0 {
+				break;
+			}
+			corpus.extend(block.transaction_views().iter().map(|t| t.gas_price()));
+			hash = header.parent_hash();
+		}
+
+		if corpus.is_empty() {
+			return None;
+		}
+
+		corpus.sort();
+		let n = corpus.len();
+		Some(GasPriceHistogram {
+			block_number: best_number,
+			sample_size: n,
+			percentiles: (0..101).map(|p| corpus[p * (n - 1) / 100]).collect(),
+		})
+	}
+}
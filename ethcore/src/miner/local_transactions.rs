@@ -0,0 +1,243 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! History of transactions originating from this node.
+
+use std::collections::{HashMap, VecDeque};
+use util::{H256, U256};
+use header::BlockNumber;
+use transaction::SignedTransaction;
+use error::TransactionError;
+
+/// Status of a local transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocalTransactionStatus {
+	/// The transaction is in the queue and can be included in the next block.
+	Pending,
+	/// The transaction is in the queue, but waits for transactions with lower nonces.
+	Future,
+	/// The transaction has been included in a block.
+	Mined {
+		/// Number of the block.
+		block_number: BlockNumber,
+		/// Hash of the block.
+		block_hash: H256,
+	},
+	/// The transaction has been replaced by another one with the same nonce and higher gas price.
+	Replaced {
+		/// Hash of the replacing transaction.
+		by: H256,
+		/// Gas price of the replacing transaction.
+		gas_price: U256,
+	},
+	/// The transaction has not been accepted to the queue.
+	Rejected(TransactionError),
+	/// The transaction has been removed from the queue because of its limits.
+	Dropped,
+	/// The transaction is no longer valid, e.g. because it was mined in a block that got retracted.
+	Invalid,
+}
+
+impl LocalTransactionStatus {
+	/// Check if the transaction is still in the queue.
+	pub fn is_queued(&self) -> bool {
+		match *self {
+			LocalTransactionStatus::Pending | LocalTransactionStatus::Future => true,
+			_ => false,
+		}
+	}
+}
+
+/// Local transaction with its current status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalTransaction {
+	/// The transaction.
+	pub transaction: SignedTransaction,
+	/// Current status.
+	pub status: LocalTransactionStatus,
+}
+
+/// Keeps track of local transactions.
+/// Transactions that are still in the queue are always kept, out of the others only `max_old` most recent ones.
+pub struct LocalTransactionsList {
+	max_old: usize,
+	order: VecDeque<H256>,
+	transactions: HashMap<H256, LocalTransaction>,
+}
+
+impl Default for LocalTransactionsList {
+	fn default() -> Self {
+		Self::new(32)
+	}
+}
+
+impl LocalTransactionsList {
+	/// Create a new list keeping at most `max_old` transactions that are no longer in the queue.
+	pub fn new(max_old: usize) -> Self {
+		LocalTransactionsList {
+			max_old: max_old,
+			order: VecDeque::new(),
+			transactions: HashMap::new(),
+		}
+	}
+
+	/// Set the status of a transaction, starting to track it if necessary.
+	pub fn insert(&mut self, transaction: SignedTransaction, status: LocalTransactionStatus) {
+		let hash = transaction.hash();
+		if !self.transactions.contains_key(&hash) {
+			self.order.push_back(hash.clone());
+		}
+		self.transactions.insert(hash, LocalTransaction {
+			transaction: transaction,
+			status: status,
+		});
+		self.clear_old();
+	}
+
+	/// Mark a queued transaction as replaced by another one.
+	pub fn mark_replaced(&mut self, hash: &H256, by: H256, gas_price: U256) {
+		self.update_queued(hash, LocalTransactionStatus::Replaced { by: by, gas_price: gas_price });
+	}
+
+	/// Mark a queued transaction as dropped because of the queue limits.
+	pub fn mark_dropped(&mut self, hash: &H256) {
+		self.update_queued(hash, LocalTransactionStatus::Dropped);
+	}
+
+	/// Mark a queued transaction as invalid.
+	pub fn mark_invalid(&mut self, hash: &H256) {
+		self.update_queued(hash, LocalTransactionStatus::Invalid);
+	}
+
+	/// Mark all queued transactions as dropped.
+	pub fn mark_all_dropped(&mut self) {
+		let queued: Vec<H256> = self.transactions.iter()
+			.filter(|&(_, tx)| tx.status.is_queued())
+			.map(|(hash, _)| hash.clone())
+			.collect();
+		for hash in &queued {
+			self.mark_dropped(hash);
+		}
+	}
+
+	/// Mark a transaction as mined, regardless of its current status.
+	pub fn mark_mined(&mut self, hash: &H256, block_number: BlockNumber, block_hash: H256) {
+		if let Some(tx) = self.transactions.get_mut(hash) {
+			tx.status = LocalTransactionStatus::Mined { block_number: block_number, block_hash: block_hash };
+		}
+		self.clear_old();
+	}
+
+	/// Get the transaction with its status.
+	pub fn get(&self, hash: &H256) -> Option<&LocalTransaction> {
+		self.transactions.get(hash)
+	}
+
+	/// All tracked transactions, oldest first.
+	pub fn all(&self) -> Vec<LocalTransaction> {
+		self.order.iter()
+			.map(|hash| self.transactions.get(hash).expect("`order` and `transactions` are always in sync; qed").clone())
+			.collect()
+	}
+
+	fn update_queued(&mut self, hash: &H256, status: LocalTransactionStatus) {
+		let updated = match self.transactions.get_mut(hash) {
+			Some(tx) => if tx.status.is_queued() {
+				tx.status = status;
+				true
+			} else {
+				false
+			},
+			None => false,
+		};
+		if updated {
+			self.clear_old();
+		}
+	}
+
+	fn clear_old(&mut self) {
+		let old = self.transactions.values().filter(|tx| !tx.status.is_queued()).count();
+		if old <= self.max_old {
+			return;
+		}
+		let mut to_remove = old - self.max_old;
+		let transactions = &mut self.transactions;
+		self.order.retain(|hash| {
+			if to_remove == 0 || transactions[hash].status.is_queued() {
+				return true;
+			}
+			transactions.remove(hash);
+			to_remove -= 1;
+			false
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::U256;
+	use ethkey::{Random, Generator};
+	use transaction::{Transaction, Action, SignedTransaction};
+	use error::TransactionError;
+	use super::{LocalTransactionsList, LocalTransactionStatus};
+
+	fn new_tx(nonce: u64) -> SignedTransaction {
+		let keypair = Random.generate().unwrap();
+		Transaction {
+			action: Action::Create,
+			value: U256::zero(),
+			data: Vec::new(),
+			gas: U256::from(100_000),
+			gas_price: U256::from(1),
+			nonce: U256::from(nonce),
+		}.sign(keypair.secret())
+	}
+
+	#[test]
+	fn should_track_status_changes() {
+		let mut list = LocalTransactionsList::default();
+		let (tx1, tx2, tx3) = (new_tx(0), new_tx(1), new_tx(2));
+		list.insert(tx1.clone(), LocalTransactionStatus::Pending);
+		list.insert(tx2.clone(), LocalTransactionStatus::Future);
+		list.insert(tx3.clone(), LocalTransactionStatus::Rejected(TransactionError::Old));
+
+		list.mark_replaced(&tx2.hash(), tx3.hash(), U256::from(2));
+		list.mark_mined(&tx1.hash(), 10, tx3.hash());
+		// only queued transactions can be dropped
+		list.mark_dropped(&tx1.hash());
+
+		let statuses: Vec<_> = list.all().into_iter().map(|tx| tx.status).collect();
+		assert_eq!(statuses, vec![
+			LocalTransactionStatus::Mined { block_number: 10, block_hash: tx3.hash() },
+			LocalTransactionStatus::Replaced { by: tx3.hash(), gas_price: U256::from(2) },
+			LocalTransactionStatus::Rejected(TransactionError::Old),
+		]);
+	}
+
+	#[test]
+	fn should_keep_bounded_history() {
+		let mut list = LocalTransactionsList::new(2);
+		let txs: Vec<_> = (0..5).map(new_tx).collect();
+		list.insert(txs[0].clone(), LocalTransactionStatus::Pending);
+		for tx in &txs[1..] {
+			list.insert(tx.clone(), LocalTransactionStatus::Future);
+			list.mark_dropped(&tx.hash());
+		}
+
+		let hashes: Vec<_> = list.all().into_iter().map(|tx| tx.transaction.hash()).collect();
+		assert_eq!(hashes, vec![txs[0].hash(), txs[3].hash(), txs[4].hash()]);
+	}
+}
@@ -16,24 +16,27 @@
 
 use rayon::prelude::*;
 use std::time::{Instant, Duration};
+use std::path::PathBuf;
 
 use util::*;
 use util::using_queue::{UsingQueue, GetAction};
 use account_provider::AccountProvider;
 use views::{BlockView, HeaderView};
+use header::BlockNumber;
 use state::State;
 use client::{MiningBlockChainClient, Executive, Executed, EnvInfo, TransactOptions, BlockID, CallAnalytics};
 use executive::contract_address;
-use block::{ClosedBlock, IsBlock, Block};
+use block::{ClosedBlock, IsBlock, Block, SealedBlock};
 use error::*;
-use transaction::{Action, SignedTransaction};
+use transaction::{Action, SignedTransaction, PendingTransaction};
 use receipt::{Receipt, RichReceipt};
 use spec::Spec;
-use engines::Engine;
-use miner::{MinerService, MinerStatus, TransactionQueue, AccountDetails, TransactionOrigin};
-use miner::work_notify::WorkPoster;
+use engines::{Engine, SealMode};
+use miner::{MinerService, MinerStatus, TransactionQueue, TransactionQueueStatus, AccountDetails, TransactionOrigin, LocalTransaction, TransactionFilter};
+use miner::{GasPriceOracle, GasPriceOracleOptions, GasPriceHistogram};
+use miner::work_notify::{WorkPoster, NotifyWork};
 use client::TransactionImportResult;
-use miner::price_info::PriceInfo;
+use miner::price_info::{PriceSource, PriceFeed};
 
 /// Different possible definitions for pending transaction set.
 #[derive(Debug, PartialEq)]
@@ -64,12 +67,24 @@ pub struct MinerOptions {
 	pub tx_gas_limit: U256,
 	/// Maximum size of the transaction queue.
 	pub tx_queue_size: usize,
+	/// Maximum number of transactions from a single sender in the queue.
+	pub tx_queue_sender_limit: usize,
+	/// Maximum total gas of transactions from a single sender in the queue.
+	pub tx_queue_sender_gas_limit: U256,
+	/// Maximum memory (in bytes) used by transactions in the queue.
+	pub tx_queue_memory_limit: Option<usize>,
 	/// Whether we should fallback to providing all the queue's transactions or just pending.
 	pub pending_set: PendingSet,
 	/// How many historical work packages can we store before running out?
 	pub work_queue_size: usize,
 	/// Can we submit two different solutions for the same block and expect both to result in an import?
 	pub enable_resubmission: bool,
+	/// JSON file with the transaction filter policy, (re)loaded by `reload_transaction_filter`.
+	pub transaction_filter: Option<PathBuf>,
+	/// How gas prices for new transactions are suggested.
+	pub gas_price_oracle: GasPriceOracleOptions,
+	/// When internally sealing engines seal blocks. Overrides the mode from the chain spec.
+	pub seal_mode: Option<SealMode>,
 }
 
 impl Default for MinerOptions {
@@ -81,10 +96,16 @@ impl Default for MinerOptions {
 			reseal_on_own_tx: true,
 			tx_gas_limit: !U256::zero(),
 			tx_queue_size: 1024,
+			tx_queue_sender_limit: usize::max_value(),
+			tx_queue_sender_gas_limit: !U256::zero(),
+			tx_queue_memory_limit: None,
 			pending_set: PendingSet::AlwaysQueue,
 			reseal_min_period: Duration::from_secs(2),
 			work_queue_size: 20,
 			enable_resubmission: true,
+			transaction_filter: None,
+			gas_price_oracle: GasPriceOracleOptions::default(),
+			seal_mode: None,
 		}
 	}
 }
@@ -96,12 +117,15 @@ pub struct GasPriceCalibratorOptions {
 	pub usd_per_tx: f32,
 	/// How frequently we should recalibrate.
 	pub recalibration_period: Duration,
+	/// Sources of the Ether price. The median of their prices is used.
+	pub price_sources: Vec<PriceSource>,
 }
 
 /// The gas price validator variant for a `GasPricer`.
 #[derive(Debug, PartialEq)]
 pub struct GasPriceCalibrator {
 	options: GasPriceCalibratorOptions,
+	price_feed: PriceFeed,
 	next_calibration: Instant,
 }
 
@@ -111,19 +135,20 @@ impl GasPriceCalibrator {
 		if Instant::now() >= self.next_calibration {
 			let usd_per_tx = self.options.usd_per_tx;
 			trace!(target: "miner", "Getting price info");
-			if let Ok(_) = PriceInfo::get(move |price: PriceInfo| {
-				trace!(target: "miner", "Price info arrived: {:?}", price);
-				let usd_per_eth = price.ethusd;
+			self.price_feed.get(move |usd_per_eth: f32| {
+				trace!(target: "miner", "Price info arrived: {:?}", usd_per_eth);
 				let wei_per_usd: f32 = 1.0e18 / usd_per_eth;
 				let gas_per_tx: f32 = 21000.0;
 				let wei_per_gas: f32 = wei_per_usd * usd_per_tx / gas_per_tx;
-				info!(target: "miner", "Updated conversion rate to Ξ1 = {} ({} wei/gas)", Colour::White.bold().paint(format!("US${}", usd_per_eth)), Colour::Yellow.bold().paint(format!("{}", wei_per_gas)));
-				set_price(U256::from_dec_str(&format!("{:.0}", wei_per_gas)).unwrap());
-			}) {
-				self.next_calibration = Instant::now() + self.options.recalibration_period;
-			} else {
-				warn!(target: "miner", "Unable to update Ether price.");
-			}
+				match U256::from_dec_str(&format!("{:.0}", wei_per_gas)) {
+					Ok(price) => {
+						info!(target: "miner", "Updated conversion rate to Ξ1 = {} ({} wei/gas)", Colour::White.bold().paint(format!("US${}", usd_per_eth)), Colour::Yellow.bold().paint(format!("{}", wei_per_gas)));
+						set_price(price);
+					},
+					Err(_) => warn!(target: "miner", "Unable to convert Ether price US${} to gas price.", usd_per_eth),
+				}
+			});
+			self.next_calibration = Instant::now() + self.options.recalibration_period;
 		}
 	}
 }
@@ -141,6 +166,7 @@ impl GasPricer {
 	/// Create a new Calibrated `GasPricer`.
 	pub fn new_calibrated(options: GasPriceCalibratorOptions) -> GasPricer {
 		GasPricer::Calibrated(GasPriceCalibrator {
+			price_feed: PriceFeed::new(options.price_sources.clone()),
 			options: options,
 			next_calibration: Instant::now(),
 		})
@@ -164,6 +190,15 @@ struct SealingWork {
 	enabled: bool,
 }
 
+/// Adjustments of the time of sealed blocks.
+#[derive(Default)]
+struct SealingTime {
+	/// Seconds added to the current time.
+	offset: u64,
+	/// Timestamp of the next sealed block.
+	next_timestamp: Option<u64>,
+}
+
 /// Keeps track of transactions using priority queue and holds currently mined block.
 pub struct Miner {
 	// NOTE [ToDr]  When locking always lock in this order!
@@ -171,6 +206,7 @@ pub struct Miner {
 	sealing_work: Mutex<SealingWork>,
 	next_allowed_reseal: Mutex<Instant>,
 	sealing_block_last_request: Mutex<u64>,
+	sealing_time: Mutex<SealingTime>,
 	// for sealing...
 	options: MinerOptions,
 
@@ -180,8 +216,10 @@ pub struct Miner {
 	engine: Arc<Engine>,
 
 	accounts: Option<Arc<AccountProvider>>,
-	work_poster: Option<WorkPoster>,
+	notifiers: RwLock<Vec<Box<NotifyWork>>>,
 	gas_pricer: Mutex<GasPricer>,
+	gas_price_oracle: GasPriceOracle,
+	stored_local_transactions: Mutex<HashSet<H256>>,
 }
 
 impl Miner {
@@ -192,25 +230,38 @@ impl Miner {
 			options: Default::default(),
 			next_allowed_reseal: Mutex::new(Instant::now()),
 			sealing_block_last_request: Mutex::new(0),
+			sealing_time: Mutex::new(SealingTime::default()),
 			sealing_work: Mutex::new(SealingWork{queue: UsingQueue::new(20), enabled: false}),
 			gas_range_target: RwLock::new((U256::zero(), U256::zero())),
 			author: RwLock::new(Address::default()),
 			extra_data: RwLock::new(Vec::new()),
 			accounts: None,
 			engine: spec.engine.clone(),
-			work_poster: None,
+			notifiers: RwLock::new(Vec::new()),
 			gas_pricer: Mutex::new(GasPricer::new_fixed(20_000_000_000u64.into())),
+			gas_price_oracle: GasPriceOracle::new(Default::default()),
+			stored_local_transactions: Mutex::new(HashSet::new()),
 		}
 	}
 
 	/// Creates new instance of miner
 	pub fn new(options: MinerOptions, gas_pricer: GasPricer, spec: &Spec, accounts: Option<Arc<AccountProvider>>) -> Arc<Miner> {
-		let work_poster = if !options.new_work_notify.is_empty() { Some(WorkPoster::new(&options.new_work_notify)) } else { None };
-		let txq = Arc::new(Mutex::new(TransactionQueue::with_limits(options.tx_queue_size, options.tx_gas_limit)));
+		let mut notifiers: Vec<Box<NotifyWork>> = Vec::new();
+		if !options.new_work_notify.is_empty() {
+			notifiers.push(Box::new(WorkPoster::new(&options.new_work_notify)));
+		}
+		let mut txq = TransactionQueue::with_limits(options.tx_queue_size, options.tx_gas_limit);
+		txq.set_sender_limits(options.tx_queue_sender_limit, options.tx_queue_sender_gas_limit);
+		if let Some(limit) = options.tx_queue_memory_limit {
+			txq.set_memory_limit(limit);
+		}
+		let txq = Arc::new(Mutex::new(txq));
+		let gas_price_oracle = GasPriceOracle::new(options.gas_price_oracle.clone());
 		Arc::new(Miner {
 			transaction_queue: txq,
 			next_allowed_reseal: Mutex::new(Instant::now()),
 			sealing_block_last_request: Mutex::new(0),
+			sealing_time: Mutex::new(SealingTime::default()),
 			sealing_work: Mutex::new(SealingWork{queue: UsingQueue::new(options.work_queue_size), enabled: options.force_sealing || !options.new_work_notify.is_empty()}),
 			gas_range_target: RwLock::new((U256::zero(), U256::zero())),
 			author: RwLock::new(Address::default()),
@@ -218,13 +269,22 @@ impl Miner {
 			options: options,
 			accounts: accounts,
 			engine: spec.engine.clone(),
-			work_poster: work_poster,
+			notifiers: RwLock::new(notifiers),
 			gas_pricer: Mutex::new(gas_pricer),
+			gas_price_oracle: gas_price_oracle,
+			stored_local_transactions: Mutex::new(HashSet::new()),
 		})
 	}
 
+	/// Adds a listener notified whenever there is new sealing work.
+	/// Sealing work is prepared continuously while there are listeners.
+	pub fn add_work_listener(&self, notifier: Box<NotifyWork>) {
+		self.notifiers.write().push(notifier);
+		self.sealing_work.lock().enabled = true;
+	}
+
 	fn forced_sealing(&self) -> bool {
-		self.options.force_sealing || !self.options.new_work_notify.is_empty()
+		self.options.force_sealing || !self.notifiers.read().is_empty()
 	}
 
 	/// Get `Some` `clone()` of the current pending block's state or `None` if we're not sealing.
@@ -237,12 +297,27 @@ impl Miner {
 		self.sealing_work.lock().queue.peek_last_ref().map(|b| b.base().clone())
 	}
 
-	/// Prepares new block for sealing including top transactions from queue.
+	/// Seal mode of internally sealing engines.
+	pub fn seal_mode(&self) -> SealMode {
+		self.options.seal_mode.unwrap_or_else(|| self.engine.seal_mode())
+	}
+
+	/// Timestamp of the next block if time of sealed blocks is adjusted.
+	fn adjusted_timestamp(&self, parent_timestamp: u64) -> Option<u64> {
+		let time = self.sealing_time.lock();
+		let timestamp = match time.next_timestamp {
+			Some(timestamp) => timestamp,
+			None if time.offset > 0 => (::time::get_time().sec as u64).saturating_add(time.offset),
+			None => return None,
+		};
+		Some(cmp::max(timestamp, parent_timestamp.saturating_add(1)))
+	}
+
+	/// Prepares new block including top transactions from queue.
+	/// Returns the block and hash of the last work package.
 	#[cfg_attr(feature="dev", allow(match_same_arms))]
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
-	fn prepare_sealing(&self, chain: &MiningBlockChainClient) {
-		trace!(target: "miner", "prepare_sealing: entering");
-
+	fn prepare_block(&self, chain: &MiningBlockChainClient) -> (ClosedBlock, Option<H256>) {
 		{
 			trace!(target: "miner", "recalibrating...");
 			let txq = self.transaction_queue.clone();
@@ -253,11 +328,15 @@ impl Miner {
 			trace!(target: "miner", "done recalibration.");
 		}
 
-		let (transactions, mut open_block, original_work_hash) = {
-			let transactions = {self.transaction_queue.lock().top_transactions()};
+		let (transactions, filtered, mut open_block, original_work_hash) = {
+			let (transactions, filtered): (Vec<_>, Vec<_>) = {
+				let queue = self.transaction_queue.lock();
+				queue.top_transactions().into_iter().partition(|tx| queue.check_filter(tx).is_ok())
+			};
 			let mut sealing_work = self.sealing_work.lock();
 			let last_work_hash = sealing_work.queue.peek_last_ref().map(|pb| pb.block().fields().header.hash());
-			let best_hash = chain.best_block_header().sha3();
+			let best_header = chain.best_block_header();
+			let best_hash = best_header.sha3();
 /*
 			// check to see if last ClosedBlock in would_seals is actually same parent block.
 			// if so
@@ -266,7 +345,7 @@ impl Miner {
 			//   otherwise, leave everything alone.
 			// otherwise, author a fresh block.
 */
-			let open_block = match sealing_work.queue.pop_if(|b| b.block().fields().header.parent_hash() == &best_hash) {
+			let mut open_block = match sealing_work.queue.pop_if(|b| b.block().fields().header.parent_hash() == &best_hash) {
 				Some(old_block) => {
 					trace!(target: "miner", "Already have previous work; updating and returning");
 					// add transactions to old_block
@@ -282,10 +361,17 @@ impl Miner {
 					)
 				}
 			};
-			(transactions, open_block, last_work_hash)
+			if let Some(timestamp) = self.adjusted_timestamp(HeaderView::new(&best_header).timestamp()) {
+				open_block.set_timestamp(timestamp);
+			}
+			(transactions, filtered, open_block, last_work_hash)
 		};
 
-		let mut invalid_transactions = HashSet::new();
+		// Transactions not permitted by the (possibly reloaded) filter are removed from the queue.
+		let mut invalid_transactions: HashSet<H256> = filtered.into_iter().map(|tx| tx.hash()).collect();
+		if !invalid_transactions.is_empty() {
+			debug!(target: "miner", "Skipping {} transactions not permitted by transaction filter", invalid_transactions.len());
+		}
 		let block_number = open_block.block().fields().header.number();
 		// TODO: push new uncles, too.
 		for tx in transactions {
@@ -329,8 +415,28 @@ impl Miner {
 				queue.remove_invalid(&hash, &fetch_account);
 			}
 		}
+		(block, original_work_hash)
+	}
+
+	/// Seals the block internally. Fails if the engine is unable to seal it.
+	fn seal_block_internally(&self, block: ClosedBlock) -> Result<SealedBlock, Error> {
+		let seal = try!(self.engine.generate_seal(block.block(), match self.accounts {
+			Some(ref x) => Some(&**x),
+			None => None,
+		}).ok_or(Error::SealingUnavailable));
+		let sealed = try!(block.lock().try_seal(&*self.engine, seal).map_err(|_| Error::SealingUnavailable));
+		// time adjustment applies to a single block only
+		self.sealing_time.lock().next_timestamp = None;
+		Ok(sealed)
+	}
+
+	/// Prepares new block for sealing including top transactions from queue.
+	fn prepare_sealing(&self, chain: &MiningBlockChainClient) {
+		trace!(target: "miner", "prepare_sealing: entering");
 
-		if !block.transactions().is_empty() {
+		let (block, original_work_hash) = self.prepare_block(chain);
+
+		if !block.transactions().is_empty() && self.seal_mode() == SealMode::Instant {
 			trace!(target: "miner", "prepare_sealing: block has transaction - attempting internal seal.");
 			// block with transactions - see if we can seal immediately.
 			let s = self.engine.generate_seal(block.block(), match self.accounts {
@@ -340,6 +446,7 @@ impl Miner {
 			if let Some(seal) = s {
 				trace!(target: "miner", "prepare_sealing: managed internal seal. importing...");
 				if let Ok(sealed) = block.lock().try_seal(&*self.engine, seal) {
+					self.sealing_time.lock().next_timestamp = None;
 					if let Ok(_) = chain.import_block(sealed.rlp_bytes()) {
 						trace!(target: "miner", "prepare_sealing: sealed internally and imported. leaving.");
 					} else {
@@ -366,7 +473,7 @@ impl Miner {
 				let is_new = original_work_hash.map_or(true, |h| block.block().fields().header.hash() != h);
 				sealing_work.queue.push(block);
 				// If push notifications are enabled we assume all work items are used.
-				if self.work_poster.is_some() && is_new {
+				if is_new && !self.notifiers.read().is_empty() {
 					sealing_work.queue.use_last_ref();
 				}
 				(Some((pow_hash, difficulty, number)), is_new)
@@ -377,14 +484,20 @@ impl Miner {
 			(work, is_new)
 		};
 		if is_new {
-			work.map(|(pow_hash, difficulty, number)| self.work_poster.as_ref().map(|p| p.notify(pow_hash, difficulty, number)));
+			if let Some((pow_hash, difficulty, number)) = work {
+				for notifier in self.notifiers.read().iter() {
+					notifier.notify(pow_hash, difficulty, number);
+				}
+			}
 		}
 	}
 
-	fn update_gas_limit(&self, chain: &MiningBlockChainClient) {
-		let gas_limit = HeaderView::new(&chain.best_block_header()).gas_limit();
+	fn update_chain_info(&self, chain: &MiningBlockChainClient) {
+		let header = chain.best_block_header();
+		let header = HeaderView::new(&header);
 		let mut queue = self.transaction_queue.lock();
-		queue.set_gas_limit(gas_limit);
+		queue.set_gas_limit(header.gas_limit());
+		queue.set_best_block(header.number(), header.timestamp());
 	}
 
 	/// Returns true if we had to prepare new pending block
@@ -419,7 +532,7 @@ impl Miner {
 		prepare_new
 	}
 
-	fn add_transactions_to_queue(&self, chain: &MiningBlockChainClient, transactions: Vec<SignedTransaction>, origin: TransactionOrigin, transaction_queue: &mut TransactionQueue) ->
+	fn add_transactions_to_queue(&self, chain: &MiningBlockChainClient, transactions: Vec<PendingTransaction>, origin: TransactionOrigin, transaction_queue: &mut TransactionQueue) ->
 		Vec<Result<TransactionImportResult, Error>> {
 
 		let fetch_account = |a: &Address| AccountDetails {
@@ -428,12 +541,65 @@ impl Miner {
 		};
 
 		transactions.into_iter()
-			.map(|tx| transaction_queue.add(tx, &fetch_account, origin))
+			.map(|tx| transaction_queue.add_with_condition(tx.transaction, &fetch_account, origin, tx.condition))
 			.collect()
 	}
 
 	/// Are we allowed to do a non-mandatory reseal?
 	fn tx_reseal_allowed(&self) -> bool { Instant::now() > *self.next_allowed_reseal.lock() }
+
+	/// Re-import local transactions persisted by the client, e.g. after a restart.
+	/// Transactions that are no longer valid in the current state (already mined, nonce too low,
+	/// insufficient balance) are dropped from the store.
+	pub fn restore_local_transactions(&self, chain: &MiningBlockChainClient) {
+		let transactions = chain.stored_local_transactions();
+		if transactions.is_empty() {
+			return;
+		}
+		let total = transactions.len();
+		*self.stored_local_transactions.lock() = transactions.iter().map(|tx| tx.hash()).collect();
+		self.update_chain_info(chain);
+
+		let results = {
+			let mut transaction_queue = self.transaction_queue.lock();
+			self.add_transactions_to_queue(chain, transactions, TransactionOrigin::Local, &mut transaction_queue)
+		};
+		let mut restored = 0;
+		for result in results {
+			match result {
+				Ok(_) => restored += 1,
+				Err(e) => debug!(target: "own_tx", "Dropping stored local transaction: {:?}", e),
+			}
+		}
+		info!(target: "own_tx", "Restored {} of {} local transactions", restored, total);
+		self.store_local_transactions(chain);
+	}
+
+	/// Persist local transactions if they have changed since they were last stored.
+	fn store_local_transactions(&self, chain: &MiningBlockChainClient) {
+		let local = self.transaction_queue.lock().local_transactions();
+		let hashes: HashSet<H256> = local.iter().map(|tx| tx.hash()).collect();
+		let mut stored = self.stored_local_transactions.lock();
+		if *stored != hashes {
+			trace!(target: "own_tx", "Storing {} local transactions", local.len());
+			chain.store_local_transactions(&local);
+			*stored = hashes;
+		}
+	}
+
+	/// Drops queued transactions and pending blocks after the chain has been reverted to an earlier block.
+	pub fn chain_reverted(&self, chain: &MiningBlockChainClient) {
+		self.transaction_queue.lock().clear();
+		self.sealing_work.lock().queue.reset();
+		self.sealing_time.lock().next_timestamp = None;
+		self.update_chain_info(chain);
+		self.store_local_transactions(chain);
+		// --------------------------------------------------------------------------
+		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
+		// | Make sure to release the locks before calling that method.             |
+		// --------------------------------------------------------------------------
+		self.update_sealing(chain);
+	}
 }
 
 const SEALING_TIMEOUT_IN_BLOCKS : u64 = 5;
@@ -442,6 +608,7 @@ impl MinerService for Miner {
 
 	fn clear_and_reset(&self, chain: &MiningBlockChainClient) {
 		self.transaction_queue.lock().clear();
+		self.store_local_transactions(chain);
 		// --------------------------------------------------------------------------
 		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 		// | Make sure to release the locks before calling that method.             |
@@ -561,6 +728,15 @@ impl MinerService for Miner {
 		*self.transaction_queue.lock().minimal_gas_price() * 110.into() / 100.into()
 	}
 
+	fn suggested_gas_price(&self, chain: &MiningBlockChainClient) -> U256 {
+		self.gas_price_oracle.gas_price(chain, self.minimal_gas_price())
+			.unwrap_or_else(|| self.sensible_gas_price())
+	}
+
+	fn gas_price_histogram(&self, chain: &MiningBlockChainClient) -> Option<GasPriceHistogram> {
+		self.gas_price_oracle.histogram(chain)
+	}
+
 	fn sensible_gas_limit(&self) -> U256 {
 		self.gas_range_target.read().0 / 5.into()
 	}
@@ -577,6 +753,15 @@ impl MinerService for Miner {
 		self.transaction_queue.lock().set_tx_gas_limit(limit)
 	}
 
+	fn reload_transaction_filter(&self) -> Result<bool, String> {
+		let filter = match self.options.transaction_filter {
+			Some(ref path) => try!(TransactionFilter::from_file(path)),
+			None => return Ok(false),
+		};
+		self.transaction_queue.lock().set_filter(Some(filter));
+		Ok(true)
+	}
+
 	/// Get the author that we will seal blocks as.
 	fn author(&self) -> Address {
 		*self.author.read()
@@ -606,7 +791,7 @@ impl MinerService for Miner {
 		let results = {
 			let mut transaction_queue = self.transaction_queue.lock();
 			self.add_transactions_to_queue(
-				chain, transactions, TransactionOrigin::External, &mut transaction_queue
+				chain, transactions.into_iter().map(Into::into).collect(), TransactionOrigin::External, &mut transaction_queue
 			)
 		};
 
@@ -617,13 +802,15 @@ impl MinerService for Miner {
 			// --------------------------------------------------------------------------
 			self.update_sealing(chain);
 		}
+		// Local transactions might have been pushed out of the queue.
+		self.store_local_transactions(chain);
 		results
 	}
 
 	fn import_own_transaction(
 		&self,
 		chain: &MiningBlockChainClient,
-		transaction: SignedTransaction,
+		transaction: PendingTransaction,
 	) -> Result<TransactionImportResult, Error> {
 
 		let hash = transaction.hash();
@@ -664,13 +851,26 @@ impl MinerService for Miner {
 				self.update_sealing(chain);
 			}
 		}
+		self.store_local_transactions(chain);
 
 		imported
 	}
 
 	fn all_transactions(&self) -> Vec<SignedTransaction> {
 		let queue = self.transaction_queue.lock();
-		queue.top_transactions()
+		queue.current_transactions()
+	}
+
+	fn future_transactions(&self) -> Vec<SignedTransaction> {
+		self.transaction_queue.lock().future_transactions()
+	}
+
+	fn queue_status(&self) -> TransactionQueueStatus {
+		self.transaction_queue.lock().status()
+	}
+
+	fn local_transactions(&self) -> Vec<LocalTransaction> {
+		self.transaction_queue.lock().local_transaction_history()
 	}
 
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
@@ -836,10 +1036,37 @@ impl MinerService for Miner {
 		})
 	}
 
+	fn seal_blocks(&self, chain: &MiningBlockChainClient, count: usize) -> Result<Vec<H256>, Error> {
+		(0..count).map(|_| {
+			let (block, _) = self.prepare_block(chain);
+			let sealed = try!(self.seal_block_internally(block));
+			let n = sealed.header().number();
+			let h = try!(chain.import_sealed_block(sealed));
+			trace!(target: "miner", "Sealed block imported. #{}: {}", n, h);
+			Ok(h)
+		}).collect()
+	}
+
+	fn set_next_block_timestamp(&self, chain: &MiningBlockChainClient, timestamp: u64) -> Result<(), Error> {
+		let header = chain.best_block_header();
+		let parent_timestamp = HeaderView::new(&header).timestamp();
+		if timestamp <= parent_timestamp {
+			return Err(BlockError::InvalidTimestamp(OutOfBounds { min: Some(parent_timestamp + 1), max: None, found: timestamp }).into());
+		}
+		self.sealing_time.lock().next_timestamp = Some(timestamp);
+		Ok(())
+	}
+
+	fn increase_time(&self, seconds: u64) -> u64 {
+		let mut time = self.sealing_time.lock();
+		time.offset = time.offset.saturating_add(seconds);
+		time.offset
+	}
+
 	fn chain_new_blocks(&self, chain: &MiningBlockChainClient, _imported: &[H256], _invalid: &[H256], enacted: &[H256], retracted: &[H256]) {
 		trace!(target: "miner", "chain_new_blocks");
 
-		fn fetch_transactions(chain: &MiningBlockChainClient, hash: &H256) -> Vec<SignedTransaction> {
+		fn fetch_transactions(chain: &MiningBlockChainClient, hash: &H256) -> (BlockNumber, Vec<SignedTransaction>) {
 			let block = chain
 				.block(BlockID::Hash(*hash))
 				// Client should send message after commit to db and inserting to chain.
@@ -850,7 +1077,7 @@ impl MinerService for Miner {
 			for tx in &txs {
 				let _sender = tx.sender();
 			}
-			txs
+			(block.header_view().number(), txs)
 		}
 
 		// 1. We ignore blocks that were `imported` (because it means that they are not in canon-chain, and transactions
@@ -858,18 +1085,23 @@ impl MinerService for Miner {
 		// 2. We ignore blocks that are `invalid` because it doesn't have any meaning in terms of the transactions that
 		//    are in those blocks
 
-		// First update gas limit in transaction queue
-		self.update_gas_limit(chain);
+		// First update gas limit and best block in transaction queue
+		self.update_chain_info(chain);
 
 		// Then import all transactions...
 		{
 			let out_of_chain = retracted
 				.par_iter()
 				.map(|h| fetch_transactions(chain, h));
-			out_of_chain.for_each(|txs| {
+			out_of_chain.for_each(|(_, txs)| {
 				let mut transaction_queue = self.transaction_queue.lock();
+				// Local transactions from retracted blocks are still local.
+				let (local, external): (Vec<_>, Vec<_>) = txs.into_iter().partition(|tx| transaction_queue.is_mined_local(&tx.hash()));
 				let _ = self.add_transactions_to_queue(
-					chain, txs, TransactionOrigin::External, &mut transaction_queue
+					chain, local.into_iter().map(Into::into).collect(), TransactionOrigin::Local, &mut transaction_queue
+				);
+				let _ = self.add_transactions_to_queue(
+					chain, external.into_iter().map(Into::into).collect(), TransactionOrigin::External, &mut transaction_queue
 				);
 			});
 		}
@@ -878,11 +1110,14 @@ impl MinerService for Miner {
 		{
 			let in_chain = enacted
 				.par_iter()
-				.map(|h: &H256| fetch_transactions(chain, h));
+				.map(|h: &H256| (h.clone(), fetch_transactions(chain, h)));
 
-			in_chain.for_each(|mut txs| {
+			in_chain.for_each(|(hash, (number, mut txs))| {
 				let mut transaction_queue = self.transaction_queue.lock();
 
+				for tx in &txs {
+					transaction_queue.mark_mined(&tx.hash(), number, hash.clone());
+				}
 				let to_remove = txs.drain(..)
 						.map(|tx| {
 							tx.sender().expect("Transaction is in block, so sender has to be defined.")
@@ -901,6 +1136,8 @@ impl MinerService for Miner {
 			// --------------------------------------------------------------------------
 			self.update_sealing(chain);
 		}
+		// Mined local transactions are no longer stored.
+		self.store_local_transactions(chain);
 	}
 }
 
@@ -912,11 +1149,13 @@ mod tests {
 	use super::*;
 	use util::*;
 	use ethkey::{Generator, Random};
-	use client::{TestBlockChainClient, EachBlockWith};
+	use client::{TestBlockChainClient, EachBlockWith, BlockChainClient, MiningBlockChainClient};
 	use client::{TransactionImportResult};
-	use types::transaction::{Transaction, Action};
+	use types::transaction::{Transaction, Action, SignedTransaction, PendingTransaction, Condition};
 	use block::*;
 	use spec::Spec;
+	use engines::SealMode;
+	use views::HeaderView;
 
 	#[test]
 	fn should_prepare_block_to_seal() {
@@ -960,9 +1199,15 @@ mod tests {
 				reseal_min_period: Duration::from_secs(5),
 				tx_gas_limit: !U256::zero(),
 				tx_queue_size: 1024,
+				tx_queue_sender_limit: usize::max_value(),
+				tx_queue_sender_gas_limit: !U256::zero(),
+				tx_queue_memory_limit: None,
 				pending_set: PendingSet::AlwaysSealing,
 				work_queue_size: 5,
 				enable_resubmission: true,
+				transaction_filter: None,
+				gas_price_oracle: GasPriceOracleOptions::default(),
+				seal_mode: None,
 			},
 			GasPricer::new_fixed(0u64.into()),
 			&Spec::new_test(),
@@ -988,7 +1233,7 @@ mod tests {
 		};
 
 		// when
-		let res = miner.import_own_transaction(&client, transaction);
+		let res = miner.import_own_transaction(&client, transaction.into());
 
 		// then
 		assert_eq!(res.unwrap(), TransactionImportResult::Current);
@@ -1029,4 +1274,115 @@ mod tests {
 		// This method will let us know if pending block was created (before calling that method)
 		assert_eq!(miner.enable_and_prepare_sealing(&client), true);
 	}
+
+	fn instant_miner(seal_mode: SealMode) -> Arc<Miner> {
+		let options = MinerOptions {
+			seal_mode: Some(seal_mode),
+			..Default::default()
+		};
+		Miner::new(options, GasPricer::new_fixed(0u64.into()), &Spec::new_instant(), None)
+	}
+
+	fn transaction() -> SignedTransaction {
+		let keypair = Random.generate().unwrap();
+		Transaction {
+			action: Action::Create,
+			value: U256::zero(),
+			data: "3331600055".from_hex().unwrap(),
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret())
+	}
+
+	#[test]
+	fn should_seal_transactions_instantly() {
+		let client = TestBlockChainClient::default();
+		let miner = instant_miner(SealMode::Instant);
+
+		miner.import_own_transaction(&client, transaction().into()).unwrap();
+		assert_eq!(client.chain_info().best_block_number, 1);
+	}
+
+	#[test]
+	fn should_seal_only_on_request_in_manual_mode() {
+		let client = TestBlockChainClient::default();
+		let miner = instant_miner(SealMode::Manual);
+		assert_eq!(miner.seal_mode(), SealMode::Manual);
+
+		miner.import_own_transaction(&client, transaction().into()).unwrap();
+		assert_eq!(client.chain_info().best_block_number, 0);
+		assert_eq!(miner.pending_transactions().len(), 1);
+
+		assert_eq!(miner.seal_blocks(&client, 3).unwrap().len(), 3);
+	}
+
+	#[test]
+	fn should_not_seal_blocks_without_internal_sealing() {
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		assert!(miner.seal_blocks(&client, 1).is_err());
+	}
+
+	#[test]
+	fn should_adjust_time_of_sealed_blocks() {
+		let client = TestBlockChainClient::default();
+		let miner = instant_miner(SealMode::Manual);
+		let parent_timestamp = HeaderView::new(&client.best_block_header()).timestamp();
+
+		assert!(miner.set_next_block_timestamp(&client, parent_timestamp).is_err());
+		miner.set_next_block_timestamp(&client, parent_timestamp + 1000).unwrap();
+		let timestamp = miner.map_sealing_work(&client, |b| b.block().fields().header.timestamp());
+		assert_eq!(timestamp, Some(parent_timestamp + 1000));
+
+		assert_eq!(miner.increase_time(100), 100);
+		assert_eq!(miner.increase_time(50), 150);
+		assert_eq!(miner.adjusted_timestamp(parent_timestamp), Some(parent_timestamp + 1000));
+		miner.seal_blocks(&client, 1).unwrap();
+		// timestamp set explicitly applies to a single block
+		let now = ::time::get_time().sec as u64;
+		assert!(miner.adjusted_timestamp(parent_timestamp).unwrap() >= now + 150);
+	}
+
+	#[test]
+	fn should_saturate_time_adjustments() {
+		let miner = instant_miner(SealMode::Manual);
+
+		miner.increase_time(100);
+		assert_eq!(miner.increase_time(u64::max_value()), u64::max_value());
+		assert_eq!(miner.adjusted_timestamp(0), Some(u64::max_value()));
+		assert_eq!(miner.adjusted_timestamp(u64::max_value()), Some(u64::max_value()));
+	}
+
+	#[test]
+	fn should_store_and_restore_local_transactions() {
+		// given
+		let client = TestBlockChainClient::default();
+		let keypair = Random.generate().unwrap();
+		let transaction = |nonce: u64| Transaction {
+			action: Action::Create,
+			value: U256::zero(),
+			data: "3331600055".from_hex().unwrap(),
+			gas: U256::from(100_000),
+			gas_price: U256::zero(),
+			nonce: nonce.into(),
+		}.sign(keypair.secret());
+		let conditional = PendingTransaction::new(transaction(1), Some(Condition::Number(10)));
+		let miner = miner();
+		miner.import_own_transaction(&client, transaction(0).into()).unwrap();
+		miner.import_own_transaction(&client, conditional.clone()).unwrap();
+		miner.import_external_transactions(&client, vec![transaction(2)]).pop().unwrap().unwrap();
+		assert_eq!(client.stored_local_transactions(), vec![transaction(0).into(), conditional.clone()]);
+
+		// when
+		client.nonces.write().insert(keypair.address(), U256::from(1));
+		let restarted = miner();
+		restarted.restore_local_transactions(&client);
+
+		// then
+		assert_eq!(restarted.queue_status().pending, 1);
+		// the condition does not hold yet, so the transaction is queued but not ready
+		assert_eq!(restarted.all_transactions(), vec![transaction(1)]);
+		assert_eq!(client.stored_local_transactions(), vec![conditional]);
+	}
 }
@@ -46,10 +46,24 @@ mod external;
 mod transaction_queue;
 mod work_notify;
 mod price_info;
-
-pub use self::transaction_queue::{TransactionQueue, AccountDetails, TransactionOrigin};
+mod local_transactions;
+mod transaction_filter;
+mod gas_price_oracle;
+mod stratum;
+mod cpu_miner;
+mod work_socket;
+
+pub use self::transaction_queue::{TransactionQueue, TransactionQueueStatus, AccountDetails, TransactionOrigin};
 pub use self::miner::{Miner, MinerOptions, PendingSet, GasPricer, GasPriceCalibratorOptions};
-pub use self::external::{ExternalMiner, ExternalMinerService};
+pub use self::external::{ExternalMiner, ExternalMinerService, WorkerStats};
+pub use self::local_transactions::{LocalTransaction, LocalTransactionStatus};
+pub use self::transaction_filter::TransactionFilter;
+pub use self::gas_price_oracle::{GasPriceOracle, GasPriceOracleOptions, GasPriceHistogram};
+pub use self::price_info::{PriceSource, PriceFeed, Error as PriceError};
+pub use self::work_notify::{NotifyWork, WorkSubscribers};
+pub use self::stratum::{Stratum, StratumJobDispatcher, StratumWorkNotifier, StratumOptions, Error as StratumError};
+pub use self::cpu_miner::{CpuMiner, CpuMinerOptions};
+pub use self::work_socket::{WorkSocket, WorkSocketOptions, Error as WorkSocketError};
 pub use client::TransactionImportResult;
 
 use std::collections::BTreeMap;
@@ -58,7 +72,7 @@ use client::{MiningBlockChainClient, Executed, CallAnalytics};
 use block::ClosedBlock;
 use receipt::{RichReceipt, Receipt};
 use error::{Error, CallError};
-use transaction::SignedTransaction;
+use transaction::{SignedTransaction, PendingTransaction};
 
 /// Miner client API
 pub trait MinerService : Send + Sync {
@@ -106,12 +120,16 @@ pub trait MinerService : Send + Sync {
 	/// Set maximum amount of gas allowed for any single transaction to mine.
 	fn set_tx_gas_limit(&self, limit: U256);
 
+	/// Reload transaction filter policy from the configured file.
+	/// Returns `false` if no filter is configured.
+	fn reload_transaction_filter(&self) -> Result<bool, String>;
+
 	/// Imports transactions to transaction queue.
 	fn import_external_transactions(&self, chain: &MiningBlockChainClient, transactions: Vec<SignedTransaction>) ->
 		Vec<Result<TransactionImportResult, Error>>;
 
 	/// Imports own (node owner) transaction to queue.
-	fn import_own_transaction(&self, chain: &MiningBlockChainClient, transaction: SignedTransaction) ->
+	fn import_own_transaction(&self, chain: &MiningBlockChainClient, transaction: PendingTransaction) ->
 		Result<TransactionImportResult, Error>;
 
 	/// Returns hashes of transactions currently in pending
@@ -130,6 +148,16 @@ pub trait MinerService : Send + Sync {
 	/// Will check the seal, but not actually insert the block into the chain.
 	fn submit_seal(&self, chain: &MiningBlockChainClient, pow_hash: H256, seal: Vec<Bytes>) -> Result<(), Error>;
 
+	/// Seals `count` new blocks internally, even if there are no transactions to include.
+	/// Returns hashes of the imported blocks.
+	fn seal_blocks(&self, chain: &MiningBlockChainClient, count: usize) -> Result<Vec<H256>, Error>;
+
+	/// Sets timestamp of the next sealed block. It has to be later than the timestamp of the best block.
+	fn set_next_block_timestamp(&self, chain: &MiningBlockChainClient, timestamp: u64) -> Result<(), Error>;
+
+	/// Moves time of sealed blocks forward by `seconds`. Returns the total number of seconds added.
+	fn increase_time(&self, seconds: u64) -> u64;
+
 	/// Get the sealing work package and if `Some`, apply some transform.
 	fn map_sealing_work<F, T>(&self, chain: &MiningBlockChainClient, f: F) -> Option<T>
 		where F: FnOnce(&ClosedBlock) -> T, Self: Sized;
@@ -137,9 +165,18 @@ pub trait MinerService : Send + Sync {
 	/// Query pending transactions for hash.
 	fn transaction(&self, hash: &H256) -> Option<SignedTransaction>;
 
-	/// Get a list of all transactions.
+	/// Get a list of all transactions, including ones waiting for their condition.
 	fn all_transactions(&self) -> Vec<SignedTransaction>;
 
+	/// Get a list of transactions waiting for transactions with lower nonces.
+	fn future_transactions(&self) -> Vec<SignedTransaction>;
+
+	/// Returns status and limits of the transaction queue.
+	fn queue_status(&self) -> TransactionQueueStatus;
+
+	/// History of transactions originating from this node, oldest first.
+	fn local_transactions(&self) -> Vec<LocalTransaction>;
+
 	/// Get a list of all pending transactions.
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
 
@@ -158,6 +195,12 @@ pub trait MinerService : Send + Sync {
 	/// Suggested gas price.
 	fn sensible_gas_price(&self) -> U256 { 20000000000u64.into() }
 
+	/// Suggested gas price based on transactions in recent blocks, never lower than the minimal gas price.
+	fn suggested_gas_price(&self, _chain: &MiningBlockChainClient) -> U256 { self.sensible_gas_price() }
+
+	/// Distribution of gas prices of transactions in recent blocks.
+	fn gas_price_histogram(&self, chain: &MiningBlockChainClient) -> Option<GasPriceHistogram>;
+
 	/// Suggested gas limit.
 	fn sensible_gas_limit(&self) -> U256 { 21000.into() }
 
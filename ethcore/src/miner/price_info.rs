@@ -14,83 +14,410 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+//! Sources of the Ether price in USD.
+
 use rustc_serialize::json::Json;
-use std::thread;
+use std::{fmt, io, thread};
 use std::io::Read;
+use std::fs::File;
+use std::path::PathBuf;
 use std::time::Duration;
 use std::str::FromStr;
-use std::sync::mpsc;
+use std::sync::{Arc, mpsc};
 use hyper::client::{Handler, Request, Response, Client};
-use hyper::{Next, Encoder, Decoder};
+use hyper::{self, Next, Encoder, Decoder, Url};
+use hyper::status::StatusCode;
 use hyper::net::HttpStream;
+use util::Mutex;
+
+const ETHERSCAN_URL: &'static str = "http://api.etherscan.io/api?module=stats&action=ethprice";
 
+/// Error fetching the price.
 #[derive(Debug)]
-pub struct PriceInfo {
-	pub ethusd: f32,
+pub enum Error {
+	/// Request to the HTTP endpoint failed.
+	Http(String),
+	/// HTTP endpoint responded with unexpected status.
+	UnexpectedStatus(StatusCode),
+	/// Reading the response or the file failed.
+	Io(io::Error),
+	/// Response is not valid JSON.
+	InvalidJson,
+	/// There is no value at given JSON path.
+	MissingPrice,
+	/// The value is not a positive number.
+	InvalidPrice(String),
+	/// No source responded and there is no previously fetched price.
+	NoPrice,
 }
 
-pub struct SetPriceHandler<F: Fn(PriceInfo) + Sync + Send + 'static> {
-	set_price: F,
-	channel: mpsc::Sender<()>,
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::Http(ref err) => write!(f, "HTTP request failed: {}", err),
+			Error::UnexpectedStatus(ref status) => write!(f, "Unexpected HTTP status: {}", status),
+			Error::Io(ref err) => write!(f, "I/O error: {}", err),
+			Error::InvalidJson => write!(f, "Invalid JSON"),
+			Error::MissingPrice => write!(f, "No price at given JSON path"),
+			Error::InvalidPrice(ref price) => write!(f, "Invalid price: {}", price),
+			Error::NoPrice => write!(f, "No price available"),
+		}
+	}
 }
 
-impl<F: Fn(PriceInfo) + Sync + Send + 'static> Drop for SetPriceHandler<F> {
-	fn drop(&mut self) {
-		let _ = self.channel.send(());
+impl From<io::Error> for Error {
+	fn from(err: io::Error) -> Self {
+		Error::Io(err)
+	}
+}
+
+/// Source of the Ether price in USD.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PriceSource {
+	/// Fixed price.
+	Fixed(f32),
+	/// File containing the price as a decimal number.
+	File(PathBuf),
+	/// HTTP endpoint returning JSON with the price at given path.
+	/// Path segments are object keys or array indices.
+	Http {
+		/// Endpoint URL.
+		url: String,
+		/// Path to the price in the response.
+		path: Vec<String>,
+	},
+}
+
+impl PriceSource {
+	/// Etherscan price feed.
+	pub fn etherscan() -> Self {
+		PriceSource::Http {
+			url: ETHERSCAN_URL.into(),
+			path: vec!["result".into(), "ethusd".into()],
+		}
+	}
+
+	/// Returns true if the source is a fixed price.
+	pub fn is_fixed(&self) -> bool {
+		match *self {
+			PriceSource::Fixed(_) => true,
+			_ => false,
+		}
+	}
+
+	/// Fetches current price. Blocks until the price is available or the request fails.
+	pub fn fetch(&self) -> Result<f32, Error> {
+		match *self {
+			PriceSource::Fixed(price) => validate(price),
+			PriceSource::File(ref path) => {
+				let mut content = String::new();
+				try!(try!(File::open(path)).read_to_string(&mut content));
+				let content = content.trim();
+				validate(try!(content.parse().map_err(|_| Error::InvalidPrice(content.into()))))
+			},
+			PriceSource::Http { ref url, ref path } => {
+				let body = try!(http_get(url));
+				let json = try!(Json::from_str(&String::from_utf8_lossy(&body)).map_err(|_| Error::InvalidJson));
+				find_price(&json, path)
+			},
+		}
+	}
+}
+
+/// Parses a price source. Accepted forms are:
+/// a decimal number (fixed price), `etherscan`, `file:PATH` and
+/// `http://URL#PATH` where `PATH` is a dot-separated path to the price in the JSON response.
+impl FromStr for PriceSource {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s == "etherscan" {
+			return Ok(PriceSource::etherscan());
+		}
+		if s.starts_with("file:") {
+			return Ok(PriceSource::File(PathBuf::from(&s[5..])));
+		}
+		if s.starts_with("https://") {
+			return Err(format!("Invalid price source {}: only plain HTTP endpoints are supported.", s));
+		}
+		if s.starts_with("http://") {
+			let mut parts = s.splitn(2, '#');
+			let url = parts.next().expect("splitn always returns at least one item; qed");
+			try!(url.parse::<Url>().map_err(|e| format!("Invalid price source URL {}: {}", url, e)));
+			let path = parts.next()
+				.map(|p| p.split('.').filter(|s| !s.is_empty()).map(Into::into).collect())
+				.unwrap_or_else(Vec::new);
+			return Ok(PriceSource::Http { url: url.into(), path: path });
+		}
+		s.parse::<f32>()
+			.map_err(|_| format!("Invalid price source: {}. Expected a number, etherscan, file:PATH or URL#PATH.", s))
+			.and_then(|price| validate(price).map_err(|e| e.to_string()))
+			.map(PriceSource::Fixed)
+	}
+}
+
+impl fmt::Display for PriceSource {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PriceSource::Fixed(price) => write!(f, "{}", price),
+			PriceSource::File(ref path) => write!(f, "file:{}", path.display()),
+			PriceSource::Http { ref url, ref path } if path.is_empty() => write!(f, "{}", url),
+			PriceSource::Http { ref url, ref path } => write!(f, "{}#{}", url, path.join(".")),
+		}
 	}
 }
 
-impl<F: Fn(PriceInfo) + Sync + Send + 'static> Handler<HttpStream> for SetPriceHandler<F> {
-	fn on_request(&mut self, _: &mut Request) -> Next { Next::read().timeout(Duration::from_secs(3)) }
-	fn on_request_writable(&mut self, _: &mut Encoder<HttpStream>) -> Next { Next::read().timeout(Duration::from_secs(3)) }
-	fn on_response(&mut self, _: Response) -> Next { Next::read().timeout(Duration::from_secs(3)) }
+/// Combines several price sources by taking the median of the prices fetched.
+/// The last good price is used when none of the sources respond.
+pub struct PriceFeed {
+	sources: Vec<PriceSource>,
+	last_price: Arc<Mutex<Option<f32>>>,
+}
 
-	fn on_response_readable(&mut self, r: &mut Decoder<HttpStream>) -> Next {
-		let mut body = String::new();
-		let _ = r.read_to_string(&mut body).ok()
-			.and_then(|_| Json::from_str(&body).ok())
-			.and_then(|json| json.find_path(&["result", "ethusd"])
-				.and_then(|obj| match *obj {
-					Json::String(ref s) => Some((self.set_price)(PriceInfo {
-						ethusd: FromStr::from_str(s).unwrap()
-					})),
-					_ => None,
-				}));
-		Next::end()
+impl fmt::Debug for PriceFeed {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "PriceFeed {{ sources: {:?} }}", self.sources)
 	}
+}
 
+impl PartialEq for PriceFeed {
+	fn eq(&self, other: &Self) -> bool {
+		self.sources == other.sources
+	}
 }
 
-impl PriceInfo {
-	pub fn get<F: Fn(PriceInfo) + Sync + Send + 'static>(set_price: F) -> Result<(), ()> {
-		// TODO: Handle each error type properly
-		let client = try!(Client::new().map_err(|_| ()));
+impl PriceFeed {
+	/// Creates new feed combining given sources.
+	pub fn new(sources: Vec<PriceSource>) -> Self {
+		PriceFeed {
+			sources: sources,
+			last_price: Arc::new(Mutex::new(None)),
+		}
+	}
+
+	/// Fetches the price from all sources. Blocks until all of them respond or fail.
+	pub fn fetch(&self) -> Result<f32, Error> {
+		Self::fetch_median(&self.sources, &self.last_price)
+	}
+
+	/// Fetches the price in the background and calls `set_price` with it.
+	pub fn get<F: Fn(f32) + Send + 'static>(&self, set_price: F) {
+		let sources = self.sources.clone();
+		let last_price = self.last_price.clone();
 		thread::spawn(move || {
-			let (tx, rx) = mpsc::channel();
-			let _ = client.request(FromStr::from_str("http://api.etherscan.io/api?module=stats&action=ethprice").unwrap(), SetPriceHandler {
-				set_price: set_price,
-				channel: tx,
-			}).ok().and_then(|_| rx.recv().ok());
-			client.close();
+			match Self::fetch_median(&sources, &last_price) {
+				Ok(price) => set_price(price),
+				Err(e) => warn!(target: "miner", "Unable to update Ether price: {}", e),
+			}
 		});
-		Ok(())
 	}
+
+	fn fetch_median(sources: &[PriceSource], last_price: &Mutex<Option<f32>>) -> Result<f32, Error> {
+		let mut prices: Vec<f32> = sources.iter()
+			.filter_map(|source| match source.fetch() {
+				Ok(price) => Some(price),
+				Err(e) => {
+					warn!(target: "miner", "Unable to fetch Ether price from {}: {}", source, e);
+					None
+				},
+			})
+			.collect();
+
+		if prices.is_empty() {
+			let last = *last_price.lock();
+			if last.is_some() {
+				debug!(target: "miner", "Using last known Ether price.");
+			}
+			return last.ok_or(Error::NoPrice);
+		}
+
+		let price = median(&mut prices);
+		*last_price.lock() = Some(price);
+		Ok(price)
+	}
+}
+
+fn median(prices: &mut [f32]) -> f32 {
+	// prices are validated, so they are never NaN.
+	prices.sort_by(|a, b| a.partial_cmp(b).expect("prices are never NaN; qed"));
+	let n = prices.len();
+	if n % 2 == 1 {
+		prices[n / 2]
+	} else {
+		(prices[n / 2 - 1] + prices[n / 2]) / 2.0
+	}
+}
+
+fn validate(price: f32) -> Result<f32, Error> {
+	if price.is_finite() && price > 0.0 {
+		Ok(price)
+	} else {
+		Err(Error::InvalidPrice(price.to_string()))
+	}
+}
+
+fn find_price(json: &Json, path: &[String]) -> Result<f32, Error> {
+	let value = try!(path.iter().fold(Some(json), |json, key| json.and_then(|json| match *json {
+		Json::Object(ref obj) => obj.get(key),
+		Json::Array(ref arr) => key.parse::<usize>().ok().and_then(|i| arr.get(i)),
+		_ => None,
+	})).ok_or(Error::MissingPrice));
+
+	match *value {
+		Json::String(ref s) => validate(try!(s.trim().parse().map_err(|_| Error::InvalidPrice(s.clone())))),
+		Json::F64(price) => validate(price as f32),
+		Json::I64(price) => validate(price as f32),
+		Json::U64(price) => validate(price as f32),
+		ref other => Err(Error::InvalidPrice(other.to_string())),
+	}
+}
+
+fn http_get(url: &str) -> Result<Vec<u8>, Error> {
+	let url = try!(url.parse::<Url>().map_err(|e| Error::Http(format!("{}", e))));
+	let client = try!(Client::new().map_err(|e| Error::Http(format!("{}", e))));
+	let (tx, rx) = mpsc::channel();
+	let result = client.request(url, FetchHandler::new(tx))
+		.map_err(|e| Error::Http(format!("{:?}", e)))
+		.and_then(|_| rx.recv().unwrap_or_else(|_| Err(Error::Http("Request aborted".into()))));
+	client.close();
+	result
+}
+
+struct FetchHandler {
+	body: Vec<u8>,
+	result: Option<Result<(), Error>>,
+	sender: mpsc::Sender<Result<Vec<u8>, Error>>,
+}
+
+impl FetchHandler {
+	fn new(sender: mpsc::Sender<Result<Vec<u8>, Error>>) -> Self {
+		FetchHandler {
+			body: Vec::new(),
+			result: None,
+			sender: sender,
+		}
+	}
+}
+
+impl Drop for FetchHandler {
+	fn drop(&mut self) {
+		let body = ::std::mem::replace(&mut self.body, Vec::new());
+		let result = self.result.take()
+			.unwrap_or_else(|| Err(Error::Http("Request not completed".into())))
+			.map(|_| body);
+		let _ = self.sender.send(result);
+	}
+}
+
+impl Handler<HttpStream> for FetchHandler {
+	fn on_request(&mut self, _: &mut Request) -> Next { read() }
+	fn on_request_writable(&mut self, _: &mut Encoder<HttpStream>) -> Next { read() }
+
+	fn on_response(&mut self, response: Response) -> Next {
+		if *response.status() != StatusCode::Ok {
+			self.result = Some(Err(Error::UnexpectedStatus(*response.status())));
+			return Next::end();
+		}
+		read()
+	}
+
+	fn on_response_readable(&mut self, decoder: &mut Decoder<HttpStream>) -> Next {
+		match decoder.read_to_end(&mut self.body) {
+			Ok(_) => {
+				self.result = Some(Ok(()));
+				Next::end()
+			},
+			Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => read(),
+			Err(e) => {
+				self.result = Some(Err(Error::Io(e)));
+				Next::end()
+			},
+		}
+	}
+
+	fn on_error(&mut self, err: hyper::Error) -> Next {
+		self.result = Some(Err(Error::Http(format!("{}", err))));
+		Next::remove()
+	}
+}
+
+fn read() -> Next {
+	Next::read().timeout(Duration::from_secs(3))
 }
 
-#[test]
-fn should_get_price_info() {
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::Write;
+	use std::path::PathBuf;
 	use std::sync::Arc;
 	use std::time::Duration;
+	use rustc_serialize::json::Json;
+	use devtools::RandomTempPath;
 	use util::log::init_log;
 	use util::{Condvar, Mutex};
+	use super::{PriceSource, PriceFeed, Error, find_price};
+
+	#[test]
+	#[cfg_attr(feature = "dev", allow(float_cmp))]
+	fn should_parse_price_sources() {
+		assert_eq!("12.5".parse(), Ok(PriceSource::Fixed(12.5)));
+		assert_eq!("etherscan".parse(), Ok(PriceSource::etherscan()));
+		assert_eq!("file:/tmp/price".parse(), Ok(PriceSource::File(PathBuf::from("/tmp/price"))));
+		assert_eq!("http://example.com/ticker#data.0.price".parse(), Ok(PriceSource::Http {
+			url: "http://example.com/ticker".into(),
+			path: vec!["data".into(), "0".into(), "price".into()],
+		}));
+		assert!("-1".parse::<PriceSource>().is_err());
+		assert!("cheap".parse::<PriceSource>().is_err());
+		assert!("https://example.com/ticker#price".parse::<PriceSource>().is_err());
+	}
+
+	#[test]
+	#[cfg_attr(feature = "dev", allow(float_cmp))]
+	fn should_find_price_in_json() {
+		let json = Json::from_str(r#"{"result":{"ethusd":"11.5"},"data":[{"price":12}]}"#).unwrap();
+		assert_eq!(find_price(&json, &["result".into(), "ethusd".into()]).unwrap(), 11.5);
+		assert_eq!(find_price(&json, &["data".into(), "0".into(), "price".into()]).unwrap(), 12.0);
+		match find_price(&json, &["result".into(), "btcusd".into()]) {
+			Err(Error::MissingPrice) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	#[cfg_attr(feature = "dev", allow(float_cmp))]
+	fn should_combine_sources_by_median_and_cache_last_price() {
+		let path = RandomTempPath::new();
+		File::create(path.as_path()).unwrap().write_all(b"13.0\n").unwrap();
+		let file = PriceSource::File(path.as_path().to_owned());
+
+		let feed = PriceFeed::new(vec![PriceSource::Fixed(10.0), file.clone(), PriceSource::Fixed(11.0)]);
+		assert_eq!(feed.fetch().unwrap(), 11.0);
+		let feed = PriceFeed::new(vec![PriceSource::Fixed(10.0), file.clone()]);
+		assert_eq!(feed.fetch().unwrap(), 11.5);
 
-	init_log();
-	let done = Arc::new((Mutex::new(PriceInfo { ethusd: 0f32 }), Condvar::new()));
-	let rdone = done.clone();
+		let feed = PriceFeed::new(vec![file]);
+		assert_eq!(feed.fetch().unwrap(), 13.0);
+		::std::fs::remove_file(path.as_path()).unwrap();
+		assert_eq!(feed.fetch().unwrap(), 13.0);
 
-	PriceInfo::get(move |price| { let mut p = rdone.0.lock(); *p = price; rdone.1.notify_one(); }).unwrap();
-	let mut p = done.0.lock();
-	let t = done.1.wait_for(&mut p, Duration::from_millis(10000));
-	assert!(!t.timed_out());
-	assert!(p.ethusd != 0f32);
+		match PriceFeed::new(vec![PriceSource::File(PathBuf::from("/nonexistent/price"))]).fetch() {
+			Err(Error::NoPrice) => {},
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_get_price_info() {
+		init_log();
+		let done = Arc::new((Mutex::new(0f32), Condvar::new()));
+		let rdone = done.clone();
+
+		PriceFeed::new(vec![PriceSource::etherscan()]).get(move |price| { let mut p = rdone.0.lock(); *p = price; rdone.1.notify_one(); });
+		let mut p = done.0.lock();
+		let t = done.1.wait_for(&mut p, Duration::from_millis(10000));
+		assert!(!t.timed_out());
+		assert!(*p != 0f32);
+	}
 }
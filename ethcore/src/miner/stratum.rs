@@ -0,0 +1,387 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Stratum job dispatcher and work notifier backed by the miner.
+
+use std::fmt;
+use std::cmp;
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::net::{SocketAddr, AddrParseError};
+use ethcore_stratum::{JobDispatcher, PushWorkHandler, Stratum as StratumService, Error as StratumServiceError, difficulty_json};
+use ethash::{SeedHashCompute, EthashManager};
+use ethereum::ethash::Ethash;
+use util::{H256, H64, U256, Mutex, FixedHash, clean_0x};
+use rlp;
+use block::IsBlock;
+use client::{Client, MiningBlockChainClient};
+use miner::{Miner, MinerService, ExternalMinerService};
+use miner::work_notify::NotifyWork;
+
+/// Number of recently dispatched jobs solutions are accepted for.
+const MAX_JOBS: usize = 16;
+
+/// Stratum server options.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StratumOptions {
+	/// Interface to listen on.
+	pub listen_addr: String,
+	/// Port to listen on.
+	pub port: u16,
+	/// Hash of the secret workers have to authorize with, if any.
+	pub secret: Option<H256>,
+	/// Share difficulty for workers. Only block solutions are accepted if not set.
+	pub share_difficulty: Option<U256>,
+}
+
+/// Error starting the stratum server.
+#[derive(Debug)]
+pub enum Error {
+	/// Listen address is invalid.
+	Address(AddrParseError),
+	/// Server failed to start.
+	Service(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::Address(ref err) => write!(f, "Invalid stratum listen address: {}", err),
+			Error::Service(ref err) => write!(f, "Unable to start stratum server: {}", err),
+		}
+	}
+}
+
+impl From<AddrParseError> for Error {
+	fn from(err: AddrParseError) -> Self {
+		Error::Address(err)
+	}
+}
+
+/// Serves stratum jobs from the sealing work prepared by the miner
+/// and verifies shares submitted by workers.
+pub struct StratumJobDispatcher {
+	seed_compute: Mutex<SeedHashCompute>,
+	ethash: EthashManager,
+	/// Recently dispatched jobs: pow hash, block difficulty and block number.
+	jobs: Mutex<VecDeque<(H256, U256, u64)>>,
+	client: Weak<MiningBlockChainClient>,
+	miner: Weak<Miner>,
+	external_miner: Arc<ExternalMinerService>,
+}
+
+impl StratumJobDispatcher {
+	/// Creates new dispatcher for given client and miner. Shares are recorded in `external_miner`.
+	pub fn new(miner: Weak<Miner>, client: Weak<MiningBlockChainClient>, external_miner: Arc<ExternalMinerService>) -> Self {
+		StratumJobDispatcher {
+			seed_compute: Mutex::new(SeedHashCompute::new()),
+			ethash: EthashManager::new(),
+			jobs: Mutex::new(VecDeque::new()),
+			client: client,
+			miner: miner,
+			external_miner: external_miner,
+		}
+	}
+
+	/// Serializes work package: pow hash, seed hash, boundary and block number.
+	pub fn payload(&self, pow_hash: H256, difficulty: U256, number: u64) -> String {
+		self.record_job(pow_hash, difficulty, number);
+		let target = Ethash::difficulty_to_boundary(&difficulty);
+		format!(
+			r#"["0x{}","0x{}","0x{}","0x{:x}"]"#,
+			pow_hash.hex(), self.seed_hash(number).hex(), target.hex(), number
+		)
+	}
+
+	/// Serializes work package as `mining.notify` params: job id (pow hash), seed hash, header hash and clean jobs flag.
+	pub fn notify_payload(&self, pow_hash: H256, difficulty: U256, number: u64) -> String {
+		self.record_job(pow_hash, difficulty, number);
+		format!(
+			r#"["{}","{}","{}",true]"#,
+			pow_hash.hex(), self.seed_hash(number).hex(), pow_hash.hex()
+		)
+	}
+
+	/// Remembers dispatched job so that solutions for it are accepted.
+	fn record_job(&self, pow_hash: H256, difficulty: U256, number: u64) {
+		let mut jobs = self.jobs.lock();
+		if !jobs.iter().any(|&(hash, _, _)| hash == pow_hash) {
+			jobs.push_back((pow_hash, difficulty, number));
+			if jobs.len() > MAX_JOBS {
+				jobs.pop_front();
+			}
+		}
+	}
+
+	fn seed_hash(&self, number: u64) -> H256 {
+		let seed_hash = self.seed_compute.lock().get_seedhash(number);
+		H256::from_slice(&seed_hash[..])
+	}
+
+	fn with_core<F, T>(&self, f: F) -> Option<T> where F: FnOnce(Arc<MiningBlockChainClient>, Arc<Miner>) -> T {
+		match (self.client.upgrade(), self.miner.upgrade()) {
+			(Some(client), Some(miner)) => Some(f(client, miner)),
+			_ => None,
+		}
+	}
+
+	fn with_sealing_work<F, T>(&self, f: F) -> Option<T> where F: FnOnce(H256, U256, u64) -> T {
+		self.with_core(|client, miner| miner.map_sealing_work(&*client, |b| {
+			let header = b.header();
+			(b.hash(), *header.difficulty(), header.number())
+		})).and_then(|work| work).map(|(pow_hash, difficulty, number)| f(pow_hash, difficulty, number))
+	}
+
+	fn job_payload(&self) -> Option<String> {
+		self.with_sealing_work(|pow_hash, difficulty, number| self.payload(pow_hash, difficulty, number))
+	}
+
+	/// Verifies share given as job id, nonce and optionally mix hash.
+	/// Shares meeting the block difficulty are submitted to the miner.
+	fn verify_share(&self, payload: &[String], share_difficulty: Option<U256>) -> Result<U256, StratumServiceError> {
+		if payload.len() < 2 {
+			return Err(StratumServiceError::InvalidSolution(format!("Expected job id and nonce, got {:?}", payload)));
+		}
+		let pow_hash: H256 = try!(clean_0x(&payload[0]).parse()
+			.map_err(|_| StratumServiceError::InvalidSolution(format!("Invalid job id: {}", payload[0]))));
+		let nonce = try!(u64::from_str_radix(clean_0x(&payload[1]), 16)
+			.map_err(|_| StratumServiceError::InvalidSolution(format!("Invalid nonce: {}", payload[1]))));
+		let (block_difficulty, number) = try!(self.jobs.lock().iter()
+			.find(|&&(hash, _, _)| hash == pow_hash)
+			.map(|&(_, difficulty, number)| (difficulty, number))
+			.ok_or_else(|| StratumServiceError::InvalidSolution(format!("Unknown or stale job: {}", pow_hash))));
+
+		let pow = self.ethash.compute_light(number, &pow_hash.0, nonce);
+		let mix_hash = H256(pow.mix_hash);
+		if let Some(submitted) = payload.get(2) {
+			if clean_0x(submitted).parse::<H256>().ok() != Some(mix_hash) {
+				return Err(StratumServiceError::InvalidSolution(format!("Invalid mix hash: {}", submitted)));
+			}
+		}
+
+		let difficulty = Ethash::boundary_to_difficulty(&H256(pow.value));
+		let share_difficulty = share_difficulty.map_or(block_difficulty, |d| cmp::min(d, block_difficulty));
+		if difficulty < share_difficulty {
+			return Err(StratumServiceError::InvalidSolution(format!("Difficulty {} below share difficulty {}", difficulty, share_difficulty)));
+		}
+
+		if difficulty >= block_difficulty {
+			let seal = vec![rlp::encode(&mix_hash).to_vec(), rlp::encode(&H64::from(nonce)).to_vec()];
+			try!(self.with_core(|client, miner| miner.submit_seal(&*client, pow_hash, seal).map_err(|e| format!("{}", e)))
+				.unwrap_or_else(|| Err("Miner is not available".to_owned()))
+				.map_err(StratumServiceError::Dispatch));
+		}
+		Ok(share_difficulty)
+	}
+}
+
+impl JobDispatcher for StratumJobDispatcher {
+	fn initial(&self) -> Option<String> {
+		self.job_payload()
+	}
+
+	fn difficulty(&self) -> Option<String> {
+		self.with_sealing_work(|_, difficulty, _| difficulty_json(difficulty))
+	}
+
+	fn job(&self, _worker_id: String) -> Option<String> {
+		self.job_payload()
+	}
+
+	fn notify_job(&self, _worker_id: String) -> Option<String> {
+		self.with_sealing_work(|pow_hash, difficulty, number| self.notify_payload(pow_hash, difficulty, number))
+	}
+
+	fn submit(&self, worker_id: String, payload: Vec<String>, share_difficulty: Option<U256>) -> Result<U256, StratumServiceError> {
+		let result = self.verify_share(&payload, share_difficulty);
+		match result {
+			Ok(difficulty) => self.external_miner.submit_share(worker_id, difficulty, true),
+			Err(ref e) => {
+				trace!(target: "stratum", "Share from {} rejected: {:?}", worker_id, e);
+				self.external_miner.submit_share(worker_id, U256::from(0), false);
+			},
+		}
+		result
+	}
+}
+
+/// Pushes new sealing work to all workers of a stratum server, local or remote.
+pub struct StratumWorkNotifier {
+	dispatcher: Arc<StratumJobDispatcher>,
+	service: Arc<PushWorkHandler>,
+}
+
+impl StratumWorkNotifier {
+	/// Creates new notifier pushing jobs formatted by `dispatcher` to `service`.
+	pub fn new(dispatcher: Arc<StratumJobDispatcher>, service: Arc<PushWorkHandler>) -> Self {
+		StratumWorkNotifier {
+			dispatcher: dispatcher,
+			service: service,
+		}
+	}
+}
+
+impl NotifyWork for StratumWorkNotifier {
+	fn notify(&self, pow_hash: H256, difficulty: U256, number: u64) {
+		trace!(target: "stratum", "Notify work");
+		let payload = self.dispatcher.payload(pow_hash, difficulty, number);
+		let notify_params = self.dispatcher.notify_payload(pow_hash, difficulty, number);
+		if let Err(e) = self.service.push_work_all(payload, notify_params) {
+			warn!(target: "stratum", "Error while pushing work: {:?}", e);
+		}
+	}
+}
+
+/// In-process stratum server fed by the miner.
+pub struct Stratum {
+	dispatcher: Arc<StratumJobDispatcher>,
+	service: Arc<StratumService>,
+}
+
+impl Stratum {
+	/// Starts the stratum server and registers it for new work notifications with the miner.
+	/// Shares submitted by workers are recorded in `external_miner`.
+	pub fn register(
+		options: &StratumOptions,
+		miner: Arc<Miner>,
+		client: Arc<Client>,
+		external_miner: Arc<ExternalMinerService>,
+	) -> Result<Arc<Stratum>, Error> {
+		let addr = try!(format!("{}:{}", options.listen_addr, options.port).parse::<SocketAddr>());
+		let client: Arc<MiningBlockChainClient> = client;
+		let dispatcher = Arc::new(StratumJobDispatcher::new(Arc::downgrade(&miner), Arc::downgrade(&client), external_miner));
+		let service = try!(StratumService::start(&addr, dispatcher.clone(), options.secret)
+			.map_err(|e| Error::Service(format!("{:?}", e))));
+		service.set_default_difficulty(options.share_difficulty);
+
+		miner.add_work_listener(Box::new(StratumWorkNotifier::new(dispatcher.clone(), service.clone())));
+		info!("Stratum server listening on {}", addr);
+
+		Ok(Arc::new(Stratum {
+			dispatcher: dispatcher,
+			service: service,
+		}))
+	}
+
+	/// Job dispatcher used by the server.
+	pub fn dispatcher(&self) -> Arc<StratumJobDispatcher> {
+		self.dispatcher.clone()
+	}
+
+	/// Underlying stratum server.
+	pub fn service(&self) -> Arc<StratumService> {
+		self.service.clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use ethcore_stratum::{JobDispatcher, PushWorkHandler, Error as StratumServiceError};
+	use util::{Mutex, H256};
+	use client::{TestBlockChainClient, MiningBlockChainClient, EachBlockWith};
+	use miner::{Miner, MinerService, ExternalMiner, ExternalMinerService};
+	use spec::Spec;
+	use super::{StratumJobDispatcher, StratumWorkNotifier};
+
+	fn dispatcher(miner: &Arc<Miner>, client: &Arc<MiningBlockChainClient>, external_miner: Arc<ExternalMiner>) -> StratumJobDispatcher {
+		StratumJobDispatcher::new(Arc::downgrade(miner), Arc::downgrade(client), external_miner)
+	}
+
+	#[derive(Default)]
+	struct TestPushHandler {
+		pushed: Mutex<Vec<String>>,
+	}
+
+	impl PushWorkHandler for TestPushHandler {
+		fn push_work_all(&self, payload: String, _notify_params: String) -> Result<(), StratumServiceError> {
+			self.pushed.lock().push(payload);
+			Ok(())
+		}
+
+		fn push_work(&self, _payloads: Vec<String>) -> Result<(), StratumServiceError> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_dispatch_sealing_work() {
+		let client: Arc<MiningBlockChainClient> = Arc::new(TestBlockChainClient::default());
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let dispatcher = dispatcher(&miner, &client, Arc::new(ExternalMiner::default()));
+
+		let job = dispatcher.job("worker".into()).unwrap();
+		let pow_hash = miner.map_sealing_work(&*client, |b| b.hash()).unwrap();
+		assert!(job.starts_with(&format!(r#"["0x{}","#, pow_hash.hex())));
+		assert_eq!(dispatcher.initial(), Some(job));
+		assert!(dispatcher.difficulty().is_some());
+
+		let notify_job = dispatcher.notify_job("worker".into()).unwrap();
+		assert!(notify_job.starts_with(&format!(r#"["{}","#, pow_hash.hex())));
+		assert!(notify_job.ends_with(&format!(r#","{}",true]"#, pow_hash.hex())));
+	}
+
+	#[test]
+	fn should_not_dispatch_work_without_client() {
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let dispatcher = {
+			let client: Arc<MiningBlockChainClient> = Arc::new(TestBlockChainClient::default());
+			dispatcher(&miner, &client, Arc::new(ExternalMiner::default()))
+		};
+		assert_eq!(dispatcher.job("worker".into()), None);
+	}
+
+	#[test]
+	fn should_push_new_work_to_workers() {
+		let test_client = Arc::new(TestBlockChainClient::default());
+		let client: Arc<MiningBlockChainClient> = test_client.clone();
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let dispatcher = Arc::new(dispatcher(&miner, &client, Arc::new(ExternalMiner::default())));
+		let handler = Arc::new(TestPushHandler::default());
+		miner.add_work_listener(Box::new(StratumWorkNotifier::new(dispatcher.clone(), handler.clone())));
+
+		miner.update_sealing(&*client);
+		assert_eq!(handler.pushed.lock().len(), 1);
+		let job = dispatcher.job("worker".into()).unwrap();
+		assert_eq!(handler.pushed.lock()[0], job);
+
+		test_client.add_blocks(1, EachBlockWith::Nothing);
+		miner.update_sealing(&*client);
+		assert_eq!(handler.pushed.lock().len(), 2);
+	}
+
+	#[test]
+	fn should_record_shares_of_workers() {
+		let client: Arc<MiningBlockChainClient> = Arc::new(TestBlockChainClient::default());
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let external_miner = Arc::new(ExternalMiner::default());
+		let dispatcher = dispatcher(&miner, &client, external_miner.clone());
+
+		dispatcher.job("worker".into()).unwrap();
+		let pow_hash = miner.map_sealing_work(&*client, |b| b.hash()).unwrap();
+
+		// any nonce satisfies share difficulty of 1
+		let share = vec![pow_hash.hex(), "0000000000000001".to_owned()];
+		assert_eq!(dispatcher.submit("worker".into(), share, Some(1.into())).unwrap(), 1.into());
+		// solutions for unknown jobs are rejected
+		let share = vec![H256::from(1).hex(), "0000000000000001".to_owned()];
+		assert!(dispatcher.submit("worker".into(), share, Some(1.into())).is_err());
+
+		let stats = external_miner.workers();
+		assert_eq!(stats["worker"].accepted_shares, 1);
+		assert_eq!(stats["worker"].rejected_shares, 1);
+	}
+}
@@ -0,0 +1,151 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction filtering policy.
+//!
+//! The policy is loaded from a JSON file of the following form (all fields are optional):
+//!
+//! ```json
+//! {
+//!		"denySenders": ["0x..."],
+//!		"denyRecipients": ["0x..."],
+//!		"allowCreators": ["0x..."],
+//!		"minGasPrice": { "0x...": "0x4a817c800" }
+//! }
+//! ```
+
+use std::fs::File;
+use std::path::Path;
+use std::collections::{HashSet, HashMap};
+use util::{Address, U256};
+use ethjson;
+use transaction::{Action, SignedTransaction};
+use error::TransactionError;
+
+/// Decides which transactions are accepted to the queue and to sealed blocks.
+#[derive(Debug, Default, PartialEq)]
+pub struct TransactionFilter {
+	deny_senders: HashSet<Address>,
+	deny_recipients: HashSet<Address>,
+	/// `None` means that anyone is allowed to create contracts.
+	allow_creators: Option<HashSet<Address>>,
+	min_gas_price: HashMap<Address, U256>,
+}
+
+impl TransactionFilter {
+	/// Loads the filter from JSON policy file.
+	pub fn from_file(path: &Path) -> Result<Self, String> {
+		let file = try!(File::open(path).map_err(|e| format!("Cannot open transaction filter file {}: {}", path.display(), e)));
+		let filter = try!(ethjson::misc::TransactionFilter::load(file).map_err(|e| format!("Invalid transaction filter file {}: {}", path.display(), e)));
+		Ok(filter.into())
+	}
+
+	/// Checks if given transaction is permitted.
+	pub fn check(&self, tx: &SignedTransaction) -> Result<(), TransactionError> {
+		let sender = try!(tx.sender().map_err(|_| TransactionError::NotAllowed));
+		if self.deny_senders.contains(&sender) {
+			return Err(TransactionError::NotAllowed);
+		}
+
+		match tx.action {
+			Action::Create => match self.allow_creators {
+				Some(ref creators) if !creators.contains(&sender) => Err(TransactionError::NotAllowed),
+				_ => Ok(()),
+			},
+			Action::Call(ref recipient) => {
+				if self.deny_recipients.contains(recipient) {
+					return Err(TransactionError::NotAllowed);
+				}
+				match self.min_gas_price.get(recipient) {
+					Some(minimal) if tx.gas_price < *minimal => Err(TransactionError::InsufficientGasPrice {
+						minimal: *minimal,
+						got: tx.gas_price,
+					}),
+					_ => Ok(()),
+				}
+			},
+		}
+	}
+}
+
+impl From<ethjson::misc::TransactionFilter> for TransactionFilter {
+	fn from(f: ethjson::misc::TransactionFilter) -> Self {
+		TransactionFilter {
+			deny_senders: f.deny_senders.unwrap_or_else(Vec::new).into_iter().map(Into::into).collect(),
+			deny_recipients: f.deny_recipients.unwrap_or_else(Vec::new).into_iter().map(Into::into).collect(),
+			allow_creators: f.allow_creators.map(|c| c.into_iter().map(Into::into).collect()),
+			min_gas_price: f.min_gas_price.unwrap_or_else(Default::default).into_iter().map(|(a, p)| (a.into(), p.into())).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use ethkey::{Random, Generator};
+	use util::{Address, U256};
+	use transaction::{Action, Transaction};
+	use error::TransactionError;
+	use ethjson;
+	use super::TransactionFilter;
+
+	fn filter(json: &str) -> TransactionFilter {
+		ethjson::misc::TransactionFilter::load(json.as_bytes()).unwrap().into()
+	}
+
+	fn transaction(action: Action, gas_price: U256) -> Transaction {
+		Transaction {
+			action: action,
+			value: U256::zero(),
+			data: vec![],
+			gas: 21_000.into(),
+			gas_price: gas_price,
+			nonce: U256::zero(),
+		}
+	}
+
+	#[test]
+	fn should_allow_everything_by_default() {
+		let keypair = Random.generate().unwrap();
+		let filter = TransactionFilter::default();
+		assert_eq!(filter.check(&transaction(Action::Create, 0.into()).sign(keypair.secret())), Ok(()));
+		assert_eq!(filter.check(&transaction(Action::Call(5.into()), 0.into()).sign(keypair.secret())), Ok(()));
+	}
+
+	#[test]
+	fn should_check_transactions_against_policy() {
+		let creator = Random.generate().unwrap();
+		let denied = Random.generate().unwrap();
+		let other = Random.generate().unwrap();
+		let filter = filter(&format!(r#"{{
+			"denySenders": ["0x{:?}"],
+			"denyRecipients": ["0x0000000000000000000000000000000000000001"],
+			"allowCreators": ["0x{:?}"],
+			"minGasPrice": {{ "0x0000000000000000000000000000000000000002": "0x64" }}
+		}}"#, denied.address(), creator.address()));
+		let denied_recipient: Address = 1.into();
+		let priced_recipient: Address = 2.into();
+
+		assert_eq!(filter.check(&transaction(Action::Call(3.into()), 0.into()).sign(denied.secret())), Err(TransactionError::NotAllowed));
+		assert_eq!(filter.check(&transaction(Action::Call(denied_recipient), 0.into()).sign(other.secret())), Err(TransactionError::NotAllowed));
+		assert_eq!(filter.check(&transaction(Action::Create, 0.into()).sign(other.secret())), Err(TransactionError::NotAllowed));
+		assert_eq!(filter.check(&transaction(Action::Create, 0.into()).sign(creator.secret())), Ok(()));
+		assert_eq!(filter.check(&transaction(Action::Call(priced_recipient), 99.into()).sign(other.secret())), Err(TransactionError::InsufficientGasPrice {
+			minimal: 100.into(),
+			got: 99.into(),
+		}));
+		assert_eq!(filter.check(&transaction(Action::Call(priced_recipient), 100.into()).sign(other.secret())), Ok(()));
+	}
+}
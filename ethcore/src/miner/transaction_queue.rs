@@ -83,12 +83,15 @@
 
 use std::cmp::Ordering;
 use std::cmp;
-use std::collections::{HashSet, HashMap, BTreeSet, BTreeMap};
+use std::collections::{HashSet, HashMap, BTreeSet, BTreeMap, BinaryHeap};
 use util::{Address, H256, Uint, U256};
 use util::table::Table;
 use transaction::*;
 use error::{Error, TransactionError};
 use client::TransactionImportResult;
+use header::BlockNumber;
+use miner::local_transactions::{LocalTransactionsList, LocalTransaction, LocalTransactionStatus};
+use miner::transaction_filter::TransactionFilter;
 
 /// Transaction origin
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -195,14 +198,21 @@ struct VerifiedTransaction {
 	transaction: SignedTransaction,
 	/// transaction origin
 	origin: TransactionOrigin,
+	/// Condition that has to hold before the transaction is ready
+	condition: Option<Condition>,
+	/// Approximate amount of memory used by the transaction (size of its RLP)
+	mem_usage: usize,
 }
 
 impl VerifiedTransaction {
-	fn new(transaction: SignedTransaction, origin: TransactionOrigin) -> Result<Self, Error> {
+	fn new(transaction: SignedTransaction, origin: TransactionOrigin, condition: Option<Condition>) -> Result<Self, Error> {
 		try!(transaction.sender());
+		let mem_usage = ::rlp::encode(&transaction).len();
 		Ok(VerifiedTransaction {
 			transaction: transaction,
 			origin: origin,
+			condition: condition,
+			mem_usage: mem_usage,
 		})
 	}
 
@@ -219,6 +229,54 @@ impl VerifiedTransaction {
 	}
 }
 
+/// Verified transactions indexed by hash, along with their total memory usage.
+#[derive(Default)]
+struct TransactionsByHash {
+	transactions: HashMap<H256, VerifiedTransaction>,
+	mem_usage: usize,
+}
+
+impl TransactionsByHash {
+	fn insert(&mut self, hash: H256, tx: VerifiedTransaction) -> Option<VerifiedTransaction> {
+		self.mem_usage += tx.mem_usage;
+		let old = self.transactions.insert(hash, tx);
+		if let Some(ref old) = old {
+			self.mem_usage -= old.mem_usage;
+		}
+		old
+	}
+
+	fn remove(&mut self, hash: &H256) -> Option<VerifiedTransaction> {
+		let tx = self.transactions.remove(hash);
+		if let Some(ref tx) = tx {
+			self.mem_usage -= tx.mem_usage;
+		}
+		tx
+	}
+
+	fn clear(&mut self) {
+		self.transactions.clear();
+		self.mem_usage = 0;
+	}
+
+	fn get(&self, hash: &H256) -> Option<&VerifiedTransaction> {
+		self.transactions.get(hash)
+	}
+
+	fn values(&self) -> ::std::collections::hash_map::Values<H256, VerifiedTransaction> {
+		self.transactions.values()
+	}
+
+	fn len(&self) -> usize {
+		self.transactions.len()
+	}
+
+	/// Approximate amount of memory (in bytes) used by all transactions.
+	fn mem_usage(&self) -> usize {
+		self.mem_usage
+	}
+}
+
 /// Holds transactions accessible by (address, nonce) and by priority
 ///
 /// `TransactionSet` keeps number of entries below limit, but it doesn't
@@ -258,7 +316,7 @@ impl TransactionSet {
 	///
 	/// It drops transactions from this set but also removes associated `VerifiedTransaction`.
 	/// Returns addresses and lowest nonces of transactions removed because of limit.
-	fn enforce_limit(&mut self, by_hash: &mut HashMap<H256, VerifiedTransaction>) -> Option<HashMap<Address, U256>> {
+	fn enforce_limit(&mut self, by_hash: &mut TransactionsByHash, local: &mut LocalTransactionsList) -> Option<HashMap<Address, U256>> {
 		let len = self.by_priority.len();
 		if len <= self.limit {
 			return None;
@@ -280,6 +338,7 @@ impl TransactionSet {
 
 				by_hash.remove(&order.hash)
 					.expect("hash is in `by_priorty`; all hashes in `by_priority` must be in `by_hash`; qed");
+				local.mark_dropped(&order.hash);
 
 				let min = removed.get(&sender).map_or(nonce, |val| cmp::min(*val, nonce));
 				removed.insert(sender, min);
@@ -349,13 +408,23 @@ impl TransactionSet {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 /// Current status of the queue
 pub struct TransactionQueueStatus {
 	/// Number of pending transactions (ready to go to block)
 	pub pending: usize,
 	/// Number of future transactions (waiting for transactions with lower nonces first)
 	pub future: usize,
+	/// Maximal number of transactions in `current` and in `future`
+	pub limit: usize,
+	/// Transactions above this gas are not accepted (block gas limit with hysteresis)
+	pub gas_limit: U256,
+	/// Maximal amount of gas a single transaction may use
+	pub tx_gas_limit: U256,
+	/// Gas price threshold for incoming transactions
+	pub minimal_gas_price: U256,
+	/// Lowest gas price a transaction needs to enter the queue, taking into account that it might be full
+	pub effective_minimum_gas_price: U256,
 }
 
 /// Details of account
@@ -382,9 +451,23 @@ pub struct TransactionQueue {
 	/// Priority queue for transactions that has been received but are not yet valid to go to block
 	future: TransactionSet,
 	/// All transactions managed by queue indexed by hash
-	by_hash: HashMap<H256, VerifiedTransaction>,
+	by_hash: TransactionsByHash,
 	/// Last nonce of transaction in current (to quickly check next expected transaction)
 	last_nonces: HashMap<Address, U256>,
+	/// History of local transactions
+	local_transactions: LocalTransactionsList,
+	/// Number of the best block, used to check transaction conditions
+	best_block: BlockNumber,
+	/// Timestamp of the best block, used to check transaction conditions
+	best_timestamp: u64,
+	/// Maximal number of external transactions from a single sender
+	max_count_per_sender: usize,
+	/// Maximal amount of gas of all external transactions from a single sender
+	max_gas_per_sender: U256,
+	/// Maximal amount of memory (in bytes) used by all transactions in the queue
+	memory_limit: usize,
+	/// Policy deciding which transactions are permitted
+	filter: Option<TransactionFilter>,
 }
 
 impl Default for TransactionQueue {
@@ -421,8 +504,15 @@ impl TransactionQueue {
 			gas_limit: !U256::zero(),
 			current: current,
 			future: future,
-			by_hash: HashMap::new(),
+			by_hash: TransactionsByHash::default(),
 			last_nonces: HashMap::new(),
+			local_transactions: LocalTransactionsList::default(),
+			best_block: 0,
+			best_timestamp: 0,
+			max_count_per_sender: usize::max_value(),
+			max_gas_per_sender: !U256::zero(),
+			memory_limit: usize::max_value(),
+			filter: None,
 		}
 	}
 
@@ -431,8 +521,42 @@ impl TransactionQueue {
 		self.current.set_limit(limit);
 		self.future.set_limit(limit);
 		// And ensure the limits
-		self.current.enforce_limit(&mut self.by_hash);
-		self.future.enforce_limit(&mut self.by_hash);
+		self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+	}
+
+	/// Sets limits on the number of transactions and the total gas of transactions from a single sender.
+	/// Transactions of senders with local transactions in the queue are not affected.
+	/// The limits are applied next time a transaction from given sender is imported.
+	pub fn set_sender_limits(&mut self, max_count: usize, max_gas: U256) {
+		self.max_count_per_sender = max_count;
+		self.max_gas_per_sender = max_gas;
+	}
+
+	/// Sets limit on the memory (in bytes) used by all transactions in the queue.
+	/// When the limit is exceeded transactions of the heaviest senders are dropped first.
+	pub fn set_memory_limit(&mut self, limit: usize) {
+		self.memory_limit = limit;
+		self.enforce_memory_limit(&mut HashMap::new());
+	}
+
+	/// Sets the policy deciding which transactions are permitted to the queue.
+	/// Transactions already in the queue are not checked again.
+	pub fn set_filter(&mut self, filter: Option<TransactionFilter>) {
+		self.filter = filter;
+	}
+
+	/// Checks if given transaction is permitted by the transaction filter.
+	pub fn check_filter(&self, tx: &SignedTransaction) -> Result<(), TransactionError> {
+		match self.filter {
+			Some(ref filter) => filter.check(tx),
+			None => Ok(()),
+		}
+	}
+
+	/// Returns approximate amount of memory (in bytes) used by transactions in the queue.
+	pub fn mem_usage(&self) -> usize {
+		self.by_hash.mem_usage()
 	}
 
 	/// Returns current limit of transactions in the queue.
@@ -468,6 +592,13 @@ impl TransactionQueue {
 		};
 	}
 
+	/// Sets the best block of the chain. Transactions with conditions that do not hold for this block
+	/// are kept in the queue, but are not returned as ready.
+	pub fn set_best_block(&mut self, number: BlockNumber, timestamp: u64) {
+		self.best_block = number;
+		self.best_timestamp = timestamp;
+	}
+
 	/// Set the new limit for the amount of gas any individual transaction may have.
 	/// Any transaction already imported to the queue is not affected.
 	pub fn set_tx_gas_limit(&mut self, limit: U256) {
@@ -479,11 +610,49 @@ impl TransactionQueue {
 		TransactionQueueStatus {
 			pending: self.current.by_priority.len(),
 			future: self.future.by_priority.len(),
+			limit: self.current.limit,
+			gas_limit: self.gas_limit,
+			tx_gas_limit: self.tx_gas_limit,
+			minimal_gas_price: self.minimal_gas_price,
+			effective_minimum_gas_price: cmp::max(self.minimal_gas_price, self.effective_minimum_gas_price()),
 		}
 	}
 
 	/// Add signed transaction to queue to be verified and imported
 	pub fn add<T>(&mut self, tx: SignedTransaction, fetch_account: &T, origin: TransactionOrigin) -> Result<TransactionImportResult, Error>
+	where T: Fn(&Address) -> AccountDetails {
+		self.add_with_condition(tx, fetch_account, origin, None)
+	}
+
+	/// Add signed transaction to queue to be verified and imported.
+	/// The transaction (and any later transaction from the same sender) is not ready before `condition` holds.
+	pub fn add_with_condition<T>(&mut self, tx: SignedTransaction, fetch_account: &T, origin: TransactionOrigin, condition: Option<Condition>) -> Result<TransactionImportResult, Error>
+	where T: Fn(&Address) -> AccountDetails {
+		if origin != TransactionOrigin::Local {
+			return self.add_internal(tx, fetch_account, origin, condition);
+		}
+
+		let hash = tx.hash();
+		let result = self.add_internal(tx.clone(), fetch_account, origin, condition);
+		match result {
+			Ok(TransactionImportResult::Current) => self.local_transactions.insert(tx, LocalTransactionStatus::Pending),
+			Ok(TransactionImportResult::Future) => self.local_transactions.insert(tx, LocalTransactionStatus::Future),
+			Err(Error::Transaction(TransactionError::AlreadyImported)) => {},
+			Err(Error::Transaction(ref err)) => {
+				// A transaction from a retracted block which can't be imported again is no longer valid.
+				let status = if self.is_mined_local(&hash) {
+					LocalTransactionStatus::Invalid
+				} else {
+					LocalTransactionStatus::Rejected(*err)
+				};
+				self.local_transactions.insert(tx, status);
+			},
+			Err(_) => {},
+		}
+		result
+	}
+
+	fn add_internal<T>(&mut self, tx: SignedTransaction, fetch_account: &T, origin: TransactionOrigin, condition: Option<Condition>) -> Result<TransactionImportResult, Error>
 	where T: Fn(&Address) -> AccountDetails {
 
 		trace!(target: "txqueue", "Importing: {:?}", tx.hash());
@@ -519,6 +688,11 @@ impl TransactionQueue {
 
 		try!(tx.check_low_s());
 
+		if let Err(e) = self.check_filter(&tx) {
+			trace!(target: "txqueue", "Dropping transaction not permitted by filter: {:?} ({:?})", tx.hash(), e);
+			return Err(Error::Transaction(e));
+		}
+
 		if tx.gas > self.gas_limit || tx.gas > self.tx_gas_limit {
 			trace!(target: "txqueue",
 				"Dropping transaction above gas limit: {:?} ({} > min({}, {}))",
@@ -534,7 +708,7 @@ impl TransactionQueue {
 			}));
 		}
 
-		let vtx = try!(VerifiedTransaction::new(tx, origin));
+		let vtx = try!(VerifiedTransaction::new(tx, origin, condition));
 		let client_account = fetch_account(&vtx.sender());
 
 		let cost = vtx.transaction.value + vtx.transaction.gas_price * vtx.transaction.gas;
@@ -589,6 +763,7 @@ impl TransactionQueue {
 		}
 
 		let transaction = transaction.unwrap();
+		self.local_transactions.mark_invalid(transaction_hash);
 		let sender = transaction.sender();
 		let nonce = transaction.nonce();
 		let current_nonce = fetch_account(&sender).nonce;
@@ -630,6 +805,7 @@ impl TransactionQueue {
 				trace!(target: "txqueue", "Removing old transaction: {:?} (nonce: {} < {})", order.hash, k, current_nonce);
 				// Remove the transaction completely
 				self.by_hash.remove(&order.hash).expect("All transactions in `future` are also in `by_hash`");
+				self.local_transactions.mark_invalid(&order.hash);
 			}
 		}
 	}
@@ -650,13 +826,47 @@ impl TransactionQueue {
 			} else {
 				trace!(target: "txqueue", "Removing old transaction: {:?} (nonce: {} < {})", order.hash, k, current_nonce);
 				self.by_hash.remove(&order.hash).expect("All transactions in `future` are also in `by_hash`");
+				self.local_transactions.mark_invalid(&order.hash);
+			}
+		}
+		self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
+	}
+
+	/// Returns senders which have transactions with conditions that do not hold yet,
+	/// together with the lowest nonce of such a transaction.
+	fn blocked_senders(&self) -> HashMap<Address, U256> {
+		let mut blocked = HashMap::new();
+		for tx in self.by_hash.values() {
+			let is_blocked = tx.condition.map_or(false, |c| !c.is_satisfied(self.best_block, self.best_timestamp));
+			if is_blocked {
+				let nonce = blocked.entry(tx.sender()).or_insert_with(|| tx.nonce());
+				*nonce = cmp::min(*nonce, tx.nonce());
 			}
 		}
-		self.future.enforce_limit(&mut self.by_hash);
+		blocked
+	}
+
+	/// Returns transactions from current that are ready to be included in a block, ordered by priority.
+	/// Transactions waiting for their condition and all subsequent transactions of the same sender are skipped.
+	fn ready_transactions(&self) -> Vec<&VerifiedTransaction> {
+		let blocked = self.blocked_senders();
+		self.current.by_priority
+			.iter()
+			.map(|t| self.by_hash.get(&t.hash).expect("All transactions in `current` and `future` are always included in `by_hash`"))
+			.filter(|t| blocked.get(&t.sender()).map_or(true, |nonce| t.nonce() < *nonce))
+			.collect()
 	}
 
 	/// Returns top transactions from the queue ordered by priority.
 	pub fn top_transactions(&self) -> Vec<SignedTransaction> {
+		self.ready_transactions()
+			.into_iter()
+			.map(|t| t.transaction.clone())
+			.collect()
+	}
+
+	/// Returns all transactions from current ordered by priority, including ones waiting for their condition.
+	pub fn current_transactions(&self) -> Vec<SignedTransaction> {
 		self.current.by_priority
 			.iter()
 			.map(|t| self.by_hash.get(&t.hash).expect("All transactions in `current` and `future` are always included in `by_hash`"))
@@ -664,17 +874,35 @@ impl TransactionQueue {
 			.collect()
 	}
 
-	/// Returns hashes of all transactions from current, ordered by priority.
-	pub fn pending_hashes(&self) -> Vec<H256> {
-		self.current.by_priority
+	/// Returns transactions waiting in future (for transactions with lower nonces) ordered by priority.
+	pub fn future_transactions(&self) -> Vec<SignedTransaction> {
+		self.future.by_priority
 			.iter()
-			.map(|t| t.hash)
+			.map(|t| self.by_hash.get(&t.hash).expect("All transactions in `current` and `future` are always included in `by_hash`"))
+			.map(|t| t.transaction.clone())
+			.collect()
+	}
+
+	/// Returns hashes of all ready transactions from current, ordered by priority.
+	pub fn pending_hashes(&self) -> Vec<H256> {
+		self.ready_transactions()
+			.into_iter()
+			.map(|t| t.hash())
 			.collect()
 	}
 
 	/// Returns true if there is at least one local transaction pending
 	pub fn has_local_pending_transactions(&self) -> bool {
-		self.current.by_priority.iter().any(|tx| tx.origin == TransactionOrigin::Local)
+		self.ready_transactions().into_iter().any(|tx| tx.origin == TransactionOrigin::Local)
+	}
+
+	/// Returns all local transactions (both current and future) with their conditions, ordered by sender and nonce.
+	pub fn local_transactions(&self) -> Vec<PendingTransaction> {
+		let mut local: Vec<&VerifiedTransaction> = self.by_hash.values()
+			.filter(|tx| tx.origin == TransactionOrigin::Local)
+			.collect();
+		local.sort_by(|a, b| (a.sender(), a.nonce()).cmp(&(b.sender(), b.nonce())));
+		local.into_iter().map(|tx| PendingTransaction::new(tx.transaction.clone(), tx.condition)).collect()
 	}
 
 	/// Finds transaction in the queue by hash (if any)
@@ -688,6 +916,35 @@ impl TransactionQueue {
 		self.future.clear();
 		self.by_hash.clear();
 		self.last_nonces.clear();
+		self.local_transactions.mark_all_dropped();
+	}
+
+	/// Returns history of local transactions, oldest first.
+	pub fn local_transaction_history(&self) -> Vec<LocalTransaction> {
+		self.local_transactions.all().into_iter().map(|mut tx| {
+			if tx.status.is_queued() {
+				let hash = tx.transaction.hash();
+				let nonce = tx.transaction.nonce;
+				let in_current = tx.transaction.sender().ok()
+					.and_then(|sender| self.current.by_address.get(&sender, &nonce).map(|order| order.hash == hash))
+					.unwrap_or(false);
+				tx.status = if in_current { LocalTransactionStatus::Pending } else { LocalTransactionStatus::Future };
+			}
+			tx
+		}).collect()
+	}
+
+	/// Returns true if given transaction is a local transaction that has been included in a block.
+	pub fn is_mined_local(&self, hash: &H256) -> bool {
+		match self.local_transactions.get(hash) {
+			Some(&LocalTransaction { status: LocalTransactionStatus::Mined { .. }, .. }) => true,
+			_ => false,
+		}
+	}
+
+	/// Notes that given transaction has been included in a block. Only affects local transactions.
+	pub fn mark_mined(&mut self, hash: &H256, block_number: BlockNumber, block_hash: H256) {
+		self.local_transactions.mark_mined(hash, block_number, block_hash);
 	}
 
 	/// Returns highest transaction nonce for given address.
@@ -760,12 +1017,14 @@ impl TransactionQueue {
 			// Update nonces of transactions in future (remove old transactions)
 			self.update_future(&address, state_nonce);
 			// Insert transaction (or replace old one with lower gas price)
-			try!(check_too_cheap(Self::replace_transaction(tx, state_nonce, &mut self.future, &mut self.by_hash)));
+			try!(check_too_cheap(Self::replace_transaction(tx, state_nonce, &mut self.future, &mut self.by_hash, &mut self.local_transactions)));
 			// Return an error if this transaction is not imported because of limit.
-			try!(check_if_removed(&address, &nonce, self.future.enforce_limit(&mut self.by_hash)));
+			try!(check_if_removed(&address, &nonce, self.future.enforce_limit(&mut self.by_hash, &mut self.local_transactions)));
+			// And per-sender and memory limits.
+			try!(check_if_removed(&address, &nonce, self.enforce_quotas(&address)));
 			return Ok(TransactionImportResult::Future);
 		}
-		try!(check_too_cheap(Self::replace_transaction(tx, state_nonce, &mut self.current, &mut self.by_hash)));
+		try!(check_too_cheap(Self::replace_transaction(tx, state_nonce, &mut self.current, &mut self.by_hash, &mut self.local_transactions)));
 		// Keep track of highest nonce stored in current
 		let new_max = self.last_nonces.get(&address).map_or(nonce, |n| cmp::max(nonce, *n));
 		self.last_nonces.insert(address, new_max);
@@ -779,20 +1038,148 @@ impl TransactionQueue {
 			// Let's insert that transaction to current (if it has higher gas_price)
 			let future_tx = self.by_hash.remove(&order.hash).expect("All transactions in `future` are always in `by_hash`.");
 			// if transaction in `current` (then one we are importing) is replaced it means that it has to low gas_price
-			try!(check_too_cheap(!Self::replace_transaction(future_tx, state_nonce, &mut self.current, &mut self.by_hash)));
+			try!(check_too_cheap(!Self::replace_transaction(future_tx, state_nonce, &mut self.current, &mut self.by_hash, &mut self.local_transactions)));
 		}
 
 		// Also enforce the limit
-		let removed = self.current.enforce_limit(&mut self.by_hash);
+		let removed = self.current.enforce_limit(&mut self.by_hash, &mut self.local_transactions);
 		// If some transaction were removed because of limit we need to update last_nonces also.
 		self.update_last_nonces(&removed);
 		// Trigger error if the transaction we are importing was removed.
 		try!(check_if_removed(&address, &nonce, removed));
+		// Now make sure neither the sender nor the whole queue use too much.
+		try!(check_if_removed(&address, &nonce, self.enforce_quotas(&address)));
 
 		trace!(target: "txqueue", "status: {:?}", self.status());
 		Ok(TransactionImportResult::Current)
 	}
 
+	/// Enforces per-sender limits for `sender` and the memory limit of the whole queue.
+	///
+	/// Transactions are dropped starting from the highest nonce, so that remaining transactions stay valid.
+	/// Senders that have any local transaction in the queue are never touched.
+	/// Returns addresses and lowest nonces of dropped transactions.
+	fn enforce_quotas(&mut self, sender: &Address) -> Option<HashMap<Address, U256>> {
+		let mut removed = HashMap::new();
+		loop {
+			let (count, gas, has_local) = {
+				let txs = self.sender_transactions(sender);
+				let gas = txs.iter().fold(U256::zero(), |acc, tx| match acc.overflowing_add(tx.transaction.gas) {
+					(_, true) => !U256::zero(),
+					(val, false) => val,
+				});
+				(txs.len(), gas, txs.iter().any(|tx| tx.origin == TransactionOrigin::Local))
+			};
+			if has_local || (count <= self.max_count_per_sender && gas <= self.max_gas_per_sender) {
+				break;
+			}
+			match self.drop_last(sender) {
+				Some(nonce) => Self::note_removed(&mut removed, *sender, nonce),
+				None => break,
+			}
+		}
+		self.enforce_memory_limit(&mut removed);
+
+		if removed.is_empty() {
+			None
+		} else {
+			Some(removed)
+		}
+	}
+
+	/// Drops transactions of the heaviest (in terms of memory) non-local senders until the memory limit is met.
+	fn enforce_memory_limit(&mut self, removed: &mut HashMap<Address, U256>) {
+		if self.memory_limit == usize::max_value() {
+			return;
+		}
+
+		if self.by_hash.mem_usage() <= self.memory_limit {
+			return;
+		}
+
+		// Memory used by each sender without local transactions, heaviest on top.
+		let mut senders: BinaryHeap<(usize, Address)> = {
+			let mut usage: HashMap<Address, (usize, bool)> = HashMap::new();
+			for tx in self.by_hash.values() {
+				let entry = usage.entry(tx.sender()).or_insert((0, false));
+				entry.0 += tx.mem_usage;
+				entry.1 = entry.1 || tx.origin == TransactionOrigin::Local;
+			}
+			usage.into_iter()
+				.filter(|&(_, (_, has_local))| !has_local)
+				.map(|(sender, (mem, _))| (mem, sender))
+				.collect()
+		};
+
+		while self.by_hash.mem_usage() > self.memory_limit {
+			// Only local transactions left.
+			let (mem, sender) = match senders.pop() {
+				Some(heaviest) => heaviest,
+				None => break,
+			};
+
+			let before = self.by_hash.mem_usage();
+			match self.drop_last(&sender) {
+				Some(nonce) => Self::note_removed(removed, sender, nonce),
+				None => continue,
+			}
+
+			let left = mem - (before - self.by_hash.mem_usage());
+			if left > 0 {
+				senders.push((left, sender));
+			}
+		}
+	}
+
+	/// Returns all transactions (`current` and `future`) from given sender.
+	fn sender_transactions(&self, sender: &Address) -> Vec<&VerifiedTransaction> {
+		let by_hash = &self.by_hash;
+		let current = self.current.by_address.row(sender).into_iter().flat_map(|row| row.values());
+		let future = self.future.by_address.row(sender).into_iter().flat_map(|row| row.values());
+		current.chain(future)
+			.map(|order| by_hash.get(&order.hash).expect("All transactions in `current` and `future` are always in `by_hash`."))
+			.collect()
+	}
+
+	/// Drops transaction with the highest nonce of given sender (the one with lowest priority).
+	/// Returns nonce of the dropped transaction.
+	fn drop_last(&mut self, sender: &Address) -> Option<U256> {
+		// Transactions in `future` always have higher nonces than the ones in `current`.
+		let (nonce, from_current) = {
+			let last = |set: &TransactionSet| set.by_address.row(sender).and_then(|row| row.keys().max().cloned());
+			match last(&self.future) {
+				Some(nonce) => (nonce, false),
+				None => match last(&self.current) {
+					Some(nonce) => (nonce, true),
+					None => return None,
+				},
+			}
+		};
+
+		let order = if from_current {
+			self.current.drop(sender, &nonce)
+		} else {
+			self.future.drop(sender, &nonce)
+		}.expect("Nonce has just been found in the set; qed");
+
+		self.by_hash.remove(&order.hash).expect("All transactions in `current` and `future` are always in `by_hash`.");
+		self.local_transactions.mark_dropped(&order.hash);
+
+		if from_current {
+			if self.current.by_address.has_row(sender) {
+				self.last_nonces.insert(*sender, nonce - U256::one());
+			} else {
+				self.last_nonces.remove(sender);
+			}
+		}
+		Some(nonce)
+	}
+
+	fn note_removed(removed: &mut HashMap<Address, U256>, sender: Address, nonce: U256) {
+		let min = removed.get(&sender).map_or(nonce, |val| cmp::min(*val, nonce));
+		removed.insert(sender, min);
+	}
+
 	/// Updates
 	fn update_last_nonces(&mut self, removed_min_nonces: &Option<HashMap<Address, U256>>) {
 		if let Some(ref min_nonces) = *removed_min_nonces {
@@ -813,7 +1200,7 @@ impl TransactionQueue {
 	///
 	/// Returns `true` if transaction actually got to the queue (`false` if there was already a transaction with higher
 	/// gas_price)
-	fn replace_transaction(tx: VerifiedTransaction, base_nonce: U256, set: &mut TransactionSet, by_hash: &mut HashMap<H256, VerifiedTransaction>) -> bool {
+	fn replace_transaction(tx: VerifiedTransaction, base_nonce: U256, set: &mut TransactionSet, by_hash: &mut TransactionsByHash, local: &mut LocalTransactionsList) -> bool {
 		let order = TransactionOrder::for_transaction(&tx, base_nonce);
 		let hash = tx.hash();
 		let address = tx.sender();
@@ -829,6 +1216,7 @@ impl TransactionQueue {
 			let new_fee = order.gas_price;
 			if old_fee.cmp(&new_fee) == Ordering::Greater {
 				// Put back old transaction since it has greater priority (higher gas_price)
+				local.mark_replaced(&hash, old.hash, old_fee);
 				set.insert(address, nonce, old);
 				// and remove new one
 				by_hash.remove(&hash).expect("The hash has been just inserted and no other line is altering `by_hash`.");
@@ -836,6 +1224,7 @@ impl TransactionQueue {
 			} else {
 				// Make sure we remove old transaction entirely
 				by_hash.remove(&old.hash).expect("The hash is coming from `future` so it has to be in `by_hash`.");
+				local.mark_replaced(&old.hash, hash, new_fee);
 				true
 			}
 		} else {
@@ -874,8 +1263,11 @@ mod test {
 	use transaction::*;
 	use error::{Error, TransactionError};
 	use super::*;
-	use super::{TransactionSet, TransactionOrder, VerifiedTransaction};
+	use super::{TransactionSet, TransactionOrder, VerifiedTransaction, TransactionsByHash};
+	use miner::local_transactions::{LocalTransactionsList, LocalTransactionStatus};
+	use miner::transaction_filter::TransactionFilter;
 	use client::TransactionImportResult;
+	use ethjson;
 
 	fn unwrap_tx_err(err: Result<TransactionImportResult, Error>) -> TransactionError {
 		match err.unwrap_err() {
@@ -976,12 +1368,12 @@ mod test {
 			limit: 1
 		};
 		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
-		let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External).unwrap();
-		let tx2 = VerifiedTransaction::new(tx2, TransactionOrigin::External).unwrap();
+		let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External, None).unwrap();
+		let tx2 = VerifiedTransaction::new(tx2, TransactionOrigin::External, None).unwrap();
 		let mut by_hash = {
-			let mut x = HashMap::new();
-			let tx1 = VerifiedTransaction::new(tx1.transaction.clone(), TransactionOrigin::External).unwrap();
-			let tx2 = VerifiedTransaction::new(tx2.transaction.clone(), TransactionOrigin::External).unwrap();
+			let mut x = TransactionsByHash::default();
+			let tx1 = VerifiedTransaction::new(tx1.transaction.clone(), TransactionOrigin::External, None).unwrap();
+			let tx2 = VerifiedTransaction::new(tx2.transaction.clone(), TransactionOrigin::External, None).unwrap();
 			x.insert(tx1.hash(), tx1);
 			x.insert(tx2.hash(), tx2);
 			x
@@ -995,7 +1387,7 @@ mod test {
 		assert_eq!(set.by_address.len(), 2);
 
 		// when
-		set.enforce_limit(&mut by_hash);
+		set.enforce_limit(&mut by_hash, &mut LocalTransactionsList::default());
 
 		// then
 		assert_eq!(by_hash.len(), 1);
@@ -1018,12 +1410,12 @@ mod test {
 		// Create two transactions with same nonce
 		// (same hash)
 		let (tx1, tx2) = new_tx_pair_default(0.into(), 0.into());
-		let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External).unwrap();
-		let tx2 = VerifiedTransaction::new(tx2, TransactionOrigin::External).unwrap();
+		let tx1 = VerifiedTransaction::new(tx1, TransactionOrigin::External, None).unwrap();
+		let tx2 = VerifiedTransaction::new(tx2, TransactionOrigin::External, None).unwrap();
 		let by_hash = {
 			let mut x = HashMap::new();
-			let tx1 = VerifiedTransaction::new(tx1.transaction.clone(), TransactionOrigin::External).unwrap();
-			let tx2 = VerifiedTransaction::new(tx2.transaction.clone(), TransactionOrigin::External).unwrap();
+			let tx1 = VerifiedTransaction::new(tx1.transaction.clone(), TransactionOrigin::External, None).unwrap();
+			let tx2 = VerifiedTransaction::new(tx2.transaction.clone(), TransactionOrigin::External, None).unwrap();
 			x.insert(tx1.hash(), tx1);
 			x.insert(tx2.hash(), tx2);
 			x
@@ -1064,10 +1456,10 @@ mod test {
 			limit: 2
 		};
 		let tx = new_tx_default();
-		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External).unwrap();
+		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External, None).unwrap();
 		let order1 = TransactionOrder::for_transaction(&tx1, U256::zero());
 		assert!(set.insert(tx1.sender(), tx1.nonce(), order1).is_none());
-		let tx2 = VerifiedTransaction::new(tx, TransactionOrigin::External).unwrap();
+		let tx2 = VerifiedTransaction::new(tx, TransactionOrigin::External, None).unwrap();
 		let order2 = TransactionOrder::for_transaction(&tx2, U256::zero());
 		assert!(set.insert(tx2.sender(), tx2.nonce(), order2).is_some());
 	}
@@ -1083,7 +1475,7 @@ mod test {
 
 		assert_eq!(set.gas_price_entry_limit(), 0.into());
 		let tx = new_tx_default();
-		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External).unwrap();
+		let tx1 = VerifiedTransaction::new(tx.clone(), TransactionOrigin::External, None).unwrap();
 		let order1 = TransactionOrder::for_transaction(&tx1, U256::zero());
 		assert!(set.insert(tx1.sender(), tx1.nonce(), order1.clone()).is_none());
 		assert_eq!(set.gas_price_entry_limit(), 2.into());
@@ -1500,6 +1892,104 @@ mod test {
 		assert_eq!(txq.status().future, 1);
 	}
 
+	#[test]
+	fn should_limit_number_of_transactions_per_sender() {
+		// given
+		let mut txq = TransactionQueue::new();
+		txq.set_sender_limits(2, !U256::zero());
+		let keypair = Random.generate().unwrap();
+		let tx1 = new_unsigned_tx(default_nonce(), default_gas_price()).sign(keypair.secret());
+		let tx2 = new_unsigned_tx(default_nonce() + 1.into(), default_gas_price()).sign(keypair.secret());
+		let tx3 = new_unsigned_tx(default_nonce() + 2.into(), default_gas_price()).sign(keypair.secret());
+		let sender = tx1.sender().unwrap();
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// when
+		let res = txq.add(tx3, &default_account_details, TransactionOrigin::External);
+
+		// then
+		assert_eq!(unwrap_tx_err(res), TransactionError::LimitReached);
+		assert_eq!(txq.status().pending, 2);
+		assert_eq!(txq.last_nonce(&sender), Some(default_nonce() + U256::one()));
+		// other senders are not affected
+		txq.add(new_tx_default(), &default_account_details, TransactionOrigin::External).unwrap();
+		assert_eq!(txq.status().pending, 3);
+	}
+
+	#[test]
+	fn should_limit_gas_of_transactions_per_sender_but_not_local_ones() {
+		// given
+		let mut txq = TransactionQueue::new();
+		// each transaction uses 100_000 gas
+		txq.set_sender_limits(10, 150_000.into());
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let (tx3, tx4) = new_tx_pair_default(1.into(), 0.into());
+		txq.add(tx1, &default_account_details, TransactionOrigin::External).unwrap();
+
+		// when
+		let res = txq.add(tx2, &default_account_details, TransactionOrigin::External);
+		txq.add(tx3, &default_account_details, TransactionOrigin::Local).unwrap();
+		txq.add(tx4, &default_account_details, TransactionOrigin::Local).unwrap();
+
+		// then
+		assert_eq!(unwrap_tx_err(res), TransactionError::LimitReached);
+		assert_eq!(txq.status().pending, 3);
+	}
+
+	#[test]
+	fn should_drop_transactions_of_heaviest_sender_when_memory_limit_is_reached() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let (tx3, tx4) = new_tx_pair_default(1.into(), 0.into());
+		let tx5 = new_tx_default();
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		let tx_size = txq.mem_usage();
+		// leave some room, signatures may differ in size
+		txq.set_memory_limit(4 * tx_size + tx_size / 2);
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		txq.add(tx3.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		txq.add(tx4.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		assert_eq!(txq.status().pending, 4);
+
+		// when
+		txq.add(tx5.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// then
+		let top = txq.top_transactions();
+		assert_eq!(top.len(), 4);
+		assert!(top.contains(&tx1));
+		assert!(!top.contains(&tx2));
+		assert!(top.contains(&tx3));
+		assert!(top.contains(&tx4));
+		assert!(top.contains(&tx5));
+		assert_eq!(txq.last_nonce(&tx1.sender().unwrap()), Some(tx1.nonce));
+		assert!(txq.mem_usage() <= 4 * tx_size + tx_size / 2);
+	}
+
+	#[test]
+	fn should_reject_transactions_not_permitted_by_filter() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let tx3 = new_tx_default();
+		let policy = format!(r#"{{ "denySenders": ["0x{:?}"] }}"#, tx1.sender().unwrap());
+		let filter: TransactionFilter = ethjson::misc::TransactionFilter::load(policy.as_bytes()).unwrap().into();
+		txq.set_filter(Some(filter));
+
+		// when
+		let res1 = txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External);
+		let res2 = txq.add(tx2.clone(), &default_account_details, TransactionOrigin::Local);
+		txq.add(tx3.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+
+		// then
+		assert_eq!(unwrap_tx_err(res1), TransactionError::NotAllowed);
+		assert_eq!(unwrap_tx_err(res2), TransactionError::NotAllowed);
+		assert_eq!(txq.top_transactions(), vec![tx3]);
+		assert_eq!(txq.local_transaction_history()[0].status, LocalTransactionStatus::Rejected(TransactionError::NotAllowed));
+	}
+
 	#[test]
 	fn should_drop_transactions_with_old_nonces() {
 		let mut txq = TransactionQueue::new();
@@ -1789,4 +2279,107 @@ mod test {
 		assert_eq!(txq.current.by_priority.len(), 3);
 	}
 
+	#[test]
+	fn should_track_local_transactions_history() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let (tx1, tx2) = new_similar_tx_pair();
+		let future = new_tx(default_nonce() + U256::from(2), default_gas_price());
+		let old = new_tx(default_nonce() - U256::one(), default_gas_price());
+		let external = new_tx_default();
+
+		// when
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		txq.add(future.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		txq.add(external.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		assert!(txq.add(old.clone(), &default_account_details, TransactionOrigin::Local).is_err());
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		txq.mark_mined(&tx2.hash(), 10, H256::from(5));
+
+		// then
+		let statuses = txq.local_transaction_history().into_iter()
+			.map(|tx| (tx.transaction.hash(), tx.status))
+			.collect::<Vec<_>>();
+		assert_eq!(statuses, vec![
+			(tx1.hash(), LocalTransactionStatus::Replaced { by: tx2.hash(), gas_price: tx2.gas_price }),
+			(future.hash(), LocalTransactionStatus::Future),
+			(old.hash(), LocalTransactionStatus::Rejected(TransactionError::Old)),
+			(tx2.hash(), LocalTransactionStatus::Mined { block_number: 10, block_hash: H256::from(5) }),
+		]);
+		assert!(txq.is_mined_local(&tx2.hash()));
+		assert!(!txq.is_mined_local(&external.hash()));
+	}
+
+	#[test]
+	fn should_mark_local_transactions_dropped_on_clear() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let tx = new_tx_default();
+		txq.add(tx.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		assert_eq!(txq.local_transaction_history()[0].status, LocalTransactionStatus::Pending);
+
+		// when
+		txq.clear();
+
+		// then
+		assert_eq!(txq.local_transaction_history()[0].status, LocalTransactionStatus::Dropped);
+	}
+
+	#[test]
+	fn should_return_future_transactions_and_limits() {
+		// given
+		let mut txq = TransactionQueue::with_limits(1, U256::from(200_000));
+		txq.set_minimal_gas_price(U256::from(2));
+		let tx1 = new_tx(default_nonce(), 3.into());
+
+		// when
+		txq.add(tx1.clone(), &default_account_details, TransactionOrigin::External).unwrap();
+		let tx3 = new_tx(default_nonce() + U256::from(5), 3.into());
+		txq.add(tx3.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+
+		// then
+		assert_eq!(txq.top_transactions(), vec![tx1]);
+		assert_eq!(txq.future_transactions(), vec![tx3]);
+		assert_eq!(txq.status(), TransactionQueueStatus {
+			pending: 1,
+			future: 1,
+			limit: 1,
+			gas_limit: !U256::zero(),
+			tx_gas_limit: U256::from(200_000),
+			minimal_gas_price: U256::from(2),
+			effective_minimum_gas_price: U256::from(4),
+		});
+	}
+
+	#[test]
+	fn should_hold_back_transactions_until_condition_holds() {
+		// given
+		let mut txq = TransactionQueue::new();
+		let (tx1, tx2) = new_tx_pair_default(1.into(), 0.into());
+		let tx3 = new_tx_default();
+		txq.add_with_condition(tx1.clone(), &default_account_details, TransactionOrigin::Local, Some(Condition::Number(5))).unwrap();
+		txq.add(tx2.clone(), &default_account_details, TransactionOrigin::Local).unwrap();
+		txq.add_with_condition(tx3.clone(), &default_account_details, TransactionOrigin::External, Some(Condition::Timestamp(100))).unwrap();
+		assert_eq!(txq.status().pending, 3);
+		assert_eq!(txq.top_transactions().len(), 0);
+		assert_eq!(txq.current_transactions().len(), 3);
+		assert!(!txq.has_local_pending_transactions());
+
+		// when
+		txq.set_best_block(5, 99);
+
+		// then
+		assert_eq!(txq.top_transactions(), vec![tx1.clone(), tx2.clone()]);
+		assert_eq!(txq.local_transactions(), vec![
+			PendingTransaction::new(tx1.clone(), Some(Condition::Number(5))),
+			PendingTransaction::new(tx2.clone(), None),
+		]);
+
+		// when
+		txq.set_best_block(6, 100);
+
+		// then
+		assert_eq!(txq.top_transactions().len(), 3);
+		assert_eq!(txq.pending_hashes().len(), 3);
+	}
 }
@@ -26,10 +26,75 @@ use hyper::Url;
 use util::*;
 use ethereum::ethash::Ethash;
 
+/// Trait for notifying about new mining work
+pub trait NotifyWork : Send + Sync {
+	/// Fired when new mining job available
+	fn notify(&self, pow_hash: H256, difficulty: U256, number: u64);
+}
+
+/// Subscribers of new work packages, shared by work notifiers.
+/// Remembers the most recent package so that new subscribers can start working right away.
+pub struct WorkSubscribers<S> {
+	subscribers: Mutex<Vec<S>>,
+	last_package: Mutex<Option<String>>,
+	seed_compute: Mutex<SeedHashCompute>,
+}
+
+impl<S> WorkSubscribers<S> {
+	/// Creates bookkeeping for given initial subscribers.
+	pub fn new(subscribers: Vec<S>) -> Self {
+		WorkSubscribers {
+			subscribers: Mutex::new(subscribers),
+			last_package: Mutex::new(None),
+			seed_compute: Mutex::new(SeedHashCompute::new()),
+		}
+	}
+
+	/// Adds a subscriber. Returns the most recent work package, if any.
+	pub fn add(&self, subscriber: S) -> Option<String> {
+		self.subscribers.lock().push(subscriber);
+		self.last_package.lock().clone()
+	}
+
+	/// Removes subscribers matching given predicate.
+	pub fn remove<F>(&self, f: F) where F: Fn(&S) -> bool {
+		self.subscribers.lock().retain(|s| !f(s));
+	}
+
+	/// Number of subscribers.
+	pub fn len(&self) -> usize {
+		self.subscribers.lock().len()
+	}
+
+	/// Whether there are no subscribers.
+	pub fn is_empty(&self) -> bool {
+		self.subscribers.lock().is_empty()
+	}
+
+	/// Formats work package as `{ "result": [pow hash, seed hash, target, number] }`, same as `eth_getWork` returns.
+	pub fn package(&self, pow_hash: H256, difficulty: U256, number: u64) -> String {
+		// TODO: move this to engine
+		let target = Ethash::difficulty_to_boundary(&difficulty);
+		let seed_hash = &self.seed_compute.lock().get_seedhash(number);
+		let seed_hash = H256::from_slice(&seed_hash[..]);
+		format!(
+			r#"{{ "result": ["0x{}","0x{}","0x{}","0x{:x}"] }}"#,
+			pow_hash.hex(), seed_hash.hex(), target.hex(), number
+		)
+	}
+
+	/// Sends new work package to all subscribers. Subscribers for which `send` returns `false` are dropped.
+	pub fn notify<F>(&self, pow_hash: H256, difficulty: U256, number: u64, mut send: F) where F: FnMut(&S, &str) -> bool {
+		let package = self.package(pow_hash, difficulty, number);
+		*self.last_package.lock() = Some(package.clone());
+		self.subscribers.lock().retain(|s| send(s, &package));
+	}
+}
+
+/// POSTs info about new work to given urls.
 pub struct WorkPoster {
-	urls: Vec<Url>,
+	subscribers: WorkSubscribers<Url>,
 	client: Mutex<Client<PostHandler>>,
-	seed_compute: Mutex<SeedHashCompute>,
 }
 
 impl WorkPoster {
@@ -46,8 +111,7 @@ impl WorkPoster {
 		let client = WorkPoster::create_client();
 		WorkPoster {
 			client: Mutex::new(client),
-			urls: urls,
-			seed_compute: Mutex::new(SeedHashCompute::new()),
+			subscribers: WorkSubscribers::new(urls),
 		}
 	}
 
@@ -57,27 +121,22 @@ impl WorkPoster {
 			.build()
 			.expect("Error creating HTTP client")
 	}
+}
 
-	pub fn notify(&self, pow_hash: H256, difficulty: U256, number: u64) {
-		// TODO: move this to engine
-		let target = Ethash::difficulty_to_boundary(&difficulty);
-		let seed_hash = &self.seed_compute.lock().get_seedhash(number);
-		let seed_hash = H256::from_slice(&seed_hash[..]);
-		let body = format!(
-			r#"{{ "result": ["0x{}","0x{}","0x{}","0x{:x}"] }}"#,
-			pow_hash.hex(), seed_hash.hex(), target.hex(), number
-		);
+impl NotifyWork for WorkPoster {
+	fn notify(&self, pow_hash: H256, difficulty: U256, number: u64) {
 		let mut client = self.client.lock();
-		for u in &self.urls {
-			if let Err(e) = client.request(u.clone(), PostHandler { body: body.clone() }) {
+		self.subscribers.notify(pow_hash, difficulty, number, |u, body| {
+			if let Err(e) = client.request(u.clone(), PostHandler { body: body.to_owned() }) {
 				warn!("Error sending HTTP notification to {} : {}, retrying", u, e);
 				// TODO: remove this once https://github.com/hyperium/hyper/issues/848 is fixed
 				*client = WorkPoster::create_client();
-				if let Err(e) = client.request(u.clone(), PostHandler { body: body.clone() }) {
+				if let Err(e) = client.request(u.clone(), PostHandler { body: body.to_owned() }) {
 					warn!("Error sending HTTP notification to {} : {}", u, e);
 				}
 			}
-		}
+			true
+		});
 	}
 }
 
@@ -115,3 +174,29 @@ impl hyper::client::Handler<HttpStream> for PostHandler {
 	}
 }
 
+#[cfg(test)]
+mod tests {
+	use util::{H256, U256};
+	use super::WorkSubscribers;
+
+	#[test]
+	fn should_send_work_to_subscribers() {
+		let subscribers = WorkSubscribers::new(vec![1, 2]);
+		assert_eq!(subscribers.add(3), None);
+
+		let mut sent = Vec::new();
+		subscribers.notify(H256::from(1), U256::from(1), 0, |s, _| {
+			sent.push(*s);
+			*s != 2
+		});
+		assert_eq!(sent, vec![1, 2, 3]);
+		// subscribers failing to receive work are dropped
+		assert_eq!(subscribers.len(), 2);
+
+		let package = subscribers.add(4).unwrap();
+		assert!(package.starts_with(&format!(r#"{{ "result": ["0x{}","#, H256::from(1).hex())));
+		assert!(package.ends_with(r#","0x0"] }"#));
+		subscribers.remove(|s| *s == 1);
+		assert_eq!(subscribers.len(), 2);
+	}
+}
@@ -0,0 +1,320 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! WebSocket endpoint pushing new work packages to connected miners and accepting their solutions.
+//!
+//! Every connection is subscribed to work packages in the `{ "result": [pow hash, seed hash, target, number] }`
+//! format used for HTTP notifications. Solutions are submitted with `eth_submitWork` requests:
+//! `{"id":1,"method":"eth_submitWork","params":["0x<nonce>","0x<pow hash>","0x<mix hash>"]}`.
+
+use std::fmt;
+use std::thread;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::net::{SocketAddr, TcpListener, AddrParseError};
+use rustc_serialize::json::Json;
+use ws;
+use util::{H256, H64, U256, clean_0x};
+use rlp;
+use client::{Client, MiningBlockChainClient};
+use miner::{Miner, MinerService};
+use miner::work_notify::{NotifyWork, WorkSubscribers};
+
+/// Work socket options.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WorkSocketOptions {
+	/// Interface to listen on.
+	pub listen_addr: String,
+	/// Port to listen on.
+	pub port: u16,
+}
+
+/// Error starting the work socket.
+#[derive(Debug)]
+pub enum Error {
+	/// Listen address is invalid.
+	Address(AddrParseError),
+	/// Server failed to start.
+	Service(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::Address(ref err) => write!(f, "Invalid work socket listen address: {}", err),
+			Error::Service(ref err) => write!(f, "Unable to start work socket: {}", err),
+		}
+	}
+}
+
+impl From<AddrParseError> for Error {
+	fn from(err: AddrParseError) -> Self {
+		Error::Address(err)
+	}
+}
+
+/// Connected miners identified by connection number.
+type Subscribers = WorkSubscribers<(usize, ws::Sender)>;
+
+/// Submits solutions received from connected miners.
+struct Solutions {
+	miner: Weak<Miner>,
+	client: Weak<MiningBlockChainClient>,
+}
+
+impl Solutions {
+	/// Parses `eth_submitWork` params and submits the seal. Returns whether the solution was accepted.
+	fn submit(&self, params: &[Json]) -> Result<bool, String> {
+		if params.len() != 3 {
+			return Err(format!("Expected nonce, pow hash and mix hash, got {} params", params.len()));
+		}
+		let param = |i: usize| params[i].as_string().map(clean_0x).ok_or_else(|| format!("Invalid param {}", i));
+		let nonce: H64 = try!(try!(param(0)).parse().map_err(|_| "Invalid nonce".to_owned()));
+		let pow_hash: H256 = try!(try!(param(1)).parse().map_err(|_| "Invalid pow hash".to_owned()));
+		let mix_hash: H256 = try!(try!(param(2)).parse().map_err(|_| "Invalid mix hash".to_owned()));
+		trace!(target: "miner", "Work socket solution: nonce={}, pow_hash={}, mix_hash={}", nonce, pow_hash, mix_hash);
+
+		let seal = vec![rlp::encode(&mix_hash).to_vec(), rlp::encode(&nonce).to_vec()];
+		match (self.client.upgrade(), self.miner.upgrade()) {
+			(Some(client), Some(miner)) => Ok(miner.submit_seal(&*client, pow_hash, seal).is_ok()),
+			_ => Err("Miner is not available".into()),
+		}
+	}
+
+	/// Handles a request message and returns the response.
+	fn handle(&self, message: &str) -> String {
+		let request = match Json::from_str(message) {
+			Ok(Json::Object(request)) => request,
+			_ => return error_response(&Json::Null, "Invalid request"),
+		};
+		let id = request.get("id").cloned().unwrap_or(Json::Null);
+		let params = match request.get("params") {
+			Some(&Json::Array(ref params)) => params.clone(),
+			_ => Vec::new(),
+		};
+		match request.get("method").and_then(Json::as_string) {
+			Some("eth_submitWork") => match self.submit(&params) {
+				Ok(accepted) => format!(r#"{{"id":{},"result":{}}}"#, id, accepted),
+				Err(e) => error_response(&id, &e),
+			},
+			Some(method) => error_response(&id, &format!("Unknown method: {}", method)),
+			None => error_response(&id, "Missing method"),
+		}
+	}
+}
+
+fn error_response(id: &Json, message: &str) -> String {
+	format!(r#"{{"id":{},"error":{}}}"#, id, Json::String(message.to_owned()))
+}
+
+struct Session {
+	id: usize,
+	out: ws::Sender,
+	subscribers: Arc<Subscribers>,
+	solutions: Arc<Solutions>,
+}
+
+impl ws::Handler for Session {
+	fn on_open(&mut self, _shake: ws::Handshake) -> ws::Result<()> {
+		trace!(target: "miner", "Work socket connection #{} opened", self.id);
+		match self.subscribers.add((self.id, self.out.clone())) {
+			Some(package) => self.out.send(package),
+			None => Ok(()),
+		}
+	}
+
+	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+		let response = self.solutions.handle(try!(msg.as_text()));
+		self.out.send(response)
+	}
+
+	fn on_close(&mut self, _code: ws::CloseCode, _reason: &str) {
+		trace!(target: "miner", "Work socket connection #{} closed", self.id);
+		let id = self.id;
+		self.subscribers.remove(|&(s, _)| s == id);
+	}
+}
+
+struct Factory {
+	next_id: AtomicUsize,
+	subscribers: Arc<Subscribers>,
+	solutions: Arc<Solutions>,
+}
+
+impl ws::Factory for Factory {
+	type Handler = Session;
+
+	fn connection_made(&mut self, sender: ws::Sender) -> Self::Handler {
+		Session {
+			id: self.next_id.fetch_add(1, AtomicOrdering::Relaxed),
+			out: sender,
+			subscribers: self.subscribers.clone(),
+			solutions: self.solutions.clone(),
+		}
+	}
+}
+
+/// Pushes new work packages to miners connected to the work socket.
+struct WorkSocketNotifier {
+	subscribers: Arc<Subscribers>,
+}
+
+impl NotifyWork for WorkSocketNotifier {
+	fn notify(&self, pow_hash: H256, difficulty: U256, number: u64) {
+		self.subscribers.notify(pow_hash, difficulty, number, |&(id, ref out), package| {
+			match out.send(package) {
+				Ok(_) => true,
+				Err(e) => {
+					trace!(target: "miner", "Dropping work socket connection #{}: {:?}", id, e);
+					false
+				}
+			}
+		});
+	}
+}
+
+/// WebSocket server streaming work packages. Closed when dropped.
+pub struct WorkSocket {
+	subscribers: Arc<Subscribers>,
+	broadcaster: ws::Sender,
+	handle: Option<thread::JoinHandle<()>>,
+}
+
+impl WorkSocket {
+	/// Starts the server and registers it for new work notifications with the miner.
+	pub fn register(
+		options: &WorkSocketOptions,
+		miner: Arc<Miner>,
+		client: Arc<Client>,
+	) -> Result<WorkSocket, Error> {
+		let addr = try!(format!("{}:{}", options.listen_addr, options.port).parse::<SocketAddr>());
+		// the server binds only once its event loop is running in the spawned thread,
+		// so check the address can be bound here to report failures to the caller.
+		try!(TcpListener::bind(addr).map_err(|e| Error::Service(format!("{}", e))));
+		let client: Arc<MiningBlockChainClient> = client;
+		let subscribers = Arc::new(WorkSubscribers::new(Vec::new()));
+		let factory = Factory {
+			next_id: AtomicUsize::new(0),
+			subscribers: subscribers.clone(),
+			solutions: Arc::new(Solutions {
+				miner: Arc::downgrade(&miner),
+				client: Arc::downgrade(&client),
+			}),
+		};
+		let socket = try!(ws::WebSocket::new(factory).map_err(|e| Error::Service(format!("{:?}", e))));
+		let broadcaster = socket.broadcaster();
+		let handle = try!(thread::Builder::new()
+			.name("Work Socket".into())
+			.spawn(move || {
+				if let Err(e) = socket.listen(addr) {
+					warn!(target: "miner", "Work socket failed listening on {}: {:?}", addr, e);
+				}
+			})
+			.map_err(|e| Error::Service(format!("{}", e))));
+
+		miner.add_work_listener(Box::new(WorkSocketNotifier { subscribers: subscribers.clone() }));
+		// prepare work for the first subscribers
+		miner.map_sealing_work(&*client, |_| ());
+		info!("Work socket listening on {}", addr);
+
+		Ok(WorkSocket {
+			subscribers: subscribers,
+			broadcaster: broadcaster,
+			handle: Some(handle),
+		})
+	}
+
+	/// Number of connected miners.
+	pub fn connections(&self) -> usize {
+		self.subscribers.len()
+	}
+}
+
+impl Drop for WorkSocket {
+	fn drop(&mut self) {
+		if let Err(e) = self.broadcaster.shutdown() {
+			warn!(target: "miner", "Work socket was not closed cleanly: {:?}", e);
+		}
+		if let Some(handle) = self.handle.take() {
+			handle.join().unwrap();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use std::net::TcpListener;
+	use util::H256;
+	use client::{TestBlockChainClient, MiningBlockChainClient};
+	use miner::{Miner, MinerService};
+	use spec::Spec;
+	use tests::helpers::generate_dummy_client;
+	use super::{Solutions, WorkSocket, WorkSocketOptions, Error};
+
+	fn solutions(miner: &Arc<Miner>, client: &Arc<MiningBlockChainClient>) -> Solutions {
+		Solutions {
+			miner: Arc::downgrade(miner),
+			client: Arc::downgrade(client),
+		}
+	}
+
+	#[test]
+	fn should_accept_solutions() {
+		let client: Arc<MiningBlockChainClient> = Arc::new(TestBlockChainClient::default());
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let solutions = solutions(&miner, &client);
+		let pow_hash = miner.map_sealing_work(&*client, |b| b.hash()).unwrap();
+
+		// null engine accepts any seal
+		let request = format!(r#"{{"id":1,"method":"eth_submitWork","params":["0x0000000000000001","0x{}","0x{}"]}}"#, pow_hash.hex(), pow_hash.hex());
+		assert_eq!(solutions.handle(&request), r#"{"id":1,"result":true}"#);
+		// unknown work package
+		let request = format!(r#"{{"id":2,"method":"eth_submitWork","params":["0x0000000000000001","0x{}","0x{}"]}}"#, H256::from(1).hex(), pow_hash.hex());
+		assert_eq!(solutions.handle(&request), r#"{"id":2,"result":false}"#);
+	}
+
+	#[test]
+	fn should_reject_invalid_requests() {
+		let client: Arc<MiningBlockChainClient> = Arc::new(TestBlockChainClient::default());
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+		let solutions = solutions(&miner, &client);
+
+		assert_eq!(solutions.handle("foo"), r#"{"id":null,"error":"Invalid request"}"#);
+		assert_eq!(solutions.handle(r#"{"id":2,"method":"eth_getWork"}"#), r#"{"id":2,"error":"Unknown method: eth_getWork"}"#);
+		assert_eq!(
+			solutions.handle(r#"{"id":3,"method":"eth_submitWork","params":["0x01"]}"#),
+			r#"{"id":3,"error":"Expected nonce, pow hash and mix hash, got 1 params"}"#
+		);
+	}
+
+	#[test]
+	fn should_fail_to_register_on_address_in_use() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let options = WorkSocketOptions {
+			listen_addr: "127.0.0.1".into(),
+			port: listener.local_addr().unwrap().port(),
+		};
+		let client_result = generate_dummy_client(0);
+		let miner = Arc::new(Miner::with_spec(&Spec::new_test()));
+
+		match WorkSocket::register(&options, miner, client_result.reference().clone()) {
+			Err(Error::Service(_)) => {},
+			_ => panic!("Work socket should not start on address in use"),
+		}
+	}
+}
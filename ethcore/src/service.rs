@@ -146,15 +146,21 @@ struct ClientIoHandler {
 
 const CLIENT_TICK_TIMER: TimerToken = 0;
 const CLIENT_TICK_MS: u64 = 5000;
+const CLIENT_SEAL_TIMER: TimerToken = 1;
 
 impl IoHandler<ClientIoMessage> for ClientIoHandler {
 	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
 		io.register_timer(CLIENT_TICK_TIMER, CLIENT_TICK_MS).expect("Error registering client timer");
+		if let Some(interval) = self.client.seal_interval() {
+			io.register_timer(CLIENT_SEAL_TIMER, interval * 1000).expect("Error registering sealing timer");
+		}
 	}
 
 	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
-		if timer == CLIENT_TICK_TIMER {
-			self.client.tick();
+		match timer {
+			CLIENT_TICK_TIMER => self.client.tick(),
+			CLIENT_SEAL_TIMER => self.client.seal_on_interval(),
+			_ => {},
 		}
 	}
 
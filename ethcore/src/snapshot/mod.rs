@@ -31,7 +31,7 @@ use util::memorydb::MemoryDB;
 use util::Mutex;
 use util::hash::{FixedHash, H256};
 use util::journaldb::{self, Algorithm, JournalDB};
-use util::kvdb::Database;
+use util::kvdb::{Database, DBTransaction};
 use util::sha3::SHA3_NULL_RLP;
 use util::trie::{TrieDB, TrieDBMut, Trie, TrieMut};
 use rlp::{DecoderError, RlpStream, Stream, UntrustedRlp, View, Compressible, RlpType};
@@ -402,7 +402,23 @@ impl ManifestData {
 	}
 }
 
+// Key under which the state rebuilder stores its state root and the accounts
+// still missing code, in the extras column of the restoration database.
+const STATE_REBUILDER_KEY: &'static [u8] = b"snapshot_state_rebuilder";
+
+// Prefix for the keys of code already seen by the state rebuilder.
+const KNOWN_CODE_PREFIX: &'static [u8] = b"snapshot_known_code";
+
+fn known_code_key(code_hash: &H256) -> Vec<u8> {
+	let mut key = KNOWN_CODE_PREFIX.to_vec();
+	key.extend_from_slice(&code_hash[..]);
+	key
+}
+
 /// Used to rebuild the state trie piece by piece.
+///
+/// The rebuilder's bookkeeping is written to the backing database along with
+/// every chunk, so an interrupted restoration can be picked up with `resume`.
 pub struct StateRebuilder {
 	db: Box<JournalDB>,
 	state_root: H256,
@@ -421,8 +437,45 @@ impl StateRebuilder {
 		}
 	}
 
+	/// Resume a state rebuilder from the bookkeeping previously written into
+	/// the given backing DB. Returns `None` if nothing was written yet.
+	pub fn resume(db: Arc<Database>, pruning: Algorithm) -> Result<Option<Self>, ::error::Error> {
+		let raw = match try!(db.get(::db::COL_EXTRA, STATE_REBUILDER_KEY).map_err(::util::UtilError::SimpleString)) {
+			Some(raw) => raw,
+			None => return Ok(None),
+		};
+
+		let rlp = UntrustedRlp::new(&raw);
+		let state_root: H256 = try!(rlp.val_at(0));
+
+		let mut missing_code = HashMap::new();
+		for entry in try!(rlp.at(1)).iter() {
+			let code_hash: H256 = try!(entry.val_at(0));
+			let accounts: Vec<H256> = try!(entry.val_at(1));
+			missing_code.insert(code_hash, accounts);
+		}
+
+		let mut code_map = HashMap::new();
+		for (key, code) in db.iter(::db::COL_EXTRA) {
+			if key.len() == KNOWN_CODE_PREFIX.len() + 32 && key.starts_with(KNOWN_CODE_PREFIX) {
+				code_map.insert(H256::from_slice(&key[KNOWN_CODE_PREFIX.len()..]), code.into_vec());
+			}
+		}
+
+		trace!(target: "snapshot", "resuming state restoration at root {:?} with {} known codes", state_root, code_map.len());
+
+		Ok(Some(StateRebuilder {
+			db: journaldb::new(db.clone(), pruning, ::db::COL_STATE),
+			state_root: state_root,
+			code_map: code_map,
+			missing_code: missing_code,
+		}))
+	}
+
 	/// Feed an uncompressed state chunk into the rebuilder.
-	pub fn feed(&mut self, chunk: &[u8]) -> Result<(), ::error::Error> {
+	/// The trie nodes and bookkeeping are put into the given batch, which
+	/// must be written before the next chunk is fed.
+	pub fn feed(&mut self, chunk: &[u8], batch: &mut DBTransaction) -> Result<(), ::error::Error> {
 		let rlp = UntrustedRlp::new(chunk);
 		let account_fat_rlps: Vec<_> = rlp.iter().map(|r| r.as_raw()).collect();
 		let mut pairs = Vec::with_capacity(rlp.item_count());
@@ -468,13 +521,16 @@ impl StateRebuilder {
 		}));
 
 		// patch up all missing code. must be done after collecting all new missing code entries.
+		let mut new_code_hashes = Vec::new();
 		for (code_hash, code) in chunk_code {
 			for addr_hash in self.missing_code.remove(&code_hash).unwrap_or_else(Vec::new) {
 				let mut db = AccountDBMut::from_hash(self.db.as_hashdb_mut(), addr_hash);
 				db.emplace(code_hash, code.clone());
 			}
 
-			self.code_map.insert(code_hash, code);
+			if self.code_map.insert(code_hash, code).is_none() {
+				new_code_hashes.push(code_hash);
+			}
 		}
 
 
@@ -491,11 +547,41 @@ impl StateRebuilder {
 			}
 		}
 
+		try!(self.db.inject(batch));
+
+		// store the bookkeeping alongside the trie nodes so they're always in sync.
+		for code_hash in new_code_hashes {
+			batch.put(::db::COL_EXTRA, &known_code_key(&code_hash), &self.code_map[&code_hash]);
+		}
+		batch.put(::db::COL_EXTRA, STATE_REBUILDER_KEY, &self.bookkeeping_rlp());
+
+		trace!(target: "snapshot", "current state root: {:?}", self.state_root);
+		Ok(())
+	}
+
+	// encode the state root and accounts missing code.
+	fn bookkeeping_rlp(&self) -> Bytes {
+		let mut stream = RlpStream::new_list(2);
+		stream.append(&self.state_root);
+		stream.begin_list(self.missing_code.len());
+		for (code_hash, accounts) in &self.missing_code {
+			stream.begin_list(2).append(code_hash).append(accounts);
+		}
+
+		stream.out()
+	}
+
+	/// Remove the bookkeeping used for resumption from the backing DB.
+	/// To be called once restoration has completed.
+	pub fn clear_bookkeeping(&self) -> Result<(), ::error::Error> {
 		let backing = self.db.backing().clone();
 		let mut batch = backing.transaction();
-		try!(self.db.inject(&mut batch));
+		for code_hash in self.code_map.keys() {
+			batch.delete(::db::COL_EXTRA, &known_code_key(code_hash));
+		}
+		batch.delete(::db::COL_EXTRA, STATE_REBUILDER_KEY);
+
 		try!(backing.write(batch).map_err(::util::UtilError::SimpleString));
-		trace!(target: "snapshot", "current state root: {:?}", self.state_root);
 		Ok(())
 	}
 
@@ -590,7 +676,21 @@ impl BlockRebuilder {
 		})
 	}
 
+	/// Resume a BlockRebuilder from an interrupted restoration, given the
+	/// disconnected chunk starts it had recorded.
+	pub fn resume(chain: BlockChain, best_number: u64, disconnected: Vec<(u64, H256)>) -> Result<Self, ::error::Error> {
+		let mut rebuilder = try!(BlockRebuilder::new(chain, best_number));
+		rebuilder.disconnected = disconnected;
+		Ok(rebuilder)
+	}
+
+	/// Get the first blocks of chunks which haven't been connected to their parents yet.
+	pub fn disconnected(&self) -> &[(u64, H256)] {
+		&self.disconnected
+	}
+
 	/// Feed the rebuilder an uncompressed block chunk.
+	/// The blocks are buffered in the database, which must be flushed to persist them.
 	/// Returns the number of blocks fed or any errors.
 	pub fn feed(&mut self, chunk: &[u8], engine: &Engine) -> Result<u64, ::error::Error> {
 		use basic_types::Seal::With;
@@ -650,6 +750,9 @@ impl BlockRebuilder {
 			if let Some(parent_hash) = self.chain.block_hash(parent_num) {
 				// if so, add the child to it.
 				self.chain.add_child(parent_hash, first_hash);
+			} else {
+				// otherwise the chain starts here; older blocks are filled in later by sync.
+				self.chain.set_first_block(first_hash);
 			}
 		}
 	}
@@ -36,8 +36,9 @@ use io::IoChannel;
 
 use util::{Bytes, H256, Mutex, RwLock, UtilError};
 use util::journaldb::Algorithm;
-use util::kvdb::{Database, DatabaseConfig};
+use util::kvdb::{Database, DatabaseConfig, DBTransaction};
 use util::snappy;
+use rlp::{RlpStream, Stream, UntrustedRlp, View};
 
 /// Statuses for restorations.
 #[derive(PartialEq, Clone, Copy, Debug)]
@@ -86,6 +87,58 @@ pub trait SnapshotService {
 	fn restore_block_chunk(&self, hash: H256, chunk: Bytes);
 }
 
+// Key in the extras column of the restoration database under which the
+// progress of the restoration is stored.
+const RESTORATION_PROGRESS_KEY: &'static [u8] = b"snapshot_restoration_progress";
+
+// Progress of a restoration, as persisted after every fed chunk.
+struct RestorationProgress {
+	manifest: ManifestData,
+	state_chunks_done: Vec<H256>,
+	block_chunks_done: Vec<H256>,
+	disconnected: Vec<(u64, H256)>,
+}
+
+impl RestorationProgress {
+	// read the progress from the restoration database, if there is any.
+	fn read(db: &Database) -> Result<Option<Self>, Error> {
+		let raw = match try!(db.get(::db::COL_EXTRA, RESTORATION_PROGRESS_KEY).map_err(UtilError::SimpleString)) {
+			Some(raw) => raw,
+			None => return Ok(None),
+		};
+
+		let rlp = UntrustedRlp::new(&raw);
+		let manifest = try!(ManifestData::from_rlp(try!(rlp.at(0)).as_raw()));
+		let state_chunks_done = try!(rlp.val_at(1));
+		let block_chunks_done = try!(rlp.val_at(2));
+
+		let mut disconnected = Vec::new();
+		for item in try!(rlp.at(3)).iter() {
+			disconnected.push((try!(item.val_at(0)), try!(item.val_at(1))));
+		}
+
+		Ok(Some(RestorationProgress {
+			manifest: manifest,
+			state_chunks_done: state_chunks_done,
+			block_chunks_done: block_chunks_done,
+			disconnected: disconnected,
+		}))
+	}
+
+	fn rlp_bytes(&self) -> Bytes {
+		let mut stream = RlpStream::new_list(4);
+		stream.append_raw(&self.manifest.clone().into_rlp(), 1);
+		stream.append(&self.state_chunks_done);
+		stream.append(&self.block_chunks_done);
+		stream.begin_list(self.disconnected.len());
+		for &(number, ref hash) in &self.disconnected {
+			stream.begin_list(2).append(&number).append(hash);
+		}
+
+		stream.out()
+	}
+}
+
 /// State restoration manager.
 struct Restoration {
 	manifest: ManifestData,
@@ -96,6 +149,7 @@ struct Restoration {
 	writer: LooseWriter,
 	snappy_buffer: Bytes,
 	final_state_root: H256,
+	db: Arc<Database>,
 }
 
 struct RestorationParams<'a> {
@@ -107,40 +161,91 @@ struct RestorationParams<'a> {
 }
 
 impl Restoration {
-	// make a new restoration using the given parameters.
+	// make a new restoration using the given parameters, resuming
+	// from any progress stored in the database for the same manifest.
 	fn new(params: RestorationParams) -> Result<Self, Error> {
 		let manifest = params.manifest;
 
-		let state_chunks = manifest.state_hashes.iter().cloned().collect();
-		let block_chunks = manifest.block_hashes.iter().cloned().collect();
+		let mut state_chunks: HashSet<H256> = manifest.state_hashes.iter().cloned().collect();
+		let mut block_chunks: HashSet<H256> = manifest.block_hashes.iter().cloned().collect();
 
 		let cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
 		let raw_db = Arc::new(try!(Database::open(&cfg, &*params.db_path.to_string_lossy())
 			.map_err(UtilError::SimpleString)));
 
 		let chain = BlockChain::new(Default::default(), params.genesis, raw_db.clone());
-		let blocks = try!(BlockRebuilder::new(chain, manifest.block_number));
+
+		let progress = try!(RestorationProgress::read(&raw_db)).and_then(|p| match p.manifest == manifest {
+			true => Some(p),
+			false => None,
+		});
+
+		let (state, blocks) = match progress {
+			Some(progress) => {
+				info!("Resuming snapshot restoration with {}/{} state chunks and {}/{} block chunks completed.",
+					progress.state_chunks_done.len(), state_chunks.len(),
+					progress.block_chunks_done.len(), block_chunks.len());
+
+				for hash in &progress.state_chunks_done { state_chunks.remove(hash); }
+				for hash in &progress.block_chunks_done { block_chunks.remove(hash); }
+
+				let state = match try!(StateRebuilder::resume(raw_db.clone(), params.pruning)) {
+					Some(state) => state,
+					None => StateRebuilder::new(raw_db.clone(), params.pruning),
+				};
+
+				(state, try!(BlockRebuilder::resume(chain, manifest.block_number, progress.disconnected)))
+			}
+			None => (
+				StateRebuilder::new(raw_db.clone(), params.pruning),
+				try!(BlockRebuilder::new(chain, manifest.block_number)),
+			),
+		};
 
 		let root = manifest.state_root.clone();
 		Ok(Restoration {
 			manifest: manifest,
 			state_chunks_left: state_chunks,
 			block_chunks_left: block_chunks,
-			state: StateRebuilder::new(raw_db, params.pruning),
+			state: state,
 			blocks: blocks,
 			writer: params.writer,
 			snappy_buffer: Vec::new(),
 			final_state_root: root,
+			db: raw_db,
 		})
 	}
 
+	// the manifest of the restoration whose progress is stored in the database at the given path.
+	fn stored_manifest(db_path: &Path) -> Option<ManifestData> {
+		// opening creates the database, so don't probe a path which holds none.
+		if !db_path.exists() {
+			return None;
+		}
+
+		let cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+		Database::open(&cfg, &*db_path.to_string_lossy()).ok()
+			.and_then(|db| RestorationProgress::read(&db).ok())
+			.and_then(|p| p)
+			.map(|p| p.manifest)
+	}
+
+	// whether the restoration database at the given path holds progress for the given manifest.
+	fn has_progress(db_path: &Path, manifest: &ManifestData) -> bool {
+		Restoration::stored_manifest(db_path).map_or(false, |m| &m == manifest)
+	}
+
 	// feeds a state chunk
 	fn feed_state(&mut self, hash: H256, chunk: &[u8]) -> Result<(), Error> {
 		if self.state_chunks_left.remove(&hash) {
 			let len = try!(snappy::decompress_into(chunk, &mut self.snappy_buffer));
 
-			try!(self.state.feed(&self.snappy_buffer[..len]));
+			// the trie nodes and the progress are committed together.
+			let mut batch = self.db.transaction();
+			try!(self.state.feed(&self.snappy_buffer[..len], &mut batch));
 			try!(self.writer.write_state_chunk(hash, chunk));
+			self.put_progress(&mut batch);
+			try!(self.db.write(batch).map_err(UtilError::SimpleString));
 		}
 
 		Ok(())
@@ -151,13 +256,41 @@ impl Restoration {
 		if self.block_chunks_left.remove(&hash) {
 			let len = try!(snappy::decompress_into(chunk, &mut self.snappy_buffer));
 
+			// the blocks are buffered, so flushing commits them along with the progress.
 			try!(self.blocks.feed(&self.snappy_buffer[..len], engine));
 			try!(self.writer.write_block_chunk(hash, chunk));
+			let mut batch = self.db.transaction();
+			self.put_progress(&mut batch);
+			self.db.write_buffered(batch);
+			try!(self.db.flush().map_err(UtilError::SimpleString));
 		}
 
 		Ok(())
 	}
 
+	// put the completed chunks and rebuilder state into the batch so the
+	// restoration can be resumed after a restart.
+	fn put_progress(&self, batch: &mut DBTransaction) {
+		let progress = RestorationProgress {
+			manifest: self.manifest.clone(),
+			state_chunks_done: self.chunks_done(&self.manifest.state_hashes, &self.state_chunks_left),
+			block_chunks_done: self.chunks_done(&self.manifest.block_hashes, &self.block_chunks_left),
+			disconnected: self.blocks.disconnected().to_vec(),
+		};
+
+		batch.put(::db::COL_EXTRA, RESTORATION_PROGRESS_KEY, &progress.rlp_bytes());
+	}
+
+	fn chunks_done(&self, all: &[H256], left: &HashSet<H256>) -> Vec<H256> {
+		all.iter().filter(|h| !left.contains(h)).cloned().collect()
+	}
+
+	// number of (state, block) chunks completed so far.
+	fn chunks_completed(&self) -> (usize, usize) {
+		(self.manifest.state_hashes.len() - self.state_chunks_left.len(),
+			self.manifest.block_hashes.len() - self.block_chunks_left.len())
+	}
+
 	// finish up restoration.
 	fn finalize(self) -> Result<(), Error> {
 		use util::trie::TrieError;
@@ -172,8 +305,14 @@ impl Restoration {
 		}
 
 		// check for missing code.
+		try!(self.state.clear_bookkeeping());
 		try!(self.state.check_missing());
 
+		// the progress is of no use once the database replaces the client's.
+		let mut batch = self.db.transaction();
+		batch.delete(::db::COL_EXTRA, RESTORATION_PROGRESS_KEY);
+		try!(self.db.write(batch).map_err(UtilError::SimpleString));
+
 		// connect out-of-order chunks.
 		self.blocks.glue_chunks();
 
@@ -245,10 +384,14 @@ impl Service {
 			}
 		}
 
-		// delete the temporary restoration dir if it does exist.
-		if let Err(e) = fs::remove_dir_all(service.restoration_dir()) {
-			if e.kind() != ErrorKind::NotFound {
-				return Err(e.into())
+		// delete the restoration dir unless it holds the progress of an interrupted
+		// restoration, which `init_restore` resumes when given the same manifest.
+		let restoration_dir = service.restoration_dir();
+		if restoration_dir.exists() && Restoration::stored_manifest(&service.restoration_db()).is_none() {
+			if let Err(e) = fs::remove_dir_all(&restoration_dir) {
+				if e.kind() != ErrorKind::NotFound {
+					return Err(e.into())
+				}
 			}
 		}
 
@@ -329,6 +472,7 @@ impl Service {
 	}
 
 	/// Initialize the restoration synchronously.
+	/// If an interrupted restoration of the same manifest is found on disk, it is resumed.
 	pub fn init_restore(&self, manifest: ManifestData) -> Result<(), Error> {
 		let rest_dir = self.restoration_dir();
 
@@ -337,11 +481,13 @@ impl Service {
 		// tear down existing restoration.
 		*res = None;
 
-		// delete and restore the restoration dir.
-		if let Err(e) = fs::remove_dir_all(&rest_dir) {
-			match e.kind() {
-				ErrorKind::NotFound => {},
-				_ => return Err(e.into()),
+		// delete and restore the restoration dir, unless it holds progress for this manifest.
+		if !Restoration::has_progress(&self.restoration_db(), &manifest) {
+			if let Err(e) = fs::remove_dir_all(&rest_dir) {
+				match e.kind() {
+					ErrorKind::NotFound => {},
+					_ => return Err(e.into()),
+				}
 			}
 		}
 
@@ -358,7 +504,12 @@ impl Service {
 			genesis: &self.genesis_block,
 		};
 
-		*res = Some(try!(Restoration::new(params)));
+		let restoration = try!(Restoration::new(params));
+		let (state_done, block_done) = restoration.chunks_completed();
+		self.state_chunks.store(state_done, Ordering::SeqCst);
+		self.block_chunks.store(block_done, Ordering::SeqCst);
+
+		*res = Some(restoration);
 
 		*self.status.lock() = RestorationStatus::Ongoing;
 		Ok(())
@@ -19,7 +19,7 @@
 use devtools::RandomTempPath;
 
 use blockchain::generator::{ChainGenerator, ChainIterator, BlockFinalizer};
-use blockchain::BlockChain;
+use blockchain::{BlockChain, BlockProvider};
 use snapshot::{chunk_blocks, BlockRebuilder, Progress};
 use snapshot::io::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
 
@@ -83,6 +83,22 @@ fn chunk_and_restore(amount: u64) {
 	// and test it.
 	let new_chain = BlockChain::new(Default::default(), &genesis, new_db);
 	assert_eq!(new_chain.best_block_hash(), best_hash);
+
+	// fill in the ancient blocks missing before the snapshot.
+	let first_number = amount.saturating_sub(::snapshot::SNAPSHOT_BLOCKS);
+	if first_number > 0 {
+		assert_eq!(new_chain.first_block_number(), first_number + 1);
+		for number in (1..first_number + 1).rev() {
+			let hash = bc.block_hash(number).unwrap();
+			assert!(new_chain.insert_ancient_block(&bc.block(&hash).unwrap(), vec![]));
+			new_chain.commit();
+		}
+	}
+
+	assert_eq!(new_chain.first_block(), new_chain.genesis_hash());
+	assert_eq!(new_chain.block_hash(1), bc.block_hash(1));
+	assert_eq!(new_chain.block_details(&best_hash).unwrap().total_difficulty,
+		bc.block_details(&best_hash).unwrap().total_difficulty);
 }
 
 #[test]
@@ -69,7 +69,9 @@ fn snap_and_restore() {
 			let raw = reader.chunk(*chunk_hash).unwrap();
 			let chunk = ::util::snappy::decompress(&raw).unwrap();
 
-			rebuilder.feed(&chunk).unwrap();
+			let mut batch = new_db.transaction();
+			rebuilder.feed(&chunk, &mut batch).unwrap();
+			new_db.write(batch).unwrap();
 		}
 
 		assert_eq!(rebuilder.state_root(), state_root);
@@ -82,3 +84,77 @@ fn snap_and_restore() {
 
 	compare_dbs(&old_db, new_db.as_hashdb());
 }
+
+#[test]
+fn resume_interrupted_restore() {
+	let mut producer = StateProducer::new();
+	let mut rng = rand::thread_rng();
+	let mut old_db = MemoryDB::new();
+	let db_cfg = DatabaseConfig::with_columns(::db::NUM_COLUMNS);
+
+	for _ in 0..150 {
+		producer.tick(&mut rng, &mut old_db);
+	}
+
+	let snap_dir = RandomTempPath::create_dir();
+	let mut snap_file = snap_dir.as_path().to_owned();
+	snap_file.push("SNAP");
+
+	let state_root = producer.state_root();
+	let writer = Mutex::new(PackedWriter::new(&snap_file).unwrap());
+
+	let state_hashes = chunk_state(&old_db, &state_root, &writer, &Progress::default()).unwrap();
+
+	writer.into_inner().finish(::snapshot::ManifestData {
+		state_hashes: state_hashes,
+		block_hashes: Vec::new(),
+		state_root: state_root,
+		block_number: 0,
+		block_hash: H256::default(),
+	}).unwrap();
+
+	let mut db_path = snap_dir.as_path().to_owned();
+	db_path.push("db");
+
+	let reader = PackedReader::new(&snap_file).unwrap().unwrap();
+	let chunks: Vec<_> = reader.manifest().state_hashes.iter()
+		.map(|hash| ::util::snappy::decompress(&reader.chunk(*hash).unwrap()).unwrap())
+		.collect();
+	let (first, second) = chunks.split_at(chunks.len() / 2);
+
+	// nothing to resume from before any chunk was fed.
+	{
+		let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+		assert!(StateRebuilder::resume(new_db, Algorithm::Archive).unwrap().is_none());
+	}
+
+	{
+		let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+		let mut rebuilder = StateRebuilder::new(new_db.clone(), Algorithm::Archive);
+		for chunk in first {
+			let mut batch = new_db.transaction();
+			rebuilder.feed(chunk, &mut batch).unwrap();
+			new_db.write(batch).unwrap();
+		}
+	}
+
+	let db = {
+		let new_db = Arc::new(Database::open(&db_cfg, &db_path.to_string_lossy()).unwrap());
+		let mut rebuilder = StateRebuilder::resume(new_db.clone(), Algorithm::Archive).unwrap().unwrap();
+		for chunk in second {
+			let mut batch = new_db.transaction();
+			rebuilder.feed(chunk, &mut batch).unwrap();
+			new_db.write(batch).unwrap();
+		}
+
+		assert_eq!(rebuilder.state_root(), state_root);
+		rebuilder.clear_bookkeeping().unwrap();
+		rebuilder.check_missing().unwrap();
+
+		new_db
+	};
+
+	let new_db = journaldb::new(db, Algorithm::Archive, ::db::COL_STATE);
+
+	compare_dbs(&old_db, new_db.as_hashdb());
+}
@@ -38,8 +38,8 @@ pub struct CommonParams {
 	pub network_id: U256,
 	/// Minimum gas limit.
 	pub min_gas_limit: U256,
-	/// Fork block to check.
-	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Blocks peers are expected to have in their chain, ordered by number.
+	pub fork_checkpoints: Vec<(BlockNumber, H256)>,
 }
 
 impl From<ethjson::spec::Params> for CommonParams {
@@ -49,7 +49,17 @@ impl From<ethjson::spec::Params> for CommonParams {
 			maximum_extra_data_size: p.maximum_extra_data_size.into(),
 			network_id: p.network_id.into(),
 			min_gas_limit: p.min_gas_limit.into(),
-			fork_block: if let (Some(n), Some(h)) = (p.fork_block, p.fork_hash) { Some((n.into(), h.into())) } else { None },
+			fork_checkpoints: {
+				let mut checkpoints: Vec<(BlockNumber, H256)> = p.fork_checkpoints.unwrap_or_else(Vec::new).into_iter()
+					.map(|c| (c.number.into(), c.hash.into()))
+					.collect();
+				if let (Some(n), Some(h)) = (p.fork_block, p.fork_hash) {
+					checkpoints.push((n.into(), h.into()));
+				}
+				checkpoints.sort();
+				checkpoints.dedup();
+				checkpoints
+			},
 		}
 	}
 }
@@ -135,7 +145,7 @@ impl Spec {
 	fn engine(engine_spec: ethjson::spec::Engine, params: CommonParams, builtins: BTreeMap<Address, Builtin>) -> Arc<Engine> {
 		match engine_spec {
 			ethjson::spec::Engine::Null => Arc::new(NullEngine::new(params, builtins)),
-			ethjson::spec::Engine::InstantSeal => Arc::new(InstantSeal::new(params, builtins)),
+			ethjson::spec::Engine::InstantSeal(instant_seal) => Arc::new(InstantSeal::new(params, instant_seal.map_or_else(Default::default, |s| From::from(s.params)), builtins)),
 			ethjson::spec::Engine::Ethash(ethash) => Arc::new(ethereum::Ethash::new(params, From::from(ethash.params), builtins)),
 			ethjson::spec::Engine::BasicAuthority(basic_authority) => Arc::new(BasicAuthority::new(params, From::from(basic_authority.params), builtins)),
 		}
@@ -155,8 +165,8 @@ impl Spec {
 	/// Get the configured Network ID.
 	pub fn network_id(&self) -> U256 { self.params.network_id }
 
-	/// Get the configured network fork block.
-	pub fn fork_block(&self) -> Option<(BlockNumber, H256)> { self.params.fork_block }
+	/// Get the configured network fork checkpoints, ordered by block number.
+	pub fn fork_checkpoints(&self) -> &[(BlockNumber, H256)] { &self.params.fork_checkpoints }
 
 	/// Get the header of the genesis block.
 	pub fn genesis_header(&self) -> Header {
@@ -253,6 +263,11 @@ impl Spec {
 		Spec::load(include_bytes!("../../res/null_morden.json"))
 	}
 
+	/// Create a new Spec with InstantSeal consensus which seals a block for every transaction.
+	pub fn new_instant() -> Spec {
+		Spec::load(include_bytes!("../../res/instant_seal.json"))
+	}
+
 	/// Create a new Spec which is a NullEngine consensus with a premine of address whose secret is sha3('').
 	pub fn new_null() -> Spec {
 		Spec::load(include_bytes!("../../res/null.json"))
@@ -15,13 +15,15 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use io::IoChannel;
-use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockID};
+use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockID, BlockStatus};
 use ethereum;
 use block::IsBlock;
 use tests::helpers::*;
 use common::*;
 use devtools::*;
-use miner::Miner;
+use miner::{Miner, MinerService, MinerOptions, GasPricer, GasPriceOracle, GasPriceOracleOptions};
+use engines::SealMode;
+use spec::Spec;
 use rlp::{Rlp, View};
 
 #[test]
@@ -139,6 +141,25 @@ fn can_generate_gas_price_statistics() {
 	assert_eq!(s, vec_into![0, 1, 3, 5, 7, 9, 11, 13, 15]);
 }
 
+#[test]
+#[cfg_attr(feature="dev", allow(useless_vec))]
+fn can_suggest_gas_price_from_recent_blocks() {
+	let client_result = generate_dummy_client_with_data(16, 1, &vec_into![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+	let client = client_result.reference();
+	let oracle = GasPriceOracle::new(GasPriceOracleOptions { blocks: 8, percentile: 50 });
+
+	let histogram = oracle.histogram(&**client).unwrap();
+	assert_eq!(histogram.block_number, client.chain_info().best_block_number);
+	assert_eq!(histogram.sample_size, 8);
+	assert_eq!(histogram.percentiles.len(), 101);
+	assert_eq!(histogram.percentile(0), 8.into());
+	assert_eq!(histogram.percentile(100), 15.into());
+
+	assert_eq!(oracle.gas_price(&**client, 0.into()), Some(11.into()));
+	// never below the floor
+	assert_eq!(oracle.gas_price(&**client, 13.into()), Some(13.into()));
+}
+
 #[test]
 fn can_handle_long_fork() {
 	let client_result = generate_dummy_client(1200);
@@ -168,3 +189,65 @@ fn can_mine() {
 
 	assert_eq!(*b.block().header().parent_hash(), BlockView::new(&dummy_blocks[0]).header_view().sha3());
 }
+
+#[test]
+fn should_revert_to_checkpoint() {
+	let dir = RandomTempPath::new();
+	let spec = Spec::new_instant();
+	let options = MinerOptions {
+		seal_mode: Some(SealMode::Manual),
+		..Default::default()
+	};
+	let miner = Miner::new(options, GasPricer::new_fixed(0u64.into()), &spec, None);
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), miner.clone(), IoChannel::disconnected()).unwrap();
+
+	miner.seal_blocks(&*client, 2).unwrap();
+	let checkpoint = client.take_checkpoint().unwrap();
+	assert_eq!(checkpoint.number, 2);
+
+	miner.seal_blocks(&*client, 3).unwrap();
+	assert_eq!(client.chain_info().best_block_number, 5);
+
+	client.revert_to_checkpoint(&checkpoint).unwrap();
+	assert_eq!(client.chain_info().best_block_number, 2);
+	assert_eq!(client.chain_info().best_block_hash, checkpoint.hash);
+	assert_eq!(client.block_hash(BlockID::Number(3)), None);
+
+	miner.seal_blocks(&*client, 1).unwrap();
+	assert_eq!(client.chain_info().best_block_number, 3);
+}
+
+#[test]
+fn should_import_reverted_blocks_again() {
+	let dir = RandomTempPath::new();
+	let spec = Spec::new_instant();
+	let options = MinerOptions {
+		seal_mode: Some(SealMode::Manual),
+		..Default::default()
+	};
+	let miner = Miner::new(options, GasPricer::new_fixed(0u64.into()), &spec, None);
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), miner.clone(), IoChannel::disconnected()).unwrap();
+
+	miner.seal_blocks(&*client, 1).unwrap();
+	let checkpoint = client.take_checkpoint().unwrap();
+	let timestamp = HeaderView::new(&client.best_block_header()).timestamp() + 1000;
+
+	miner.set_next_block_timestamp(&*client, timestamp).unwrap();
+	let reverted = miner.seal_blocks(&*client, 1).unwrap()[0];
+	client.revert_to_checkpoint(&checkpoint).unwrap();
+	assert_eq!(client.block_status(BlockID::Hash(reverted)), BlockStatus::Unknown);
+
+	// the identical block is sealed again
+	miner.set_next_block_timestamp(&*client, timestamp).unwrap();
+	assert_eq!(miner.seal_blocks(&*client, 1).unwrap(), vec![reverted]);
+	assert_eq!(client.chain_info().best_block_hash, reverted);
+	assert_eq!(client.block_status(BlockID::Hash(reverted)), BlockStatus::InChain);
+}
+
+#[test]
+fn should_not_take_checkpoints_of_pow_chains() {
+	let dir = RandomTempPath::new();
+	let spec = ethereum::new_morden();
+	let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), Arc::new(Miner::with_spec(&spec)), IoChannel::disconnected()).unwrap();
+	assert!(client.take_checkpoint().is_err());
+}
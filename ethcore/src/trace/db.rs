@@ -194,6 +194,23 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		});
 	}
 
+	/// Removes trace blooms of blocks in range `first..last` (inclusive),
+	/// which are no longer part of the canon chain.
+	pub fn revert(&self, batch: &mut DBTransaction, first: BlockNumber, last: BlockNumber) {
+		if !self.tracing_enabled() || first > last {
+			return;
+		}
+
+		let chain = BloomGroupChain::new(self.bloom_config, self);
+		let trace_blooms = chain.replace(&(first as Number..last as Number), vec![]);
+		let blooms_to_insert = trace_blooms.into_iter()
+			.map(|p| (From::from(p.0), From::from(p.1)))
+			.collect::<HashMap<TraceGroupPosition, blooms::BloomGroup>>();
+
+		let mut blooms = self.blooms.write();
+		batch.extend_with_cache(db::COL_TRACE, &mut *blooms, blooms_to_insert, CacheUpdatePolicy::Remove);
+	}
+
 	/// Returns traces for block with hash.
 	fn traces(&self, block_hash: &H256) -> Option<FlatBlockTraces> {
 		let result = self.tracesdb.read_with_cache(db::COL_TRACE, &self.traces, block_hash);
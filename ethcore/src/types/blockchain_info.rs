@@ -31,5 +31,9 @@ pub struct BlockChainInfo {
 	/// Best blockchain block hash.
 	pub best_block_hash: H256,
 	/// Best blockchain block number.
-	pub best_block_number: BlockNumber
+	pub best_block_number: BlockNumber,
+	/// First block of the chain, if older blocks are missing (e.g. after snapshot restoration).
+	pub first_block_hash: Option<H256>,
+	/// Number of the first block of the chain, if older blocks are missing.
+	pub first_block_number: Option<BlockNumber>,
 }
@@ -350,6 +350,92 @@ impl Deref for LocalizedTransaction {
 	}
 }
 
+/// Condition that has to be met before a transaction can be included in a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Condition {
+	/// Valid once the chain reaches given block number.
+	Number(BlockNumber),
+	/// Valid once the chain reaches given UNIX timestamp.
+	Timestamp(u64),
+}
+
+impl Condition {
+	/// Check if the condition holds for the given best block.
+	pub fn is_satisfied(&self, best_block: BlockNumber, best_timestamp: u64) -> bool {
+		match *self {
+			Condition::Number(number) => number <= best_block,
+			Condition::Timestamp(timestamp) => timestamp <= best_timestamp,
+		}
+	}
+}
+
+/// Signed transaction with an optional condition for its release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingTransaction {
+	/// Signed transaction data.
+	pub transaction: SignedTransaction,
+	/// Condition that has to be met before the transaction is propagated and mined.
+	pub condition: Option<Condition>,
+}
+
+impl PendingTransaction {
+	/// Create a new pending transaction from signed transaction.
+	pub fn new(signed: SignedTransaction, condition: Option<Condition>) -> Self {
+		PendingTransaction {
+			transaction: signed,
+			condition: condition,
+		}
+	}
+}
+
+impl Deref for PendingTransaction {
+	type Target = SignedTransaction;
+
+	fn deref(&self) -> &SignedTransaction { &self.transaction }
+}
+
+impl From<SignedTransaction> for PendingTransaction {
+	fn from(t: SignedTransaction) -> Self {
+		PendingTransaction::new(t, None)
+	}
+}
+
+impl Encodable for PendingTransaction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(2);
+		s.append(&self.transaction);
+		match self.condition {
+			None => { s.begin_list(0); },
+			Some(Condition::Number(number)) => { s.begin_list(2).append(&0u8).append(&number); },
+			Some(Condition::Timestamp(timestamp)) => { s.begin_list(2).append(&1u8).append(&timestamp); },
+		}
+	}
+}
+
+impl Decodable for PendingTransaction {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		// plain signed transaction, as stored before conditions were introduced
+		if d.item_count() == 9 {
+			return Ok(PendingTransaction::new(try!(Decodable::decode(decoder)), None));
+		}
+		if d.item_count() != 2 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+		let c = try!(d.at(1));
+		let condition = match c.item_count() {
+			0 => None,
+			2 => match try!(c.val_at::<u8>(0)) {
+				0 => Some(Condition::Number(try!(c.val_at(1)))),
+				1 => Some(Condition::Timestamp(try!(c.val_at(1)))),
+				_ => return Err(DecoderError::Custom("Unknown transaction condition")),
+			},
+			_ => return Err(DecoderError::RlpIncorrectListLen),
+		};
+		Ok(PendingTransaction::new(try!(d.val_at(0)), condition))
+	}
+}
+
 #[test]
 fn sender_test() {
 	let t: SignedTransaction = decode(&::rustc_serialize::hex::FromHex::from_hex("f85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804").unwrap());
@@ -395,3 +481,23 @@ fn fake_signing() {
 	let t = t.clone();
 	assert_eq!(Address::from(0x69), t.sender().unwrap());
 }
+
+#[test]
+fn pending_transaction_rlp() {
+	use ethkey::{Random, Generator};
+	let t = Transaction {
+		action: Action::Create,
+		nonce: U256::from(42),
+		gas_price: U256::from(3000),
+		gas: U256::from(50_000),
+		value: U256::from(1),
+		data: b"Hello!".to_vec()
+	}.sign(&Random.generate().unwrap().secret());
+
+	for condition in vec![None, Some(Condition::Number(100)), Some(Condition::Timestamp(1_500_000_000))] {
+		let pending = PendingTransaction::new(t.clone(), condition);
+		assert_eq!(decode::<PendingTransaction>(&encode(&pending)), pending);
+	}
+	// plain transactions decode as unconditional
+	assert_eq!(decode::<PendingTransaction>(&encode(&t)), PendingTransaction::new(t, None));
+}
@@ -17,5 +17,7 @@
 //! Misc deserialization.
 
 mod account_meta;
+mod transaction_filter;
 
 pub use self::account_meta::AccountMeta;
+pub use self::transaction_filter::TransactionFilter;
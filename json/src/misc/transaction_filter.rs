@@ -0,0 +1,76 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction filter policy deserialization.
+
+use std::io::Read;
+use std::collections::BTreeMap;
+use serde_json;
+use serde_json::Error;
+use hash::Address;
+use uint::Uint;
+
+/// Transaction filter policy.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct TransactionFilter {
+	/// Transactions from these senders are refused.
+	#[serde(rename="denySenders")]
+	pub deny_senders: Option<Vec<Address>>,
+	/// Transactions to these recipients are refused.
+	#[serde(rename="denyRecipients")]
+	pub deny_recipients: Option<Vec<Address>>,
+	/// Only these senders may create contracts. Anyone may if not present.
+	#[serde(rename="allowCreators")]
+	pub allow_creators: Option<Vec<Address>>,
+	/// Minimal gas price of transactions to given recipients.
+	#[serde(rename="minGasPrice")]
+	pub min_gas_price: Option<BTreeMap<Address, Uint>>,
+}
+
+impl TransactionFilter {
+	/// Loads filter policy from json.
+	pub fn load<R>(reader: R) -> Result<Self, Error> where R: Read {
+		serde_json::from_reader(reader)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use std::str::FromStr;
+	use util::{H160, U256};
+	use hash::Address;
+	use uint::Uint;
+	use misc::TransactionFilter;
+
+	#[test]
+	fn transaction_filter_deserialization() {
+		let s = r#"{
+			"denySenders": ["0x0000000000000000000000000000000000000001"],
+			"allowCreators": [],
+			"minGasPrice": {
+				"0x0000000000000000000000000000000000000002": "0x04a817c800"
+			}
+		}"#;
+
+		let deserialized: TransactionFilter = serde_json::from_str(s).unwrap();
+		let recipient = Address(H160::from_str("0000000000000000000000000000000000000002").unwrap());
+		assert_eq!(deserialized.deny_senders, Some(vec![Address(H160::from_str("0000000000000000000000000000000000000001").unwrap())]));
+		assert_eq!(deserialized.deny_recipients, None);
+		assert_eq!(deserialized.allow_creators, Some(vec![]));
+		assert_eq!(deserialized.min_gas_price.unwrap().get(&recipient), Some(&Uint(U256::from(20_000_000_000u64))));
+	}
+}
@@ -18,14 +18,15 @@
 
 use spec::Ethash;
 use spec::BasicAuthority;
+use spec::InstantSeal;
 
 /// Engine deserialization.
 #[derive(Debug, PartialEq, Deserialize)]
 pub enum Engine {
 	/// Null engine.
 	Null,
-	/// Instantly sealing engine.
-	InstantSeal,
+	/// Instantly sealing engine. Seals on every transaction if params are not given.
+	InstantSeal(Option<InstantSeal>),
 	/// Ethash engine.
 	Ethash(Ethash),
 	/// BasicAuthority engine.
@@ -51,7 +52,17 @@ mod tests {
 		}"#;
 
 		let deserialized: Engine = serde_json::from_str(s).unwrap();
-		assert_eq!(Engine::InstantSeal, deserialized);
+		assert_eq!(Engine::InstantSeal(None), deserialized);
+
+		let s = r#"{
+			"InstantSeal": {
+				"params": {
+					"mode": "manual"
+				}
+			}
+		}"#;
+
+		let _deserialized: Engine = serde_json::from_str(s).unwrap();
 
 
 		let s = r#"{
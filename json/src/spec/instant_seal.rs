@@ -0,0 +1,80 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Instant seal engine params deserialization.
+
+use uint::Uint;
+
+/// Sealing mode deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub enum SealMode {
+	/// Seal a block as soon as there are transactions.
+	#[serde(rename="instant")]
+	Instant,
+	/// Seal a block every `interval` seconds, even if empty.
+	#[serde(rename="interval")]
+	Interval,
+	/// Seal blocks only on request.
+	#[serde(rename="manual")]
+	Manual,
+}
+
+/// Instant seal params deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct InstantSealParams {
+	/// Sealing mode.
+	pub mode: Option<SealMode>,
+	/// Seconds between blocks in interval mode.
+	pub interval: Option<Uint>,
+}
+
+/// Instant seal engine deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct InstantSeal {
+	/// Instant seal params.
+	pub params: InstantSealParams,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use uint::Uint;
+	use util::U256;
+	use spec::instant_seal::{InstantSeal, SealMode};
+
+	#[test]
+	fn instant_seal_deserialization() {
+		let s = r#"{
+			"params": {
+				"mode": "interval",
+				"interval": "0x05"
+			}
+		}"#;
+
+		let deserialized: InstantSeal = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.params.mode, Some(SealMode::Interval));
+		assert_eq!(deserialized.params.interval, Some(Uint(U256::from(5))));
+
+		let s = r#"{
+			"params": {}
+		}"#;
+
+		let deserialized: InstantSeal = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.params.mode, None);
+		assert_eq!(deserialized.params.interval, None);
+	}
+}
@@ -19,6 +19,15 @@
 use uint::Uint;
 use hash::H256;
 
+/// Block which peers are expected to have in their chain.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct Checkpoint {
+	/// Block number.
+	pub number: Uint,
+	/// Block hash.
+	pub hash: H256,
+}
+
 /// Spec params.
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct Params {
@@ -40,12 +49,19 @@ pub struct Params {
 	/// Expected fork block hash.
 	#[serde(rename="forkCanonHash")]
 	pub fork_hash: Option<H256>,
+	/// Additional blocks to check.
+	#[serde(rename="forkCheckpoints")]
+	pub fork_checkpoints: Option<Vec<Checkpoint>>,
 }
 
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use spec::params::Params;
+	use spec::params::{Params, Checkpoint};
+	use uint::Uint;
+	use hash::H256;
+	use std::str::FromStr;
+	use util::{hash, U256};
 
 	#[test]
 	fn params_deserialization() {
@@ -60,4 +76,23 @@ mod tests {
 		let _deserialized: Params = serde_json::from_str(s).unwrap();
 		// TODO: validate all fields
 	}
+
+	#[test]
+	fn params_checkpoints_deserialization() {
+		let s = r#"{
+			"maximumExtraDataSize": "0x20",
+			"networkID" : "0x1",
+			"minGasLimit": "0x1388",
+			"accountStartNonce": "0x00",
+			"forkCheckpoints": [
+				{ "number": "0x1d4c00", "hash": "0x94365e3a8c0b35089c1d1195081fe7489b528a84b22199c916180db8b28ade7f" }
+			]
+		}"#;
+
+		let deserialized: Params = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized.fork_checkpoints, Some(vec![Checkpoint {
+			number: Uint(U256::from(0x1d4c00)),
+			hash: H256(hash::H256::from_str("94365e3a8c0b35089c1d1195081fe7489b528a84b22199c916180db8b28ade7f").unwrap()),
+		}]));
+	}
 }
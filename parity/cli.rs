@@ -88,6 +88,15 @@ Networking Options:
                            [default: 30303].
   --min-peers NUM          Try to maintain at least NUM peers [default: 25].
   --max-peers NUM          Allow up to that many peers [default: 50].
+  --max-inbound-peers NUM  Accept at most NUM connections from other peers.
+                           Can't exceed --max-peers.
+  --max-outbound-peers NUM
+                           Open at most NUM connections to discovered peers.
+                           Can't exceed --max-peers.
+  --reserved-outbound-peers NUM
+                           Keep NUM of --max-peers slots for connections we
+                           open, so that inbound peers can't take all of them.
+                           Capped to half of --max-peers [default: 10].
   --nat METHOD             Specify method to use for determining public
                            address. Must be one of: any, none, upnp,
                            extip:<IP> [default: any].
@@ -102,6 +111,32 @@ Networking Options:
                            These nodes will always have a reserved slot on top
                            of the normal maximum peers.
   --reserved-only          Connect only to reserved nodes.
+  --allow-ips FILTER       Connect to and accept connections from peers with
+                           these addresses only. FILTER must be one of: all,
+                           public, private, none. Reserved peers are not
+                           filtered. With all, only public addresses and
+                           networks given by --allow-ip are discovered
+                           [default: all].
+  --allow-ip NETWORKS      Always allow peers from these networks. NETWORKS
+                           should be comma-delimited CIDR ranges, e.g.
+                           10.0.0.0/8,192.168.1.7.
+  --deny-ip NETWORKS       Never connect to peers from these networks.
+                           NETWORKS should be comma-delimited CIDR ranges.
+  --max-upload KBPS        Limit total upload rate to KBPS KiB per second.
+  --max-download KBPS      Limit total download rate to KBPS KiB per second.
+  --peer-max-upload KBPS   Limit upload rate to a single peer to KBPS KiB per
+                           second.
+  --peer-max-download KBPS
+                           Limit download rate from a single peer to KBPS KiB
+                           per second.
+  --fast-sync              Import blocks without executing them up to a recent
+                           block and download its state from peers. Only
+                           applies to an empty database. Not supported with
+                           tracing enabled.
+  --whisper                Enable the Whisper messaging protocol. Its API must
+                           be enabled separately with --jsonrpc-apis shh.
+  --whisper-pool-size MB   Keep up to MB megabytes of Whisper messages to relay
+                           to other peers [default: 10].
 
 API and Console Options:
   --no-jsonrpc             Disable the JSON-RPC API server.
@@ -114,7 +149,7 @@ API and Console Options:
   --jsonrpc-apis APIS      Specify the APIs available through the JSONRPC
                            interface. APIS is a comma-delimited list of API
                            name. Possible name are web3, eth, net, personal,
-                           ethcore, ethcore_set, traces, rpc.
+                           ethcore, ethcore_set, traces, rpc, shh, txpool, evm.
                            [default: web3,eth,net,ethcore,personal,traces,rpc].
   --jsonrpc-hosts HOSTS    List of allowed Host header values. This option will
                            validate the Host header sent by the browser, it
@@ -163,6 +198,12 @@ Sealing/Mining Options:
   --reseal-min-period MS   Specify the minimum time between reseals from
                            incoming transactions. MS is time measured in
                            milliseconds [default: 2000].
+  --seal-mode MODE         Specify when a chain with an InstantSeal engine
+                           should seal new blocks. MODE is one of:
+                           instant - seal as soon as transactions arrive;
+                           interval:SECS - seal every SECS seconds;
+                           manual - seal only on RPC request.
+                           By default the mode from the chain spec is used.
   --work-queue-size ITEMS  Specify the number of historical work packages
                            which are kept cached lest a solution is found for
                            them later. High values take more memory but result
@@ -180,14 +221,25 @@ Sealing/Mining Options:
   --usd-per-tx USD         Amount of USD to be paid for a basic transaction
                            [default: 0]. The minimum gas price is set
                            accordingly.
-  --usd-per-eth SOURCE     USD value of a single ETH. SOURCE may be either an
-                           amount in USD, a web service or 'auto' to use each
-                           web service in turn and fallback on the last known
-                           good value [default: auto].
+  --usd-per-eth SOURCE     USD value of a single ETH. SOURCE is a comma-separated
+                           list of sources, each of which may be an amount in
+                           USD, 'etherscan', file:PATH to read the amount from
+                           a file or http://URL#PATH to read it from a JSON
+                           web service at the dot-separated PATH. The median
+                           of the sources is used, falling back on the last
+                           known good value. 'auto' uses the default web
+                           service [default: auto].
   --price-update-period T  T will be allowed to pass between each gas price
                            update. T may be daily, hourly, a number of seconds,
                            or a time string of the form "2 days", "30 minutes"
                            etc. [default: hourly].
+  --gas-price-percentile PCT
+                           Percentile of gas prices of transactions in recent
+                           blocks suggested as the gas price of new
+                           transactions [default: 50].
+  --gas-price-blocks BLOCKS
+                           Number of recent blocks sampled for the suggested
+                           gas price [default: 100].
   --gas-floor-target GAS   Amount of gas per block to target when sealing a new
                            block [default: 4700000].
   --gas-cap GAS            A cap on how large we will raise the gas limit per
@@ -196,12 +248,52 @@ Sealing/Mining Options:
                            more than 32 characters.
   --tx-queue-size LIMIT    Maximum amount of transactions in the queue (waiting
                            to be included in next block) [default: 1024].
+  --tx-queue-per-sender LIMIT
+                           Maximum amount of transactions from a single sender
+                           in the queue. Local transactions are not limited.
+  --tx-queue-gas-per-sender GAS
+                           Maximum total gas of transactions from a single
+                           sender in the queue. Local transactions are not
+                           limited.
+  --tx-queue-mem-limit MB  Maximum amount of memory used by transactions in
+                           the queue. When exceeded, transactions of senders
+                           using the most memory are dropped first.
+  --tx-filter FILE         JSON file with the policy of transactions accepted
+                           to the queue and to authored blocks. It may deny
+                           senders and recipients (denySenders,
+                           denyRecipients), restrict contract creation
+                           (allowCreators) and require minimal gas price per
+                           recipient (minGasPrice). The file is reloaded with
+                           ethcore_reloadTransactionFilter RPC.
   --remove-solved          Move solved blocks from the work package queue
                            instead of cloning them. This gives a slightly
                            faster import speed, but means that extra solutions
                            submitted for the same work package will go unused.
   --notify-work URLS       URLs to which work package notifications are pushed.
                            URLS should be a comma-delimited list of HTTP URLs.
+  --stratum                Run Stratum server for miner push notification.
+  --stratum-interface IP   Network interface address for the Stratum server.
+                           IP may be local, all or an interface address
+                           [default: local].
+  --stratum-port PORT      Port for the Stratum server to listen on
+                           [default: 8008].
+  --stratum-secret STRING  Secret Stratum workers have to authorize with.
+  --stratum-difficulty DIFF
+                           Difficulty of shares Stratum workers submit. By
+                           default only block solutions are accepted.
+  --work-socket            Run WebSocket server pushing work packages to
+                           connected miners. Miners submit solutions over the
+                           same connection.
+  --work-socket-interface IP
+                           Network interface address for the work socket.
+                           IP may be local, all or an interface address
+                           [default: local].
+  --work-socket-port PORT  Port for the work socket to listen on
+                           [default: 8009].
+  --cpu-mining THREADS     Mine with the built-in CPU miner using THREADS
+                           threads. It computes hashes from the ethash light
+                           cache and is meant for low-difficulty development
+                           chains only.
 
 Footprint Options:
   --pruning METHOD         Configure pruning of the state/storage trie. METHOD
@@ -327,11 +419,24 @@ pub struct Args {
 	pub flag_port: u16,
 	pub flag_min_peers: u16,
 	pub flag_max_peers: u16,
+	pub flag_max_inbound_peers: Option<u32>,
+	pub flag_max_outbound_peers: Option<u32>,
+	pub flag_reserved_outbound_peers: u32,
 	pub flag_no_discovery: bool,
 	pub flag_nat: String,
 	pub flag_node_key: Option<String>,
 	pub flag_reserved_peers: Option<String>,
 	pub flag_reserved_only: bool,
+	pub flag_allow_ips: String,
+	pub flag_allow_ip: Option<String>,
+	pub flag_deny_ip: Option<String>,
+	pub flag_max_upload: Option<u64>,
+	pub flag_max_download: Option<u64>,
+	pub flag_peer_max_upload: Option<u64>,
+	pub flag_peer_max_download: Option<u64>,
+	pub flag_fast_sync: bool,
+	pub flag_whisper: bool,
+	pub flag_whisper_pool_size: usize,
 
 	pub flag_pruning: String,
 	pub flag_tracing: String,
@@ -369,6 +474,7 @@ pub struct Args {
 	pub flag_force_sealing: bool,
 	pub flag_reseal_on_txs: String,
 	pub flag_reseal_min_period: u64,
+	pub flag_seal_mode: Option<String>,
 	pub flag_work_queue_size: usize,
 	pub flag_remove_solved: bool,
 	pub flag_tx_gas_limit: Option<String>,
@@ -377,11 +483,26 @@ pub struct Args {
 	pub flag_usd_per_tx: String,
 	pub flag_usd_per_eth: String,
 	pub flag_price_update_period: String,
+	pub flag_gas_price_percentile: usize,
+	pub flag_gas_price_blocks: usize,
 	pub flag_gas_floor_target: String,
 	pub flag_gas_cap: String,
 	pub flag_extra_data: Option<String>,
 	pub flag_tx_queue_size: usize,
+	pub flag_tx_queue_per_sender: Option<usize>,
+	pub flag_tx_queue_gas_per_sender: Option<String>,
+	pub flag_tx_queue_mem_limit: Option<u32>,
+	pub flag_tx_filter: Option<String>,
 	pub flag_notify_work: Option<String>,
+	pub flag_stratum: bool,
+	pub flag_stratum_interface: String,
+	pub flag_stratum_port: u16,
+	pub flag_stratum_secret: Option<String>,
+	pub flag_stratum_difficulty: Option<String>,
+	pub flag_work_socket: bool,
+	pub flag_work_socket_interface: String,
+	pub flag_work_socket_port: u16,
+	pub flag_cpu_mining: Option<usize>,
 	pub flag_logging: Option<String>,
 	pub flag_version: bool,
 	pub flag_from: String,
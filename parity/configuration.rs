@@ -23,15 +23,16 @@ use cli::{USAGE, Args};
 use docopt::{Docopt, Error as DocoptError};
 use util::{Hashable, U256, Uint, Bytes, version_data, Secret, Address};
 use util::log::Colour;
-use ethsync::{NetworkConfiguration, is_valid_node_url};
+use ethsync::{NetworkConfiguration, IpFilter, is_valid_node_url};
+use whisper::WhisperConfig;
 use ethcore::client::{VMType, Mode};
-use ethcore::miner::MinerOptions;
+use ethcore::miner::{MinerOptions, GasPriceOracleOptions, PriceSource, PriceFeed, StratumOptions, CpuMinerOptions, WorkSocketOptions};
 
 use rpc::{IpcConfiguration, HttpConfiguration};
 use ethcore_rpc::NetworkSettings;
 use cache::CacheConfig;
-use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_price, replace_home,
-geth_ipc_path, parity_ipc_path, to_bootnodes, to_addresses, to_address};
+use helpers::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_seal_mode, to_price, to_price_sources, replace_home,
+geth_ipc_path, parity_ipc_path, to_bootnodes, to_ip_networks, to_addresses, to_address};
 use params::{ResealPolicy, AccountsConfig, GasPricerConfig, MinerExtras, SpecType};
 use ethcore_logger::Config as LogConfig;
 use dir::Directories;
@@ -232,6 +233,11 @@ impl Configuration {
 				ui: self.args.cmd_ui,
 				name: self.args.flag_identity,
 				custom_bootnodes: self.args.flag_bootnodes.is_some(),
+				fast_sync: self.args.flag_fast_sync,
+				whisper: self.whisper_config(),
+				stratum: try!(self.stratum_options()),
+				work_socket: self.work_socket_options(),
+				cpu_miner: try!(self.cpu_miner_options()),
 			};
 			Cmd::Run(run_cmd)
 		};
@@ -327,6 +333,9 @@ impl Configuration {
 
 	fn miner_options(&self) -> Result<MinerOptions, String> {
 		let reseal = try!(self.args.flag_reseal_on_txs.parse::<ResealPolicy>());
+		if self.args.flag_gas_price_percentile > 100 {
+			return Err(format!("Invalid gas price percentile: {}. Expected a value between 0 and 100.", self.args.flag_gas_price_percentile));
+		}
 
 		let options = MinerOptions {
 			new_work_notify: self.work_notify(),
@@ -338,10 +347,25 @@ impl Configuration {
 				None => U256::max_value(),
 			},
 			tx_queue_size: self.args.flag_tx_queue_size,
+			tx_queue_sender_limit: self.args.flag_tx_queue_per_sender.unwrap_or(usize::max_value()),
+			tx_queue_sender_gas_limit: match self.args.flag_tx_queue_gas_per_sender {
+				Some(ref d) => try!(to_u256(d)),
+				None => U256::max_value(),
+			},
+			tx_queue_memory_limit: self.args.flag_tx_queue_mem_limit.map(|mb| mb as usize * 1024 * 1024),
 			pending_set: try!(to_pending_set(&self.args.flag_relay_set)),
 			reseal_min_period: Duration::from_millis(self.args.flag_reseal_min_period),
 			work_queue_size: self.args.flag_work_queue_size,
 			enable_resubmission: !self.args.flag_remove_solved,
+			transaction_filter: self.args.flag_tx_filter.as_ref().map(|f| PathBuf::from(replace_home(f))),
+			gas_price_oracle: GasPriceOracleOptions {
+				blocks: self.args.flag_gas_price_blocks,
+				percentile: self.args.flag_gas_price_percentile,
+			},
+			seal_mode: match self.args.flag_seal_mode {
+				Some(ref mode) => Some(try!(to_seal_mode(mode))),
+				None => None,
+			},
 		};
 
 		Ok(options)
@@ -375,14 +399,16 @@ impl Configuration {
 		}
 
 		let usd_per_tx = try!(to_price(&self.args.flag_usd_per_tx));
-		if "auto" == self.args.flag_usd_per_eth.as_str() {
+		let price_sources = try!(to_price_sources(&self.args.flag_usd_per_eth));
+		if !price_sources.iter().all(PriceSource::is_fixed) {
 			return Ok(GasPricerConfig::Calibrated {
 				usd_per_tx: usd_per_tx,
 				recalibration_period: try!(to_duration(self.args.flag_price_update_period.as_str())),
+				price_sources: price_sources,
 			});
 		}
 
-		let usd_per_eth = try!(to_price(&self.args.flag_usd_per_eth));
+		let usd_per_eth = try!(PriceFeed::new(price_sources).fetch().map_err(|e| format!("Invalid Ether price: {}", e)));
 		let wei_per_usd: f32 = 1.0e18 / usd_per_eth;
 		let gas_per_tx: f32 = 21000.0;
 		let wei_per_gas: f32 = wei_per_usd * usd_per_tx / gas_per_tx;
@@ -393,7 +419,9 @@ impl Configuration {
 			Colour::Yellow.bold().paint(format!("{}", wei_per_gas))
 		);
 
-		Ok(GasPricerConfig::Fixed(U256::from_dec_str(&format!("{:.0}", wei_per_gas)).unwrap()))
+		U256::from_dec_str(&format!("{:.0}", wei_per_gas))
+			.map(GasPricerConfig::Fixed)
+			.map_err(|_| format!("Unable to convert Ether price US${} to gas price.", usd_per_eth))
 	}
 
 	fn extra_data(&self) -> Result<Bytes, String> {
@@ -446,6 +474,9 @@ impl Configuration {
 		ret.discovery_enabled = !self.args.flag_no_discovery && !self.args.flag_nodiscover;
 		ret.max_peers = self.max_peers();
 		ret.min_peers = self.min_peers();
+		ret.max_inbound_peers = self.args.flag_max_inbound_peers;
+		ret.max_outbound_peers = self.args.flag_max_outbound_peers;
+		ret.reserved_outbound_peers = self.args.flag_reserved_outbound_peers;
 		let mut net_path = PathBuf::from(self.directories().db);
 		net_path.push("network");
 		let net_specific_path = net_path.join(&try!(self.network_specific_path()));
@@ -453,9 +484,62 @@ impl Configuration {
 		ret.net_config_path = Some(net_specific_path.to_str().unwrap().to_owned());
 		ret.reserved_nodes = try!(self.init_reserved_nodes());
 		ret.allow_non_reserved = !self.args.flag_reserved_only;
+		ret.allow_ips = self.args.flag_allow_ips.clone();
+		ret.allow_ip_networks = to_ip_networks(&self.args.flag_allow_ip);
+		ret.deny_ip_networks = to_ip_networks(&self.args.flag_deny_ip);
+		try!(IpFilter::parse(&ret.allow_ips, &ret.allow_ip_networks, &ret.deny_ip_networks).map_err(|e| format!("{}", e)));
+		ret.max_upload_rate = self.args.flag_max_upload.map(|k| k * 1024);
+		ret.max_download_rate = self.args.flag_max_download.map(|k| k * 1024);
+		ret.peer_max_upload_rate = self.args.flag_peer_max_upload.map(|k| k * 1024);
+		ret.peer_max_download_rate = self.args.flag_peer_max_download.map(|k| k * 1024);
 		Ok(ret)
 	}
 
+	fn whisper_config(&self) -> Option<WhisperConfig> {
+		if !self.args.flag_whisper {
+			return None;
+		}
+		Some(WhisperConfig {
+			pool_size: self.args.flag_whisper_pool_size * 1024 * 1024,
+			..Default::default()
+		})
+	}
+
+	fn stratum_options(&self) -> Result<Option<StratumOptions>, String> {
+		if !self.args.flag_stratum {
+			return Ok(None);
+		}
+		Ok(Some(StratumOptions {
+			listen_addr: self.stratum_interface(),
+			port: self.args.flag_stratum_port,
+			secret: self.args.flag_stratum_secret.as_ref().map(|s| s.sha3()),
+			share_difficulty: match self.args.flag_stratum_difficulty {
+				Some(ref d) => Some(try!(to_u256(d))),
+				None => None,
+			},
+		}))
+	}
+
+	fn work_socket_options(&self) -> Option<WorkSocketOptions> {
+		if !self.args.flag_work_socket {
+			return None;
+		}
+		Some(WorkSocketOptions {
+			listen_addr: self.work_socket_interface(),
+			port: self.args.flag_work_socket_port,
+		})
+	}
+
+	fn cpu_miner_options(&self) -> Result<Option<CpuMinerOptions>, String> {
+		match self.args.flag_cpu_mining {
+			Some(0) => Err("Invalid number of CPU mining threads: 0".into()),
+			Some(threads) => Ok(Some(CpuMinerOptions {
+				threads: threads,
+			})),
+			None => Ok(None),
+		}
+	}
+
 	fn network_specific_path(&self) -> Result<PathBuf, String> {
 		let spec_type : SpecType = try!(self.chain().parse());
 		let spec = try!(spec_type.spec());
@@ -598,14 +682,26 @@ impl Configuration {
 		}.into()
 	}
 
-	fn rpc_interface(&self) -> String {
-		match self.network_settings().rpc_interface.as_str() {
+	fn interface(interface: &str) -> String {
+		match interface {
 			"all" => "0.0.0.0",
 			"local" => "127.0.0.1",
 			x => x,
 		}.into()
 	}
 
+	fn rpc_interface(&self) -> String {
+		Self::interface(&self.network_settings().rpc_interface)
+	}
+
+	fn stratum_interface(&self) -> String {
+		Self::interface(&self.args.flag_stratum_interface)
+	}
+
+	fn work_socket_interface(&self) -> String {
+		Self::interface(&self.args.flag_work_socket_interface)
+	}
+
 	fn dapps_interface(&self) -> String {
 		match self.args.flag_dapps_interface.as_str() {
 			"local" => "127.0.0.1",
@@ -645,6 +741,12 @@ mod tests {
 	use account::{AccountCmd, NewAccount, ImportAccounts};
 	use devtools::{RandomTempPath};
 	use std::io::Write;
+	use std::path::PathBuf;
+	use ethcore::miner::{GasPriceOracleOptions, PriceSource, StratumOptions, CpuMinerOptions, WorkSocketOptions};
+	use ethcore::engines::SealMode;
+	use util::Hashable;
+	use params::GasPricerConfig;
+	use std::time::Duration;
 	use std::fs::{File, create_dir};
 
 	#[derive(Debug, PartialEq)]
@@ -811,7 +913,12 @@ mod tests {
 			ui: false,
 			name: "".into(),
 			custom_bootnodes: false,
+			fast_sync: false,
 			fat_db: Default::default(),
+			whisper: None,
+			stratum: None,
+			work_socket: None,
+			cpu_miner: None,
 		}));
 	}
 
@@ -973,5 +1080,140 @@ mod tests {
 		let conf = Configuration::parse(args).unwrap();
 		assert!(conf.init_reserved_nodes().is_ok());
 	}
-}
 
+	#[test]
+	fn should_parse_ip_filter() {
+		let conf = parse(&["parity", "--allow-ips", "public", "--allow-ip", "10.0.0.0/8, 192.168.1.7", "--deny-ip", "1.2.3.0/24"]);
+		let net = conf.net_config().unwrap();
+		assert_eq!(net.allow_ips, "public");
+		assert_eq!(net.allow_ip_networks, vec!["10.0.0.0/8".to_owned(), "192.168.1.7".to_owned()]);
+		assert_eq!(net.deny_ip_networks, vec!["1.2.3.0/24".to_owned()]);
+
+		assert!(parse(&["parity", "--allow-ips", "local"]).net_config().is_err());
+		assert!(parse(&["parity", "--deny-ip", "1.2.3.0/33"]).net_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_bandwidth_limits() {
+		let conf = parse(&["parity", "--max-upload", "100", "--peer-max-download", "10"]);
+		let net = conf.net_config().unwrap();
+		assert_eq!(net.max_upload_rate, Some(100 * 1024));
+		assert_eq!(net.max_download_rate, None);
+		assert_eq!(net.peer_max_upload_rate, None);
+		assert_eq!(net.peer_max_download_rate, Some(10 * 1024));
+	}
+
+	#[test]
+	fn should_parse_inbound_outbound_limits() {
+		let conf = parse(&["parity", "--max-inbound-peers", "20", "--reserved-outbound-peers", "5"]);
+		let net = conf.net_config().unwrap();
+		assert_eq!(net.max_inbound_peers, Some(20));
+		assert_eq!(net.max_outbound_peers, None);
+		assert_eq!(net.reserved_outbound_peers, 5);
+
+		let net = parse(&["parity"]).net_config().unwrap();
+		assert_eq!(net.max_inbound_peers, None);
+		assert_eq!(net.reserved_outbound_peers, 10);
+	}
+
+	#[test]
+	fn should_parse_whisper_config() {
+		assert_eq!(parse(&["parity"]).whisper_config(), None);
+		let whisper = parse(&["parity", "--whisper", "--whisper-pool-size", "2"]).whisper_config().unwrap();
+		assert_eq!(whisper.pool_size, 2 * 1024 * 1024);
+	}
+
+	#[test]
+	fn should_parse_transaction_queue_limits() {
+		let conf = parse(&["parity", "--tx-queue-per-sender", "16", "--tx-queue-gas-per-sender", "1000000", "--tx-queue-mem-limit", "4"]);
+		let options = conf.miner_options().unwrap();
+		assert_eq!(options.tx_queue_sender_limit, 16);
+		assert_eq!(options.tx_queue_sender_gas_limit, 1_000_000.into());
+		assert_eq!(options.tx_queue_memory_limit, Some(4 * 1024 * 1024));
+
+		let options = parse(&["parity"]).miner_options().unwrap();
+		assert_eq!(options.tx_queue_sender_limit, usize::max_value());
+		assert_eq!(options.tx_queue_memory_limit, None);
+	}
+
+	#[test]
+	fn should_parse_transaction_filter_path() {
+		assert_eq!(parse(&["parity"]).miner_options().unwrap().transaction_filter, None);
+		let options = parse(&["parity", "--tx-filter", "/etc/parity/filter.json"]).miner_options().unwrap();
+		assert_eq!(options.transaction_filter, Some(PathBuf::from("/etc/parity/filter.json")));
+	}
+
+	#[test]
+	fn should_parse_gas_price_oracle_options() {
+		let options = parse(&["parity", "--gas-price-percentile", "60", "--gas-price-blocks", "20"]).miner_options().unwrap();
+		assert_eq!(options.gas_price_oracle, GasPriceOracleOptions { blocks: 20, percentile: 60 });
+		assert!(parse(&["parity", "--gas-price-percentile", "101"]).miner_options().is_err());
+	}
+
+	#[test]
+	fn should_parse_usd_per_eth_sources() {
+		let conf = parse(&["parity", "--usd-per-tx", "0.0025", "--usd-per-eth", "etherscan,file:/tmp/price"]);
+		assert_eq!(conf.gas_pricer_config().unwrap(), GasPricerConfig::Calibrated {
+			usd_per_tx: 0.0025,
+			recalibration_period: Duration::from_secs(3600),
+			price_sources: vec![PriceSource::etherscan(), PriceSource::File(PathBuf::from("/tmp/price"))],
+		});
+
+		// fixed prices are combined up front
+		let median = parse(&["parity", "--usd-per-tx", "0.0025", "--usd-per-eth", "10,12,14"]).gas_pricer_config().unwrap();
+		let single = parse(&["parity", "--usd-per-tx", "0.0025", "--usd-per-eth", "12"]).gas_pricer_config().unwrap();
+		assert_eq!(median, single);
+		match median {
+			GasPricerConfig::Fixed(_) => {},
+			other => panic!("Unexpected gas pricer: {:?}", other),
+		}
+		assert!(parse(&["parity", "--usd-per-eth", "free"]).gas_pricer_config().is_err());
+	}
+
+	#[test]
+	fn should_parse_stratum_options() {
+		assert_eq!(parse(&["parity"]).stratum_options(), Ok(None));
+		assert_eq!(parse(&["parity", "--stratum"]).stratum_options(), Ok(Some(StratumOptions {
+			listen_addr: "127.0.0.1".into(),
+			port: 8008,
+			secret: None,
+			share_difficulty: None,
+		})));
+		assert_eq!(parse(&["parity", "--stratum", "--stratum-interface", "all", "--stratum-port", "3333", "--stratum-secret", "secret", "--stratum-difficulty", "4294967296"]).stratum_options(), Ok(Some(StratumOptions {
+			listen_addr: "0.0.0.0".into(),
+			port: 3333,
+			secret: Some("secret".sha3()),
+			share_difficulty: Some(4294967296u64.into()),
+		})));
+	}
+
+	#[test]
+	fn should_parse_work_socket_options() {
+		assert_eq!(parse(&["parity"]).work_socket_options(), None);
+		assert_eq!(parse(&["parity", "--work-socket"]).work_socket_options(), Some(WorkSocketOptions {
+			listen_addr: "127.0.0.1".into(),
+			port: 8009,
+		}));
+		assert_eq!(parse(&["parity", "--work-socket", "--work-socket-interface", "all", "--work-socket-port", "8010"]).work_socket_options(), Some(WorkSocketOptions {
+			listen_addr: "0.0.0.0".into(),
+			port: 8010,
+		}));
+	}
+
+	#[test]
+	fn should_parse_cpu_miner_options() {
+		assert_eq!(parse(&["parity"]).cpu_miner_options(), Ok(None));
+		assert_eq!(parse(&["parity", "--cpu-mining", "2"]).cpu_miner_options(), Ok(Some(CpuMinerOptions {
+			threads: 2,
+		})));
+		assert!(parse(&["parity", "--cpu-mining", "0"]).cpu_miner_options().is_err());
+	}
+
+	#[test]
+	fn should_parse_seal_mode() {
+		assert_eq!(parse(&["parity"]).miner_options().unwrap().seal_mode, None);
+		assert_eq!(parse(&["parity", "--seal-mode", "manual"]).miner_options().unwrap().seal_mode, Some(SealMode::Manual));
+		assert_eq!(parse(&["parity", "--seal-mode", "interval:3"]).miner_options().unwrap().seal_mode, Some(SealMode::Interval(3)));
+		assert!(parse(&["parity", "--seal-mode", "interval:0"]).miner_options().is_err());
+	}
+}
@@ -22,7 +22,8 @@ use std::fs::File;
 use util::{clean_0x, U256, Uint, Address, path, H256, CompactionProfile};
 use util::journaldb::Algorithm;
 use ethcore::client::{Mode, BlockID, Switch, VMType, DatabaseCompactionProfile, ClientConfig};
-use ethcore::miner::PendingSet;
+use ethcore::miner::{PendingSet, PriceSource};
+use ethcore::engines::SealMode;
 use cache::CacheConfig;
 use dir::Directories;
 use params::Pruning;
@@ -94,6 +95,18 @@ pub fn to_pending_set(s: &str) -> Result<PendingSet, String> {
 	}
 }
 
+pub fn to_seal_mode(s: &str) -> Result<SealMode, String> {
+	match s {
+		"instant" => Ok(SealMode::Instant),
+		"manual" => Ok(SealMode::Manual),
+		other if other.starts_with("interval:") => match other["interval:".len()..].parse::<u64>() {
+			Ok(secs) if secs > 0 => Ok(SealMode::Interval(secs)),
+			_ => Err(format!("Invalid seal interval: {:?}", other)),
+		},
+		other => Err(format!("Invalid seal mode value: {:?}", other)),
+	}
+}
+
 pub fn to_address(s: Option<String>) -> Result<Address, String> {
 	match s {
 		Some(ref a) => clean_0x(a).parse().map_err(|_| format!("Invalid address: {:?}", a)),
@@ -115,6 +128,21 @@ pub fn to_price(s: &str) -> Result<f32, String> {
 	s.parse::<f32>().map_err(|_| format!("Invalid transaciton price 's' given. Must be a decimal number."))
 }
 
+/// Tries to parse comma-separated list of Ether price sources.
+/// `auto` stands for the default web service.
+pub fn to_price_sources(s: &str) -> Result<Vec<PriceSource>, String> {
+	if s == "auto" {
+		return Ok(vec![PriceSource::etherscan()]);
+	}
+
+	s.split(',')
+		.map(|source| match source.trim() {
+			source if source.starts_with("file:") => Ok(PriceSource::File(replace_home(&source[5..]).into())),
+			source => source.parse(),
+		})
+		.collect()
+}
+
 /// Replaces `$HOME` str with home directory path.
 pub fn replace_home(arg: &str) -> String {
 	// the $HOME directory on mac os should be `~/Library` or `~/Library/Application Support`
@@ -167,6 +195,13 @@ pub fn to_bootnodes(bootnodes: &Option<String>) -> Result<Vec<String>, String> {
 	}
 }
 
+pub fn to_ip_networks(networks: &Option<String>) -> Vec<String> {
+	match *networks {
+		Some(ref x) => x.split(',').map(|s| s.trim().to_owned()).filter(|s| !s.is_empty()).collect(),
+		None => Vec::new(),
+	}
+}
+
 #[cfg(test)]
 pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 	use ethsync::NetworkConfiguration;
@@ -182,8 +217,18 @@ pub fn default_network_config() -> ::ethsync::NetworkConfiguration {
 		use_secret: None,
 		max_peers: 50,
 		min_peers: 25,
+		max_inbound_peers: None,
+		max_outbound_peers: None,
+		reserved_outbound_peers: 10,
 		reserved_nodes: Vec::new(),
 		allow_non_reserved: true,
+		allow_ips: "all".into(),
+		allow_ip_networks: Vec::new(),
+		deny_ip_networks: Vec::new(),
+		max_upload_rate: None,
+		max_download_rate: None,
+		peer_max_upload_rate: None,
+		peer_max_download_rate: None,
 	}
 }
 
@@ -302,8 +347,9 @@ mod tests {
 	use std::time::Duration;
 	use util::{U256};
 	use ethcore::client::{Mode, BlockID};
-	use ethcore::miner::PendingSet;
-	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_address, to_addresses, to_price, geth_ipc_path, to_bootnodes};
+	use ethcore::miner::{PendingSet, PriceSource};
+	use ethcore::engines::SealMode;
+	use super::{to_duration, to_mode, to_block_id, to_u256, to_pending_set, to_seal_mode, to_address, to_addresses, to_price, to_price_sources, geth_ipc_path, to_bootnodes};
 
 	#[test]
 	fn test_to_duration() {
@@ -361,6 +407,16 @@ mod tests {
 		assert!(to_pending_set("othe").is_err());
 	}
 
+	#[test]
+	fn test_to_seal_mode() {
+		assert_eq!(to_seal_mode("instant").unwrap(), SealMode::Instant);
+		assert_eq!(to_seal_mode("manual").unwrap(), SealMode::Manual);
+		assert_eq!(to_seal_mode("interval:15").unwrap(), SealMode::Interval(15));
+		assert!(to_seal_mode("interval:0").is_err());
+		assert!(to_seal_mode("interval").is_err());
+		assert!(to_seal_mode("other").is_err());
+	}
+
 	#[test]
 	fn test_to_address() {
 		assert_eq!(
@@ -394,6 +450,19 @@ mod tests {
 		assert_eq!(to_price("2.33").unwrap(), 2.33);
 	}
 
+	#[test]
+	#[cfg_attr(feature = "dev", allow(float_cmp))]
+	fn test_to_price_sources() {
+		assert_eq!(to_price_sources("auto").unwrap(), vec![PriceSource::etherscan()]);
+		assert_eq!(to_price_sources("10.5").unwrap(), vec![PriceSource::Fixed(10.5)]);
+		assert_eq!(to_price_sources("etherscan, http://example.com/ticker#data.price, file:/tmp/price").unwrap(), vec![
+			PriceSource::etherscan(),
+			PriceSource::Http { url: "http://example.com/ticker".into(), path: vec!["data".into(), "price".into()] },
+			PriceSource::File("/tmp/price".into()),
+		]);
+		assert!(to_price_sources("etherscan,nothing").is_err());
+	}
+
 	#[test]
 	#[cfg(windows)]
 	fn test_geth_ipc_path() {
@@ -39,6 +39,8 @@ pub struct Informant {
 	net: Option<Arc<ManageNetwork>>,
 	last_import: Mutex<Instant>,
 	skipped: AtomicUsize,
+	/// Total bytes sent and received at the last tick.
+	last_traffic: Mutex<Option<(u64, u64)>>,
 }
 
 trait MillisecondDuration {
@@ -65,6 +67,7 @@ impl Informant {
 			net: net,
 			last_import: Mutex::new(Instant::now()),
 			skipped: AtomicUsize::new(0),
+			last_traffic: Mutex::new(None),
 		}
 	}
 
@@ -87,6 +90,7 @@ impl Informant {
 		let queue_info = self.client.queue_info();
 		let cache_info = self.client.blockchain_cache_info();
 		let network_config = self.net.as_ref().map(|n| n.network_config());
+		let network_traffic = self.net.as_ref().map(|n| n.network_traffic());
 		let sync_status = self.sync.as_ref().map(|s| s.status());
 
 		let importing = queue_info.unverified_queue_size + queue_info.verified_queue_size > 3
@@ -100,6 +104,14 @@ impl Informant {
 		let mut write_report = self.report.write();
 		let report = self.client.report();
 
+		let traffic_rate = network_traffic.map(|t| {
+			let mut last_traffic = self.last_traffic.lock();
+			let (last_sent, last_recv) = last_traffic.unwrap_or((t.sent_bytes, t.recv_bytes));
+			*last_traffic = Some((t.sent_bytes, t.recv_bytes));
+			let ms = ::std::cmp::max(elapsed.as_milliseconds(), 1);
+			(t.sent_bytes.saturating_sub(last_sent) * 1000 / ms, t.recv_bytes.saturating_sub(last_recv) * 1000 / ms)
+		});
+
 		let paint = |c: Style, t: String| match self.with_color && stdout_isatty() {
 			true => format!("{}", c.paint(t)),
 			false => t,
@@ -124,14 +136,25 @@ impl Informant {
 				false => String::new(),
 			},
 			match (&sync_status, &network_config) {
-				(&Some(ref sync_info), &Some(ref net_config)) => format!("{}{}/{}/{} peers",
+				(&Some(ref sync_info), &Some(ref net_config)) => format!("{}{}{}/{}/{} peers{}",
 					match importing {
 						true => format!("{}   ", paint(Green.bold(), format!("{:>8}", format!("#{}", sync_info.last_imported_block_number.unwrap_or(chain_info.best_block_number))))),
 						false => String::new(),
 					},
+					match sync_info.ancient_blocks_left {
+						Some(left) => format!("{} old blocks   ", paint(Yellow.bold(), format!("{}", left))),
+						None => String::new(),
+					},
 					paint(Cyan.bold(), format!("{:2}", sync_info.num_active_peers)),
 					paint(Cyan.bold(), format!("{:2}", sync_info.num_peers)),
 					paint(Cyan.bold(), format!("{:2}", sync_info.current_max_peers(net_config.min_peers, net_config.max_peers))),
+					match traffic_rate {
+						Some((up, down)) => format!(" {}/s up {}/s down",
+							paint(Cyan.bold(), format!("{:>8}", Informant::format_bytes(up as usize))),
+							paint(Cyan.bold(), format!("{:>8}", Informant::format_bytes(down as usize))),
+						),
+						None => String::new(),
+					},
 				),
 				_ => String::new(),
 			},
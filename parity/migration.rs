@@ -29,7 +29,7 @@ use ethcore::migrations::Extract;
 /// Database is assumed to be at default version, when no version file is found.
 const DEFAULT_VERSION: u32 = 5;
 /// Current version of database models.
-const CURRENT_VERSION: u32 = 9;
+const CURRENT_VERSION: u32 = 10;
 /// First version of the consolidated database.
 const CONSOLIDATION_VERSION: u32 = 9;
 /// Defines how many items are migrated to the new version of database at once.
@@ -140,7 +140,8 @@ pub fn default_migration_settings(compaction_profile: &CompactionProfile) -> Mig
 
 /// Migrations on the consolidated database.
 fn consolidated_database_migrations(compaction_profile: &CompactionProfile) -> Result<MigrationManager, Error> {
-	let manager = MigrationManager::new(default_migration_settings(compaction_profile));
+	let mut manager = MigrationManager::new(default_migration_settings(compaction_profile));
+	try!(manager.add_migration(migrations::TO_V10).map_err(|_| Error::MigrationImpossible));
 	Ok(manager)
 }
 
@@ -218,7 +219,7 @@ fn exists(path: &Path) -> bool {
 /// Migrates the database.
 pub fn migrate(path: &Path, pruning: Algorithm, compaction_profile: CompactionProfile) -> Result<(), Error> {
 	// read version file.
-	let version = try!(current_version(path));
+	let mut version = try!(current_version(path));
 
 	// migrate the databases.
 	// main db directory may already exists, so let's check if we have blocks dir
@@ -249,6 +250,7 @@ pub fn migrate(path: &Path, pruning: Algorithm, compaction_profile: CompactionPr
 		let _ = fs::remove_dir_all(legacy::extras_database_path(path));
 		let _ = fs::remove_dir_all(legacy::state_database_path(path));
 		let _ = fs::remove_dir_all(legacy::trace_database_path(path));
+		version = CONSOLIDATION_VERSION;
 		println!("Migration finished");
 	}
 
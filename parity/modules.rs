@@ -17,13 +17,19 @@
 use std::sync::Arc;
 use ethcore::client::BlockChainClient;
 use hypervisor::Hypervisor;
-use ethsync::{SyncConfig, NetworkConfiguration, NetworkError};
+use ethsync::{SyncConfig, NetworkConfiguration, NetworkError, AttachedProtocol};
 #[cfg(not(feature="ipc"))]
 use self::no_ipc_deps::*;
 #[cfg(feature="ipc")]
 use self::ipc_deps::*;
 use ethcore_logger::Config as LogConfig;
 use std::path::Path;
+use ethcore::client::Client;
+use ethcore::miner::{Miner, ExternalMinerService, StratumOptions};
+#[cfg(not(feature="stratum"))]
+use ethcore::miner::Stratum;
+#[cfg(feature="stratum")]
+use self::stratum_deps::*;
 
 pub mod service_urls {
 	use std::path::PathBuf;
@@ -37,6 +43,8 @@ pub mod service_urls {
 	pub const STRATUM: &'static str = "parity-stratum.ipc";
 	#[cfg(feature="stratum")]
 	pub const MINING_JOB_DISPATCHER: &'static str = "parity-mining-jobs.ipc";
+	#[cfg(feature="stratum")]
+	pub const STRATUM_CONTROL: &'static str = "parity-stratum-control.ipc";
 
 
 	pub fn with_base(data_dir: &str, service_path: &str) -> String {
@@ -73,6 +81,23 @@ mod ipc_deps {
 	pub use ipc::binary::serialize;
 }
 
+#[cfg(feature="stratum")]
+pub type StratumModule = GuardedSocket<RemoteWorkHandler<NanoSocket>>;
+
+#[cfg(not(feature="stratum"))]
+pub type StratumModule = Arc<Stratum>;
+
+#[cfg(feature="stratum")]
+mod stratum_deps {
+	pub use std::net::SocketAddr;
+	pub use std::sync::atomic::AtomicBool;
+	pub use ethcore::client::MiningBlockChainClient;
+	pub use ethcore::miner::{StratumJobDispatcher, StratumWorkNotifier};
+	pub use ethcore_stratum::{JobDispatcher, RemoteWorkHandler, ServiceConfiguration as StratumServiceConfiguration};
+	pub use hypervisor::STRATUM_MODULE_ID;
+	pub use boot;
+}
+
 #[cfg(feature="ipc")]
 pub fn hypervisor(base_path: &Path) -> Option<Hypervisor> {
 	Some(Hypervisor
@@ -119,10 +144,15 @@ pub fn sync
 		sync_cfg: SyncConfig,
 		net_cfg: NetworkConfiguration,
 		_client: Arc<BlockChainClient>,
+		attached_protos: Vec<AttachedProtocol>,
 		log_settings: &LogConfig,
 	)
 	-> Result<SyncModules, NetworkError>
 {
+	if !attached_protos.is_empty() {
+		warn!("Subprotocols other than eth are not supported with sync running in a separate process. Disabling: {}",
+			attached_protos.iter().map(|p| p.protocol_id).collect::<Vec<_>>().join(", "));
+	}
 	let mut hypervisor = hypervisor_ref.take().expect("There should be hypervisor for ipc configuration");
 	let args = sync_arguments(&hypervisor.io_path, sync_cfg, net_cfg, log_settings);
 	hypervisor = hypervisor.module(SYNC_MODULE_ID, args);
@@ -148,10 +178,92 @@ pub fn sync
 		sync_cfg: SyncConfig,
 		net_cfg: NetworkConfiguration,
 		client: Arc<BlockChainClient>,
+		attached_protos: Vec<AttachedProtocol>,
 		_log_settings: &LogConfig,
 	)
 	-> Result<SyncModules, NetworkError>
 {
-	let eth_sync = try!(EthSync::new(sync_cfg, client, net_cfg));
+	let eth_sync = try!(EthSync::new(sync_cfg, client, net_cfg, attached_protos));
 	Ok((eth_sync.clone() as Arc<SyncProvider>, eth_sync.clone() as Arc<ManageNetwork>, eth_sync.clone() as Arc<ChainNotify>))
 }
+
+#[cfg(feature="stratum")]
+fn stratum_arguments(io_path: &str, listen_addr: &SocketAddr, options: &StratumOptions, log_settings: &LogConfig) -> BootArgs {
+	let service_config = StratumServiceConfiguration {
+		listen_addr: format!("{}", listen_addr),
+		secret: options.secret,
+		share_difficulty: options.share_difficulty,
+		io_path: io_path.to_owned(),
+	};
+
+	// initialisation payload is passed via stdin
+	let service_payload = serialize(&service_config).expect("Any binary-derived struct is serializable by definition");
+
+	// logging settings are passed in command line
+	let mut cli_args = Vec::new();
+	cli_args.push("stratum".to_owned());
+	if !log_settings.color { cli_args.push("--no-color".to_owned()); }
+	if let Some(ref mode) = log_settings.mode {
+		cli_args.push("-l".to_owned());
+		cli_args.push(mode.to_owned());
+	}
+	if let Some(ref file) = log_settings.file {
+		cli_args.push("--log-file".to_owned());
+		cli_args.push(file.to_owned());
+	}
+
+	BootArgs::new().stdin(service_payload).cli(cli_args)
+}
+
+/// Hosts the job dispatcher for the stratum module and pushes new work to it.
+/// The module itself is started along with the others by the hypervisor.
+#[cfg(feature="stratum")]
+pub fn stratum
+	(
+		hypervisor_ref: &mut Option<Hypervisor>,
+		options: &StratumOptions,
+		miner: Arc<Miner>,
+		client: Arc<Client>,
+		external_miner: Arc<ExternalMinerService>,
+		log_settings: &LogConfig,
+	)
+	-> Result<StratumModule, String>
+{
+	let listen_addr = try!(format!("{}:{}", options.listen_addr, options.port).parse::<SocketAddr>()
+		.map_err(|e| format!("Invalid stratum listen address: {}", e)));
+	let mut hypervisor = hypervisor_ref.take().expect("There should be hypervisor for ipc configuration");
+
+	let client: Arc<MiningBlockChainClient> = client;
+	let dispatcher = Arc::new(StratumJobDispatcher::new(Arc::downgrade(&miner), Arc::downgrade(&client), external_miner));
+	boot::host_service(
+		&service_urls::with_base(&hypervisor.io_path, service_urls::MINING_JOB_DISPATCHER),
+		Arc::new(AtomicBool::new(false)),
+		dispatcher.clone() as Arc<JobDispatcher>
+	);
+
+	let work_handler = try!(init_client::<RemoteWorkHandler<_>>(
+		&service_urls::with_base(&hypervisor.io_path, service_urls::STRATUM))
+		.map_err(|e| format!("Unable to connect to stratum module: {:?}", e)));
+	miner.add_work_listener(Box::new(StratumWorkNotifier::new(dispatcher, work_handler.service())));
+
+	let args = stratum_arguments(&hypervisor.io_path, &listen_addr, options, log_settings);
+	hypervisor = hypervisor.module(STRATUM_MODULE_ID, args);
+
+	*hypervisor_ref = Some(hypervisor);
+	Ok(work_handler)
+}
+
+#[cfg(not(feature="stratum"))]
+pub fn stratum
+	(
+		_hypervisor: &mut Option<Hypervisor>,
+		options: &StratumOptions,
+		miner: Arc<Miner>,
+		client: Arc<Client>,
+		external_miner: Arc<ExternalMinerService>,
+		_log_settings: &LogConfig,
+	)
+	-> Result<StratumModule, String>
+{
+	Stratum::register(options, miner, client, external_miner).map_err(|e| format!("{}", e))
+}
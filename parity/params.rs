@@ -21,7 +21,7 @@ use util::{contents, H256, Address, U256, version_data};
 use util::journaldb::Algorithm;
 use ethcore::spec::Spec;
 use ethcore::ethereum;
-use ethcore::miner::{GasPricer, GasPriceCalibratorOptions};
+use ethcore::miner::{GasPricer, GasPriceCalibratorOptions, PriceSource};
 use dir::Directories;
 
 #[derive(Debug, PartialEq)]
@@ -173,6 +173,7 @@ pub enum GasPricerConfig {
 	Calibrated {
 		usd_per_tx: f32,
 		recalibration_period: Duration,
+		price_sources: Vec<PriceSource>,
 	}
 }
 
@@ -181,6 +182,7 @@ impl Default for GasPricerConfig {
 		GasPricerConfig::Calibrated {
 			usd_per_tx: 0f32,
 			recalibration_period: Duration::from_secs(3600),
+			price_sources: vec![PriceSource::etherscan()],
 		}
 	}
 }
@@ -189,10 +191,11 @@ impl Into<GasPricer> for GasPricerConfig {
 	fn into(self) -> GasPricer {
 		match self {
 			GasPricerConfig::Fixed(u) => GasPricer::Fixed(u),
-			GasPricerConfig::Calibrated { usd_per_tx, recalibration_period } => {
+			GasPricerConfig::Calibrated { usd_per_tx, recalibration_period, price_sources } => {
 				GasPricer::new_calibrated(GasPriceCalibratorOptions {
 					usd_per_tx: usd_per_tx,
 					recalibration_period: recalibration_period,
+					price_sources: price_sources,
 				})
 			}
 		}
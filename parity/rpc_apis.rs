@@ -24,6 +24,7 @@ use ethcore::miner::{Miner, ExternalMiner};
 use ethcore::client::Client;
 use ethcore::account_provider::AccountProvider;
 use ethsync::{ManageNetwork, SyncProvider};
+use whisper::Whisper;
 use ethcore_rpc::{Extendable, NetworkSettings};
 pub use ethcore_rpc::ConfirmationsQueue;
 
@@ -39,6 +40,9 @@ pub enum Api {
 	EthcoreSet,
 	Traces,
 	Rpc,
+	Shh,
+	TxPool,
+	Evm,
 }
 
 impl FromStr for Api {
@@ -57,6 +61,9 @@ impl FromStr for Api {
 			"ethcore_set" => Ok(EthcoreSet),
 			"traces" => Ok(Traces),
 			"rpc" => Ok(Rpc),
+			"shh" => Ok(Shh),
+			"txpool" => Ok(TxPool),
+			"evm" => Ok(Evm),
 			api => Err(format!("Unknown api: {}", api))
 		}
 	}
@@ -104,6 +111,7 @@ pub struct Dependencies {
 	pub logger: Arc<RotatingLogger>,
 	pub settings: Arc<NetworkSettings>,
 	pub net_service: Arc<ManageNetwork>,
+	pub whisper: Option<Arc<Whisper>>,
 	pub geth_compatibility: bool,
 }
 
@@ -120,6 +128,9 @@ fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 			Api::EthcoreSet => ("ethcore_set", "1.0"),
 			Api::Traces => ("traces", "1.0"),
 			Api::Rpc => ("rpc", "1.0"),
+			Api::Shh => ("shh", "1.0"),
+			Api::TxPool => ("txpool", "1.0"),
+			Api::Evm => ("evm", "1.0"),
 		};
 		modules.insert(name.into(), version.into());
 	}
@@ -186,7 +197,7 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 			},
 			Api::Ethcore => {
 				let queue = deps.signer_port.map(|_| deps.signer_queue.clone());
-				server.add_delegate(EthcoreClient::new(&deps.client, &deps.miner, &deps.sync, &deps.net_service, deps.logger.clone(), deps.settings.clone(), queue).to_delegate())
+				server.add_delegate(EthcoreClient::new(&deps.client, &deps.miner, &deps.sync, &deps.net_service, deps.external_miner.clone(), deps.logger.clone(), deps.settings.clone(), queue).to_delegate())
 			},
 			Api::EthcoreSet => {
 				server.add_delegate(EthcoreSetClient::new(&deps.client, &deps.miner, &deps.net_service).to_delegate())
@@ -197,6 +208,21 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 			Api::Rpc => {
 				let modules = to_modules(&apis);
 				server.add_delegate(RpcClient::new(modules).to_delegate());
+			},
+			Api::Shh => {
+				match deps.whisper {
+					Some(ref whisper) => server.add_delegate(ShhClient::new(whisper).to_delegate()),
+					None => warn!("Whisper API requested, but Whisper is disabled. Enable it with --whisper."),
+				}
+			},
+			Api::TxPool => {
+				server.add_delegate(TxPoolClient::new(&deps.miner).to_delegate());
+			},
+			Api::Evm => {
+				match deps.client.allows_revert() {
+					true => server.add_delegate(EvmClient::new(&deps.client).to_delegate()),
+					false => warn!("EVM API requested, but the chain can not be reverted. Use a chain with InstantSeal or Null engine."),
+				}
 			}
 		}
 	}
@@ -218,6 +244,9 @@ mod test {
 		assert_eq!(Api::EthcoreSet, "ethcore_set".parse().unwrap());
 		assert_eq!(Api::Traces, "traces".parse().unwrap());
 		assert_eq!(Api::Rpc, "rpc".parse().unwrap());
+		assert_eq!(Api::Shh, "shh".parse().unwrap());
+		assert_eq!(Api::TxPool, "txpool".parse().unwrap());
+		assert_eq!(Api::Evm, "evm".parse().unwrap());
 		assert!("rp".parse::<Api>().is_err());
 	}
 
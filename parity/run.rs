@@ -28,8 +28,9 @@ use io::{MayPanic, ForwardPanic, PanicHandler};
 use ethcore::client::{Mode, Switch, DatabaseCompactionProfile, VMType, ChainNotify};
 use ethcore::service::ClientService;
 use ethcore::account_provider::AccountProvider;
-use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions};
-use ethsync::SyncConfig;
+use ethcore::miner::{Miner, MinerService, ExternalMiner, MinerOptions, StratumOptions, CpuMiner, CpuMinerOptions, WorkSocket, WorkSocketOptions};
+use ethsync::{SyncConfig, ForkCheckpoint, AttachedProtocol};
+use whisper::{self, Whisper, WhisperConfig};
 use informant::Informant;
 
 use rpc::{HttpServer, IpcServer, HttpConfiguration, IpcConfiguration};
@@ -79,6 +80,11 @@ pub struct RunCmd {
 	pub ui: bool,
 	pub name: String,
 	pub custom_bootnodes: bool,
+	pub fast_sync: bool,
+	pub whisper: Option<WhisperConfig>,
+	pub stratum: Option<StratumOptions>,
+	pub work_socket: Option<WorkSocketOptions>,
+	pub cpu_miner: Option<CpuMinerOptions>,
 }
 
 pub fn execute(cmd: RunCmd) -> Result<(), String> {
@@ -143,7 +149,8 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		Some(id) => id,
 		None => spec.network_id(),
 	};
-	sync_config.fork_block = spec.fork_block();
+	sync_config.fork_checkpoints = spec.fork_checkpoints().iter().map(|&(number, hash)| ForkCheckpoint { number: number, hash: hash }).collect();
+	sync_config.fast_sync = cmd.fast_sync;
 
 	// prepare account provider
 	let account_provider = Arc::new(try!(prepare_account_provider(&cmd.dirs, cmd.acc_conf)));
@@ -155,6 +162,7 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	miner.set_gas_ceil_target(cmd.miner_extras.gas_ceil_target);
 	miner.set_extra_data(cmd.miner_extras.extra_data);
 	miner.set_transactions_limit(cmd.miner_extras.transactions_limit);
+	try!(miner.reload_transaction_filter());
 
 	// create client config
 	let client_config = to_client_config(
@@ -196,12 +204,47 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// take handle to client
 	let client = service.client();
 
+	// blocks imported by fast sync are not executed, so they have no traces.
+	if cmd.fast_sync && client.tracing_enabled() {
+		return Err("Fast sync is not supported with tracing enabled. Please rerun with `--tracing off`.".into());
+	}
+
 	// create external miner
 	let external_miner = Arc::new(ExternalMiner::default());
 
+	// start stratum server
+	let stratum = match cmd.stratum {
+		Some(ref stratum_options) => Some(try!(modules::stratum(
+			&mut hypervisor, stratum_options, miner.clone(), client.clone(), external_miner.clone(), &cmd.logger_config,
+		).map_err(|e| format!("Stratum error: {}", e)))),
+		None => None,
+	};
+
+	// start work socket
+	let work_socket = match cmd.work_socket {
+		Some(ref work_socket_options) => Some(try!(WorkSocket::register(work_socket_options, miner.clone(), client.clone())
+			.map_err(|e| format!("Work socket error: {}", e)))),
+		None => None,
+	};
+
+	// start built-in CPU miner
+	let cpu_miner = match cmd.cpu_miner {
+		Some(ref cpu_miner_options) => Some(try!(CpuMiner::start(cpu_miner_options, miner.clone(), client.clone(), external_miner.clone()))),
+		None => None,
+	};
+
+	// create whisper protocol handler
+	let whisper = cmd.whisper.map(|config| Arc::new(Whisper::new(config)));
+	let attached_protos = whisper.iter().map(|w| AttachedProtocol {
+		handler: w.clone(),
+		protocol_id: whisper::PROTOCOL_ID,
+		packet_count: whisper::PACKET_COUNT,
+		versions: whisper::PROTOCOL_VERSIONS,
+	}).collect();
+
 	// create sync object
 	let (sync_provider, manage_network, chain_notify) = try!(modules::sync(
-		&mut hypervisor, sync_config, net_conf.into(), client.clone(), &cmd.logger_config,
+		&mut hypervisor, sync_config, net_conf.into(), client.clone(), attached_protos, &cmd.logger_config,
 	).map_err(|e| format!("Sync error: {}", e)));
 
 	service.add_notify(chain_notify.clone());
@@ -224,6 +267,7 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 		logger: logger.clone(),
 		settings: Arc::new(cmd.net_settings.clone()),
 		net_service: manage_network.clone(),
+		whisper: whisper.clone(),
 		geth_compatibility: cmd.geth_compatibility,
 	});
 
@@ -280,6 +324,11 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
 	// terminated gracefully
 	drop(hypervisor);
 
+	// stop mining threads and disconnect miners
+	drop(cpu_miner);
+	drop(work_socket);
+	drop(stratum);
+
 	Ok(())
 }
 
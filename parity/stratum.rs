@@ -14,19 +14,30 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Parity sync service
+//! Parity stratum service
 
-use std;
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use ethcore_stratum::{Stratum as StratumServer, PushWorkHandler, RemoteJobDispatcher, ServiceConfiguration};
-use std::thread;
+use hypervisor::{STRATUM_MODULE_ID, HYPERVISOR_IPC_URL, ControlService};
 use modules::service_urls;
 use boot;
-use hypervisor::service::IpcModuleId;
+use nanoipc;
 use std::net::SocketAddr;
 use std::str::FromStr;
 
-const STRATUM_MODULE_ID: IpcModuleId = 8000;
+#[derive(Default)]
+struct StratumControlService {
+	pub stop: Arc<AtomicBool>,
+}
+
+impl ControlService for StratumControlService {
+	fn shutdown(&self) -> bool {
+		trace!(target: "hypervisor", "Received shutdown from control service");
+		self.stop.store(true, ::std::sync::atomic::Ordering::SeqCst);
+		true
+	}
+}
 
 pub fn main() {
 	boot::setup_cli_logger("stratum");
@@ -34,9 +45,11 @@ pub fn main() {
 	let service_config: ServiceConfiguration = boot::payload()
 		.unwrap_or_else(|e| panic!("Fatal: error reading boot arguments ({:?})", e));
 
-	let job_dispatcher = dependency!(RemoteJobDispatcher, service_urls::MINING_JOB_DISPATCHER);
+	let job_dispatcher = dependency!(RemoteJobDispatcher, &service_urls::with_base(&service_config.io_path, service_urls::MINING_JOB_DISPATCHER));
+
+	let _ = boot::main_thread();
+	let service_stop = Arc::new(AtomicBool::new(false));
 
-	let stop = boot::main_thread();
 	let server =
 		StratumServer::start(
 			&SocketAddr::from_str(&service_config.listen_addr)
@@ -46,12 +59,33 @@ pub fn main() {
 		).unwrap_or_else(
 			|e| panic!("Fatal: cannot start stratum server({:?})", e)
 		);
+	server.set_default_difficulty(service_config.share_difficulty);
+
+	boot::host_service(
+		&service_urls::with_base(&service_config.io_path, service_urls::STRATUM),
+		service_stop.clone(),
+		server.clone() as Arc<PushWorkHandler>
+	);
 
-	boot::host_service(service_urls::STRATUM, stop.clone(), server.clone() as Arc<PushWorkHandler>);
+	let hypervisor = boot::register(
+		&service_urls::with_base(&service_config.io_path, HYPERVISOR_IPC_URL),
+		&service_urls::with_base(&service_config.io_path, service_urls::STRATUM_CONTROL),
+		STRATUM_MODULE_ID
+	);
 
-	let _ = boot::register(STRATUM_MODULE_ID);
+	let control_service = Arc::new(StratumControlService::default());
+	let as_control = control_service.clone() as Arc<ControlService>;
+	let mut worker = nanoipc::Worker::<ControlService>::new(&as_control);
+	let thread_stop = control_service.stop.clone();
+	worker.add_reqrep(
+		&service_urls::with_base(&service_config.io_path, service_urls::STRATUM_CONTROL)
+	).unwrap();
 
-	while !stop.load(::std::sync::atomic::Ordering::Relaxed) {
-		thread::park_timeout(std::time::Duration::from_millis(1000));
+	while !thread_stop.load(::std::sync::atomic::Ordering::SeqCst) {
+		worker.poll();
 	}
+	service_stop.store(true, ::std::sync::atomic::Ordering::SeqCst);
+
+	hypervisor.module_shutdown(STRATUM_MODULE_ID);
+	trace!(target: "hypervisor", "Stratum process terminated gracefully");
 }
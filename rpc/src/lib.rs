@@ -30,6 +30,7 @@ extern crate ethcore;
 extern crate ethkey;
 extern crate ethstore;
 extern crate ethsync;
+extern crate ethcore_whisper as whisper;
 extern crate transient_hashmap;
 extern crate json_ipc_server as ipc;
 extern crate ethcore_ipc;
@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use util::{Address, U256, Bytes, H256};
+use ethcore::transaction::Condition;
 
 /// Transaction request coming from RPC
 #[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
@@ -33,6 +34,8 @@ pub struct TransactionRequest {
 	pub data: Option<Bytes>,
 	/// Transaction's nonce
 	pub nonce: Option<U256>,
+	/// Condition to be met before the transaction is propagated and mined
+	pub condition: Option<Condition>,
 }
 
 /// Transaction request coming from RPC with default values filled in.
@@ -52,6 +55,8 @@ pub struct FilledTransactionRequest {
 	pub data: Bytes,
 	/// Transaction's nonce
 	pub nonce: Option<U256>,
+	/// Condition to be met before the transaction is propagated and mined
+	pub condition: Option<Condition>,
 }
 
 impl From<FilledTransactionRequest> for TransactionRequest {
@@ -64,6 +69,7 @@ impl From<FilledTransactionRequest> for TransactionRequest {
 			value: Some(r.value),
 			data: Some(r.data),
 			nonce: r.nonce,
+			condition: r.condition,
 		}
 	}
 }
@@ -328,6 +328,7 @@ mod test {
 			value: 10_000_000.into(),
 			data: vec![],
 			nonce: None,
+			condition: None,
 		})
 	}
 
@@ -24,15 +24,18 @@ use util::misc::version_data;
 use ethkey::{Brain, Generator};
 use ethstore::random_phrase;
 use ethsync::{SyncProvider, ManageNetwork};
-use ethcore::miner::MinerService;
+use ethcore::miner::{MinerService, ExternalMinerService};
 use ethcore::client::{MiningBlockChainClient};
 use ethcore::ids::BlockID;
+use ethcore::transaction::{SignedTransaction, PendingTransaction};
+use rlp::{UntrustedRlp, View};
 
 use jsonrpc_core::*;
 use v1::traits::Ethcore;
-use v1::types::{Bytes, U256, H160, Peers};
+use v1::types::{Bytes, U256, H160, Peers, PeerReputation, LocalTransactionStatus, TransactionCondition, GasPriceHistogram, WorkerStats};
 use v1::helpers::{errors, SigningQueue, ConfirmationsQueue, NetworkSettings};
 use v1::helpers::params::expect_no_params;
+use v1::helpers::dispatch::dispatch_transaction;
 
 /// Ethcore implementation.
 pub struct EthcoreClient<C, M, S: ?Sized> where
@@ -44,6 +47,7 @@ pub struct EthcoreClient<C, M, S: ?Sized> where
 	miner: Weak<M>,
 	sync: Weak<S>,
 	net: Weak<ManageNetwork>,
+	external_miner: Arc<ExternalMinerService>,
 	logger: Arc<RotatingLogger>,
 	settings: Arc<NetworkSettings>,
 	confirmations_queue: Option<Arc<ConfirmationsQueue>>,
@@ -56,6 +60,7 @@ impl<C, M, S: ?Sized> EthcoreClient<C, M, S> where C: MiningBlockChainClient, M:
 		miner: &Arc<M>,
 		sync: &Arc<S>,
 		net: &Arc<ManageNetwork>,
+		external_miner: Arc<ExternalMinerService>,
 		logger: Arc<RotatingLogger>,
 		settings: Arc<NetworkSettings>,
 		queue: Option<Arc<ConfirmationsQueue>>
@@ -65,6 +70,7 @@ impl<C, M, S: ?Sized> EthcoreClient<C, M, S> where C: MiningBlockChainClient, M:
 			miner: Arc::downgrade(miner),
 			sync: Arc::downgrade(sync),
 			net: Arc::downgrade(net),
+			external_miner: external_miner,
 			logger: logger,
 			settings: settings,
 			confirmations_queue: queue,
@@ -134,15 +140,31 @@ impl<C, M, S: ?Sized> Ethcore for EthcoreClient<C, M, S> where M: MinerService +
 		try!(expect_no_params(params));
 
 		let sync_status = take_weak!(self.sync).status();
-		let net_config = take_weak!(self.net).network_config();
+		let net = take_weak!(self.net);
+		let net_config = net.network_config();
+		let traffic = net.network_traffic();
+		let counts = net.peer_counts();
 
 		Ok(to_value(&Peers {
 			active: sync_status.num_active_peers,
 			connected: sync_status.num_peers,
 			max: sync_status.current_max_peers(net_config.min_peers, net_config.max_peers),
+			inbound: counts.inbound,
+			outbound: counts.outbound,
+			max_inbound: counts.max_inbound,
+			max_outbound: counts.max_outbound,
+			peers: traffic.peers.into_iter().map(Into::into).collect(),
 		}))
 	}
 
+	fn net_peer_reputations(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		let reputations: Vec<PeerReputation> = take_weak!(self.net).peer_reputations().into_iter().map(Into::into).collect();
+		Ok(to_value(&reputations))
+	}
+
 	fn net_port(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		try!(expect_no_params(params));
@@ -195,6 +217,24 @@ impl<C, M, S: ?Sized> Ethcore for EthcoreClient<C, M, S> where M: MinerService +
 		}
 	}
 
+	fn gas_price_histogram(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
+		Ok(to_value(&miner.gas_price_histogram(&*client).map(GasPriceHistogram::from)))
+	}
+
+	fn mining_workers(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		Ok(to_value(&self.external_miner.workers()
+			.into_iter()
+			.map(|(worker, stats)| (worker, WorkerStats::from(stats)))
+			.collect::<BTreeMap<_, _>>()))
+	}
+
 	fn unsigned_transactions_count(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		try!(expect_no_params(params));
@@ -236,4 +276,27 @@ impl<C, M, S: ?Sized> Ethcore for EthcoreClient<C, M, S> where M: MinerService +
 			Ok(Value::Null)
 		)
 	}
+
+	fn local_transactions(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+
+		let transactions: Vec<LocalTransactionStatus> = take_weak!(self.miner).local_transactions().into_iter().map(Into::into).collect();
+		Ok(to_value(&transactions))
+	}
+
+	fn send_raw_transaction_with_condition(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+
+		from_params::<(Bytes, TransactionCondition)>(params).and_then(|(raw_transaction, condition)| {
+			let raw_transaction = raw_transaction.to_vec();
+			match UntrustedRlp::new(&raw_transaction).as_val::<SignedTransaction>() {
+				Ok(signed_transaction) => {
+					let transaction = PendingTransaction::new(signed_transaction, Some(condition.into()));
+					dispatch_transaction(&*take_weak!(self.client), &*take_weak!(self.miner), transaction)
+				},
+				Err(e) => Err(errors::invalid_params("transaction", e)),
+			}
+		})
+	}
 }
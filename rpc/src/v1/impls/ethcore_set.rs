@@ -23,7 +23,7 @@ use ethsync::ManageNetwork;
 use v1::helpers::errors;
 use v1::helpers::params::expect_no_params;
 use v1::traits::EthcoreSet;
-use v1::types::{Bytes, H160, U256};
+use v1::types::{Bytes, H160, H256, U256};
 
 /// Ethcore-specific rpc interface for operations altering the settings.
 pub struct EthcoreSetClient<C, M> where
@@ -114,6 +114,41 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 		})
 	}
 
+	fn reload_transaction_filter(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+		take_weak!(self.miner).reload_transaction_filter()
+			.map(|reloaded| to_value(&reloaded))
+			.map_err(|e| errors::internal("Could not reload transaction filter.", e))
+	}
+
+	fn mine_blocks(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(usize,)>(params).and_then(|(count,)| {
+			let client = take_weak!(self.client);
+			take_weak!(self.miner).seal_blocks(&*client, count)
+				.map(|hashes| to_value(&hashes.into_iter().map(H256::from).collect::<Vec<_>>()))
+				.map_err(|e| errors::internal("Could not seal blocks.", e))
+		})
+	}
+
+	fn set_next_block_timestamp(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(u64,)>(params).and_then(|(timestamp,)| {
+			let client = take_weak!(self.client);
+			take_weak!(self.miner).set_next_block_timestamp(&*client, timestamp)
+				.map(|_| to_value(&true))
+				.map_err(|e| errors::invalid_params("Timestamp", e))
+		})
+	}
+
+	fn increase_time(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(u64,)>(params).and_then(|(seconds,)| {
+			Ok(to_value(&take_weak!(self.miner).increase_time(seconds)))
+		})
+	}
+
 	fn add_reserved_peer(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		from_params::<(String,)>(params).and_then(|(peer,)| {
@@ -134,6 +169,26 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 		})
 	}
 
+	fn set_peer_reputation(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String, i32)>(params).and_then(|(peer, score)| {
+			match take_weak!(self.net).set_peer_reputation(peer, score) {
+				Ok(()) => Ok(to_value(&true)),
+				Err(e) => Err(errors::invalid_params("Peer id", e)),
+			}
+		})
+	}
+
+	fn set_ip_filter(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String, Vec<String>, Vec<String>)>(params).and_then(|(mode, allow, deny)| {
+			match take_weak!(self.net).set_ip_filter(mode, allow, deny) {
+				Ok(()) => Ok(to_value(&true)),
+				Err(e) => Err(errors::invalid_params("IP filter", e)),
+			}
+		})
+	}
+
 	fn drop_non_reserved_peers(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		try!(expect_no_params(params));
@@ -0,0 +1,77 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Test chain rpc implementation.
+use std::sync::{Arc, Weak};
+use jsonrpc_core::*;
+use util::{Mutex, Uint, U256 as EthU256};
+use ethcore::client::{MiningBlockChainClient, ChainCheckpoint};
+use v1::helpers::errors;
+use v1::helpers::params::expect_no_params;
+use v1::traits::Evm;
+use v1::types::U256;
+
+/// Test chain rpc implementation.
+pub struct EvmClient<C> where C: MiningBlockChainClient {
+	client: Weak<C>,
+	snapshots: Mutex<Vec<ChainCheckpoint>>,
+}
+
+impl<C> EvmClient<C> where C: MiningBlockChainClient {
+	/// Creates new `EvmClient`.
+	pub fn new(client: &Arc<C>) -> Self {
+		EvmClient {
+			client: Arc::downgrade(client),
+			snapshots: Mutex::new(Vec::new()),
+		}
+	}
+
+	fn active(&self) -> Result<(), Error> {
+		// TODO: only call every 30s at most.
+		take_weak!(self.client).keep_alive();
+		Ok(())
+	}
+}
+
+impl<C> Evm for EvmClient<C> where C: MiningBlockChainClient + 'static {
+	fn snapshot(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		try!(expect_no_params(params));
+		let checkpoint = try!(take_weak!(self.client).take_checkpoint()
+			.map_err(|e| errors::internal("Could not take snapshot.", e)));
+		let mut snapshots = self.snapshots.lock();
+		snapshots.push(checkpoint);
+		Ok(to_value(&U256::from(snapshots.len())))
+	}
+
+	fn revert(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(U256,)>(params).and_then(|(id,)| {
+			let id: EthU256 = id.into();
+			let mut snapshots = self.snapshots.lock();
+			if id.is_zero() || id > EthU256::from(snapshots.len()) {
+				return Ok(to_value(&false));
+			}
+
+			let index = id.low_u64() as usize - 1;
+			try!(take_weak!(self.client).revert_to_checkpoint(&snapshots[index])
+				.map_err(|e| errors::internal("Could not revert chain.", e)));
+			snapshots.truncate(index);
+			Ok(to_value(&true))
+		})
+	}
+}
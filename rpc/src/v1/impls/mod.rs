@@ -30,11 +30,14 @@ mod eth_filter;
 mod eth_signing;
 mod ethcore;
 mod ethcore_set;
+mod evm;
 mod net;
 mod personal;
 mod personal_signer;
 mod rpc;
+mod shh;
 mod traces;
+mod txpool;
 mod web3;
 
 pub use self::web3::Web3Client;
@@ -46,5 +49,8 @@ pub use self::personal::PersonalClient;
 pub use self::personal_signer::SignerClient;
 pub use self::ethcore::EthcoreClient;
 pub use self::ethcore_set::EthcoreSetClient;
+pub use self::evm::EvmClient;
 pub use self::traces::TracesClient;
 pub use self::rpc::RpcClient;
+pub use self::shh::ShhClient;
+pub use self::txpool::TxPoolClient;
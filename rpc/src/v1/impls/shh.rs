@@ -0,0 +1,111 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Whisper rpc implementation.
+use std::sync::{Arc, Weak};
+use jsonrpc_core::*;
+use util::{U256, Uint};
+use whisper::{Whisper, Filter, MessageParams, Error as WhisperError, MAX_TTL};
+use v1::traits::Shh;
+use v1::types::{H512, WhisperPost, WhisperFilter, WhisperMessage, Index, U256 as RpcU256};
+use v1::helpers::errors;
+use v1::helpers::params::expect_no_params;
+
+/// Max time to spend on proof of work of a posted message.
+const MAX_WORK_MS: u64 = 10_000;
+
+/// Whisper rpc implementation.
+pub struct ShhClient {
+	whisper: Weak<Whisper>,
+}
+
+impl ShhClient {
+	/// Creates new ShhClient.
+	pub fn new(whisper: &Arc<Whisper>) -> Self {
+		ShhClient {
+			whisper: Arc::downgrade(whisper),
+		}
+	}
+}
+
+impl Shh for ShhClient {
+	fn post(&self, params: Params) -> Result<Value, Error> {
+		let whisper = take_weak!(self.whisper);
+		from_params::<(WhisperPost,)>(params).and_then(|(post,)| {
+			let ttl: U256 = post.ttl.into();
+			if ttl.is_zero() || ttl > U256::from(MAX_TTL) {
+				return Err(errors::invalid_params("ttl", format!("Time to live must be between 1 and {} seconds", MAX_TTL)));
+			}
+			let priority: U256 = post.priority.into();
+			if priority > U256::from(MAX_WORK_MS) {
+				return Err(errors::invalid_params("priority", format!("Proof of work can't take longer than {} ms", MAX_WORK_MS)));
+			}
+			let params = MessageParams {
+				from: None,
+				to: post.to.map(Into::into),
+				topics: post.topics.into_iter().map(|t| t.to_vec()).collect(),
+				payload: post.payload.to_vec(),
+				ttl: ttl.low_u32(),
+				work_ms: priority.low_u64(),
+			};
+			whisper.post(post.from.map(Into::into), params)
+				.map(|_| Value::Bool(true))
+				.map_err(|e| match e {
+					WhisperError::UnknownIdentity => errors::invalid_params("from", e),
+					e => errors::internal("Could not post message", e),
+				})
+		})
+	}
+
+	fn new_identity(&self, params: Params) -> Result<Value, Error> {
+		try!(expect_no_params(params));
+		take_weak!(self.whisper).new_identity()
+			.map(|public| to_value(&H512::from(public)))
+			.map_err(|e| errors::internal("Could not create identity", e))
+	}
+
+	fn has_identity(&self, params: Params) -> Result<Value, Error> {
+		let whisper = take_weak!(self.whisper);
+		from_params::<(H512,)>(params).map(|(public,)| Value::Bool(whisper.has_identity(&public.into())))
+	}
+
+	fn new_filter(&self, params: Params) -> Result<Value, Error> {
+		let whisper = take_weak!(self.whisper);
+		from_params::<(WhisperFilter,)>(params).and_then(|(filter,)| {
+			let topics: Vec<_> = filter.topics.into_iter().map(|t| t.to_vec()).collect();
+			whisper.new_filter(Filter::new(&topics, filter.to.map(Into::into)))
+				.map(|id| to_value(&RpcU256::from(id)))
+				.map_err(|e| errors::internal("Could not install filter", e))
+		})
+	}
+
+	fn uninstall_filter(&self, params: Params) -> Result<Value, Error> {
+		let whisper = take_weak!(self.whisper);
+		from_params::<(Index,)>(params).map(|(index,)| Value::Bool(whisper.uninstall_filter(index.value())))
+	}
+
+	fn filter_changes(&self, params: Params) -> Result<Value, Error> {
+		let whisper = take_weak!(self.whisper);
+		from_params::<(Index,)>(params).map(|(index,)| {
+			let messages: Vec<WhisperMessage> = whisper.filter_changes(index.value())
+				.unwrap_or_else(Vec::new)
+				.into_iter()
+				.map(Into::into)
+				.collect();
+			to_value(&messages)
+		})
+	}
+}
@@ -0,0 +1,57 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool rpc implementation.
+use std::sync::{Arc, Weak};
+use jsonrpc_core::*;
+use ethcore::miner::MinerService;
+use v1::traits::TxPool;
+use v1::types::{TxPoolStatus, TxPoolContent, TxPoolInspect};
+use v1::helpers::params::expect_no_params;
+
+/// Transaction pool rpc implementation.
+pub struct TxPoolClient<M> where M: MinerService {
+	miner: Weak<M>,
+}
+
+impl<M> TxPoolClient<M> where M: MinerService {
+	/// Creates new TxPoolClient.
+	pub fn new(miner: &Arc<M>) -> Self {
+		TxPoolClient {
+			miner: Arc::downgrade(miner),
+		}
+	}
+}
+
+impl<M> TxPool for TxPoolClient<M> where M: MinerService + 'static {
+	fn status(&self, params: Params) -> Result<Value, Error> {
+		try!(expect_no_params(params));
+		let status: TxPoolStatus = take_weak!(self.miner).queue_status().into();
+		Ok(to_value(&status))
+	}
+
+	fn content(&self, params: Params) -> Result<Value, Error> {
+		try!(expect_no_params(params));
+		let miner = take_weak!(self.miner);
+		Ok(to_value(&TxPoolContent::new(miner.all_transactions(), miner.future_transactions())))
+	}
+
+	fn inspect(&self, params: Params) -> Result<Value, Error> {
+		try!(expect_no_params(params));
+		let miner = take_weak!(self.miner);
+		Ok(to_value(&TxPoolInspect::new(miner.all_transactions(), miner.future_transactions())))
+	}
+}
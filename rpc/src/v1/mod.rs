@@ -26,6 +26,6 @@ pub mod traits;
 pub mod tests;
 pub mod types;
 
-pub use self::traits::{Web3, Eth, EthFilter, EthSigning, Personal, PersonalSigner, Net, Ethcore, EthcoreSet, Traces, Rpc};
+pub use self::traits::{Web3, Eth, EthFilter, EthSigning, Personal, PersonalSigner, Net, Ethcore, EthcoreSet, Evm, Traces, Rpc, Shh, TxPool};
 pub use self::impls::*;
 pub use self::helpers::{SigningQueue, ConfirmationsQueue, NetworkSettings};
@@ -57,11 +57,17 @@ fn miner_service(spec: &Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
 			reseal_on_external_tx: true,
 			reseal_on_own_tx: true,
 			tx_queue_size: 1024,
+			tx_queue_sender_limit: usize::max_value(),
+			tx_queue_sender_gas_limit: !U256::zero(),
+			tx_queue_memory_limit: None,
 			tx_gas_limit: !U256::zero(),
 			pending_set: PendingSet::SealingOrElseQueue,
 			reseal_min_period: Duration::from_secs(0),
 			work_queue_size: 50,
 			enable_resubmission: true,
+			transaction_filter: None,
+			gas_price_oracle: Default::default(),
+			seal_mode: None,
 		},
 		GasPricer::new_fixed(20_000_000_000u64.into()),
 		&spec,
@@ -21,22 +21,36 @@ use util::standard::*;
 use ethcore::error::{Error, CallError};
 use ethcore::client::{MiningBlockChainClient, Executed, CallAnalytics};
 use ethcore::block::{ClosedBlock, IsBlock};
-use ethcore::transaction::SignedTransaction;
+use ethcore::transaction::{SignedTransaction, PendingTransaction, Condition};
 use ethcore::receipt::{Receipt, RichReceipt};
-use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult};
+use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, TransactionQueueStatus, LocalTransaction, GasPriceHistogram};
 
 /// Test miner service.
 pub struct TestMinerService {
 	/// Imported transactions.
 	pub imported_transactions: Mutex<Vec<SignedTransaction>>,
+	/// Conditions of imported own transactions.
+	pub imported_conditions: Mutex<HashMap<H256, Condition>>,
 	/// Latest closed block.
 	pub latest_closed_block: Mutex<Option<ClosedBlock>>,
 	/// Pre-existed pending transactions
 	pub pending_transactions: Mutex<HashMap<H256, SignedTransaction>>,
+	/// Pre-existed future transactions
+	pub future_transactions: Mutex<Vec<SignedTransaction>>,
 	/// Pre-existed pending receipts
 	pub pending_receipts: Mutex<BTreeMap<H256, Receipt>>,
 	/// Last nonces.
 	pub last_nonces: RwLock<HashMap<Address, U256>>,
+	/// Local transactions history.
+	pub local_transactions: Mutex<Vec<LocalTransaction>>,
+	/// Gas prices in recent blocks.
+	pub gas_price_histogram: Mutex<Option<GasPriceHistogram>>,
+	/// Number of blocks sealed on request.
+	pub sealed_blocks: Mutex<u64>,
+	/// Timestamp of the next sealed block.
+	pub next_block_timestamp: RwLock<Option<u64>>,
+	/// Seconds added to the time of sealed blocks.
+	pub time_offset: RwLock<u64>,
 
 	min_gas_price: RwLock<U256>,
 	gas_range_target: RwLock<(U256, U256)>,
@@ -50,10 +64,17 @@ impl Default for TestMinerService {
 	fn default() -> TestMinerService {
 		TestMinerService {
 			imported_transactions: Mutex::new(Vec::new()),
+			imported_conditions: Mutex::new(HashMap::new()),
 			latest_closed_block: Mutex::new(None),
 			pending_transactions: Mutex::new(HashMap::new()),
+			future_transactions: Mutex::new(Vec::new()),
 			pending_receipts: Mutex::new(BTreeMap::new()),
 			last_nonces: RwLock::new(HashMap::new()),
+			local_transactions: Mutex::new(Vec::new()),
+			gas_price_histogram: Mutex::new(None),
+			sealed_blocks: Mutex::new(0),
+			next_block_timestamp: RwLock::new(None),
+			time_offset: RwLock::new(0),
 			min_gas_price: RwLock::new(U256::from(20_000_000)),
 			gas_range_target: RwLock::new((U256::from(12345), U256::from(54321))),
 			author: RwLock::new(Address::zero()),
@@ -105,6 +126,10 @@ impl MinerService for TestMinerService {
 		*self.tx_gas_limit.write() = limit;
 	}
 
+	fn reload_transaction_filter(&self) -> Result<bool, String> {
+		Ok(false)
+	}
+
 	fn transactions_limit(&self) -> usize {
 		*self.limit.read()
 	}
@@ -146,7 +171,7 @@ impl MinerService for TestMinerService {
 	}
 
 	/// Imports transactions to transaction queue.
-	fn import_own_transaction(&self, chain: &MiningBlockChainClient, transaction: SignedTransaction) ->
+	fn import_own_transaction(&self, chain: &MiningBlockChainClient, transaction: PendingTransaction) ->
 		Result<TransactionImportResult, Error> {
 
 		// keep the pending nonces up to date
@@ -155,8 +180,12 @@ impl MinerService for TestMinerService {
 			self.last_nonces.write().insert(sender.clone(), nonce + U256::from(1));
 		}
 
+		if let Some(condition) = transaction.condition {
+			self.imported_conditions.lock().insert(transaction.hash(), condition);
+		}
+
 		// lets assume that all txs are valid
-		self.imported_transactions.lock().push(transaction);
+		self.imported_transactions.lock().push(transaction.transaction);
 
 		Ok(TransactionImportResult::Current)
 	}
@@ -181,6 +210,25 @@ impl MinerService for TestMinerService {
 		unimplemented!();
 	}
 
+	fn seal_blocks(&self, _chain: &MiningBlockChainClient, count: usize) -> Result<Vec<H256>, Error> {
+		let mut sealed = self.sealed_blocks.lock();
+		Ok((0..count).map(|_| {
+			*sealed += 1;
+			H256::from(*sealed)
+		}).collect())
+	}
+
+	fn set_next_block_timestamp(&self, _chain: &MiningBlockChainClient, timestamp: u64) -> Result<(), Error> {
+		*self.next_block_timestamp.write() = Some(timestamp);
+		Ok(())
+	}
+
+	fn increase_time(&self, seconds: u64) -> u64 {
+		let mut offset = self.time_offset.write();
+		*offset += seconds;
+		*offset
+	}
+
 	fn map_sealing_work<F, T>(&self, chain: &MiningBlockChainClient, f: F) -> Option<T> where F: FnOnce(&ClosedBlock) -> T {
 		let open_block = chain.prepare_open_block(self.author(), *self.gas_range_target.write(), self.extra_data());
 		Some(f(&open_block.close()))
@@ -194,6 +242,30 @@ impl MinerService for TestMinerService {
 		self.pending_transactions.lock().values().cloned().collect()
 	}
 
+	fn future_transactions(&self) -> Vec<SignedTransaction> {
+		self.future_transactions.lock().clone()
+	}
+
+	fn queue_status(&self) -> TransactionQueueStatus {
+		TransactionQueueStatus {
+			pending: self.pending_transactions.lock().len(),
+			future: self.future_transactions.lock().len(),
+			limit: *self.limit.read(),
+			gas_limit: !U256::zero(),
+			tx_gas_limit: *self.tx_gas_limit.read(),
+			minimal_gas_price: *self.min_gas_price.read(),
+			effective_minimum_gas_price: *self.min_gas_price.read(),
+		}
+	}
+
+	fn local_transactions(&self) -> Vec<LocalTransaction> {
+		self.local_transactions.lock().clone()
+	}
+
+	fn gas_price_histogram(&self, _chain: &MiningBlockChainClient) -> Option<GasPriceHistogram> {
+		self.gas_price_histogram.lock().clone()
+	}
+
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.pending_transactions.lock().values().cloned().collect()
 	}
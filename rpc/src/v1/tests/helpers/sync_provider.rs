@@ -49,6 +49,9 @@ impl TestSyncProvider {
 				num_peers: config.num_peers,
 				num_active_peers: 0,
 				mem_used: 0,
+				ancient_blocks_left: None,
+				state_nodes_downloaded: 0,
+				state_nodes_pending: 0,
 			}),
 		}
 	}
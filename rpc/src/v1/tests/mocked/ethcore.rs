@@ -16,9 +16,11 @@
 
 use std::sync::Arc;
 use util::log::RotatingLogger;
-use util::U256;
+use util::{U256, Address};
 use ethsync::ManageNetwork;
 use ethcore::client::{TestBlockChainClient};
+use ethcore::miner::{LocalTransaction, LocalTransactionStatus, GasPriceHistogram, ExternalMiner, ExternalMinerService};
+use ethcore::transaction::{Transaction, Condition};
 
 use jsonrpc_core::IoHandler;
 use v1::{Ethcore, EthcoreClient};
@@ -41,6 +43,10 @@ fn sync_provider() -> Arc<TestSyncProvider> {
 	}))
 }
 
+fn external_miner() -> Arc<ExternalMiner> {
+	Arc::new(ExternalMiner::default())
+}
+
 fn logger() -> Arc<RotatingLogger> {
 	Arc::new(RotatingLogger::new("rpc=trace".to_owned()))
 }
@@ -65,7 +71,7 @@ fn ethcore_client(
 	miner: &Arc<TestMinerService>,
 	sync: &Arc<TestSyncProvider>,
 	net: &Arc<ManageNetwork>) -> EthcoreClient<TestBlockChainClient, TestMinerService, TestSyncProvider> {
-	EthcoreClient::new(client, miner, sync, net, logger(), settings(), None)
+	EthcoreClient::new(client, miner, sync, net, external_miner(), logger(), settings(), None)
 }
 
 #[test]
@@ -140,7 +146,7 @@ fn rpc_ethcore_dev_logs() {
 	let logger = logger();
 	logger.append("a".to_owned());
 	logger.append("b".to_owned());
-	let ethcore = EthcoreClient::new(&client, &miner, &sync, &net, logger.clone(), settings(), None).to_delegate();
+	let ethcore = EthcoreClient::new(&client, &miner, &sync, &net, external_miner(), logger.clone(), settings(), None).to_delegate();
 	let io = IoHandler::new();
 	io.add_delegate(ethcore);
 
@@ -205,7 +211,22 @@ fn rpc_ethcore_net_peers() {
 	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_netPeers", "params":[], "id": 1}"#;
-	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"max":50},"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"active":0,"connected":120,"inbound":20,"max":50,"maxInbound":40,"maxOutbound":50,"outbound":30,"peers":[{"id":"00","packets":[{"packetId":1,"protocol":"eth","recvBytes":50,"recvPackets":4,"sentBytes":30,"sentPackets":2}],"recvBytes":200,"remoteAddress":"127.0.0.1:30303","sentBytes":100}]},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_net_peer_reputations() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_netPeerReputations", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"bannedUntil":1,"id":"00","score":-60}],"id":1}"#;
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
@@ -263,7 +284,7 @@ fn rpc_ethcore_unsigned_transactions_count() {
 	let net = network_service();
 	let io = IoHandler::new();
 	let queue = Arc::new(ConfirmationsQueue::default());
-	let ethcore = EthcoreClient::new(&client, &miner, &sync, &net, logger(), settings(), Some(queue)).to_delegate();
+	let ethcore = EthcoreClient::new(&client, &miner, &sync, &net, external_miner(), logger(), settings(), Some(queue)).to_delegate();
 	io.add_delegate(ethcore);
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_unsignedTransactionsCount", "params":[], "id": 1}"#;
@@ -286,3 +307,81 @@ fn rpc_ethcore_unsigned_transactions_count_when_signer_disabled() {
 
 	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_ethcore_local_transactions() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_localTransactions", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let tx = Transaction::default().fake_sign(Address::default());
+	miner.local_transactions.lock().push(LocalTransaction {
+		transaction: tx,
+		status: LocalTransactionStatus::Dropped,
+	});
+	let response = io.handle_request_sync(request).unwrap();
+	assert!(response.contains(r#""status":"dropped""#));
+}
+
+#[test]
+fn rpc_ethcore_gas_price_histogram() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_gasPriceHistogram", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	*miner.gas_price_histogram.lock() = Some(GasPriceHistogram {
+		block_number: 10,
+		sample_size: 3,
+		percentiles: vec![U256::from(1), U256::from(2), U256::from(3)],
+	});
+	let response = r#"{"jsonrpc":"2.0","result":{"blockNumber":"0xa","percentiles":["0x1","0x2","0x3"],"sampleSize":3},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_mining_workers() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let external_miner = external_miner();
+	external_miner.submit_share("worker1".into(), U256::from(1000), true);
+	external_miner.submit_share("worker1".into(), U256::from(1000), false);
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreClient::new(&client, &miner, &sync, &net, external_miner, logger(), settings(), None).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_miningWorkers", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"worker1":{"acceptedShares":1,"hashrate":"0x3e8","rejectedShares":1}},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_send_raw_transaction_with_condition() {
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let net = network_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner, &sync, &net).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_sendRawTransactionWithCondition", "params":["0xf85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804", {"block": 10}], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x41df922fd0d4766fcc02e161f8295ec28522f329ae487f14d811e4b64c8d6e31","id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	let hash = miner.imported_transactions.lock()[0].hash();
+	assert_eq!(miner.imported_conditions.lock().get(&hash), Some(&Condition::Number(10)));
+}
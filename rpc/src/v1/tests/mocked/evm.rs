@@ -0,0 +1,79 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::sync::Arc;
+use ethcore::client::{BlockChainClient, TestBlockChainClient, EachBlockWith};
+use ethcore::ethereum;
+use jsonrpc_core::IoHandler;
+use v1::{Evm, EvmClient};
+
+fn setup() -> (Arc<TestBlockChainClient>, IoHandler) {
+	setup_with_client(TestBlockChainClient::default())
+}
+
+fn setup_with_client(client: TestBlockChainClient) -> (Arc<TestBlockChainClient>, IoHandler) {
+	let client = Arc::new(client);
+	let io = IoHandler::new();
+	io.add_delegate(EvmClient::new(&client).to_delegate());
+	(client, io)
+}
+
+#[test]
+fn rpc_evm_snapshot_and_revert() {
+	let (client, io) = setup();
+	client.add_blocks(2, EachBlockWith::Nothing);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "evm_snapshot", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x1","id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	client.add_blocks(3, EachBlockWith::Nothing);
+	assert_eq!(client.chain_info().best_block_number, 5);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "evm_revert", "params":["0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(client.chain_info().best_block_number, 2);
+
+	// snapshot is discarded once reverted to.
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_evm_revert_unknown_snapshot() {
+	let (_client, io) = setup();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "evm_revert", "params":["0x0"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_evm_snapshot_unavailable_for_pow_chain() {
+	let (client, io) = setup_with_client(TestBlockChainClient::new_with_spec(ethereum::new_morden()));
+	client.add_blocks(2, EachBlockWith::Nothing);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "evm_snapshot", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error occurred: Could not take snapshot.","data":"RevertUnavailable"},"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "evm_revert", "params":["0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+	assert_eq!(client.chain_info().best_block_number, 2);
+}
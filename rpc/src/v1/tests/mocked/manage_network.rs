@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethsync::{ManageNetwork, NetworkConfiguration};
+use ethsync::{ManageNetwork, NetworkConfiguration, PeerReputation, IpFilter, NetworkTraffic, PeerTraffic, PacketTraffic, PeerCounts};
 
 pub struct TestManageNetwork;
 
@@ -27,4 +27,27 @@ impl ManageNetwork for TestManageNetwork {
 	fn start_network(&self) {}
 	fn stop_network(&self) {}
 	fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::new_local() }
+	fn peer_reputations(&self) -> Vec<PeerReputation> {
+		vec![PeerReputation { id: "00".into(), score: -60, banned_until: Some(1) }]
+	}
+	fn set_peer_reputation(&self, _node: String, _score: i32) -> Result<(), String> { Ok(()) }
+	fn set_ip_filter(&self, mode: String, allow: Vec<String>, deny: Vec<String>) -> Result<(), String> {
+		IpFilter::parse(&mode, &allow, &deny).map(|_| ()).map_err(|e| format!("{}", e))
+	}
+	fn peer_counts(&self) -> PeerCounts {
+		PeerCounts { inbound: 20, outbound: 30, max_inbound: 40, max_outbound: 50, reserved_outbound: 10 }
+	}
+	fn network_traffic(&self) -> NetworkTraffic {
+		NetworkTraffic {
+			sent_bytes: 300,
+			recv_bytes: 400,
+			peers: vec![PeerTraffic {
+				id: "00".into(),
+				remote_address: "127.0.0.1:30303".into(),
+				sent_bytes: 100,
+				recv_bytes: 200,
+				packets: vec![PacketTraffic { protocol: "eth".into(), packet_id: 1, sent_packets: 2, sent_bytes: 30, recv_packets: 4, recv_bytes: 50 }],
+			}],
+		}
+	}
 }
@@ -25,5 +25,8 @@ mod personal;
 mod personal_signer;
 mod ethcore;
 mod ethcore_set;
+mod evm;
 mod rpc;
 mod manage_network;
+mod shh;
+mod txpool;
@@ -79,6 +79,7 @@ fn should_return_list_of_items_to_confirm() {
 		value: U256::from(1),
 		data: vec![],
 		nonce: None,
+		condition: None,
 	})).unwrap();
 	tester.queue.add_request(ConfirmationPayload::Sign(1.into(), 5.into())).unwrap();
 
@@ -108,6 +109,7 @@ fn should_reject_transaction_from_queue_without_dispatching() {
 		value: U256::from(1),
 		data: vec![],
 		nonce: None,
+		condition: None,
 	})).unwrap();
 	assert_eq!(tester.queue.requests().len(), 1);
 
@@ -133,6 +135,7 @@ fn should_not_remove_transaction_if_password_is_invalid() {
 		value: U256::from(1),
 		data: vec![],
 		nonce: None,
+		condition: None,
 	})).unwrap();
 	assert_eq!(tester.queue.requests().len(), 1);
 
@@ -175,6 +178,7 @@ fn should_confirm_transaction_and_dispatch() {
 		value: U256::from(1),
 		data: vec![],
 		nonce: None,
+		condition: None,
 	})).unwrap();
 
 	let t = Transaction {
@@ -0,0 +1,86 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use std::iter::repeat;
+use jsonrpc_core::IoHandler;
+use serde_json::{self, Value};
+use whisper::{Whisper, WhisperConfig};
+use v1::{Shh, ShhClient};
+
+fn setup() -> (Arc<Whisper>, IoHandler) {
+	let whisper = Arc::new(Whisper::new(WhisperConfig::default()));
+	let io = IoHandler::new();
+	io.add_delegate(ShhClient::new(&whisper).to_delegate());
+	(whisper, io)
+}
+
+fn result(io: &IoHandler, request: &str) -> Value {
+	let response: Value = serde_json::from_str(&io.handle_request_sync(request).unwrap()).unwrap();
+	response.find("result").expect("request should succeed").clone()
+}
+
+#[test]
+fn rpc_shh_identities() {
+	let (_whisper, io) = setup();
+
+	let identity = result(&io, r#"{"jsonrpc": "2.0", "method": "shh_newIdentity", "params": [], "id": 1}"#);
+	let identity = identity.as_str().unwrap();
+	assert_eq!(identity.len(), 2 + 128);
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "shh_hasIdentity", "params": ["{}"], "id": 1}}"#, identity);
+	assert_eq!(result(&io, &request), Value::Bool(true));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "shh_hasIdentity", "params": ["0x{}"], "id": 1}}"#, repeat("00").take(64).collect::<String>());
+	assert_eq!(result(&io, &request), Value::Bool(false));
+}
+
+#[test]
+fn rpc_shh_post_and_poll() {
+	let (whisper, io) = setup();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "shh_newFilter", "params": [{"topics": ["0x63686174"]}], "id": 1}"#;
+	assert_eq!(result(&io, request), Value::String("0x0".into()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "shh_post", "params": [{"topics": ["0x63686174"], "payload": "0x010203", "priority": "0x0", "ttl": "0x32"}], "id": 1}"#;
+	assert_eq!(result(&io, request), Value::Bool(true));
+	assert_eq!(whisper.pool_len(), 1);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "shh_getFilterChanges", "params": ["0x0"], "id": 1}"#;
+	let messages = result(&io, request);
+	let messages = messages.as_array().unwrap();
+	assert_eq!(messages.len(), 1);
+	assert_eq!(messages[0].find("payload"), Some(&Value::String("0x010203".into())));
+	assert_eq!(messages[0].find("ttl"), Some(&Value::U64(50)));
+	assert_eq!(messages[0].find("from"), Some(&Value::Null));
+	assert_eq!(result(&io, request), Value::Array(vec![]));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "shh_uninstallFilter", "params": ["0x0"], "id": 1}"#;
+	assert_eq!(result(&io, request), Value::Bool(true));
+}
+
+#[test]
+fn rpc_shh_post_rejects_invalid_params() {
+	let (_whisper, io) = setup();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "shh_post", "params": [{"topics": [], "payload": "0x01", "priority": "0x0", "ttl": "0x0"}], "id": 1}"#;
+	let response: Value = serde_json::from_str(&io.handle_request_sync(request).unwrap()).unwrap();
+	assert!(response.find("error").is_some());
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "shh_post", "params": [{{"from": "0x{}", "topics": [], "payload": "0x01", "priority": "0x0", "ttl": "0x32"}}], "id": 1}}"#, repeat("11").take(64).collect::<String>());
+	let response: Value = serde_json::from_str(&io.handle_request_sync(&request).unwrap()).unwrap();
+	assert!(response.find("error").is_some());
+}
@@ -0,0 +1,80 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use rustc_serialize::hex::FromHex;
+use util::{H256, U256, Uint, Address};
+use ethcore::transaction::{Transaction, SignedTransaction, Action};
+use jsonrpc_core::IoHandler;
+use v1::{TxPool, TxPoolClient};
+use v1::tests::helpers::TestMinerService;
+
+fn setup() -> (Arc<TestMinerService>, IoHandler) {
+	let miner = Arc::new(TestMinerService::default());
+	let io = IoHandler::new();
+	io.add_delegate(TxPoolClient::new(&miner).to_delegate());
+	(miner, io)
+}
+
+fn pending_tx() -> SignedTransaction {
+	::rlp::decode(&FromHex::from_hex("f85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804").unwrap())
+}
+
+fn queued_tx() -> SignedTransaction {
+	Transaction {
+		nonce: U256::from(5),
+		gas_price: U256::from(2),
+		gas: U256::from(21_000),
+		action: Action::Create,
+		value: U256::zero(),
+		data: vec![],
+	}.fake_sign(Address::from(1))
+}
+
+#[test]
+fn rpc_txpool_status() {
+	let (miner, io) = setup();
+	miner.pending_transactions.lock().insert(H256::zero(), pending_tx());
+	miner.future_transactions.lock().push(queued_tx());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "txpool_status", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"effectiveMinGasPrice":"0x1312d00","gasLimit":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff","limit":1024,"minGasPrice":"0x1312d00","pending":1,"queued":1,"txGasLimit":"0xffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_txpool_content() {
+	let (miner, io) = setup();
+	miner.pending_transactions.lock().insert(H256::zero(), pending_tx());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "txpool_content", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"pending":{"0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e":{"0x0":{"blockHash":null,"blockNumber":null,"creates":null,"from":"0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e","gas":"0x5208","gasPrice":"0x1","hash":"0x41df922fd0d4766fcc02e161f8295ec28522f329ae487f14d811e4b64c8d6e31","input":"0x","nonce":"0x0","raw":"0xf85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804","to":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","transactionIndex":null,"value":"0xa"}}},"queued":{}},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_txpool_inspect() {
+	let (miner, io) = setup();
+	miner.pending_transactions.lock().insert(H256::zero(), pending_tx());
+	miner.future_transactions.lock().push(queued_tx());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "txpool_inspect", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"pending":{"0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e":{"0x0":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87: 10 wei + 21000 gas x 1 wei"}},"queued":{"0x0000000000000000000000000000000000000001":{"0x5":"contract creation: 0 wei + 21000 gas x 2 wei"}}},"id":1}"#;
+
+	assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
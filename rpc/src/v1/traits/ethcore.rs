@@ -48,6 +48,9 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 	/// Returns peers details
 	fn net_peers(&self, _: Params) -> Result<Value, Error>;
 
+	/// Returns reputation of known network nodes
+	fn net_peer_reputations(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns network port
 	fn net_port(&self, _: Params) -> Result<Value, Error>;
 
@@ -63,6 +66,12 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 	/// Returns distribution of gas price in latest blocks.
 	fn gas_price_statistics(&self, _: Params) -> Result<Value, Error>;
 
+	/// Returns gas prices of transactions in latest blocks at each percentile.
+	fn gas_price_histogram(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns share statistics of mining workers.
+	fn mining_workers(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns number of unsigned transactions waiting in the signer queue (if signer enabled)
 	/// Returns error when signer is disabled
 	fn unsigned_transactions_count(&self, _: Params) -> Result<Value, Error>;
@@ -84,6 +93,13 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 	/// or null if not.
 	fn list_storage_keys(&self, _: Params) -> Result<Value, Error>;
 
+	/// Returns the history of transactions originating from this node with their current status, oldest first.
+	fn local_transactions(&self, _: Params) -> Result<Value, Error>;
+
+	/// Imports a signed raw transaction (first parameter) which is held back until the condition
+	/// (second parameter, `{"block": number}` or `{"time": timestamp}`) is met. Returns the transaction hash.
+	fn send_raw_transaction_with_condition(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -97,17 +113,22 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_devLogsLevels", Ethcore::dev_logs_levels);
 		delegate.add_method("ethcore_netChain", Ethcore::net_chain);
 		delegate.add_method("ethcore_netPeers", Ethcore::net_peers);
+		delegate.add_method("ethcore_netPeerReputations", Ethcore::net_peer_reputations);
 		delegate.add_method("ethcore_netPort", Ethcore::net_port);
 		delegate.add_method("ethcore_rpcSettings", Ethcore::rpc_settings);
 		delegate.add_method("ethcore_nodeName", Ethcore::node_name);
 		delegate.add_method("ethcore_defaultExtraData", Ethcore::default_extra_data);
 		delegate.add_method("ethcore_gasPriceStatistics", Ethcore::gas_price_statistics);
+		delegate.add_method("ethcore_gasPriceHistogram", Ethcore::gas_price_histogram);
+		delegate.add_method("ethcore_miningWorkers", Ethcore::mining_workers);
 		delegate.add_method("ethcore_unsignedTransactionsCount", Ethcore::unsigned_transactions_count);
 		delegate.add_method("ethcore_generateSecretPhrase", Ethcore::generate_secret_phrase);
 		delegate.add_method("ethcore_phraseToAddress", Ethcore::phrase_to_address);
 		delegate.add_method("ethcore_registryAddress", Ethcore::registry_address);
 		delegate.add_method("ethcore_listAccounts", Ethcore::list_accounts);
 		delegate.add_method("ethcore_listStorageKeys", Ethcore::list_storage_keys);
+		delegate.add_method("ethcore_localTransactions", Ethcore::local_transactions);
+		delegate.add_method("ethcore_sendRawTransactionWithCondition", Ethcore::send_raw_transaction_with_condition);
 
 		delegate
 	}
@@ -43,12 +43,30 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 	/// Sets the maximum amount of gas a single transaction may consume.
 	fn set_tx_gas_limit(&self, _: Params) -> Result<Value, Error>;
 
+	/// Reloads the transaction filter policy file. Returns false if no filter is configured.
+	fn reload_transaction_filter(&self, _: Params) -> Result<Value, Error>;
+
+	/// Seals given number of blocks with internally sealing engine. Returns hashes of the blocks.
+	fn mine_blocks(&self, _: Params) -> Result<Value, Error>;
+
+	/// Sets timestamp of the next sealed block.
+	fn set_next_block_timestamp(&self, _: Params) -> Result<Value, Error>;
+
+	/// Moves time of sealed blocks forward by given number of seconds. Returns total number of seconds added.
+	fn increase_time(&self, _: Params) -> Result<Value, Error>;
+
 	/// Add a reserved peer.
 	fn add_reserved_peer(&self, _: Params) -> Result<Value, Error>;
 
 	/// Remove a reserved peer.
 	fn remove_reserved_peer(&self, _: Params) -> Result<Value, Error>;
 
+	/// Set reputation of a network node. Nodes with low reputation get banned.
+	fn set_peer_reputation(&self, _: Params) -> Result<Value, Error>;
+
+	/// Set the filter for addresses of non-reserved peers.
+	fn set_ip_filter(&self, _: Params) -> Result<Value, Error>;
+
 	/// Drop all non-reserved peers.
 	fn drop_non_reserved_peers(&self, _: Params) -> Result<Value, Error>;
 
@@ -71,8 +89,14 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_setAuthor", EthcoreSet::set_author);
 		delegate.add_method("ethcore_setMaxTransactionGas", EthcoreSet::set_tx_gas_limit);
 		delegate.add_method("ethcore_setTransactionsLimit", EthcoreSet::set_transactions_limit);
+		delegate.add_method("ethcore_reloadTransactionFilter", EthcoreSet::reload_transaction_filter);
+		delegate.add_method("ethcore_mineBlocks", EthcoreSet::mine_blocks);
+		delegate.add_method("ethcore_setNextBlockTimestamp", EthcoreSet::set_next_block_timestamp);
+		delegate.add_method("ethcore_increaseTime", EthcoreSet::increase_time);
 		delegate.add_method("ethcore_addReservedPeer", EthcoreSet::add_reserved_peer);
 		delegate.add_method("ethcore_removeReservedPeer", EthcoreSet::remove_reserved_peer);
+		delegate.add_method("ethcore_setPeerReputation", EthcoreSet::set_peer_reputation);
+		delegate.add_method("ethcore_setIpFilter", EthcoreSet::set_ip_filter);
 		delegate.add_method("ethcore_dropNonReservedPeers", EthcoreSet::drop_non_reserved_peers);
 		delegate.add_method("ethcore_acceptNonReservedPeers", EthcoreSet::accept_non_reserved_peers);
 
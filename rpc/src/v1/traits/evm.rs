@@ -0,0 +1,38 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Test chain rpc interface.
+use std::sync::Arc;
+use jsonrpc_core::*;
+
+/// Test chain rpc interface. Allows rolling the chain back between test cases.
+pub trait Evm: Sized + Send + Sync + 'static {
+	/// Records the best block and its state root. Returns id of the snapshot.
+	fn snapshot(&self, _: Params) -> Result<Value, Error>;
+
+	/// Reverts the chain to the snapshot with given id, discarding it and any later snapshots.
+	/// Returns false if there is no such snapshot.
+	fn revert(&self, _: Params) -> Result<Value, Error>;
+
+	/// Should be used to convert object to io delegate.
+	fn to_delegate(self) -> IoDelegate<Self> {
+		let mut delegate = IoDelegate::new(Arc::new(self));
+		delegate.add_method("evm_snapshot", Evm::snapshot);
+		delegate.add_method("evm_revert", Evm::revert);
+		delegate
+	}
+}
@@ -22,8 +22,11 @@ pub mod net;
 pub mod personal;
 pub mod ethcore;
 pub mod ethcore_set;
+pub mod evm;
 pub mod traces;
 pub mod rpc;
+pub mod shh;
+pub mod txpool;
 
 pub use self::web3::Web3;
 pub use self::eth::{Eth, EthFilter, EthSigning};
@@ -31,7 +34,10 @@ pub use self::net::Net;
 pub use self::personal::{Personal, PersonalSigner};
 pub use self::ethcore::Ethcore;
 pub use self::ethcore_set::EthcoreSet;
+pub use self::evm::Evm;
 pub use self::traces::Traces;
 pub use self::rpc::Rpc;
+pub use self::shh::Shh;
+pub use self::txpool::TxPool;
 
 
@@ -0,0 +1,52 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Whisper rpc interface.
+use std::sync::Arc;
+use jsonrpc_core::*;
+
+/// Whisper rpc interface.
+pub trait Shh: Sized + Send + Sync + 'static {
+	/// Posts a message. Returns true if the message was added to the pool.
+	fn post(&self, _: Params) -> Result<Value, Error>;
+
+	/// Creates a new identity. Returns its public key.
+	fn new_identity(&self, _: Params) -> Result<Value, Error>;
+
+	/// Checks if the node has the private key of an identity.
+	fn has_identity(&self, _: Params) -> Result<Value, Error>;
+
+	/// Installs a new message filter. Returns its id.
+	fn new_filter(&self, _: Params) -> Result<Value, Error>;
+
+	/// Uninstalls a filter.
+	fn uninstall_filter(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns messages received by the filter since the last poll.
+	fn filter_changes(&self, _: Params) -> Result<Value, Error>;
+
+	/// Should be used to convert object to io delegate.
+	fn to_delegate(self) -> IoDelegate<Self> {
+		let mut delegate = IoDelegate::new(Arc::new(self));
+		delegate.add_method("shh_post", Shh::post);
+		delegate.add_method("shh_newIdentity", Shh::new_identity);
+		delegate.add_method("shh_hasIdentity", Shh::has_identity);
+		delegate.add_method("shh_newFilter", Shh::new_filter);
+		delegate.add_method("shh_uninstallFilter", Shh::uninstall_filter);
+		delegate.add_method("shh_getFilterChanges", Shh::filter_changes);
+		delegate
+	}
+}
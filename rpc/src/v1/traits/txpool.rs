@@ -0,0 +1,41 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool rpc interface.
+use std::sync::Arc;
+use jsonrpc_core::*;
+
+/// Transaction pool rpc interface.
+pub trait TxPool: Sized + Send + Sync + 'static {
+	/// Returns number of pending and queued transactions together with the queue limits
+	/// and the minimal gas price required to enter the queue.
+	fn status(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns all pending and queued transactions grouped by sender and nonce.
+	fn content(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns a textual summary of all pending and queued transactions grouped by sender and nonce.
+	fn inspect(&self, _: Params) -> Result<Value, Error>;
+
+	/// Should be used to convert object to io delegate.
+	fn to_delegate(self) -> IoDelegate<Self> {
+		let mut delegate = IoDelegate::new(Arc::new(self));
+		delegate.add_method("txpool_status", TxPool::status);
+		delegate.add_method("txpool_content", TxPool::content);
+		delegate.add_method("txpool_inspect", TxPool::inspect);
+		delegate
+	}
+}
@@ -115,6 +115,7 @@ mod tests {
 				value: 100_000.into(),
 				data: vec![1, 2, 3],
 				nonce: Some(1.into()),
+				condition: None,
 			}),
 		};
 
@@ -0,0 +1,59 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::miner::GasPriceHistogram as EthGasPriceHistogram;
+use v1::types::U256;
+
+/// Distribution of gas prices of transactions in recent blocks.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct GasPriceHistogram {
+	/// Best block at the time the histogram was computed
+	#[serde(rename="blockNumber")]
+	pub block_number: U256,
+	/// Number of sampled transactions
+	#[serde(rename="sampleSize")]
+	pub sample_size: usize,
+	/// Gas prices at percentiles 0, 1, ..., 100
+	pub percentiles: Vec<U256>,
+}
+
+impl From<EthGasPriceHistogram> for GasPriceHistogram {
+	fn from(h: EthGasPriceHistogram) -> Self {
+		GasPriceHistogram {
+			block_number: h.block_number.into(),
+			sample_size: h.sample_size,
+			percentiles: h.percentiles.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::U256;
+	use super::GasPriceHistogram;
+
+	#[test]
+	fn gas_price_histogram_serialization() {
+		let histogram = GasPriceHistogram {
+			block_number: U256::from(5),
+			sample_size: 2,
+			percentiles: vec![U256::from(1), U256::from(2)],
+		};
+		let serialized = serde_json::to_string(&histogram).unwrap();
+		assert_eq!(serialized, r#"{"blockNumber":"0x5","sampleSize":2,"percentiles":["0x1","0x2"]}"#);
+	}
+}
@@ -0,0 +1,137 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::miner::LocalTransaction as EthLocalTransaction;
+use serde::{Serialize, Serializer};
+use v1::types::{Transaction, H256, U256};
+
+/// Local transaction status.
+#[derive(Debug)]
+pub enum LocalTransactionStatus {
+	/// Waiting in the queue, ready to be included in a block.
+	Pending(Transaction),
+	/// Waiting in the queue for transactions with lower nonces.
+	Future(Transaction),
+	/// Included in a block.
+	Mined(Transaction, U256, H256),
+	/// Replaced by another transaction with higher gas price.
+	Replaced(Transaction, H256, U256),
+	/// Not accepted to the queue.
+	Rejected(Transaction, String),
+	/// Removed from the queue because of its limits.
+	Dropped(Transaction),
+	/// No longer valid.
+	Invalid(Transaction),
+}
+
+impl Serialize for LocalTransactionStatus {
+	fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+	where S: Serializer {
+		use self::LocalTransactionStatus::*;
+
+		let elems = match *self {
+			Pending(_) | Future(_) | Dropped(_) | Invalid(_) => 2,
+			Rejected(..) => 3,
+			Mined(..) | Replaced(..) => 4,
+		};
+
+		let status = "status";
+		let transaction = "transaction";
+
+		let mut state = try!(serializer.serialize_struct("LocalTransactionStatus", elems));
+		match *self {
+			Pending(ref tx) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "pending"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+			},
+			Future(ref tx) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "future"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+			},
+			Mined(ref tx, ref number, ref hash) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "mined"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+				try!(serializer.serialize_struct_elt(&mut state, "blockNumber", number));
+				try!(serializer.serialize_struct_elt(&mut state, "blockHash", hash));
+			},
+			Replaced(ref tx, ref by, ref gas_price) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "replaced"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+				try!(serializer.serialize_struct_elt(&mut state, "replacedBy", by));
+				try!(serializer.serialize_struct_elt(&mut state, "gasPrice", gas_price));
+			},
+			Rejected(ref tx, ref error) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "rejected"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+				try!(serializer.serialize_struct_elt(&mut state, "error", error));
+			},
+			Dropped(ref tx) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "dropped"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+			},
+			Invalid(ref tx) => {
+				try!(serializer.serialize_struct_elt(&mut state, status, "invalid"));
+				try!(serializer.serialize_struct_elt(&mut state, transaction, tx));
+			},
+		}
+		serializer.serialize_struct_end(state)
+	}
+}
+
+impl From<EthLocalTransaction> for LocalTransactionStatus {
+	fn from(t: EthLocalTransaction) -> Self {
+		use ethcore::miner::LocalTransactionStatus::*;
+
+		let tx: Transaction = t.transaction.into();
+		match t.status {
+			Pending => LocalTransactionStatus::Pending(tx),
+			Future => LocalTransactionStatus::Future(tx),
+			Mined { block_number, block_hash } => LocalTransactionStatus::Mined(tx, block_number.into(), block_hash.into()),
+			Replaced { by, gas_price } => LocalTransactionStatus::Replaced(tx, by.into(), gas_price.into()),
+			Rejected(err) => LocalTransactionStatus::Rejected(tx, format!("{}", err)),
+			Dropped => LocalTransactionStatus::Dropped(tx),
+			Invalid => LocalTransactionStatus::Invalid(tx),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{Transaction, H256, U256};
+	use super::LocalTransactionStatus;
+
+	#[test]
+	fn test_serialize_local_transaction_status() {
+		let tx_ser = serde_json::to_string(&Transaction::default()).unwrap();
+
+		let status = LocalTransactionStatus::Pending(Transaction::default());
+		let serialized = serde_json::to_string(&status).unwrap();
+		assert_eq!(serialized, format!(r#"{{"status":"pending","transaction":{}}}"#, tx_ser));
+
+		let status = LocalTransactionStatus::Mined(Transaction::default(), U256::from(5), H256::default());
+		let serialized = serde_json::to_string(&status).unwrap();
+		assert_eq!(serialized, format!(r#"{{"status":"mined","transaction":{},"blockNumber":"0x5","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000"}}"#, tx_ser));
+
+		let status = LocalTransactionStatus::Replaced(Transaction::default(), H256::default(), U256::from(10));
+		let serialized = serde_json::to_string(&status).unwrap();
+		assert_eq!(serialized, format!(r#"{{"status":"replaced","transaction":{},"replacedBy":"0x0000000000000000000000000000000000000000000000000000000000000000","gasPrice":"0xa"}}"#, tx_ser));
+
+		let status = LocalTransactionStatus::Rejected(Transaction::default(), "No longer valid".into());
+		let serialized = serde_json::to_string(&status).unwrap();
+		assert_eq!(serialized, format!(r#"{{"status":"rejected","transaction":{},"error":"No longer valid"}}"#, tx_ser));
+	}
+}
@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+use ethsync::{PeerReputation as EthPeerReputation, PeerTraffic as EthPeerTraffic, PacketTraffic as EthPacketTraffic};
 use serde::{Serialize, Serializer};
 use v1::types::U256;
 
@@ -40,6 +41,105 @@ pub struct Peers {
 	pub connected: usize,
 	/// Max number of peers
 	pub max: u32,
+	/// Number of peers connected to us
+	pub inbound: u32,
+	/// Number of peers we connected to
+	pub outbound: u32,
+	/// Max number of peers connected to us
+	#[serde(rename="maxInbound")]
+	pub max_inbound: u32,
+	/// Max number of peers we connect to
+	#[serde(rename="maxOutbound")]
+	pub max_outbound: u32,
+	/// Traffic of connected peers
+	pub peers: Vec<PeerTraffic>,
+}
+
+/// Traffic of a connected peer
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerTraffic {
+	/// Node id
+	pub id: String,
+	/// Remote address
+	#[serde(rename="remoteAddress")]
+	pub remote_address: String,
+	/// Bytes sent, including framing and encryption
+	#[serde(rename="sentBytes")]
+	pub sent_bytes: u64,
+	/// Bytes received, including framing and encryption
+	#[serde(rename="recvBytes")]
+	pub recv_bytes: u64,
+	/// Per protocol and packet id traffic
+	pub packets: Vec<PacketTraffic>,
+}
+
+impl From<EthPeerTraffic> for PeerTraffic {
+	fn from(t: EthPeerTraffic) -> Self {
+		PeerTraffic {
+			id: t.id,
+			remote_address: t.remote_address,
+			sent_bytes: t.sent_bytes,
+			recv_bytes: t.recv_bytes,
+			packets: t.packets.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Traffic of a single packet type
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PacketTraffic {
+	/// Protocol id
+	pub protocol: String,
+	/// Packet id within the protocol
+	#[serde(rename="packetId")]
+	pub packet_id: u8,
+	/// Number of packets sent
+	#[serde(rename="sentPackets")]
+	pub sent_packets: u64,
+	/// Packet bytes sent
+	#[serde(rename="sentBytes")]
+	pub sent_bytes: u64,
+	/// Number of packets received
+	#[serde(rename="recvPackets")]
+	pub recv_packets: u64,
+	/// Packet bytes received
+	#[serde(rename="recvBytes")]
+	pub recv_bytes: u64,
+}
+
+impl From<EthPacketTraffic> for PacketTraffic {
+	fn from(t: EthPacketTraffic) -> Self {
+		PacketTraffic {
+			protocol: t.protocol,
+			packet_id: t.packet_id,
+			sent_packets: t.sent_packets,
+			sent_bytes: t.sent_bytes,
+			recv_packets: t.recv_packets,
+			recv_bytes: t.recv_bytes,
+		}
+	}
+}
+
+/// Network node reputation
+#[derive(Default, Debug, Serialize, PartialEq)]
+pub struct PeerReputation {
+	/// Node id
+	pub id: String,
+	/// Reputation score
+	pub score: i32,
+	/// Unix timestamp when the ban expires, if the node is banned
+	#[serde(rename="bannedUntil")]
+	pub banned_until: Option<i64>,
+}
+
+impl From<EthPeerReputation> for PeerReputation {
+	fn from(r: EthPeerReputation) -> Self {
+		PeerReputation {
+			id: r.id,
+			score: r.score,
+			banned_until: r.banned_until,
+		}
+	}
 }
 
 /// Sync status
@@ -64,7 +164,7 @@ impl Serialize for SyncStatus {
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use super::{SyncInfo, SyncStatus, Peers};
+	use super::{SyncInfo, SyncStatus, Peers, PeerReputation, PeerTraffic, PacketTraffic};
 
 	#[test]
 	fn test_serialize_sync_info() {
@@ -77,7 +177,27 @@ mod tests {
 	fn test_serialize_peers() {
 		let t = Peers::default();
 		let serialized = serde_json::to_string(&t).unwrap();
-		assert_eq!(serialized, r#"{"active":0,"connected":0,"max":0}"#);
+		assert_eq!(serialized, r#"{"active":0,"connected":0,"max":0,"inbound":0,"outbound":0,"maxInbound":0,"maxOutbound":0,"peers":[]}"#);
+	}
+
+	#[test]
+	fn test_serialize_peer_traffic() {
+		let t = PeerTraffic {
+			id: "00".into(),
+			remote_address: "127.0.0.1:30303".into(),
+			sent_bytes: 100,
+			recv_bytes: 200,
+			packets: vec![PacketTraffic { protocol: "eth".into(), packet_id: 1, sent_packets: 2, sent_bytes: 30, recv_packets: 4, recv_bytes: 50 }],
+		};
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"id":"00","remoteAddress":"127.0.0.1:30303","sentBytes":100,"recvBytes":200,"packets":[{"protocol":"eth","packetId":1,"sentPackets":2,"sentBytes":30,"recvPackets":4,"recvBytes":50}]}"#);
+	}
+
+	#[test]
+	fn test_serialize_peer_reputation() {
+		let t = PeerReputation { id: "00".into(), score: -60, banned_until: Some(1) };
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"id":"00","score":-60,"bannedUntil":1}"#);
 	}
 
 	#[test]
@@ -0,0 +1,65 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::transaction::Condition;
+
+/// Condition that has to be met before a transaction is propagated and mined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TransactionCondition {
+	/// Valid once the chain reaches given block number.
+	#[serde(rename="block")]
+	Number(u64),
+	/// Valid once the chain reaches given UNIX timestamp.
+	#[serde(rename="time")]
+	Timestamp(u64),
+}
+
+impl Into<Condition> for TransactionCondition {
+	fn into(self) -> Condition {
+		match self {
+			TransactionCondition::Number(n) => Condition::Number(n),
+			TransactionCondition::Timestamp(n) => Condition::Timestamp(n),
+		}
+	}
+}
+
+impl From<Condition> for TransactionCondition {
+	fn from(condition: Condition) -> Self {
+		match condition {
+			Condition::Number(n) => TransactionCondition::Number(n),
+			Condition::Timestamp(n) => TransactionCondition::Timestamp(n),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::TransactionCondition;
+
+	#[test]
+	fn condition_deserialization() {
+		let s = r#"[{ "block": 51 }, { "time": 10 }]"#;
+		let deserialized: Vec<TransactionCondition> = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, vec![TransactionCondition::Number(51), TransactionCondition::Timestamp(10)]);
+	}
+
+	#[test]
+	fn condition_serialization() {
+		let serialized = serde_json::to_string(&TransactionCondition::Number(51)).unwrap();
+		assert_eq!(serialized, r#"{"block":51}"#);
+	}
+}
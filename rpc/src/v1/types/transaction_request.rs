@@ -16,7 +16,7 @@
 
 //! `TransactionRequest` type
 
-use v1::types::{Bytes, H160, U256};
+use v1::types::{Bytes, H160, U256, TransactionCondition};
 use v1::helpers;
 
 /// Transaction request coming from RPC
@@ -37,6 +37,9 @@ pub struct TransactionRequest {
 	pub data: Option<Bytes>,
 	/// Transaction's nonce
 	pub nonce: Option<U256>,
+	/// Condition to be met before the transaction is propagated and mined
+	#[serde(skip_serializing_if="Option::is_none")]
+	pub condition: Option<TransactionCondition>,
 }
 
 impl From<helpers::TransactionRequest> for TransactionRequest {
@@ -49,6 +52,7 @@ impl From<helpers::TransactionRequest> for TransactionRequest {
 			value: r.value.map(Into::into),
 			data: r.data.map(Into::into),
 			nonce: r.nonce.map(Into::into),
+			condition: r.condition.map(Into::into),
 		}
 	}
 }
@@ -63,6 +67,7 @@ impl From<helpers::FilledTransactionRequest> for TransactionRequest {
 			value: Some(r.value.into()),
 			data: Some(r.data.into()),
 			nonce: r.nonce.map(Into::into),
+			condition: r.condition.map(Into::into),
 		}
 	}
 }
@@ -77,6 +82,7 @@ impl Into<helpers::TransactionRequest> for TransactionRequest {
 			value: self.value.map(Into::into),
 			data: self.data.map(Into::into),
 			nonce: self.nonce.map(Into::into),
+			condition: self.condition.map(Into::into),
 		}
 	}
 }
@@ -87,7 +93,7 @@ mod tests {
 	use std::str::FromStr;
 	use rustc_serialize::hex::FromHex;
 	use serde_json;
-	use v1::types::{U256, H160};
+	use v1::types::{U256, H160, TransactionCondition};
 	use super::*;
 
 	#[test]
@@ -99,7 +105,8 @@ mod tests {
 			"gas":"0x2",
 			"value":"0x3",
 			"data":"0x123456",
-			"nonce":"0x4"
+			"nonce":"0x4",
+			"condition": { "block": 5 }
 		}"#;
 		let deserialized: TransactionRequest = serde_json::from_str(s).unwrap();
 
@@ -111,6 +118,7 @@ mod tests {
 			value: Some(U256::from(3)),
 			data: Some(vec![0x12, 0x34, 0x56].into()),
 			nonce: Some(U256::from(4)),
+			condition: Some(TransactionCondition::Number(5)),
 		});
 	}
 
@@ -133,7 +141,8 @@ mod tests {
 			gas: Some(U256::from_str("76c0").unwrap()),
 			value: Some(U256::from_str("9184e72a").unwrap()),
 			data: Some("d46e8dd67c5d32be8d46e8dd67c5d32be8058bb8eb970870f072445675058bb8eb970870f072445675".from_hex().unwrap().into()),
-			nonce: None
+			nonce: None,
+			condition: None,
 		});
 	}
 
@@ -150,6 +159,7 @@ mod tests {
 			value: None,
 			data: None,
 			nonce: None,
+			condition: None,
 		});
 	}
 
@@ -173,6 +183,7 @@ mod tests {
 			value: None,
 			data: Some(vec![0x85, 0x95, 0xba, 0xb1].into()),
 			nonce: None,
+			condition: None,
 		});
 	}
 
@@ -0,0 +1,150 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use ethcore::miner::TransactionQueueStatus;
+use ethcore::transaction::{SignedTransaction, Action};
+use v1::types::{Transaction, H160, U256};
+
+/// Transaction pool status.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct TxPoolStatus {
+	/// Number of transactions ready to be included in a block
+	pub pending: usize,
+	/// Number of transactions waiting for transactions with lower nonces
+	pub queued: usize,
+	/// Maximal number of pending and of queued transactions
+	pub limit: usize,
+	/// Transactions with more gas are not accepted
+	#[serde(rename="gasLimit")]
+	pub gas_limit: U256,
+	/// Maximal amount of gas of a single transaction
+	#[serde(rename="txGasLimit")]
+	pub tx_gas_limit: U256,
+	/// Configured minimal gas price
+	#[serde(rename="minGasPrice")]
+	pub min_gas_price: U256,
+	/// Lowest gas price a transaction needs to be accepted right now
+	#[serde(rename="effectiveMinGasPrice")]
+	pub effective_min_gas_price: U256,
+}
+
+impl From<TransactionQueueStatus> for TxPoolStatus {
+	fn from(s: TransactionQueueStatus) -> Self {
+		TxPoolStatus {
+			pending: s.pending,
+			queued: s.future,
+			limit: s.limit,
+			gas_limit: s.gas_limit.into(),
+			tx_gas_limit: s.tx_gas_limit.into(),
+			min_gas_price: s.minimal_gas_price.into(),
+			effective_min_gas_price: s.effective_minimum_gas_price.into(),
+		}
+	}
+}
+
+/// Transactions grouped by sender and nonce.
+pub type TransactionsBySender<T> = BTreeMap<H160, BTreeMap<U256, T>>;
+
+fn by_sender<T, F>(transactions: Vec<SignedTransaction>, f: F) -> TransactionsBySender<T> where F: Fn(SignedTransaction) -> T {
+	let mut map = TransactionsBySender::new();
+	for tx in transactions {
+		let sender = match tx.sender() {
+			Ok(sender) => sender,
+			Err(_) => continue,
+		};
+		let nonce = tx.nonce;
+		map.entry(sender.into()).or_insert_with(BTreeMap::new).insert(nonce.into(), f(tx));
+	}
+	map
+}
+
+/// Transaction pool content.
+#[derive(Debug, Default, Serialize)]
+pub struct TxPoolContent {
+	/// Transactions ready to be included in a block
+	pub pending: TransactionsBySender<Transaction>,
+	/// Transactions waiting for transactions with lower nonces
+	pub queued: TransactionsBySender<Transaction>,
+}
+
+impl TxPoolContent {
+	/// Groups given pending and queued transactions.
+	pub fn new(pending: Vec<SignedTransaction>, queued: Vec<SignedTransaction>) -> Self {
+		TxPoolContent {
+			pending: by_sender(pending, Into::into),
+			queued: by_sender(queued, Into::into),
+		}
+	}
+}
+
+/// Transaction pool content summary.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct TxPoolInspect {
+	/// Transactions ready to be included in a block
+	pub pending: TransactionsBySender<String>,
+	/// Transactions waiting for transactions with lower nonces
+	pub queued: TransactionsBySender<String>,
+}
+
+fn summary(tx: SignedTransaction) -> String {
+	let to = match tx.action {
+		Action::Create => "contract creation".to_owned(),
+		Action::Call(ref to) => format!("0x{:?}", to),
+	};
+	format!("{}: {} wei + {} gas x {} wei", to, tx.value, tx.gas, tx.gas_price)
+}
+
+impl TxPoolInspect {
+	/// Summarizes given pending and queued transactions.
+	pub fn new(pending: Vec<SignedTransaction>, queued: Vec<SignedTransaction>) -> Self {
+		TxPoolInspect {
+			pending: by_sender(pending, summary),
+			queued: by_sender(queued, summary),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use util::{U256, Address};
+	use ethcore::transaction::{Transaction, Action};
+	use super::{TxPoolStatus, TxPoolInspect};
+
+	#[test]
+	fn test_serialize_txpool_status() {
+		let t = TxPoolStatus::default();
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"pending":0,"queued":0,"limit":0,"gasLimit":"0x0","txGasLimit":"0x0","minGasPrice":"0x0","effectiveMinGasPrice":"0x0"}"#);
+	}
+
+	#[test]
+	fn test_serialize_txpool_inspect() {
+		let tx = |nonce: u64, action: Action| Transaction {
+			nonce: U256::from(nonce),
+			gas_price: U256::from(20),
+			gas: U256::from(21_000),
+			action: action,
+			value: U256::from(1),
+			data: vec![],
+		}.fake_sign(Address::from(1));
+
+		let t = TxPoolInspect::new(vec![tx(1, Action::Create)], vec![tx(3, Action::Call(Address::from(2)))]);
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"pending":{"0x0000000000000000000000000000000000000001":{"0x1":"contract creation: 1 wei + 21000 gas x 20 wei"}},"queued":{"0x0000000000000000000000000000000000000001":{"0x3":"0x0000000000000000000000000000000000000002: 1 wei + 21000 gas x 20 wei"}}}"#);
+	}
+}
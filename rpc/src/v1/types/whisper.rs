@@ -0,0 +1,114 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use whisper::Message;
+use v1::types::{Bytes, H256, H512, U256};
+
+/// Whisper message to post.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WhisperPost {
+	/// Sender identity. The message is signed if present.
+	pub from: Option<H512>,
+	/// Recipient identity. The message is encrypted if present.
+	pub to: Option<H512>,
+	/// Full topics
+	pub topics: Vec<Bytes>,
+	/// Message payload
+	pub payload: Bytes,
+	/// Time to spend on proof of work in milliseconds
+	pub priority: U256,
+	/// Time to live in seconds
+	pub ttl: U256,
+}
+
+/// Whisper message filter.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WhisperFilter {
+	/// Full topics the messages must have, all of them
+	pub topics: Vec<Bytes>,
+	/// Recipient identity. Only unencrypted messages match if not set.
+	pub to: Option<H512>,
+}
+
+/// Received whisper message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WhisperMessage {
+	/// Envelope hash
+	pub hash: H256,
+	/// Sender identity if the message is signed
+	pub from: Option<H512>,
+	/// Recipient identity if the message is encrypted
+	pub to: Option<H512>,
+	/// Unix time the message expires at
+	pub expiry: u32,
+	/// Time to live in seconds
+	pub ttl: u32,
+	/// Unix time the message was sent at
+	pub sent: u32,
+	/// Abridged topics
+	pub topics: Vec<Bytes>,
+	/// Message payload
+	pub payload: Bytes,
+	/// Proof of work of the envelope
+	#[serde(rename="workProved")]
+	pub work_proved: u32,
+}
+
+impl From<Message> for WhisperMessage {
+	fn from(m: Message) -> Self {
+		WhisperMessage {
+			hash: m.hash.into(),
+			from: m.from.map(Into::into),
+			to: m.to.map(Into::into),
+			expiry: m.expiry,
+			ttl: m.ttl,
+			sent: m.sent,
+			topics: m.topics.into_iter().map(|t| Bytes::new(t.0.to_vec())).collect(),
+			payload: Bytes::new(m.payload),
+			work_proved: m.work,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{WhisperPost, WhisperFilter};
+	use v1::types::{Bytes, U256};
+
+	#[test]
+	fn post_deserialization() {
+		let s = r#"{"topics":["0x6869"],"payload":"0x0102","priority":"0x64","ttl":"0x32"}"#;
+		let post: WhisperPost = serde_json::from_str(s).unwrap();
+		assert_eq!(post, WhisperPost {
+			from: None,
+			to: None,
+			topics: vec![Bytes::new(vec![0x68, 0x69])],
+			payload: Bytes::new(vec![1, 2]),
+			priority: U256::from(100),
+			ttl: U256::from(50),
+		});
+	}
+
+	#[test]
+	fn filter_deserialization() {
+		let s = r#"{"topics":["0x6869"]}"#;
+		let filter: WhisperFilter = serde_json::from_str(s).unwrap();
+		assert_eq!(filter, WhisperFilter { topics: vec![Bytes::new(vec![0x68, 0x69])], to: None });
+	}
+}
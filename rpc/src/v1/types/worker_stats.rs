@@ -0,0 +1,59 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::miner::WorkerStats as EthWorkerStats;
+use v1::types::U256;
+
+/// Share statistics of a mining worker.
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct WorkerStats {
+	/// Number of accepted shares
+	#[serde(rename="acceptedShares")]
+	pub accepted_shares: u64,
+	/// Number of rejected shares
+	#[serde(rename="rejectedShares")]
+	pub rejected_shares: u64,
+	/// Hashrate estimated from recently accepted shares
+	pub hashrate: U256,
+}
+
+impl From<EthWorkerStats> for WorkerStats {
+	fn from(s: EthWorkerStats) -> Self {
+		WorkerStats {
+			accepted_shares: s.accepted_shares,
+			rejected_shares: s.rejected_shares,
+			hashrate: s.hashrate.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::U256;
+	use super::WorkerStats;
+
+	#[test]
+	fn worker_stats_serialization() {
+		let stats = WorkerStats {
+			accepted_shares: 5,
+			rejected_shares: 1,
+			hashrate: U256::from(1000),
+		};
+		let serialized = serde_json::to_string(&stats).unwrap();
+		assert_eq!(serialized, r#"{"acceptedShares":5,"rejectedShares":1,"hashrate":"0x3e8"}"#);
+	}
+}
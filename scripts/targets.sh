@@ -14,6 +14,7 @@ export TARGETS="
 	-p ethkey \
 	-p ethstore \
 	-p ethsync \
+	-p ethcore-whisper \
 	-p ethcore-ipc \
 	-p ethcore-ipc-tests \
 	-p ethcore-ipc-nano \
@@ -50,7 +50,41 @@ use std::sync::Arc;
 
 use std::net::SocketAddr;
 use std::collections::{HashSet, HashMap};
-use util::{H256, Hashable, RwLock, RwLockReadGuard};
+use util::{H256, U256, Hashable, Mutex, RwLock, RwLockReadGuard, clean_0x};
+
+/// Protocol version announced to EthereumStratum clients.
+const ETHEREUM_STRATUM_VERSION: &'static str = "EthereumStratum/1.0.0";
+/// Length of the hex-encoded nonce.
+const NONCE_HEX_LENGTH: usize = 16;
+/// Length of the hex-encoded extranonce.
+const EXTRANONCE_HEX_LENGTH: usize = 4;
+
+/// Assigns extranonces unique among connected EthereumStratum clients.
+#[derive(Default)]
+struct ExtranonceAllocator {
+	/// Lowest extranonce never assigned so far
+	next: u32,
+	/// Extranonces of closed sessions
+	released: Vec<u16>,
+}
+
+impl ExtranonceAllocator {
+	/// Takes a free extranonce, `None` if all of them are in use.
+	fn allocate(&mut self) -> Option<u16> {
+		self.released.pop().or_else(|| {
+			if self.next > u16::max_value() as u32 {
+				return None;
+			}
+			self.next += 1;
+			Some((self.next - 1) as u16)
+		})
+	}
+
+	/// Makes extranonce available for new sessions.
+	fn release(&mut self, extranonce: u16) {
+		self.released.push(extranonce);
+	}
+}
 
 pub struct Stratum {
 	rpc_server: JsonRpcServer,
@@ -63,6 +97,15 @@ pub struct Stratum {
 	dispatcher: Arc<JobDispatcher>,
 	/// Authorized workers (socket - worker_id)
 	workers: Arc<RwLock<HashMap<SocketAddr, String>>>,
+	/// Sessions of clients subscribed with `mining.subscribe` (socket - extranonce);
+	/// other clients speak the legacy `miner.*` protocol
+	extranonces: RwLock<HashMap<SocketAddr, u16>>,
+	/// Extranonces available for new EthereumStratum sessions
+	extranonce_allocator: Mutex<ExtranonceAllocator>,
+	/// Share difficulty for workers without specific one
+	default_difficulty: RwLock<Option<U256>>,
+	/// Share difficulties of particular workers (worker_id - difficulty)
+	difficulties: RwLock<HashMap<String, U256>>,
 	/// Secret if any
 	secret: Option<H256>,
 }
@@ -82,12 +125,21 @@ impl Stratum {
 			job_que: RwLock::new(HashSet::new()),
 			dispatcher: dispatcher,
 			workers: Arc::new(RwLock::new(HashMap::new())),
+			extranonces: RwLock::new(HashMap::new()),
+			extranonce_allocator: Mutex::new(ExtranonceAllocator::default()),
+			default_difficulty: RwLock::new(None),
+			difficulties: RwLock::new(HashMap::new()),
 			secret: secret,
 		});
 
 		let mut delegate = IoDelegate::<Stratum>::new(stratum.clone());
 		delegate.add_method("miner.subscribe", Stratum::subscribe);
 		delegate.add_method("miner.authorize", Stratum::authorize);
+		delegate.add_method("miner.submit", Stratum::submit);
+		delegate.add_method("mining.subscribe", Stratum::subscribe_ethereum_stratum);
+		delegate.add_method("mining.extranonce.subscribe", Stratum::extranonce_subscribe);
+		delegate.add_method("mining.authorize", Stratum::authorize);
+		delegate.add_method("mining.submit", Stratum::submit);
 		stratum.handler.add_delegate(delegate);
 
 		try!(stratum.rpc_server.run_async());
@@ -115,6 +167,42 @@ impl Stratum {
 		})
 	}
 
+	/// EthereumStratum subscription; responds with the session id and the extranonce assigned to the client.
+	fn subscribe_ethereum_stratum(&self, _params: Params) -> std::result::Result<jsonrpc_core::Value, jsonrpc_core::Error> {
+		match self.rpc_server.request_context() {
+			Some(context) => {
+				let extranonce = {
+					let mut extranonces = self.extranonces.write();
+					match extranonces.get(&context.socket_addr).cloned().or_else(|| self.extranonce_allocator.lock().allocate()) {
+						Some(extranonce) => {
+							extranonces.insert(context.socket_addr, extranonce);
+							format!("{:04x}", extranonce)
+						},
+						None => {
+							warn!(target: "stratum", "No extranonce left for {:?}, subscription rejected", context.socket_addr);
+							return Ok(to_value(&false));
+						},
+					}
+				};
+				if !self.subscribers.read().contains(&context.socket_addr) {
+					self.subscribers.write().push(context.socket_addr);
+				}
+				self.job_que.write().insert(context.socket_addr);
+				trace!(target: "stratum", "EthereumStratum subscription request from {:?}, extranonce {}", context.socket_addr, extranonce);
+				Ok(to_value(&(("mining.notify", extranonce.clone(), ETHEREUM_STRATUM_VERSION), extranonce)))
+			},
+			None => {
+				warn!(target: "stratum", "Subscribe without valid context received!");
+				Ok(to_value(&false))
+			},
+		}
+	}
+
+	fn extranonce_subscribe(&self, _params: Params) -> std::result::Result<jsonrpc_core::Value, jsonrpc_core::Error> {
+		// extranonce is fixed for the whole session
+		Ok(to_value(&true))
+	}
+
 	fn authorize(&self, params: Params) -> std::result::Result<jsonrpc_core::Value, jsonrpc_core::Error> {
 		from_params::<(String, String)>(params).map(|(worker_id, secret)|{
 			if let Some(valid_secret) = self.secret {
@@ -124,7 +212,16 @@ impl Stratum {
 				}
 			}
 			if let Some(context) = self.rpc_server.request_context() {
-				self.workers.write().insert(context.socket_addr, worker_id);
+				self.workers.write().insert(context.socket_addr, worker_id.clone());
+				match self.worker_difficulty(&worker_id) {
+					Some(difficulty) => self.push_difficulty(&context.socket_addr, &difficulty_json(difficulty)),
+					None => if let Some(difficulty) = self.dispatcher.difficulty() {
+						self.push_difficulty(&context.socket_addr, &difficulty);
+					},
+				}
+				// worker gets current job right away instead of waiting for the next work update
+				self.job_que.write().remove(&context.socket_addr);
+				self.push_job(&context.socket_addr, &worker_id);
 				to_value(&true)
 			}
 			else {
@@ -134,19 +231,139 @@ impl Stratum {
 		})
 	}
 
+	/// Submits solution found by an authorized worker.
+	/// Params are the worker name, the job id and the nonce, optionally followed by the header hash and the mix hash.
+	/// Nonce submitted by EthereumStratum clients is prefixed with the extranonce of the session.
+	fn submit(&self, params: Params) -> std::result::Result<jsonrpc_core::Value, jsonrpc_core::Error> {
+		from_params::<Vec<String>>(params).map(|params| {
+			let socket_addr = match self.rpc_server.request_context() {
+				Some(context) => context.socket_addr,
+				None => {
+					warn!(target: "stratum", "Submit without valid context received!");
+					return to_value(&false);
+				},
+			};
+			let worker_id = match self.workers.read().get(&socket_addr) {
+				Some(worker_id) => worker_id.clone(),
+				None => {
+					trace!(target: "stratum", "Solution submitted by unauthorized worker ('{:?}')", socket_addr);
+					return to_value(&false);
+				},
+			};
+			if params.len() < 3 {
+				trace!(target: "stratum", "Invalid solution submitted by {}: {:?}", worker_id, params);
+				return to_value(&false);
+			}
+
+			let nonce = {
+				let nonce = clean_0x(&params[2]);
+				match self.extranonces.read().get(&socket_addr) {
+					Some(extranonce) if nonce.len() == NONCE_HEX_LENGTH - EXTRANONCE_HEX_LENGTH => format!("{:04x}{}", extranonce, nonce),
+					_ => nonce.to_owned(),
+				}
+			};
+			let mut payload = vec![params[1].clone(), nonce];
+			payload.extend(params.into_iter().skip(4));
+
+			match self.dispatcher.submit(worker_id.clone(), payload, self.worker_difficulty(&worker_id)) {
+				Ok(difficulty) => {
+					trace!(target: "stratum", "Share of difficulty {} accepted from {}", difficulty, worker_id);
+					to_value(&true)
+				},
+				Err(e) => {
+					trace!(target: "stratum", "Share rejected from {}: {:?}", worker_id, e);
+					to_value(&false)
+				},
+			}
+		})
+	}
+
 	pub fn subscribers(&self) -> RwLockReadGuard<Vec<SocketAddr>> {
 		self.subscribers.read()
 	}
 
+	/// Sets share difficulty for workers without a specific one.
+	/// With `None` only full solutions are accepted as shares.
+	pub fn set_default_difficulty(&self, difficulty: Option<U256>) {
+		*self.default_difficulty.write() = difficulty;
+		if let Some(difficulty) = difficulty {
+			let difficulties = self.difficulties.read();
+			for (addr, worker_id) in self.workers.read().iter() {
+				if !difficulties.contains_key(worker_id) {
+					self.push_difficulty(addr, &difficulty_json(difficulty));
+				}
+			}
+		}
+	}
+
+	/// Sets share difficulty for given worker and notifies it if connected.
+	pub fn set_worker_difficulty(&self, worker_id: &str, difficulty: U256) {
+		self.difficulties.write().insert(worker_id.to_owned(), difficulty);
+		for (addr, _) in self.workers.read().iter().filter(|&(_, id)| id == worker_id) {
+			self.push_difficulty(addr, &difficulty_json(difficulty));
+		}
+	}
+
+	/// Share difficulty of given worker.
+	pub fn worker_difficulty(&self, worker_id: &str) -> Option<U256> {
+		self.difficulties.read().get(worker_id).cloned().or_else(|| *self.default_difficulty.read())
+	}
+
+	fn push_difficulty(&self, addr: &SocketAddr, difficulty: &str) {
+		let message = format!(r#"{{"id":null,"method":"mining.set_difficulty","params":[{}]}}"#, difficulty);
+		if let Err(e) = self.rpc_server.push_message(addr, message.as_bytes()) {
+			warn!(target: "stratum", "Error pushing difficulty to {:?}: {:?}", addr, e);
+		}
+	}
+
+	/// Whether client at `addr` subscribed with `mining.subscribe`.
+	fn is_ethereum_stratum(&self, addr: &SocketAddr) -> bool {
+		self.extranonces.read().contains_key(addr)
+	}
+
+	/// Forgets the session of a client which is no longer connected, releasing its extranonce.
+	fn close_session(&self, addr: &SocketAddr) {
+		trace!(target: "stratum", "Closing session of {:?}", addr);
+		self.subscribers.write().retain(|subscriber| subscriber != addr);
+		self.job_que.write().remove(addr);
+		self.workers.write().remove(addr);
+		if let Some(extranonce) = self.extranonces.write().remove(addr) {
+			self.extranonce_allocator.lock().release(extranonce);
+		}
+	}
+
+	/// Pushes message to the client at `addr`.
+	/// The server does not report closed connections, so the session is closed if the push fails.
+	fn push_message(&self, addr: &SocketAddr, message: &str) -> bool {
+		match self.rpc_server.push_message(addr, message.as_bytes()) {
+			Ok(_) => true,
+			Err(e) => {
+				trace!(target: "stratum", "Error pushing message to {:?}: {:?}", addr, e);
+				self.close_session(addr);
+				false
+			},
+		}
+	}
+
+	/// Pushes current job of `worker_id` to the client at `addr`, framed for the protocol of its session.
+	fn push_job(&self, addr: &SocketAddr, worker_id: &str) {
+		let message = match self.is_ethereum_stratum(addr) {
+			true => self.dispatcher.notify_job(worker_id.to_owned()).map(|params| notify_message(&params)),
+			false => self.dispatcher.job(worker_id.to_owned()),
+		};
+		if let Some(message) = message {
+			if let Err(e) = self.rpc_server.push_message(addr, message.as_bytes()) {
+				trace!(target: "stratum", "Error pushing job to {:?}: {:?}", addr, e);
+			}
+		}
+	}
+
 	pub fn maintain(&self) {
 		let mut job_que = self.job_que.write();
 		let workers = self.workers.read();
 		for socket_addr in job_que.drain() {
 			if let Some(worker_id) = workers.get(&socket_addr) {
-				let job_payload = self.dispatcher.job(worker_id.to_owned());
-				job_payload.map(
-					|json| self.rpc_server.push_message(&socket_addr, json.as_bytes())
-				);
+				self.push_job(&socket_addr, worker_id);
 			}
 			else {
 				trace!(
@@ -158,12 +375,28 @@ impl Stratum {
 	}
 }
 
+/// Formats share difficulty for `mining.set_difficulty`; EthereumStratum difficulty 1 stands for 2^32 hashes.
+pub fn difficulty_json(difficulty: U256) -> String {
+	let U256(ref words) = difficulty;
+	let difficulty = words.iter().rev().fold(0f64, |acc, &word| acc * 18446744073709551616f64 + word as f64);
+	format!("{}", difficulty / 4294967296f64)
+}
+
+/// Frames `mining.notify` params as a notification.
+fn notify_message(params: &str) -> String {
+	format!(r#"{{"id":null,"method":"mining.notify","params":{}}}"#, params)
+}
+
 impl PushWorkHandler for Stratum {
-	fn push_work_all(&self, payload: String) -> Result<(), Error> {
-		let workers = self.workers.read();
-		println!("pushing work for {} workers", workers.len());
-		for (ref addr, _) in workers.iter() {
-			try!(self.rpc_server.push_message(addr, payload.as_bytes()));
+	fn push_work_all(&self, payload: String, notify_params: String) -> Result<(), Error> {
+		let addrs = self.workers.read().keys().cloned().collect::<Vec<SocketAddr>>();
+		trace!(target: "stratum", "Pushing work for {} workers", addrs.len());
+		let notify = notify_message(&notify_params);
+		for addr in addrs {
+			match self.is_ethereum_stratum(&addr) {
+				true => self.push_message(&addr, &notify),
+				false => self.push_message(&addr, &payload),
+			};
 		}
 		Ok(())
 	}
@@ -173,8 +406,8 @@ impl PushWorkHandler for Stratum {
 			return Err(Error::NoWork);
 		}
 		let workers = self.workers.read();
-		let addrs = workers.keys().collect::<Vec<&SocketAddr>>();
-		if !workers.len() > 0 {
+		let addrs = workers.keys().filter(|addr| !self.is_ethereum_stratum(addr)).collect::<Vec<&SocketAddr>>();
+		if !addrs.len() > 0 {
 			return Err(Error::NoWorkers);
 		}
 		let mut que = payloads;
@@ -201,6 +434,7 @@ mod tests {
 	use std::net::SocketAddr;
 	use std::sync::{Arc, RwLock};
 	use std::thread;
+	use util::U256;
 
 	pub struct VoidManager;
 
@@ -308,7 +542,9 @@ mod tests {
 	}
 
 	struct DummyManager {
-		initial_payload: String
+		initial_payload: String,
+		job_payload: Option<String>,
+		submissions: RwLock<Vec<(String, Vec<String>, Option<U256>)>>,
 	}
 
 	impl DummyManager {
@@ -317,19 +553,41 @@ mod tests {
 		}
 
 		fn build() -> DummyManager {
-			DummyManager { initial_payload: r#"[ "dummy payload" ]"#.to_owned() }
+			DummyManager {
+				initial_payload: r#"[ "dummy payload" ]"#.to_owned(),
+				job_payload: None,
+				submissions: RwLock::new(Vec::new()),
+			}
 		}
 
 		fn of_initial(mut self, new_initial: &str) -> DummyManager {
 			self.initial_payload = new_initial.to_owned();
 			self
 		}
+
+		fn of_job(mut self, new_job: &str) -> DummyManager {
+			self.job_payload = Some(new_job.to_owned());
+			self
+		}
 	}
 
 	impl JobDispatcher for DummyManager {
 		fn initial(&self) -> Option<String> {
 			Some(self.initial_payload.clone())
 		}
+
+		fn job(&self, _worker_id: String) -> Option<String> {
+			self.job_payload.clone()
+		}
+
+		fn notify_job(&self, _worker_id: String) -> Option<String> {
+			self.job_payload.clone()
+		}
+
+		fn submit(&self, worker_id: String, payload: Vec<String>, share_difficulty: Option<U256>) -> Result<U256, Error> {
+			self.submissions.write().unwrap().push((worker_id, payload, share_difficulty));
+			Ok(share_difficulty.unwrap_or_else(U256::one))
+		}
 	}
 
 	#[test]
@@ -381,9 +639,118 @@ mod tests {
 		);
 		::std::thread::park_timeout(::std::time::Duration::from_millis(150));
 
-		stratum.push_work_all(r#"{ "00040008", "100500" }"#.to_owned()).unwrap();
+		stratum.push_work_all(r#"{ "00040008", "100500" }"#.to_owned(), r#"["00040008",true]"#.to_owned()).unwrap();
 		::std::thread::park_timeout(::std::time::Duration::from_millis(150));
 
 		assert_eq!(2, result.read().unwrap().len());
+		assert_eq!(r#"{ "00040008", "100500" }"#, result.read().unwrap()[1]);
+	}
+
+	#[test]
+	fn pushes_job_right_after_authorize() {
+		init_log();
+
+		let addr = SocketAddr::from_str("0.0.0.0:19945").unwrap();
+		Stratum::start(&addr, Arc::new(DummyManager::build().of_job(r#"["00040008",true]"#)), None).unwrap();
+
+		let result = Arc::new(RwLock::new(Vec::<String>::new()));
+		let _stop = dummy_async_waiter(
+			&addr,
+			vec![
+				r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["miner/1.0", "EthereumStratum/1.0.0"], "id": 1}"#.to_owned(),
+				r#"{"jsonrpc": "2.0", "method": "mining.authorize", "params": ["miner1", ""], "id": 2}"#.to_owned(),
+			],
+			result.clone(),
+		);
+		::std::thread::park_timeout(::std::time::Duration::from_millis(300));
+
+		let responses = result.read().unwrap().join("");
+		assert!(responses.contains(r#"{"id":null,"method":"mining.notify","params":["00040008",true]}"#));
+	}
+
+	#[test]
+	fn can_push_work_to_ethereum_stratum_clients() {
+		init_log();
+
+		let addr = SocketAddr::from_str("0.0.0.0:19950").unwrap();
+		let stratum = Stratum::start(&addr, DummyManager::new(), None).unwrap();
+
+		let result = Arc::new(RwLock::new(Vec::<String>::new()));
+		let _stop = dummy_async_waiter(
+			&addr,
+			vec![
+				r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["miner/1.0", "EthereumStratum/1.0.0"], "id": 1}"#.to_owned(),
+				r#"{"jsonrpc": "2.0", "method": "mining.authorize", "params": ["miner1", ""], "id": 2}"#.to_owned(),
+			],
+			result.clone(),
+		);
+		::std::thread::park_timeout(::std::time::Duration::from_millis(300));
+
+		stratum.push_work_all(r#"{ "00040008", "100500" }"#.to_owned(), r#"["00040008",true]"#.to_owned()).unwrap();
+		::std::thread::park_timeout(::std::time::Duration::from_millis(150));
+
+		let responses = result.read().unwrap().join("");
+		assert!(responses.contains(r#"{"id":null,"method":"mining.notify","params":["00040008",true]}"#));
+		assert!(!responses.contains("100500"));
+	}
+
+	#[test]
+	fn recycles_released_extranonces() {
+		let mut allocator = ExtranonceAllocator::default();
+		for expected in 0..u16::max_value() as u32 + 1 {
+			assert_eq!(allocator.allocate(), Some(expected as u16));
+		}
+		assert_eq!(allocator.allocate(), None);
+
+		allocator.release(5);
+		assert_eq!(allocator.allocate(), Some(5));
+		assert_eq!(allocator.allocate(), None);
+	}
+
+	#[test]
+	fn converts_difficulty_beyond_64_bits() {
+		assert_eq!(difficulty_json(U256::from(1u64 << 32)), "1");
+		assert_eq!(difficulty_json(U256::from(1u64 << 32) << 64), "18446744073709552000");
+	}
+
+	#[test]
+	fn responds_to_ethereum_stratum_subscription() {
+		let addr = SocketAddr::from_str("0.0.0.0:19960").unwrap();
+		Stratum::start(&addr, DummyManager::new(), None).unwrap();
+		let request = r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["miner/1.0", "EthereumStratum/1.0.0"], "id": 1}"#;
+
+		let response = String::from_utf8(dummy_request(&addr, request.as_bytes())).unwrap();
+
+		assert_eq!(r#"{"jsonrpc":"2.0","result":[["mining.notify","0000","EthereumStratum/1.0.0"],"0000"],"id":1}"#, response);
+	}
+
+	#[test]
+	fn routes_submitted_shares_to_dispatcher() {
+		init_log();
+
+		let addr = SocketAddr::from_str("0.0.0.0:19955").unwrap();
+		let manager = DummyManager::new();
+		let stratum = Stratum::start(&addr, manager.clone(), None).unwrap();
+		stratum.set_worker_difficulty("miner1", U256::from(1u64 << 32));
+
+		let result = Arc::new(RwLock::new(Vec::<String>::new()));
+		let _stop = dummy_async_waiter(
+			&addr,
+			vec![
+				r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["miner/1.0", "EthereumStratum/1.0.0"], "id": 1}"#.to_owned(),
+				r#"{"jsonrpc": "2.0", "method": "mining.authorize", "params": ["miner1", ""], "id": 2}"#.to_owned(),
+				r#"{"jsonrpc": "2.0", "method": "mining.submit", "params": ["miner1", "0xbeef", "000000000001"], "id": 3}"#.to_owned(),
+			],
+			result.clone(),
+		);
+		::std::thread::park_timeout(::std::time::Duration::from_millis(500));
+
+		let responses = result.read().unwrap().join("");
+		assert!(responses.contains(r#""method":"mining.set_difficulty","params":[1]"#));
+		assert!(responses.contains(r#"{"jsonrpc":"2.0","result":true,"id":3}"#));
+		assert_eq!(
+			*manager.submissions.read().unwrap(),
+			vec![("miner1".to_owned(), vec!["0xbeef".to_owned(), "0000000000000001".to_owned()], Some(U256::from(1u64 << 32)))]
+		);
 	}
 }
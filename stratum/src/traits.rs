@@ -16,7 +16,7 @@
 
 use std;
 use std::error::Error as StdError;
-use util::H256;
+use util::{H256, U256};
 use ipc::IpcConfig;
 
 #[derive(Debug, Clone, Binary)]
@@ -24,6 +24,8 @@ pub enum Error {
 	NoWork,
 	NoWorkers,
 	Io(String),
+	InvalidSolution(String),
+	Dispatch(String),
 }
 
 impl From<std::io::Error> for Error {
@@ -42,16 +44,24 @@ pub trait JobDispatcher: Send + Sync {
 	fn difficulty(&self) -> Option<String> { None }
 	// json for job update given worker_id (payload manager should split job!)
 	fn job(&self, _worker_id: String) -> Option<String> { None }
+	// json params of `mining.notify` job update given worker_id, for EthereumStratum clients
+	fn notify_job(&self, _worker_id: String) -> Option<String> { None }
+	// submit solution (pow-specific payload) found by worker_id, checked against share_difficulty if any;
+	// returns difficulty credited to the worker for the share
+	fn submit(&self, _worker_id: String, _payload: Vec<String>, _share_difficulty: Option<U256>) -> Result<U256, Error> {
+		Err(Error::Dispatch("Solution submission is not supported".to_owned()))
+	}
 }
 
 #[derive(Ipc)]
 #[ipc(client_ident="RemoteWorkHandler")]
 /// Interface that can handle requests to push job for workers
 pub trait PushWorkHandler: Send + Sync {
-	/// push the same work package for all workers (`payload`: json of pow-specific set of work specification)
-	fn push_work_all(&self, payload: String) -> Result<(), Error>;
+	/// push the same work package for all workers (`payload`: json of pow-specific set of work specification,
+	/// `notify_params`: the same work as json params of `mining.notify` for EthereumStratum clients)
+	fn push_work_all(&self, payload: String, notify_params: String) -> Result<(), Error>;
 
-	/// push the work packages worker-wise (`payload`: json of pow-specific set of work specification)
+	/// push the work packages worker-wise to legacy clients (`payload`: json of pow-specific set of work specification)
 	fn push_work(&self, payloads: Vec<String>) -> Result<(), Error>;
 }
 
@@ -59,6 +69,8 @@ pub trait PushWorkHandler: Send + Sync {
 pub struct ServiceConfiguration {
 	pub listen_addr: String,
 	pub secret: Option<H256>,
+	pub share_difficulty: Option<U256>,
+	pub io_path: String,
 }
 
 impl IpcConfig for PushWorkHandler { }
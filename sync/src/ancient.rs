@@ -0,0 +1,265 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Download of ancient blocks: the blocks missing before the first block of a chain
+//! restored from a snapshot. Blocks are downloaded in reverse, starting with the parent
+//! of the first block, so each header is verified by the hash chain we already have.
+
+use util::*;
+use rlp::*;
+use network::{NetworkError, PeerId};
+use ethcore::header::{BlockNumber, Header as BlockHeader};
+
+/// What to request next from a peer.
+#[derive(Debug, PartialEq)]
+pub enum AncientRequest {
+	/// Request headers in reverse starting with the given hash.
+	Headers(H256, usize),
+	/// Request bodies for the given hashes.
+	Bodies(Vec<H256>),
+	/// Request receipts for the given hashes.
+	Receipts(Vec<H256>),
+}
+
+/// Ancient block download state.
+#[derive(Default)]
+pub struct AncientBlocks {
+	/// Hash and number of the next block needed: the parent of the oldest block we have.
+	next: Option<(H256, BlockNumber)>,
+	/// Verified headers waiting for bodies, highest first.
+	headers: VecDeque<(H256, Bytes)>,
+	/// Complete blocks waiting for receipts along with the peer which sent the body, highest first.
+	blocks: Vec<(H256, Bytes, PeerId)>,
+}
+
+impl AncientBlocks {
+	/// Create a new instance.
+	pub fn new() -> AncientBlocks {
+		AncientBlocks::default()
+	}
+
+	/// Reset the download to start with the given block, dropping all downloaded data.
+	/// `None` means there's nothing to download.
+	pub fn reset_to(&mut self, next: Option<(H256, BlockNumber)>) {
+		self.next = next;
+		self.headers.clear();
+		self.blocks.clear();
+	}
+
+	/// Hash and number of the next block to be imported, if any.
+	pub fn next(&self) -> Option<(H256, BlockNumber)> {
+		self.next
+	}
+
+	/// Whether there's downloaded data which hasn't been imported yet.
+	pub fn has_pending(&self) -> bool {
+		!self.headers.is_empty() || !self.blocks.is_empty()
+	}
+
+	/// Get the next request to make, if the download isn't complete.
+	pub fn needed(&self, max_headers: usize, max_bodies: usize) -> Option<AncientRequest> {
+		let (next_hash, next_number) = match self.next {
+			Some(next) => next,
+			None => return None,
+		};
+
+		if !self.blocks.is_empty() {
+			Some(AncientRequest::Receipts(self.blocks.iter().map(|&(ref h, _, _)| h.clone()).collect()))
+		} else if !self.headers.is_empty() {
+			Some(AncientRequest::Bodies(self.headers.iter().take(max_bodies).map(|&(ref h, _)| h.clone()).collect()))
+		} else {
+			// genesis is always there, so don't ask for it.
+			Some(AncientRequest::Headers(next_hash, min(max_headers as BlockNumber, next_number) as usize))
+		}
+	}
+
+	/// Insert headers received in reverse order. Headers must form a hash chain
+	/// starting with the next needed block. Returns the number of headers inserted.
+	pub fn insert_headers(&mut self, headers: &UntrustedRlp) -> Result<usize, NetworkError> {
+		let (mut expected, mut number) = match self.next {
+			Some(next) if self.headers.is_empty() && self.blocks.is_empty() => next,
+			_ => return Ok(0),
+		};
+
+		let mut inserted = 0;
+		for item in headers.iter() {
+			let header: BlockHeader = try!(item.as_val());
+			if header.hash() != expected || header.number() != number {
+				trace!(target: "sync", "Ancient header mismatch: expected #{} ({}), got #{} ({})", number, expected, header.number(), header.hash());
+				return Err(NetworkError::BadProtocol);
+			}
+
+			self.headers.push_back((expected, item.as_raw().to_vec()));
+			inserted += 1;
+
+			if number <= 1 {
+				break;
+			}
+			expected = header.parent_hash().clone();
+			number -= 1;
+		}
+
+		Ok(inserted)
+	}
+
+	/// Insert bodies sent by `peer` for the downloaded headers, in the order they were requested.
+	/// Returns the number of bodies inserted.
+	pub fn insert_bodies(&mut self, bodies: &UntrustedRlp, peer: PeerId) -> Result<usize, NetworkError> {
+		let mut inserted = 0;
+		for body in bodies.iter() {
+			let (hash, header_bytes) = match self.headers.pop_front() {
+				Some(h) => h,
+				None => break,
+			};
+
+			let header: BlockHeader = try!(UntrustedRlp::new(&header_bytes).as_val());
+			let tx = try!(body.at(0));
+			let tx_root = ordered_trie_root(tx.iter().map(|r| r.as_raw().to_vec()).collect());
+			let uncles = try!(body.at(1));
+			if &tx_root != header.transactions_root() || &uncles.as_raw().sha3() != header.uncles_hash() {
+				trace!(target: "sync", "Ancient body mismatch for {}", hash);
+				self.headers.push_front((hash, header_bytes));
+				return Err(NetworkError::BadProtocol);
+			}
+
+			let mut block = RlpStream::new_list(3);
+			block.append_raw(&header_bytes, 1);
+			block.append_raw(tx.as_raw(), 1);
+			block.append_raw(uncles.as_raw(), 1);
+			self.blocks.push((hash, block.out(), peer));
+			inserted += 1;
+		}
+
+		Ok(inserted)
+	}
+
+	/// Take blocks for which receipts were received, in the order they need to be
+	/// imported, along with the peer which sent the body. Receipts are verified on import.
+	/// Blocks left without receipts will be requested again.
+	pub fn drain(&mut self, receipts: &UntrustedRlp) -> Result<Vec<(Bytes, Bytes, PeerId)>, NetworkError> {
+		let count = min(receipts.item_count(), self.blocks.len());
+		let mut drained = Vec::with_capacity(count);
+		for (i, (_, block, peer)) in self.blocks.drain(..count).enumerate() {
+			drained.push((block, try!(receipts.at(i)).as_raw().to_vec(), peer));
+		}
+
+		Ok(drained)
+	}
+
+	/// Note that a block was imported, moving on to its parent.
+	pub fn imported(&mut self, header: &BlockHeader) {
+		self.next = match header.number() {
+			0 | 1 => None,
+			n => Some((header.parent_hash().clone(), n - 1)),
+		};
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{AncientBlocks, AncientRequest};
+	use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockID, BlockChainClient};
+	use ethcore::header::Header as BlockHeader;
+	use util::*;
+	use rlp::*;
+
+	fn headers_rlp(client: &TestBlockChainClient, from: u64, count: u64) -> Bytes {
+		let mut rlp = RlpStream::new_list(count as usize);
+		for n in (from - count + 1 .. from + 1).rev() {
+			rlp.append_raw(&client.block_header(BlockID::Number(n)).unwrap(), 1);
+		}
+		rlp.out()
+	}
+
+	fn bodies_rlp(client: &TestBlockChainClient, hashes: &[H256]) -> Bytes {
+		let mut rlp = RlpStream::new_list(hashes.len());
+		for h in hashes {
+			rlp.append_raw(&client.block_body(BlockID::Hash(h.clone())).unwrap(), 1);
+		}
+		rlp.out()
+	}
+
+	fn receipts_rlp(count: usize) -> Bytes {
+		let mut rlp = RlpStream::new_list(count);
+		for _ in 0..count {
+			rlp.append_raw(&::rlp::EMPTY_LIST_RLP, 1);
+		}
+		rlp.out()
+	}
+
+	#[test]
+	fn downloads_in_reverse() {
+		let client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Nothing);
+		let next = (client.block_hash(BlockID::Number(50)).unwrap(), 50);
+
+		let mut ancient = AncientBlocks::new();
+		assert_eq!(ancient.needed(10, 10), None);
+
+		ancient.reset_to(Some(next));
+		assert_eq!(ancient.needed(10, 10), Some(AncientRequest::Headers(next.0, 10)));
+
+		let headers = headers_rlp(&client, 50, 10);
+		assert_eq!(ancient.insert_headers(&UntrustedRlp::new(&headers)).unwrap(), 10);
+
+		let hashes = match ancient.needed(10, 5) {
+			Some(AncientRequest::Bodies(hashes)) => hashes,
+			other => panic!("Unexpected request {:?}", other),
+		};
+		assert_eq!(hashes.len(), 5);
+		assert_eq!(hashes[0], next.0);
+
+		let bodies = bodies_rlp(&client, &hashes);
+		assert_eq!(ancient.insert_bodies(&UntrustedRlp::new(&bodies), 0).unwrap(), 5);
+		assert_eq!(ancient.needed(10, 5), Some(AncientRequest::Receipts(hashes.clone())));
+
+		let blocks = ancient.drain(&UntrustedRlp::new(&receipts_rlp(5))).unwrap();
+		assert_eq!(blocks.len(), 5);
+		for (block, _, peer) in blocks {
+			assert_eq!(peer, 0);
+			let header: BlockHeader = Rlp::new(&block).val_at(0);
+			ancient.imported(&header);
+		}
+
+		assert_eq!(ancient.next(), Some((client.block_hash(BlockID::Number(45)).unwrap(), 45)));
+		assert!(ancient.has_pending());
+	}
+
+	#[test]
+	fn rejects_headers_off_the_hash_chain() {
+		let client = TestBlockChainClient::new();
+		client.add_blocks(100, EachBlockWith::Nothing);
+
+		let mut ancient = AncientBlocks::new();
+		ancient.reset_to(Some((client.block_hash(BlockID::Number(50)).unwrap(), 50)));
+
+		let headers = headers_rlp(&client, 60, 10);
+		assert!(ancient.insert_headers(&UntrustedRlp::new(&headers)).is_err());
+	}
+
+	#[test]
+	fn stops_at_genesis() {
+		let client = TestBlockChainClient::new();
+		client.add_blocks(10, EachBlockWith::Nothing);
+
+		let mut ancient = AncientBlocks::new();
+		ancient.reset_to(Some((client.block_hash(BlockID::Number(3)).unwrap(), 3)));
+		assert_eq!(ancient.needed(128, 128), Some(AncientRequest::Headers(client.block_hash(BlockID::Number(3)).unwrap(), 3)));
+
+		let headers = headers_rlp(&client, 3, 3);
+		assert_eq!(ancient.insert_headers(&UntrustedRlp::new(&headers)).unwrap(), 3);
+	}
+}
@@ -15,8 +15,8 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
-use network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId,
-	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError};
+use network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId, ProtocolId,
+	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, NetworkError, IpFilter};
 use util::{U256, H256};
 use io::{TimerToken};
 use ethcore::client::{BlockChainClient, ChainNotify};
@@ -30,16 +30,33 @@ use parking_lot::RwLock;
 
 /// Ethereum sync protocol
 pub const ETH_PROTOCOL: &'static str = "eth";
+/// Number of packet IDs used by the ethereum sync protocol
+pub const ETH_PACKET_COUNT: u8 = 0x11;
+
+/// Block which peers are required to have in their chain to be synced with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForkCheckpoint {
+	/// Block number
+	pub number: BlockNumber,
+	/// Expected block hash
+	pub hash: H256,
+}
+
+binary_fixed_size!(ForkCheckpoint);
 
 /// Sync configuration
-#[derive(Debug, Clone, Copy)]
+#[derive(Binary, Debug, Clone)]
 pub struct SyncConfig {
 	/// Max blocks to download ahead
 	pub max_download_ahead_blocks: usize,
 	/// Network ID
 	pub network_id: U256,
-	/// Fork block to check
-	pub fork_block: Option<(BlockNumber, H256)>,
+	/// Fork blocks to check, ordered by number
+	pub fork_checkpoints: Vec<ForkCheckpoint>,
+	/// Download blocks missing before the first block of a chain restored from a snapshot
+	pub download_old_blocks: bool,
+	/// Fast sync: import blocks without execution up to a recent pivot block and download its state
+	pub fast_sync: bool,
 }
 
 impl Default for SyncConfig {
@@ -47,12 +64,13 @@ impl Default for SyncConfig {
 		SyncConfig {
 			max_download_ahead_blocks: 20000,
 			network_id: U256::from(1),
-			fork_block: None,
+			fork_checkpoints: Vec::new(),
+			download_old_blocks: true,
+			fast_sync: false,
 		}
 	}
 }
 
-binary_fixed_size!(SyncConfig);
 binary_fixed_size!(SyncStatus);
 
 /// Current sync status
@@ -61,22 +79,38 @@ pub trait SyncProvider: Send + Sync {
 	fn status(&self) -> SyncStatus;
 }
 
+/// Additional subprotocol to run on the same network service as the ethereum protocol
+#[derive(Clone)]
+pub struct AttachedProtocol {
+	/// Protocol handler
+	pub handler: Arc<NetworkProtocolHandler + Send + Sync>,
+	/// Protocol name
+	pub protocol_id: ProtocolId,
+	/// Number of packet IDs the protocol uses
+	pub packet_count: u8,
+	/// Supported protocol versions
+	pub versions: &'static [u8],
+}
+
 /// Ethereum network protocol handler
 pub struct EthSync {
 	/// Network service
 	network: NetworkService,
 	/// Protocol handler
 	handler: Arc<SyncProtocolHandler>,
+	/// Other protocols registered with the network service
+	attached_protos: Vec<AttachedProtocol>,
 }
 
 impl EthSync {
 	/// Creates and register protocol with the network service
-	pub fn new(config: SyncConfig, chain: Arc<BlockChainClient>, network_config: NetworkConfiguration) -> Result<Arc<EthSync>, NetworkError> {
+	pub fn new(config: SyncConfig, chain: Arc<BlockChainClient>, network_config: NetworkConfiguration, attached_protos: Vec<AttachedProtocol>) -> Result<Arc<EthSync>, NetworkError> {
 		let chain_sync = ChainSync::new(config, &*chain);
 		let service = try!(NetworkService::new(try!(network_config.into_basic())));
 		let sync = Arc::new(EthSync{
 			network: service,
 			handler: Arc::new(SyncProtocolHandler { sync: RwLock::new(chain_sync), chain: chain }),
+			attached_protos: attached_protos,
 		});
 
 		Ok(sync)
@@ -146,8 +180,12 @@ impl ChainNotify for EthSync {
 
 	fn start(&self) {
 		self.network.start().unwrap_or_else(|e| warn!("Error starting network: {:?}", e));
-		self.network.register_protocol(self.handler.clone(), ETH_PROTOCOL, &[62u8, 63u8])
+		self.network.register_protocol(self.handler.clone(), ETH_PROTOCOL, ETH_PACKET_COUNT, &[62u8, 63u8])
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
+		for proto in &self.attached_protos {
+			self.network.register_protocol(proto.handler.clone(), proto.protocol_id, proto.packet_count, proto.versions)
+				.unwrap_or_else(|e| warn!("Error registering {} protocol: {:?}", proto.protocol_id, e));
+		}
 	}
 
 	fn stop(&self) {
@@ -174,6 +212,17 @@ pub trait ManageNetwork : Send + Sync {
 	fn stop_network(&self);
 	/// Query the current configuration of the network
 	fn network_config(&self) -> NetworkConfiguration;
+	/// Reputation of all nodes that have one
+	fn peer_reputations(&self) -> Vec<PeerReputation>;
+	/// Set node reputation. Takes either a node id or an enode URL.
+	fn set_peer_reputation(&self, node: String, score: i32) -> Result<(), String>;
+	/// Set the filter for addresses of non-reserved peers. Mode is one of `all`, `public`, `private` or `none`;
+	/// networks are in CIDR notation. Disconnects peers that are no longer allowed.
+	fn set_ip_filter(&self, mode: String, allow: Vec<String>, deny: Vec<String>) -> Result<(), String>;
+	/// Total traffic and traffic of each connected peer
+	fn network_traffic(&self) -> NetworkTraffic;
+	/// Number of inbound and outbound peers and their limits
+	fn peer_counts(&self) -> PeerCounts;
 }
 
 
@@ -211,6 +260,127 @@ impl ManageNetwork for EthSync {
 	fn network_config(&self) -> NetworkConfiguration {
 		NetworkConfiguration::from(self.network.config().clone())
 	}
+
+	fn peer_reputations(&self) -> Vec<PeerReputation> {
+		self.network.node_reputations().into_iter().map(|(id, r)| PeerReputation {
+			id: id.hex(),
+			score: r.score,
+			banned_until: r.banned_until,
+		}).collect()
+	}
+
+	fn set_peer_reputation(&self, node: String, score: i32) -> Result<(), String> {
+		self.network.set_node_reputation(&node, score).map_err(|e| format!("{:?}", e))
+	}
+
+	fn set_ip_filter(&self, mode: String, allow: Vec<String>, deny: Vec<String>) -> Result<(), String> {
+		let filter = try!(IpFilter::parse(&mode, &allow, &deny).map_err(|e| format!("{}", e)));
+		self.network.set_ip_filter(filter);
+		Ok(())
+	}
+
+	fn peer_counts(&self) -> PeerCounts {
+		let (inbound, outbound) = self.network.session_counts();
+		let config = self.network.config();
+		PeerCounts {
+			inbound: inbound as u32,
+			outbound: outbound as u32,
+			max_inbound: config.max_inbound(),
+			max_outbound: config.max_outbound(),
+			reserved_outbound: config.reserved_outbound(),
+		}
+	}
+
+	fn network_traffic(&self) -> NetworkTraffic {
+		let stats = self.network.stats();
+		NetworkTraffic {
+			sent_bytes: stats.send() as u64,
+			recv_bytes: stats.recv() as u64,
+			peers: self.network.peers_traffic().into_iter().map(|p| PeerTraffic {
+				id: p.id.hex(),
+				remote_address: p.remote_address,
+				sent_bytes: p.sent_bytes,
+				recv_bytes: p.recv_bytes,
+				packets: p.packets.into_iter().map(|t| PacketTraffic {
+					protocol: t.protocol.to_owned(),
+					packet_id: t.packet_id,
+					sent_packets: t.sent_packets,
+					sent_bytes: t.sent_bytes,
+					recv_packets: t.recv_packets,
+					recv_bytes: t.recv_bytes,
+				}).collect(),
+			}).collect(),
+		}
+	}
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Reputation of a network node
+pub struct PeerReputation {
+	/// Node id
+	pub id: String,
+	/// Reputation score
+	pub score: i32,
+	/// Unix timestamp in seconds when the ban expires, if the node is banned
+	pub banned_until: Option<i64>,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Number of connected peers by direction
+pub struct PeerCounts {
+	/// Peers connected to us
+	pub inbound: u32,
+	/// Peers we connected to
+	pub outbound: u32,
+	/// Limit of inbound peers
+	pub max_inbound: u32,
+	/// Limit of outbound peers
+	pub max_outbound: u32,
+	/// Slots inbound peers can't take
+	pub reserved_outbound: u32,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Traffic of a single packet type within a peer session
+pub struct PacketTraffic {
+	/// Protocol id, `p2p` for the base protocol
+	pub protocol: String,
+	/// Packet id within the protocol
+	pub packet_id: u8,
+	/// Number of packets sent
+	pub sent_packets: u64,
+	/// Packet bytes sent, before compression
+	pub sent_bytes: u64,
+	/// Number of packets received
+	pub recv_packets: u64,
+	/// Packet bytes received, after decompression
+	pub recv_bytes: u64,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Traffic of a connected peer
+pub struct PeerTraffic {
+	/// Node id
+	pub id: String,
+	/// Remote address
+	pub remote_address: String,
+	/// Bytes sent, including framing and encryption
+	pub sent_bytes: u64,
+	/// Bytes received, including framing and encryption
+	pub recv_bytes: u64,
+	/// Per protocol and packet id traffic
+	pub packets: Vec<PacketTraffic>,
+}
+
+#[derive(Binary, Debug, Clone, PartialEq, Eq)]
+/// Network traffic
+pub struct NetworkTraffic {
+	/// Total bytes sent since the network was started
+	pub sent_bytes: u64,
+	/// Total bytes received since the network was started
+	pub recv_bytes: u64,
+	/// Traffic of connected peers
+	pub peers: Vec<PeerTraffic>,
 }
 
 #[derive(Binary, Debug, Clone, PartialEq, Eq)]
@@ -238,10 +408,30 @@ pub struct NetworkConfiguration {
 	pub max_peers: u32,
 	/// Min number of connected peers to maintain
 	pub min_peers: u32,
+	/// Max number of peers connected to us
+	pub max_inbound_peers: Option<u32>,
+	/// Max number of peers we connect to
+	pub max_outbound_peers: Option<u32>,
+	/// Number of slots inbound peers can't take
+	pub reserved_outbound_peers: u32,
 	/// List of reserved node addresses.
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub allow_non_reserved: bool,
+	/// Predefined set of allowed peer addresses: `all`, `public`, `private` or `none`.
+	pub allow_ips: String,
+	/// Networks in CIDR notation to allow regardless of `allow_ips`.
+	pub allow_ip_networks: Vec<String>,
+	/// Networks in CIDR notation to never connect to.
+	pub deny_ip_networks: Vec<String>,
+	/// Upload limit for all peers in bytes per second
+	pub max_upload_rate: Option<u64>,
+	/// Download limit for all peers in bytes per second
+	pub max_download_rate: Option<u64>,
+	/// Upload limit for a single peer in bytes per second
+	pub peer_max_upload_rate: Option<u64>,
+	/// Download limit for a single peer in bytes per second
+	pub peer_max_download_rate: Option<u64>,
 }
 
 impl NetworkConfiguration {
@@ -263,7 +453,8 @@ impl NetworkConfiguration {
 		Ok(())
 	}
 
-	pub fn into_basic(self) -> Result<BasicNetworkConfiguration, AddrParseError> {
+	pub fn into_basic(self) -> Result<BasicNetworkConfiguration, NetworkError> {
+		let ip_filter = try!(IpFilter::parse(&self.allow_ips, &self.allow_ip_networks, &self.deny_ip_networks));
 
 		Ok(BasicNetworkConfiguration {
 			config_path: self.config_path,
@@ -277,8 +468,16 @@ impl NetworkConfiguration {
 			use_secret: self.use_secret,
 			max_peers: self.max_peers,
 			min_peers: self.min_peers,
+			max_inbound_peers: self.max_inbound_peers,
+			max_outbound_peers: self.max_outbound_peers,
+			reserved_outbound_peers: self.reserved_outbound_peers,
 			reserved_nodes: self.reserved_nodes,
 			non_reserved_mode: if self.allow_non_reserved { NonReservedPeerMode::Accept } else { NonReservedPeerMode::Deny },
+			ip_filter: ip_filter,
+			max_upload_rate: self.max_upload_rate,
+			max_download_rate: self.max_download_rate,
+			peer_max_upload_rate: self.peer_max_upload_rate,
+			peer_max_download_rate: self.peer_max_download_rate,
 		})
 	}
 }
@@ -297,8 +496,18 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			use_secret: other.use_secret,
 			max_peers: other.max_peers,
 			min_peers: other.min_peers,
+			max_inbound_peers: other.max_inbound_peers,
+			max_outbound_peers: other.max_outbound_peers,
+			reserved_outbound_peers: other.reserved_outbound_peers,
 			reserved_nodes: other.reserved_nodes,
 			allow_non_reserved: match other.non_reserved_mode { NonReservedPeerMode::Accept => true, _ => false } ,
+			allow_ips: other.ip_filter.predefined.as_str().to_owned(),
+			allow_ip_networks: other.ip_filter.custom_allow.iter().map(|n| format!("{}", n)).collect(),
+			deny_ip_networks: other.ip_filter.custom_block.iter().map(|n| format!("{}", n)).collect(),
+			max_upload_rate: other.max_upload_rate,
+			max_download_rate: other.max_download_rate,
+			peer_max_upload_rate: other.peer_max_upload_rate,
+			peer_max_download_rate: other.peer_max_download_rate,
 		}
 	}
 }
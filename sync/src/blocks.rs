@@ -18,13 +18,23 @@ use util::*;
 use rlp::*;
 use network::NetworkError;
 use ethcore::header::{ Header as BlockHeader};
+use ethcore::views::HeaderView;
 
 known_heap_size!(0, HeaderId, SyncBlock);
 
-/// Block data with optional body.
+/// Block data with optional body and receipts.
 struct SyncBlock {
 	header: Bytes,
 	body: Option<Bytes>,
+	receipts: Option<Bytes>,
+}
+
+/// Block with its receipts, if these were downloaded.
+pub struct BlockAndReceipts {
+	/// Block data.
+	pub block: Bytes,
+	/// Block receipts RLP list.
+	pub receipts: Option<Bytes>,
 }
 
 /// Used to identify header by transactions and uncles hashes
@@ -53,11 +63,17 @@ pub struct BlockCollection {
 	downloading_headers: HashSet<H256>,
 	/// Set of block bodies being downloaded identified by block hash.
 	downloading_bodies: HashSet<H256>,
+	/// Used to map receipts to headers, by receipts root.
+	receipt_ids: HashMap<H256, Vec<H256>>,
+	/// Set of block receipts being downloaded identified by receipts root.
+	downloading_receipts: HashSet<H256>,
+	/// Download receipts along with the blocks.
+	need_receipts: bool,
 }
 
 impl BlockCollection {
 	/// Create a new instance.
-	pub fn new() -> BlockCollection {
+	pub fn new(download_receipts: bool) -> BlockCollection {
 		BlockCollection {
 			blocks: HashMap::new(),
 			header_ids: HashMap::new(),
@@ -66,6 +82,9 @@ impl BlockCollection {
 			head: None,
 			downloading_headers: HashSet::new(),
 			downloading_bodies: HashSet::new(),
+			receipt_ids: HashMap::new(),
+			downloading_receipts: HashSet::new(),
+			need_receipts: download_receipts,
 		}
 	}
 
@@ -78,6 +97,8 @@ impl BlockCollection {
 		self.head = None;
 		self.downloading_headers.clear();
 		self.downloading_bodies.clear();
+		self.receipt_ids.clear();
+		self.downloading_receipts.clear();
 	}
 
 	/// Reset collection for a new sync round with given subchain block hashes.
@@ -110,6 +131,48 @@ impl BlockCollection {
 		inserted
 	}
 
+	/// Insert a collection of block receipts for previously downloaded headers.
+	pub fn insert_receipts(&mut self, receipts: Vec<Bytes>) -> usize {
+		if !self.need_receipts {
+			return 0;
+		}
+		let mut inserted = 0;
+		for r in receipts.into_iter() {
+			if let Err(e) =  self.insert_receipt(r) {
+				trace!(target: "sync", "Ignored invalid receipt: {:?}", e);
+			}
+			else {
+				inserted += 1;
+			}
+		}
+		inserted
+	}
+
+	/// Returns a set of block hashes that require a receipts download. The returned set is marked as being downloaded.
+	pub fn needed_receipts(&mut self, count: usize, _ignore_downloading: bool) -> Vec<H256> {
+		if self.head.is_none() || !self.need_receipts {
+			return Vec::new();
+		}
+		let mut needed_receipts: Vec<H256> = Vec::new();
+		let mut head = self.head;
+		while head.is_some() && needed_receipts.len() < count {
+			head = self.parents.get(&head.unwrap()).cloned();
+			if let Some(head) = head {
+				match self.blocks.get(&head) {
+					Some(block) if block.receipts.is_none() => {
+						let receipts_root = HeaderView::new(&block.header).receipts_root();
+						if !self.downloading_receipts.contains(&receipts_root) {
+							self.downloading_receipts.insert(receipts_root);
+							needed_receipts.push(head.clone());
+						}
+					}
+					_ => (),
+				}
+			}
+		}
+		needed_receipts
+	}
+
 	/// Returns a set of block hashes that require a body download. The returned set is marked as being downloaded.
 	pub fn needed_bodies(&mut self, count: usize, _ignore_downloading: bool) -> Vec<H256> {
 		if self.head.is_none() {
@@ -167,8 +230,15 @@ impl BlockCollection {
 		self.downloading_bodies.remove(hash);
 	}
 
+	/// Unmark block receipts as being downloaded.
+	pub fn clear_receipt_download(&mut self, hash: &H256) {
+		if let Some(block) = self.blocks.get(hash) {
+			self.downloading_receipts.remove(&HeaderView::new(&block.header).receipts_root());
+		}
+	}
+
 	/// Get a valid chain of blocks ordered in descending order and ready for importing into blockchain.
-	pub fn drain(&mut self) -> Vec<Bytes> {
+	pub fn drain(&mut self) -> Vec<BlockAndReceipts> {
 		if self.blocks.is_empty() || self.head.is_none() {
 			return Vec::new();
 		}
@@ -182,7 +252,7 @@ impl BlockCollection {
 				head = self.parents.get(&head.unwrap()).cloned();
 				if let Some(head) = head {
 					match self.blocks.get(&head) {
-						Some(block) if block.body.is_some() => {
+						Some(block) if block.body.is_some() && (!self.need_receipts || block.receipts.is_some()) => {
 							blocks.push(block);
 							hashes.push(head);
 							self.head = Some(head);
@@ -198,7 +268,10 @@ impl BlockCollection {
 				let body = Rlp::new(block.body.as_ref().unwrap()); // incomplete blocks are filtered out in the loop above
 				block_rlp.append_raw(body.at(0).as_raw(), 1);
 				block_rlp.append_raw(body.at(1).as_raw(), 1);
-				drained.push(block_rlp.out());
+				drained.push(BlockAndReceipts {
+					block: block_rlp.out(),
+					receipts: block.receipts.clone(),
+				});
 			}
 		}
 		for h in hashes {
@@ -262,6 +335,35 @@ impl BlockCollection {
 		}
 	}
 
+	fn insert_receipt(&mut self, r: Bytes) -> Result<(), NetworkError> {
+		let receipt_root = {
+			let receipts = UntrustedRlp::new(&r);
+			ordered_trie_root(receipts.iter().map(|r| r.as_raw().to_vec()).collect()) //TODO: get rid of vectors here
+		};
+		self.downloading_receipts.remove(&receipt_root);
+		match self.receipt_ids.remove(&receipt_root) {
+			Some(hashes) => {
+				for h in hashes {
+					match self.blocks.get_mut(&h) {
+						Some(ref mut block) => {
+							trace!(target: "sync", "Got receipt {}", h);
+							block.receipts = Some(r.clone());
+						},
+						None => {
+							warn!("Got receipt with no header {}", h);
+							return Err(NetworkError::BadProtocol)
+						}
+					}
+				}
+				Ok(())
+			}
+			None => {
+				trace!(target: "sync", "Ignored unknown/stale block receipt {:?}", receipt_root);
+				Err(NetworkError::BadProtocol)
+			}
+		}
+	}
+
 	fn insert_header(&mut self, header: Bytes) -> Result<H256, UtilError> {
 		let info: BlockHeader = try!(UntrustedRlp::new(&header).as_val());
 		let hash = info.hash();
@@ -279,6 +381,7 @@ impl BlockCollection {
 		let mut block = SyncBlock {
 			header: header,
 			body: None,
+			receipts: None,
 		};
 		let header_id = HeaderId {
 			transactions_root: info.transactions_root().clone(),
@@ -294,6 +397,14 @@ impl BlockCollection {
 		else {
 			self.header_ids.insert(header_id, hash.clone());
 		}
+		if self.need_receipts {
+			let receipt_root = info.receipts_root().clone();
+			if receipt_root == sha3::SHA3_NULL_RLP {
+				block.receipts = Some(::rlp::EMPTY_LIST_RLP.to_vec());
+			} else {
+				self.receipt_ids.entry(receipt_root).or_insert_with(Vec::new).push(hash.clone());
+			}
+		}
 
 		self.parents.insert(info.parent_hash().clone(), hash.clone());
 		self.blocks.insert(hash.clone(), block);
@@ -340,6 +451,10 @@ mod test {
 	use util::*;
 	use rlp::*;
 
+	fn drain(bc: &mut BlockCollection) -> Vec<Bytes> {
+		bc.drain().into_iter().map(|b| b.block).collect()
+	}
+
 	fn is_empty(bc: &BlockCollection) -> bool {
 		bc.heads.is_empty() &&
 		bc.blocks.is_empty() &&
@@ -347,12 +462,14 @@ mod test {
 		bc.header_ids.is_empty() &&
 		bc.head.is_none() &&
 		bc.downloading_headers.is_empty() &&
-		bc.downloading_bodies.is_empty()
+		bc.downloading_bodies.is_empty() &&
+		bc.receipt_ids.is_empty() &&
+		bc.downloading_receipts.is_empty()
 	}
 
 	#[test]
 	fn create_clear() {
-		let mut bc = BlockCollection::new();
+		let mut bc = BlockCollection::new(false);
 		assert!(is_empty(&bc));
 		let client = TestBlockChainClient::new();
 		client.add_blocks(100, EachBlockWith::Nothing);
@@ -365,7 +482,7 @@ mod test {
 
 	#[test]
 	fn insert_headers() {
-		let mut bc = BlockCollection::new();
+		let mut bc = BlockCollection::new(false);
 		assert!(is_empty(&bc));
 		let client = TestBlockChainClient::new();
 		let nblocks = 200;
@@ -397,7 +514,7 @@ mod test {
 		assert!(!bc.is_downloading(&hashes[0]));
 		assert!(bc.contains(&hashes[0]));
 
-		assert_eq!(&bc.drain()[..], &blocks[0..6]);
+		assert_eq!(&drain(&mut bc)[..], &blocks[0..6]);
 		assert!(!bc.contains(&hashes[0]));
 		assert_eq!(hashes[5], bc.head.unwrap());
 
@@ -408,7 +525,7 @@ mod test {
 		bc.insert_headers(headers[10..16].to_vec());
 		assert!(bc.drain().is_empty());
 		bc.insert_headers(headers[5..10].to_vec());
-		assert_eq!(&bc.drain()[..], &blocks[6..16]);
+		assert_eq!(&drain(&mut bc)[..], &blocks[6..16]);
 		assert_eq!(hashes[15], bc.heads[0]);
 
 		bc.insert_headers(headers[15..].to_vec());
@@ -418,7 +535,7 @@ mod test {
 
 	#[test]
 	fn insert_headers_with_gap() {
-		let mut bc = BlockCollection::new();
+		let mut bc = BlockCollection::new(false);
 		assert!(is_empty(&bc));
 		let client = TestBlockChainClient::new();
 		let nblocks = 200;
@@ -440,7 +557,7 @@ mod test {
 
 	#[test]
 	fn insert_headers_no_gap() {
-		let mut bc = BlockCollection::new();
+		let mut bc = BlockCollection::new(false);
 		assert!(is_empty(&bc));
 		let client = TestBlockChainClient::new();
 		let nblocks = 200;
@@ -456,5 +573,21 @@ mod test {
 		bc.insert_headers(headers[0..1].to_vec());
 		assert_eq!(bc.drain().len(), 2);
 	}
-}
 
+	#[test]
+	fn drains_with_empty_receipts() {
+		let mut bc = BlockCollection::new(true);
+		let client = TestBlockChainClient::new();
+		client.add_blocks(10, EachBlockWith::Nothing);
+		let blocks: Vec<_> = (0 .. 10).map(|i| (&client as &BlockChainClient).block(BlockID::Number(i as BlockNumber)).unwrap()).collect();
+		let headers: Vec<_> = blocks.iter().map(|b| Rlp::new(b).at(0).as_raw().to_vec()).collect();
+		let hashes: Vec<_> = headers.iter().map(|h| HeaderView::new(h).sha3()).collect();
+		bc.reset_to(vec![hashes[0].clone()]);
+
+		bc.insert_headers(headers.clone());
+		assert!(bc.needed_receipts(10, false).is_empty());
+		let drained = bc.drain();
+		assert_eq!(drained.len(), 10);
+		assert!(drained.iter().all(|b| b.receipts.as_ref().map_or(false, |r| &r[..] == &::rlp::EMPTY_LIST_RLP[..])));
+	}
+}
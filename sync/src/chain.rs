@@ -100,6 +100,8 @@ use sync_io::SyncIo;
 use time;
 use super::SyncConfig;
 use blocks::BlockCollection;
+use ancient::{AncientBlocks, AncientRequest};
+use state::StateDownload;
 use rand::{thread_rng, Rng};
 
 known_heap_size!(0, PeerInfo);
@@ -114,6 +116,8 @@ const MAX_RECEIPTS_TO_SEND: usize = 1024;
 const MAX_RECEIPTS_HEADERS_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_REQUEST: usize = 128;
 const MAX_BODIES_TO_REQUEST: usize = 128;
+const MAX_RECEIPTS_TO_REQUEST: usize = 128;
+const MAX_NODE_DATA_TO_REQUEST: usize = 384;
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
 const MAX_PEER_LAG_PROPAGATION: BlockNumber = 20;
@@ -122,6 +126,13 @@ const MAX_ROUND_PARENTS: usize = 32;
 const MAX_NEW_HASHES: usize = 64;
 const MAX_TX_TO_IMPORT: usize = 512;
 const MAX_NEW_BLOCK_AGE: BlockNumber = 20;
+// Distance of the fast sync pivot block from the best block of a peer. Peers only keep
+// the state of recent blocks, so the pivot must not be too old.
+const FAST_SYNC_PIVOT_DISTANCE: BlockNumber = 32;
+// Number of peers which must agree on the fast sync pivot block.
+const FAST_SYNC_PIVOT_PEERS: usize = 3;
+// Fall back to full sync if the pivot block can't be agreed on within this time.
+const FAST_SYNC_PIVOT_TIMEOUT_SEC: f64 = 60f64;
 
 const STATUS_PACKET: u8 = 0x00;
 const NEW_BLOCK_HASHES_PACKET: u8 = 0x01;
@@ -139,6 +150,8 @@ const RECEIPTS_PACKET: u8 = 0x10;
 
 const HEADERS_TIMEOUT_SEC: f64 = 15f64;
 const BODIES_TIMEOUT_SEC: f64 = 5f64;
+const RECEIPTS_TIMEOUT_SEC: f64 = 10f64;
+const NODE_DATA_TIMEOUT_SEC: f64 = 10f64;
 const FORK_HEADER_TIMEOUT_SEC: f64 = 3f64;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -154,6 +167,8 @@ pub enum SyncState {
 	Blocks,
 	/// Downloading blocks learned from `NewHashes` packet
 	NewBlocks,
+	/// Downloading the state of the fast sync pivot block
+	State,
 }
 
 /// Syncing status and statistics
@@ -181,6 +196,13 @@ pub struct SyncStatus {
 	pub num_active_peers: usize,
 	/// Heap memory used in bytes
 	pub mem_used: usize,
+	/// Number of ancient blocks still missing before the first block of a chain
+	/// restored from a snapshot, if any.
+	pub ancient_blocks_left: Option<BlockNumber>,
+	/// Number of state nodes downloaded by fast sync
+	pub state_nodes_downloaded: usize,
+	/// Number of state nodes being downloaded or waiting for their children
+	pub state_nodes_pending: usize,
 }
 
 impl SyncStatus {
@@ -206,7 +228,24 @@ enum PeerAsking {
 	ForkHeader,
 	BlockHeaders,
 	BlockBodies,
+	BlockReceipts,
 	Heads,
+	BestHeader,
+	PivotHeader,
+	NodeData,
+	AncientHeaders,
+	AncientBodies,
+	AncientReceipts,
+}
+
+impl PeerAsking {
+	/// Whether this is a request for ancient blocks, which run independently of the sync rounds.
+	fn is_ancient(&self) -> bool {
+		match *self {
+			PeerAsking::AncientHeaders | PeerAsking::AncientBodies | PeerAsking::AncientReceipts => true,
+			_ => false,
+		}
+	}
 }
 
 #[derive(Clone, Eq, PartialEq)]
@@ -248,6 +287,10 @@ struct PeerInfo {
 	expired: bool,
 	/// Peer fork confirmation status
 	confirmation: ForkConfirmation,
+	/// Index of the fork checkpoint being confirmed
+	fork_checkpoint: usize,
+	/// Peer doesn't serve the ancient blocks we're missing
+	no_ancient_blocks: bool,
 }
 
 impl PeerInfo {
@@ -291,8 +334,26 @@ pub struct ChainSync {
 	round_parents: VecDeque<(H256, H256)>,
 	/// Network ID
 	network_id: U256,
-	/// Optional fork block to check
-	fork_block: Option<(BlockNumber, H256)>,
+	/// Fork blocks to check, ordered by number
+	fork_checkpoints: Vec<(BlockNumber, H256)>,
+	/// Ancient blocks being downloaded, missing before a restored snapshot
+	ancient_blocks: AncientBlocks,
+	/// Download ancient blocks
+	download_old_blocks: bool,
+	/// Import blocks up to the pivot block without executing them and download the pivot state
+	fast_sync: bool,
+	/// Fast sync pivot block number and hash, once agreed on by enough peers
+	pivot: Option<(BlockNumber, H256)>,
+	/// Number of the pivot block being agreed on
+	pivot_candidate: Option<BlockNumber>,
+	/// Pivot candidate block hashes reported by peers
+	pivot_votes: HashMap<PeerId, H256>,
+	/// Time the search for the pivot block started
+	pivot_search_start: Option<f64>,
+	/// Fast sync pivot block with receipts, waiting for its state
+	pivot_block: Option<(Bytes, Bytes)>,
+	/// Pivot block state being downloaded
+	state_download: Option<StateDownload>,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -301,6 +362,8 @@ impl ChainSync {
 	/// Create a new instance of syncing strategy.
 	pub fn new(config: SyncConfig, chain: &BlockChainClient) -> ChainSync {
 		let chain = chain.chain_info();
+		// fast sync is only possible for an empty chain
+		let fast_sync = config.fast_sync && chain.best_block_number == 0;
 		let mut sync = ChainSync {
 			state: SyncState::ChainHead,
 			starting_block: chain.best_block_number,
@@ -309,14 +372,23 @@ impl ChainSync {
 			last_imported_hash: chain.best_block_hash,
 			peers: HashMap::new(),
 			active_peers: HashSet::new(),
-			blocks: BlockCollection::new(),
+			blocks: BlockCollection::new(fast_sync),
 			syncing_difficulty: U256::from(0u64),
 			last_sent_block_number: 0,
 			imported_this_round: None,
 			round_parents: VecDeque::new(),
 			_max_download_ahead_blocks: max(MAX_HEADERS_TO_REQUEST, config.max_download_ahead_blocks),
 			network_id: config.network_id,
-			fork_block: config.fork_block,
+			fork_checkpoints: config.fork_checkpoints.iter().map(|c| (c.number, c.hash.clone())).collect(),
+			ancient_blocks: AncientBlocks::new(),
+			download_old_blocks: config.download_old_blocks,
+			fast_sync: fast_sync,
+			pivot: None,
+			pivot_candidate: None,
+			pivot_votes: HashMap::new(),
+			pivot_search_start: None,
+			pivot_block: None,
+			state_download: None,
 		};
 		sync.reset();
 		sync
@@ -339,6 +411,9 @@ impl ChainSync {
 				self.blocks.heap_size()
 				+ self.peers.heap_size_of_children()
 				+ self.round_parents.heap_size_of_children(),
+			ancient_blocks_left: self.ancient_blocks.next().map(|(_, n)| n),
+			state_nodes_downloaded: self.state_download.as_ref().map_or(0, |s| s.written()),
+			state_nodes_pending: self.state_download.as_ref().map_or(0, |s| s.pending()),
 		}
 	}
 
@@ -355,8 +430,8 @@ impl ChainSync {
 		for (_, ref mut p) in &mut self.peers {
 			p.asking_blocks.clear();
 			p.asking_hash = None;
-			// mark any pending requests as expired
-			if p.asking != PeerAsking::Nothing && p.is_allowed() {
+			// mark any pending requests as expired. ancient block requests are independent of the round.
+			if p.asking != PeerAsking::Nothing && !p.asking.is_ancient() && p.is_allowed() {
 				p.expired = true;
 			}
 		}
@@ -411,7 +486,9 @@ impl ChainSync {
 			ask_time: 0f64,
 			last_sent_transactions: HashSet::new(),
 			expired: false,
-			confirmation: if self.fork_block.is_none() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
+			confirmation: if self.fork_checkpoints.is_empty() { ForkConfirmation::Confirmed } else { ForkConfirmation::Unconfirmed },
+			fork_checkpoint: 0,
+			no_ancient_blocks: false,
 		};
 
 		trace!(target: "sync", "New peer {} (protocol: {}, network: {:?}, difficulty: {:?}, latest:{}, genesis:{})", peer_id, peer.protocol_version, peer.network_id, peer.difficulty, peer.latest_hash, peer.genesis);
@@ -439,41 +516,71 @@ impl ChainSync {
 		self.peers.insert(peer_id.clone(), peer);
 		self.active_peers.insert(peer_id.clone());
 		debug!(target: "sync", "Connected {}:{}", peer_id, io.peer_info(peer_id));
-		if let Some((fork_block, _)) = self.fork_block {
-			self.request_headers_by_number(io, peer_id, fork_block, 1, 0, false, PeerAsking::ForkHeader);
-		} else {
+		if self.fork_checkpoints.is_empty() {
 			self.sync_peer(io, peer_id, false);
+		} else {
+			self.request_fork_header(io, peer_id);
 		}
 		Ok(())
 	}
 
+	/// Request the header of the next fork checkpoint to be confirmed by the peer.
+	fn request_fork_header(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		let index = self.peers.get(&peer_id).map_or(0, |p| p.fork_checkpoint);
+		let number = self.fork_checkpoints[index].0;
+		self.request_headers_by_number(io, peer_id, number, 1, 0, false, PeerAsking::ForkHeader);
+	}
+
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
 	/// Called by peer once it has new block headers during sync
 	fn on_peer_block_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
 		let confirmed = match self.peers.get_mut(&peer_id) {
 			Some(ref mut peer) if peer.asking == PeerAsking::ForkHeader => {
 				let item_count = r.item_count();
-				if item_count == 0 || (item_count == 1 && try!(r.at(0)).as_raw().sha3() == self.fork_block.unwrap().1) {
+				let (number, ref hash) = self.fork_checkpoints[peer.fork_checkpoint];
+				if item_count == 0 || (item_count == 1 && &try!(r.at(0)).as_raw().sha3() == hash) {
 					peer.asking = PeerAsking::Nothing;
 					if item_count == 0 {
-						trace!(target: "sync", "{}: Chain is too short to confirm the block", peer_id);
+						// later checkpoints can't be confirmed either
+						trace!(target: "sync", "{}: Chain is too short to confirm block {}", peer_id, number);
 						peer.confirmation = ForkConfirmation::TooShort;
+						Some(true)
+					} else if peer.fork_checkpoint + 1 < self.fork_checkpoints.len() {
+						trace!(target: "sync", "{}: Confirmed block {}", peer_id, number);
+						peer.fork_checkpoint += 1;
+						Some(false)
 					} else {
 						trace!(target: "sync", "{}: Confirmed peer", peer_id);
 						peer.confirmation = ForkConfirmation::Confirmed;
+						Some(true)
 					}
-					true
 				} else {
-					trace!(target: "sync", "{}: Fork mismatch", peer_id);
-					io.disconnect_peer(peer_id);
+					// peer is on a different chain; make sure it is not reconnected soon
+					trace!(target: "sync", "{}: Fork mismatch at block {}", peer_id, number);
+					io.report_peer(peer_id, PeerReport::InvalidData);
+					io.disable_peer(peer_id);
 					return Ok(());
 				}
 			},
-			_ => false,
+			_ => None,
 		};
-		if confirmed {
-			self.sync_peer(io, peer_id, false);
-			return Ok(());
+		match confirmed {
+			Some(true) => {
+				self.sync_peer(io, peer_id, false);
+				return Ok(());
+			},
+			Some(false) => {
+				self.request_fork_header(io, peer_id);
+				return Ok(());
+			},
+			None => (),
+		}
+
+		match self.peers.get(&peer_id).map(|p| p.asking.clone()) {
+			Some(PeerAsking::AncientHeaders) => return self.on_peer_ancient_headers(io, peer_id, r),
+			Some(PeerAsking::BestHeader) => return self.on_peer_best_header(io, peer_id, r),
+			Some(PeerAsking::PivotHeader) => return self.on_peer_pivot_header(io, peer_id, r),
+			_ => (),
 		}
 
 		self.clear_peer_download(peer_id);
@@ -534,6 +641,7 @@ impl ChainSync {
 				},
 				BlockStatus::Bad => {
 					warn!(target: "sync", "Bad header {} ({}) from {}: {}, state = {:?}", number, hash, peer_id, io.peer_info(peer_id), self.state);
+					io.report_peer(peer_id, PeerReport::BadBlock);
 					io.disable_peer(peer_id);
 					return Ok(());
 				},
@@ -547,6 +655,7 @@ impl ChainSync {
 		// Disable the peer for this syncing round if it gives invalid chain
 		if !valid_response {
 			trace!(target: "sync", "{} Disabled for invalid headers response", peer_id);
+			io.report_peer(peer_id, PeerReport::InvalidData);
 			io.disable_peer(peer_id);
 		}
 
@@ -554,6 +663,8 @@ impl ChainSync {
 			// Peer does not have any new subchain heads, deactivate it nd try with another
 			trace!(target: "sync", "{} Disabled for no data", peer_id);
 			io.disable_peer(peer_id);
+		} else if valid_response {
+			io.report_peer(peer_id, PeerReport::Useful);
 		}
 		match self.state {
 			SyncState::ChainHead => {
@@ -584,8 +695,73 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called by peer once it has its best block header, which is needed to choose the fast sync pivot.
+	fn on_peer_best_header(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::BestHeader);
+		trace!(target: "sync", "{} -> BlockHeaders (best, {} entries)", peer_id, r.item_count());
+		let expected_hash = self.peers.get(&peer_id).and_then(|p| p.asking_hash);
+		let header: Option<BlockHeader> = match r.item_count() {
+			1 => Some(try!(r.val_at(0))),
+			_ => None,
+		};
+		match header {
+			Some(ref header) if Some(header.hash()) == expected_hash => {
+				if header.number() > self.highest_block.unwrap_or(0) {
+					self.highest_block = Some(header.number());
+				}
+				self.peers.get_mut(&peer_id).expect("peer checked by caller; qed").latest_number = Some(header.number());
+			},
+			_ => {
+				trace!(target: "sync", "{}: Invalid best header", peer_id);
+				io.report_peer(peer_id, PeerReport::InvalidData);
+				self.deactivate_peer(io, peer_id);
+			}
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has the header of the fast sync pivot candidate block.
+	fn on_peer_pivot_header(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::PivotHeader);
+		trace!(target: "sync", "{} -> BlockHeaders (pivot, {} entries)", peer_id, r.item_count());
+		if !self.fast_sync {
+			trace!(target: "sync", "{}: Ignored pivot header, pivot no longer needed", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		let header: Option<BlockHeader> = match r.item_count() {
+			1 => Some(try!(r.val_at(0))),
+			_ => None,
+		};
+		match header {
+			Some(ref header) if Some(header.number()) == self.pivot_candidate => {
+				match self.pivot {
+					Some((_, hash)) if hash != header.hash() => {
+						debug!(target: "sync", "{}: Voted for another fast sync pivot block", peer_id);
+						self.deactivate_peer(io, peer_id);
+					},
+					Some(_) => {},
+					None => {
+						self.pivot_votes.insert(peer_id, header.hash());
+						self.count_pivot_votes(io);
+					},
+				}
+			},
+			_ => {
+				trace!(target: "sync", "{}: Invalid pivot header", peer_id);
+				self.deactivate_peer(io, peer_id);
+			}
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
 	/// Called by peer once it has new block bodies
 	fn on_peer_block_bodies(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::AncientBodies) {
+			return self.on_peer_ancient_bodies(io, peer_id, r);
+		}
 		self.clear_peer_download(peer_id);
 		self.reset_peer_asking(peer_id, PeerAsking::BlockBodies);
 		let item_count = r.item_count();
@@ -607,7 +783,10 @@ impl ChainSync {
 			}
 			if self.blocks.insert_bodies(bodies) != item_count {
 				trace!(target: "sync", "Deactivating peer for giving invalid block bodies");
+				io.report_peer(peer_id, PeerReport::InvalidData);
 				self.deactivate_peer(io, peer_id);
+			} else {
+				io.report_peer(peer_id, PeerReport::Useful);
 			}
 			self.collect_blocks(io);
 		}
@@ -636,6 +815,10 @@ impl ChainSync {
 			peer.latest_hash = header.hash();
 			peer.latest_number = Some(header.number());
 		}
+		if self.fast_sync {
+			trace!(target: "sync", "Ignored new block {:?} during fast sync", h);
+			return Ok(());
+		}
 		if self.last_imported_block > header.number() && self.last_imported_block - header.number() > MAX_NEW_BLOCK_AGE {
 			trace!(target: "sync", "Ignored ancient new block {:?}", h);
 			io.disable_peer(peer_id);
@@ -661,6 +844,7 @@ impl ChainSync {
 			},
 			Err(e) => {
 				debug!(target: "sync", "Bad new block {:?} : {:?}", h, e);
+				io.report_peer(peer_id, PeerReport::BadBlock);
 				io.disable_peer(peer_id);
 			}
 		};
@@ -690,7 +874,7 @@ impl ChainSync {
 			trace!(target: "sync", "Ignoring new hashes from unconfirmed peer {}", peer_id);
 			return Ok(());
 		}
-		if self.state != SyncState::Idle {
+		if self.state != SyncState::Idle || self.fast_sync {
 			trace!(target: "sync", "Ignoring new hashes since we're already downloading.");
 			let max = r.iter().take(MAX_NEW_HASHES).map(|item| item.val_at::<BlockNumber>(1).unwrap_or(0)).fold(0u64, max);
 			if max > self.highest_block.unwrap_or(0) {
@@ -735,6 +919,7 @@ impl ChainSync {
 				},
 				BlockStatus::Bad => {
 					debug!(target: "sync", "Bad new block hash {:?}", hash);
+					io.report_peer(peer_id, PeerReport::BadBlock);
 					io.disable_peer(peer_id);
 					return Ok(());
 				}
@@ -772,18 +957,190 @@ impl ChainSync {
 
 	/// Resume downloading
 	fn continue_sync(&mut self, io: &mut SyncIo) {
-		let mut peers: Vec<(PeerId, U256)> = self.peers.iter().filter_map(|(k, p)|
-			if p.can_sync() { Some((*k, p.difficulty.unwrap_or_else(U256::zero))) } else { None }).collect();
-		thread_rng().shuffle(&mut peers); //TODO: sort by rating
+		let mut peers: Vec<(PeerId, i32)> = self.peers.iter().filter_map(|(k, p)|
+			if p.can_sync() { Some((*k, io.peer_reputation(*k))) } else { None }).collect();
+		// peers with higher reputation get download tasks first
+		thread_rng().shuffle(&mut peers);
+		peers.sort_by(|&(_, a), &(_, b)| b.cmp(&a));
 		trace!(target: "sync", "Syncing with {}/{} peers", self.active_peers.len(), peers.len());
 		for (p, _) in peers {
 			if self.active_peers.contains(&p) {
 				self.sync_peer(io, p, false);
 			}
 		}
-		if self.state != SyncState::Waiting && !self.peers.values().any(|p| p.asking != PeerAsking::Nothing && p.can_sync()) {
+		if self.state != SyncState::Waiting && self.state != SyncState::State && !self.peers.values().any(|p| p.asking != PeerAsking::Nothing && !p.asking.is_ancient() && p.can_sync()) {
 			self.complete_sync();
 		}
+		self.continue_ancient_sync(io);
+	}
+
+	/// Give an ancient blocks download task to an idle peer, if blocks before
+	/// the first block in the chain are missing.
+	fn continue_ancient_sync(&mut self, io: &mut SyncIo) {
+		if !self.download_old_blocks || self.peers.values().any(|p| p.asking.is_ancient()) {
+			return;
+		}
+
+		if !self.ancient_blocks.has_pending() {
+			// pick up where the chain is, e.g. after a snapshot has been restored.
+			let chain_info = io.chain().chain_info();
+			let next = chain_info.first_block_hash
+				.and_then(|h| io.chain().block_header(BlockID::Hash(h)))
+				.map(|h| HeaderView::new(&h).parent_hash())
+				.and_then(|parent| match chain_info.first_block_number {
+					Some(n) if n > 1 => Some((parent, n - 1)),
+					_ => None,
+				});
+			if next != self.ancient_blocks.next() {
+				self.ancient_blocks.reset_to(next);
+			}
+		}
+
+		let request = match self.ancient_blocks.needed(MAX_HEADERS_TO_REQUEST, MAX_BODIES_TO_REQUEST) {
+			Some(request) => request,
+			None => return,
+		};
+
+		let peer_id = match self.peers.iter()
+			.find(|&(id, p)| p.asking == PeerAsking::Nothing && p.can_sync() && !p.no_ancient_blocks && self.active_peers.contains(id))
+			.map(|(id, _)| *id) {
+			Some(id) => id,
+			None => return,
+		};
+
+		match request {
+			AncientRequest::Headers(hash, count) => {
+				self.request_headers_by_hash(io, peer_id, &hash, count, 0, true, PeerAsking::AncientHeaders);
+			},
+			AncientRequest::Bodies(hashes) => {
+				trace!(target: "sync", "{} <- GetBlockBodies (ancient): {} entries", peer_id, hashes.len());
+				let mut rlp = RlpStream::new_list(hashes.len());
+				for h in &hashes { rlp.append(h); }
+				self.send_request(io, peer_id, PeerAsking::AncientBodies, GET_BLOCK_BODIES_PACKET, rlp.out());
+			},
+			AncientRequest::Receipts(hashes) => {
+				trace!(target: "sync", "{} <- GetReceipts (ancient): {} entries", peer_id, hashes.len());
+				let mut rlp = RlpStream::new_list(hashes.len());
+				for h in &hashes { rlp.append(h); }
+				self.send_request(io, peer_id, PeerAsking::AncientReceipts, GET_RECEIPTS_PACKET, rlp.out());
+			},
+		}
+	}
+
+	/// Called by peer once it has ancient block headers.
+	fn on_peer_ancient_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::AncientHeaders);
+		trace!(target: "sync", "{} -> BlockHeaders (ancient, {} entries)", peer_id, r.item_count());
+		match self.ancient_blocks.insert_headers(r) {
+			Ok(0) => {
+				trace!(target: "sync", "{}: No ancient headers", peer_id);
+				self.peers.get_mut(&peer_id).expect("peer checked by caller; qed").no_ancient_blocks = true;
+			},
+			Ok(_) => {},
+			Err(_) => {
+				trace!(target: "sync", "{}: Invalid ancient headers", peer_id);
+				io.disable_peer(peer_id);
+			},
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has ancient block bodies.
+	fn on_peer_ancient_bodies(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.reset_peer_asking(peer_id, PeerAsking::AncientBodies);
+		trace!(target: "sync", "{} -> BlockBodies (ancient, {} entries)", peer_id, r.item_count());
+		match self.ancient_blocks.insert_bodies(r, peer_id) {
+			Ok(0) => {
+				trace!(target: "sync", "{}: No ancient bodies", peer_id);
+				self.peers.get_mut(&peer_id).expect("peer checked by caller; qed").no_ancient_blocks = true;
+			},
+			Ok(_) => {},
+			Err(_) => {
+				trace!(target: "sync", "{}: Invalid ancient bodies", peer_id);
+				io.disable_peer(peer_id);
+			},
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has block receipts.
+	fn on_peer_block_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::AncientReceipts) {
+			return self.on_peer_ancient_receipts(io, peer_id, r);
+		}
+		self.clear_peer_download(peer_id);
+		if !self.reset_peer_asking(peer_id, PeerAsking::BlockReceipts) {
+			trace!(target: "sync", "{}: Ignored unexpected receipts", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> Receipts ({} entries)", peer_id, item_count);
+		if item_count == 0 {
+			self.deactivate_peer(io, peer_id);
+		}
+		else if self.state != SyncState::Blocks && self.state != SyncState::NewBlocks {
+			trace!(target: "sync", "Ignored unexpected block receipts");
+		}
+		else {
+			let mut receipts = Vec::with_capacity(item_count);
+			for i in 0..item_count {
+				receipts.push(try!(r.at(i)).as_raw().to_vec());
+			}
+			if self.blocks.insert_receipts(receipts) != item_count {
+				trace!(target: "sync", "Deactivating peer for giving invalid block receipts");
+				self.deactivate_peer(io, peer_id);
+			}
+			self.collect_blocks(io);
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called by peer once it has ancient block receipts.
+	fn on_peer_ancient_receipts(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.reset_peer_asking(peer_id, PeerAsking::AncientReceipts) {
+			trace!(target: "sync", "{}: Ignored unexpected receipts", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} -> Receipts (ancient, {} entries)", peer_id, r.item_count());
+
+		let blocks = match self.ancient_blocks.drain(r) {
+			Ok(blocks) => blocks,
+			Err(_) => {
+				io.disable_peer(peer_id);
+				Vec::new()
+			}
+		};
+		if blocks.is_empty() {
+			self.peers.get_mut(&peer_id).expect("peer checked by reset_peer_asking; qed").no_ancient_blocks = true;
+		}
+
+		for (block, receipts, body_peer_id) in blocks {
+			let header = BlockView::new(&block).header();
+			match io.chain().import_ancient_block(block, receipts) {
+				Ok(_) | Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
+					trace!(target: "sync", "Imported ancient block #{} ({})", header.number(), header.hash());
+					self.ancient_blocks.imported(&header);
+				},
+				Err(e) => {
+					debug!(target: "sync", "{}: Bad ancient block #{} ({}): {:?}", peer_id, header.number(), header.hash(), e);
+					// the body might have come from another peer, so we can't tell which one is wrong.
+					if body_peer_id == peer_id {
+						io.disable_peer(peer_id);
+					}
+					// drop the downloaded data; bodies and receipts are requested again.
+					self.ancient_blocks.reset_to(None);
+					break;
+				},
+			}
+		}
+
+		self.continue_sync(io);
+		Ok(())
 	}
 
 	/// Called after all blocks have been downloaded
@@ -805,7 +1162,7 @@ impl ChainSync {
 			trace!(target: "sync", "Skipping deactivated peer");
 			return;
 		}
-		let (peer_latest, peer_difficulty) = {
+		let (peer_latest, peer_latest_number, peer_difficulty) = {
 			let peer = self.peers.get_mut(&peer_id).unwrap();
 			if peer.asking != PeerAsking::Nothing || !peer.can_sync() {
 				return;
@@ -814,13 +1171,17 @@ impl ChainSync {
 				trace!(target: "sync", "Waiting for the block queue");
 				return;
 			}
-			(peer.latest_hash.clone(), peer.difficulty.clone())
+			(peer.latest_hash.clone(), peer.latest_number, peer.difficulty.clone())
 		};
 		let chain_info = io.chain().chain_info();
 		let td = chain_info.pending_total_difficulty;
 		let syncing_difficulty = max(self.syncing_difficulty, td);
 
 		if force || self.state == SyncState::NewBlocks || peer_difficulty.map_or(true, |pd| pd > syncing_difficulty) {
+			if self.fast_sync && self.pivot.is_none() && self.sync_pivot(io, peer_id, peer_latest, peer_latest_number) {
+				// no blocks are downloaded until the pivot is agreed on
+				return;
+			}
 			match self.state {
 				SyncState::Idle => {
 					if self.last_imported_block < chain_info.best_block_number {
@@ -844,12 +1205,105 @@ impl ChainSync {
 						self.request_blocks(io, peer_id, false);
 					}
 				}
+				SyncState::State => {
+					self.request_state(io, peer_id);
+				},
 				SyncState::Waiting => ()
 			}
 		}
 	}
 
+	/// Agree on the fast sync pivot block with a peer. The pivot must be known to `FAST_SYNC_PIVOT_PEERS`
+	/// peers which all report the same hash for it. Returns false if fast sync has been abandoned instead.
+	fn sync_pivot(&mut self, io: &mut SyncIo, peer_id: PeerId, peer_latest: H256, peer_latest_number: Option<BlockNumber>) -> bool {
+		if self.pivot_search_start.is_none() {
+			self.pivot_search_start = Some(time::precise_time_s());
+		}
+		let peer_latest_number = match peer_latest_number {
+			Some(n) => n,
+			None => {
+				// the pivot is chosen relative to the best blocks of the peers
+				self.request_headers_by_hash(io, peer_id, &peer_latest, 1, 0, false, PeerAsking::BestHeader);
+				return true;
+			},
+		};
+		let candidate = match self.pivot_candidate {
+			Some(n) => n,
+			None => {
+				let mut numbers: Vec<_> = self.peers.values().filter(|p| p.can_sync()).filter_map(|p| p.latest_number).collect();
+				if numbers.len() < FAST_SYNC_PIVOT_PEERS {
+					trace!(target: "sync", "Waiting for more peers to choose the fast sync pivot");
+					return true;
+				}
+				// the highest block enough peers claim to have
+				numbers.sort();
+				let common = numbers[numbers.len() - FAST_SYNC_PIVOT_PEERS];
+				if common <= FAST_SYNC_PIVOT_DISTANCE {
+					trace!(target: "sync", "Chain is too short for fast sync");
+					self.disable_fast_sync();
+					return false;
+				}
+				trace!(target: "sync", "Fast sync pivot candidate block #{}", common - FAST_SYNC_PIVOT_DISTANCE);
+				self.pivot_candidate = Some(common - FAST_SYNC_PIVOT_DISTANCE);
+				common - FAST_SYNC_PIVOT_DISTANCE
+			},
+		};
+		if peer_latest_number >= candidate && !self.pivot_votes.contains_key(&peer_id) {
+			self.request_headers_by_number(io, peer_id, candidate, 1, 0, false, PeerAsking::PivotHeader);
+		}
+		true
+	}
+
+	/// Choose the pivot candidate as the fast sync pivot block once enough peers agree on its hash.
+	/// Peers which voted for another hash are deactivated. If no hash gets enough votes,
+	/// fast sync is abandoned when the pivot search times out.
+	fn count_pivot_votes(&mut self, io: &mut SyncIo) {
+		let number = match self.pivot_candidate {
+			Some(n) => n,
+			None => return,
+		};
+		let mut votes: HashMap<H256, usize> = HashMap::new();
+		for hash in self.pivot_votes.values() {
+			*votes.entry(hash.clone()).or_insert(0) += 1;
+		}
+		let hash = match votes.into_iter().find(|&(_, count)| count >= FAST_SYNC_PIVOT_PEERS) {
+			Some((hash, _)) => hash,
+			None => return,
+		};
+		trace!(target: "sync", "Fast sync pivot block #{} ({})", number, hash);
+		self.pivot = Some((number, hash));
+
+		let dissenting: Vec<PeerId> = self.pivot_votes.iter().filter(|&(_, h)| *h != hash).map(|(p, _)| *p).collect();
+		for peer_id in dissenting {
+			debug!(target: "sync", "{}: Voted for another fast sync pivot block", peer_id);
+			self.deactivate_peer(io, peer_id);
+		}
+	}
+
+	/// Fall back to full sync if the pivot block hasn't been agreed on in time.
+	fn check_pivot_timeout(&mut self, io: &mut SyncIo) {
+		let expired = self.pivot_search_start.map_or(false, |t| time::precise_time_s() - t > FAST_SYNC_PIVOT_TIMEOUT_SEC);
+		if self.fast_sync && self.pivot.is_none() && expired {
+			debug!(target: "sync", "Fast sync pivot block could not be agreed on, falling back to full sync");
+			self.disable_fast_sync();
+			self.continue_sync(io);
+		}
+	}
+
+	/// Import all blocks with execution from now on.
+	fn disable_fast_sync(&mut self) {
+		self.fast_sync = false;
+		self.pivot_candidate = None;
+		self.pivot_votes.clear();
+		self.blocks = BlockCollection::new(false);
+	}
+
 	fn start_sync_round(&mut self, io: &mut SyncIo) {
+		if self.state_download.is_some() {
+			// blocks can't be imported until the pivot state is there
+			self.state = SyncState::State;
+			return;
+		}
 		self.state = SyncState::ChainHead;
 		trace!(target: "sync", "Starting round (last imported count = {:?}, block = {:?}", self.imported_this_round, self.last_imported_block);
 		// Check if need to retract to find the common block. The problem is that the peers still return headers by hash even
@@ -896,6 +1350,14 @@ impl ChainSync {
 			return;
 		}
 
+		// check to see if we need to download any block receipts for fast sync
+		let needed_receipts = self.blocks.needed_receipts(MAX_RECEIPTS_TO_REQUEST, ignore_others);
+		if !needed_receipts.is_empty() {
+			replace(&mut self.peers.get_mut(&peer_id).unwrap().asking_blocks, needed_receipts.clone());
+			self.request_receipts(io, peer_id, needed_receipts);
+			return;
+		}
+
 		// find subchain to download
 		if let Some((h, count)) = self.blocks.needed_headers(MAX_HEADERS_TO_REQUEST, ignore_others) {
 			replace(&mut self.peers.get_mut(&peer_id).unwrap().asking_blocks, vec![h.clone()]);
@@ -917,6 +1379,16 @@ impl ChainSync {
 					self.blocks.clear_body_download(b);
 				}
 			},
+			PeerAsking::BlockReceipts => {
+				for b in &peer.asking_blocks {
+					self.blocks.clear_receipt_download(b);
+				}
+			},
+			PeerAsking::NodeData => {
+				if let Some(ref mut state) = self.state_download {
+					state.clear_download(&peer.asking_blocks);
+				}
+			},
 			_ => (),
 		}
 		peer.asking_blocks.clear();
@@ -935,22 +1407,41 @@ impl ChainSync {
 	fn collect_blocks(&mut self, io: &mut SyncIo) {
 		let mut restart = false;
 		let mut imported = HashSet::new();
+		let mut pivot_block = None;
 		let blocks = self.blocks.drain();
 		let count = blocks.len();
 		for block in blocks {
 			let (h, number, parent) = {
-				let header = BlockView::new(&block).header_view();
+				let header = BlockView::new(&block.block).header_view();
 				(header.sha3(), header.number(), header.parent_hash())
 			};
 
 			// Perform basic block verification
-			if !Block::is_good(&block) {
-				debug!(target: "sync", "Bad block rlp {:?} : {:?}", h, block);
+			if !Block::is_good(&block.block) {
+				debug!(target: "sync", "Bad block rlp {:?} : {:?}", h, block.block);
 				restart = true;
 				break;
 			}
 
-			match io.chain().import_block(block) {
+			// below the pivot block fast sync imports blocks without executing them.
+			let result = match (self.fast_sync, self.pivot.clone(), block.receipts) {
+				(true, Some((pivot, pivot_hash)), Some(receipts)) => {
+					if number == pivot {
+						if h != pivot_hash {
+							debug!(target: "sync", "Downloaded block #{} ({}) is not the agreed pivot block {}", number, h, pivot_hash);
+							restart = true;
+							break;
+						}
+						self.block_imported(&h, number, &parent);
+						pivot_block = Some((block.block, receipts));
+						break;
+					}
+					io.chain().import_block_with_receipts(block.block, receipts, false)
+				},
+				(_, _, _) => io.chain().import_block(block.block),
+			};
+
+			match result {
 				Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
 					trace!(target: "sync", "Block already in chain {:?}", h);
 					self.block_imported(&h, number, &parent);
@@ -978,6 +1469,11 @@ impl ChainSync {
 		trace!(target: "sync", "Imported {} of {}", imported.len(), count);
 		self.imported_this_round = Some(self.imported_this_round.unwrap_or(0) + imported.len());
 
+		if let Some((block, receipts)) = pivot_block {
+			self.start_state_download(io, block, receipts);
+			return;
+		}
+
 		if restart {
 			self.restart_on_bad_block(io);
 			return;
@@ -990,6 +1486,121 @@ impl ChainSync {
 		}
 	}
 
+	/// Start downloading the state of the fast sync pivot block. Block download is stopped until it completes.
+	fn start_state_download(&mut self, io: &mut SyncIo, block: Bytes, receipts: Bytes) {
+		let state_root = BlockView::new(&block).header_view().state_root();
+		trace!(target: "sync", "Reached fast sync pivot block, downloading state {}", state_root);
+		let state = StateDownload::new(state_root, &|a, h| io.chain().has_state_node(a, h.clone()));
+		let complete = state.is_complete();
+		self.pivot_block = Some((block, receipts));
+		self.state_download = Some(state);
+		self.reset();
+		self.state = SyncState::State;
+		if complete {
+			self.complete_state_download(io);
+		}
+	}
+
+	/// Import the pivot block once its state is downloaded and continue with full sync.
+	fn complete_state_download(&mut self, io: &mut SyncIo) {
+		trace!(target: "sync", "Fast sync complete");
+		self.state_download = None;
+		self.disable_fast_sync();
+		if let Some((block, receipts)) = self.pivot_block.take() {
+			match io.chain().import_block_with_receipts(block, receipts, true) {
+				Ok(_) | Err(BlockImportError::Import(ImportError::AlreadyInChain)) => (),
+				Err(e) => warn!(target: "sync", "Error importing fast sync pivot block: {:?}", e),
+			}
+		}
+		self.restart(io);
+	}
+
+	/// Find some state nodes to download for a peer.
+	fn request_state(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		let hashes = match self.state_download {
+			Some(ref mut state) => state.needed(MAX_NODE_DATA_TO_REQUEST),
+			None => return,
+		};
+		if hashes.is_empty() {
+			return;
+		}
+		trace!(target: "sync", "{} <- GetNodeData: {} entries", peer_id, hashes.len());
+		let mut rlp = RlpStream::new_list(hashes.len());
+		for h in &hashes {
+			rlp.append(h);
+		}
+		replace(&mut self.peers.get_mut(&peer_id).unwrap().asking_blocks, hashes);
+		self.send_request(io, peer_id, PeerAsking::NodeData, GET_NODE_DATA_PACKET, rlp.out());
+	}
+
+	/// Called by peer once it has state node data.
+	fn on_peer_node_data(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(true, |p| p.asking != PeerAsking::NodeData) {
+			trace!(target: "sync", "{}: Ignored unexpected node data", peer_id);
+			return Ok(());
+		}
+		let item_count = r.item_count();
+		trace!(target: "sync", "{} -> NodeData ({} entries)", peer_id, item_count);
+		let nodes = match self.state_download {
+			Some(ref mut state) => state.insert_nodes(r, &|a, h| io.chain().has_state_node(a, h.clone())),
+			None => Ok(Vec::new()),
+		};
+		// nodes not delivered are requested again
+		self.clear_peer_download(peer_id);
+		self.reset_peer_asking(peer_id, PeerAsking::NodeData);
+
+		match nodes {
+			Ok(nodes) => {
+				if item_count == 0 {
+					trace!(target: "sync", "{}: No node data", peer_id);
+					self.deactivate_peer(io, peer_id);
+				}
+				if let Err(e) = ChainSync::write_state_nodes(io, nodes) {
+					warn!(target: "sync", "Error writing state nodes: {}, restarting state download", e);
+					self.restart_state_download(io);
+				}
+			},
+			Err(e) => {
+				trace!(target: "sync", "{}: Invalid node data: {:?}", peer_id, e);
+				io.disable_peer(peer_id);
+			}
+		}
+
+		if self.state_download.as_ref().map_or(false, |s| s.is_complete()) {
+			self.complete_state_download(io);
+			return Ok(());
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Write state nodes in order, batched by account.
+	fn write_state_nodes(io: &mut SyncIo, nodes: Vec<(Option<H256>, Bytes)>) -> Result<(), String> {
+		let mut batch = Vec::new();
+		let mut batch_address = None;
+		for (address_hash, node) in nodes {
+			if address_hash != batch_address && !batch.is_empty() {
+				try!(io.chain().import_state_nodes(batch_address, replace(&mut batch, Vec::new())));
+			}
+			batch_address = address_hash;
+			batch.push(node);
+		}
+		if !batch.is_empty() {
+			try!(io.chain().import_state_nodes(batch_address, batch));
+		}
+		Ok(())
+	}
+
+	/// Start the state download over after nodes failed to be written. Nodes written before
+	/// have their whole subtree in the database, so only the rest is downloaded again.
+	fn restart_state_download(&mut self, io: &mut SyncIo) {
+		let root = match self.state_download {
+			Some(ref state) => state.root().clone(),
+			None => return,
+		};
+		self.state_download = Some(StateDownload::new(root, &|a, h| io.chain().has_state_node(a, h.clone())));
+	}
+
 	/// Request headers from a peer by block hash
 	#[cfg_attr(feature="dev", allow(too_many_arguments))]
 	fn request_headers_by_hash(&mut self, sync: &mut SyncIo, peer_id: PeerId, h: &H256, count: usize, skip: usize, reverse: bool, asking: PeerAsking) {
@@ -1026,6 +1637,16 @@ impl ChainSync {
 		self.send_request(sync, peer_id, PeerAsking::BlockBodies, GET_BLOCK_BODIES_PACKET, rlp.out());
 	}
 
+	/// Request block receipts from a peer
+	fn request_receipts(&mut self, sync: &mut SyncIo, peer_id: PeerId, hashes: Vec<H256>) {
+		let mut rlp = RlpStream::new_list(hashes.len());
+		trace!(target: "sync", "{} <- GetReceipts: {} entries starting from {:?}", peer_id, hashes.len(), hashes.first());
+		for h in hashes {
+			rlp.append(&h);
+		}
+		self.send_request(sync, peer_id, PeerAsking::BlockReceipts, GET_RECEIPTS_PACKET, rlp.out());
+	}
+
 	/// Reset peer status after request is complete.
 	fn reset_peer_asking(&mut self, peer_id: PeerId, asking: PeerAsking) -> bool {
 		let peer = self.peers.get_mut(&peer_id).unwrap();
@@ -1289,6 +1910,8 @@ impl ChainSync {
 			BLOCK_BODIES_PACKET => self.on_peer_block_bodies(io, peer, &rlp),
 			NEW_BLOCK_PACKET => self.on_peer_new_block(io, peer, &rlp),
 			NEW_BLOCK_HASHES_PACKET => self.on_peer_new_hashes(io, peer, &rlp),
+			RECEIPTS_PACKET => self.on_peer_block_receipts(io, peer, &rlp),
+			NODE_DATA_PACKET => self.on_peer_node_data(io, peer, &rlp),
 			_ => {
 				debug!(target: "sync", "Unknown packet {}", packet_id);
 				Ok(())
@@ -1296,6 +1919,7 @@ impl ChainSync {
 		};
 		result.unwrap_or_else(|e| {
 			debug!(target:"sync", "{} -> Malformed packet {} : {}", peer, packet_id, e);
+			io.report_peer(peer, PeerReport::InvalidData);
 		})
 	}
 
@@ -1306,11 +1930,18 @@ impl ChainSync {
 			let timeout = match peer.asking {
 				PeerAsking::BlockHeaders | PeerAsking::Heads => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
 				PeerAsking::BlockBodies => (tick - peer.ask_time) > BODIES_TIMEOUT_SEC,
+				PeerAsking::BlockReceipts => (tick - peer.ask_time) > RECEIPTS_TIMEOUT_SEC,
+				PeerAsking::BestHeader | PeerAsking::PivotHeader => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
+				PeerAsking::NodeData => (tick - peer.ask_time) > NODE_DATA_TIMEOUT_SEC,
 				PeerAsking::Nothing => false,
 				PeerAsking::ForkHeader => (tick - peer.ask_time) > FORK_HEADER_TIMEOUT_SEC,
+				PeerAsking::AncientHeaders => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
+				PeerAsking::AncientBodies => (tick - peer.ask_time) > BODIES_TIMEOUT_SEC,
+				PeerAsking::AncientReceipts => (tick - peer.ask_time) > RECEIPTS_TIMEOUT_SEC,
 			};
 			if timeout {
 				trace!(target:"sync", "Timeout {}", peer_id);
+				io.report_peer(*peer_id, PeerReport::Timeout);
 				io.disconnect_peer(*peer_id);
 				aborting.push(*peer_id);
 			}
@@ -1539,6 +2170,8 @@ impl ChainSync {
 	/// Maintain other peers. Send out any new blocks and transactions
 	pub fn maintain_sync(&mut self, io: &mut SyncIo) {
 		self.check_resume(io);
+		self.check_pivot_timeout(io);
+		self.continue_ancient_sync(io);
 	}
 
 	/// called when block is imported to chain - propagates the blocks and updates transactions sent to peers
@@ -1758,6 +2391,8 @@ mod tests {
 				last_sent_transactions: HashSet::new(),
 				expired: false,
 				confirmation: super::ForkConfirmation::Confirmed,
+				fork_checkpoint: 0,
+				no_ancient_blocks: false,
 			});
 		sync
 	}
@@ -56,7 +56,7 @@
 //!			miner,
 //!			IoChannel::disconnected()
 //!		).unwrap();
-//! 	let sync = EthSync::new(SyncConfig::default(), client, NetworkConfiguration::from(NetworkConfiguration::new())).unwrap();
+//! 	let sync = EthSync::new(SyncConfig::default(), client, NetworkConfiguration::from(NetworkConfiguration::new()), Vec::new()).unwrap();
 //! 	sync.start_network();
 //! }
 //! ```
@@ -82,6 +82,8 @@ extern crate ethcore_ipc as ipc;
 
 mod chain;
 mod blocks;
+mod ancient;
+mod state;
 mod sync_io;
 
 #[cfg(test)]
@@ -92,8 +94,8 @@ mod api {
 	include!(concat!(env!("OUT_DIR"), "/api.rs"));
 }
 
-pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig,
-	ServiceConfiguration, NetworkConfiguration};
+pub use api::{EthSync, AttachedProtocol, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig,
+	ServiceConfiguration, NetworkConfiguration, PeerReputation, ForkCheckpoint, NetworkTraffic, PeerTraffic, PacketTraffic, PeerCounts};
 pub use chain::{SyncStatus, SyncState};
-pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError};
+pub use network::{is_valid_node_url, NonReservedPeerMode, NetworkError, IpFilter};
 
@@ -0,0 +1,368 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State download for fast sync. The state trie of the pivot block is downloaded node by node
+//! with `GetNodeData`, along with the storage tries and code of all accounts. A node is only
+//! handed out for writing once all of its children have been, so any node found in the
+//! database has its complete subtree there as well and never needs to be downloaded again.
+
+use util::*;
+use util::trie::node::Node;
+use rlp::*;
+use network::NetworkError;
+
+/// Kind of a state node.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum NodeKind {
+	/// Account trie node.
+	Account,
+	/// Storage trie node.
+	Storage,
+	/// Contract code.
+	Code,
+}
+
+/// State node identifier. Storage trie nodes and code are stored separately for each account,
+/// so these are identified by the account address hash as well.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct NodeId {
+	hash: H256,
+	kind: NodeKind,
+	address_hash: Option<H256>,
+}
+
+/// A node being downloaded or waiting for its children to be written.
+struct PendingNode {
+	/// Node data, once downloaded.
+	data: Option<Bytes>,
+	/// Key nibbles leading to an account trie node.
+	path: Vec<u8>,
+	/// Number of children not written yet.
+	deps: usize,
+	/// Nodes waiting for this one to be written.
+	parents: Vec<NodeId>,
+}
+
+/// Downloads a state trie.
+pub struct StateDownload {
+	/// State root.
+	root: H256,
+	/// Nodes being downloaded or waiting for their children.
+	pending: HashMap<NodeId, PendingNode>,
+	/// Nodes waiting for data, by node hash.
+	by_hash: HashMap<H256, Vec<NodeId>>,
+	/// Node hashes to be requested.
+	queue: VecDeque<H256>,
+	/// Node hashes being downloaded.
+	downloading: HashSet<H256>,
+	/// Number of nodes written.
+	written: usize,
+	/// Whether the whole state has been written.
+	complete: bool,
+}
+
+impl StateDownload {
+	/// Create a new instance for given state root. `known` checks if a node of given account is in the database.
+	pub fn new(root: H256, known: &Fn(Option<H256>, &H256) -> bool) -> StateDownload {
+		let mut download = StateDownload {
+			root: root,
+			pending: HashMap::new(),
+			by_hash: HashMap::new(),
+			queue: VecDeque::new(),
+			downloading: HashSet::new(),
+			written: 0,
+			complete: false,
+		};
+		if root == SHA3_NULL_RLP || known(None, &root) {
+			download.complete = true;
+		} else {
+			download.add_node(NodeId { hash: root, kind: NodeKind::Account, address_hash: None }, Vec::new(), None);
+		}
+		download
+	}
+
+	/// State root being downloaded.
+	pub fn root(&self) -> &H256 {
+		&self.root
+	}
+
+	/// Whether the whole state has been written.
+	pub fn is_complete(&self) -> bool {
+		self.complete
+	}
+
+	/// Number of nodes written so far.
+	pub fn written(&self) -> usize {
+		self.written
+	}
+
+	/// Number of nodes being downloaded or waiting for their children.
+	pub fn pending(&self) -> usize {
+		self.pending.len()
+	}
+
+	/// Returns a set of node hashes to download. The returned set is marked as being downloaded.
+	pub fn needed(&mut self, count: usize) -> Vec<H256> {
+		let mut needed = Vec::new();
+		while needed.len() < count {
+			match self.queue.pop_front() {
+				Some(hash) => if self.by_hash.contains_key(&hash) && self.downloading.insert(hash.clone()) {
+					needed.push(hash);
+				},
+				None => break,
+			}
+		}
+		needed
+	}
+
+	/// Unmark nodes as being downloaded. Nodes which haven't been received are requested again.
+	pub fn clear_download(&mut self, hashes: &[H256]) {
+		for hash in hashes {
+			if self.downloading.remove(hash) && self.by_hash.contains_key(hash) {
+				self.queue.push_front(hash.clone());
+			}
+		}
+	}
+
+	/// Insert a `NodeData` response. Returns nodes ready to be written to the database in order,
+	/// along with the address hash of the account storage trie nodes and code belong to.
+	pub fn insert_nodes(&mut self, nodes: &UntrustedRlp, known: &Fn(Option<H256>, &H256) -> bool) -> Result<Vec<(Option<H256>, Bytes)>, NetworkError> {
+		let mut written = Vec::new();
+		for item in nodes.iter() {
+			let data: Bytes = try!(item.as_val());
+			let hash = data.sha3();
+			let ids = match self.by_hash.remove(&hash) {
+				Some(ids) => ids,
+				None => {
+					trace!(target: "sync", "Ignored unexpected node {}", hash);
+					continue;
+				}
+			};
+			self.downloading.remove(&hash);
+			for id in ids {
+				self.on_node_data(id, data.clone(), known, &mut written);
+			}
+		}
+		Ok(written)
+	}
+
+	fn on_node_data(&mut self, id: NodeId, data: Bytes, known: &Fn(Option<H256>, &H256) -> bool, written: &mut Vec<(Option<H256>, Bytes)>) {
+		let mut children = Vec::new();
+		if id.kind != NodeKind::Code {
+			let path = self.pending.get(&id).expect("nodes waiting for data are pending; qed").path.clone();
+			// the data matches the requested hash, so it is a valid trie node.
+			Self::node_children(&id, &path, &data, &mut children);
+		}
+
+		let mut deps = 0;
+		for (child, path) in children {
+			if !known(child.address_hash, &child.hash) && self.add_node(child, path, Some(id)) {
+				deps += 1;
+			}
+		}
+
+		{
+			let node = self.pending.get_mut(&id).expect("nodes waiting for data are pending; qed");
+			node.data = Some(data);
+			node.deps = deps;
+		}
+		if deps == 0 {
+			self.write(id, written);
+		}
+	}
+
+	// add a node to download, or add a parent to the node if it's already pending.
+	fn add_node(&mut self, id: NodeId, path: Vec<u8>, parent: Option<NodeId>) -> bool {
+		if let Some(node) = self.pending.get_mut(&id) {
+			node.parents.extend(parent);
+			return true;
+		}
+
+		self.pending.insert(id, PendingNode {
+			data: None,
+			path: path,
+			deps: 0,
+			parents: parent.into_iter().collect(),
+		});
+		let waiting = self.by_hash.entry(id.hash).or_insert_with(Vec::new);
+		if waiting.is_empty() {
+			self.queue.push_back(id.hash);
+		}
+		waiting.push(id);
+		true
+	}
+
+	// hand out the node for writing, along with any parents which are complete now.
+	fn write(&mut self, id: NodeId, written: &mut Vec<(Option<H256>, Bytes)>) {
+		let mut ready = vec![id];
+		while let Some(id) = ready.pop() {
+			let node = self.pending.remove(&id).expect("only pending nodes are written; qed");
+			written.push((id.address_hash, node.data.expect("only downloaded nodes are written; qed")));
+			self.written += 1;
+			if id.kind == NodeKind::Account && id.hash == self.root {
+				trace!(target: "sync", "State download complete, {} nodes", self.written);
+				self.complete = true;
+			}
+
+			for parent in node.parents {
+				if let Some(p) = self.pending.get_mut(&parent) {
+					p.deps -= 1;
+					if p.deps == 0 && p.data.is_some() {
+						ready.push(parent);
+					}
+				}
+			}
+		}
+	}
+
+	// collect the children of a trie node.
+	fn node_children(id: &NodeId, path: &[u8], node: &[u8], children: &mut Vec<(NodeId, Vec<u8>)>) {
+		match Node::decoded(node) {
+			Node::Empty => {},
+			Node::Leaf(slice, value) => if id.kind == NodeKind::Account {
+				let mut key = path.to_vec();
+				key.extend((0..slice.len()).map(|i| slice.at(i)));
+				let address_hash = Some(Self::nibbles_to_hash(&key));
+
+				let account = Rlp::new(value);
+				let storage_root: H256 = account.val_at(2);
+				let code_hash: H256 = account.val_at(3);
+				if storage_root != SHA3_NULL_RLP {
+					children.push((NodeId { hash: storage_root, kind: NodeKind::Storage, address_hash: address_hash }, Vec::new()));
+				}
+				if code_hash != SHA3_EMPTY {
+					children.push((NodeId { hash: code_hash, kind: NodeKind::Code, address_hash: address_hash }, Vec::new()));
+				}
+			},
+			Node::Extension(slice, child) => {
+				let mut child_path = path.to_vec();
+				child_path.extend((0..slice.len()).map(|i| slice.at(i)));
+				Self::child(id, child_path, child, children);
+			},
+			Node::Branch(nodes, _) => {
+				for (i, child) in nodes.iter().enumerate() {
+					let mut child_path = path.to_vec();
+					child_path.push(i as u8);
+					Self::child(id, child_path, child, children);
+				}
+			},
+		}
+	}
+
+	fn child(id: &NodeId, path: Vec<u8>, child: &[u8], children: &mut Vec<(NodeId, Vec<u8>)>) {
+		let path = if id.kind == NodeKind::Account { path } else { Vec::new() };
+		let r = Rlp::new(child);
+		if r.is_data() && r.size() == 32 {
+			children.push((NodeId { hash: r.as_val(), kind: id.kind, address_hash: id.address_hash }, path));
+		} else if r.is_list() {
+			// nodes shorter than 32 bytes are inlined in the parent.
+			Self::node_children(id, &path, child, children);
+		}
+	}
+
+	fn nibbles_to_hash(nibbles: &[u8]) -> H256 {
+		let mut hash = H256::new();
+		{
+			let bytes: &mut [u8] = &mut hash;
+			for (i, n) in nibbles.iter().take(64).enumerate() {
+				bytes[i / 2] |= if i % 2 == 0 { n << 4 } else { *n };
+			}
+		}
+		hash
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::StateDownload;
+	use util::*;
+	use rlp::*;
+
+	fn account(storage_root: H256, code_hash: H256) -> Bytes {
+		let mut stream = RlpStream::new_list(4);
+		stream.append(&U256::zero()).append(&U256::from(1000)).append(&storage_root).append(&code_hash);
+		stream.out()
+	}
+
+	// serve node data from `source` until the download is complete, writing into `target`.
+	fn download(source: &MemoryDB, download: &mut StateDownload, target: &mut HashMap<(Option<H256>, H256), Bytes>) {
+		while !download.is_complete() {
+			let needed = download.needed(16);
+			assert!(!needed.is_empty());
+			let mut rlp = RlpStream::new_list(needed.len());
+			for h in &needed {
+				rlp.append(&source.get(h).unwrap().to_vec());
+			}
+			let data = rlp.out();
+			let written = download.insert_nodes(&UntrustedRlp::new(&data), &|a, h| target.contains_key(&(a, h.clone()))).unwrap();
+			download.clear_download(&needed);
+			for (address_hash, node) in written {
+				target.insert((address_hash, node.sha3()), node);
+			}
+		}
+	}
+
+	#[test]
+	fn downloads_accounts_storage_and_code() {
+		let mut source = MemoryDB::new();
+		let code = vec![0x60u8, 0x00, 0x60, 0x00, 0xf3];
+		let code_hash = source.insert(&code);
+
+		let mut storage_root = H256::new();
+		{
+			let mut t = TrieDBMut::new(&mut source, &mut storage_root);
+			for i in 0..50u64 {
+				t.insert(&H256::from(i).sha3(), &encode(&U256::from(i + 1))).unwrap();
+			}
+		}
+
+		let contract = H256::from(7).sha3();
+		let mut state_root = H256::new();
+		{
+			let mut t = TrieDBMut::new(&mut source, &mut state_root);
+			for i in 0..100u64 {
+				t.insert(&H256::from(i).sha3(), &account(SHA3_NULL_RLP, SHA3_EMPTY)).unwrap();
+			}
+			t.insert(&contract, &account(storage_root, code_hash)).unwrap();
+		}
+
+		let mut target = HashMap::new();
+		let mut state = StateDownload::new(state_root, &|_, _| false);
+		download(&source, &mut state, &mut target);
+
+		assert_eq!(state.pending(), 0);
+		assert!(target.contains_key(&(None, state_root)));
+		assert!(target.contains_key(&(Some(contract), storage_root)));
+		assert!(target.contains_key(&(Some(contract), code_hash)));
+		assert_eq!(state.written(), target.len());
+	}
+
+	#[test]
+	fn skips_known_subtrees() {
+		let mut source = MemoryDB::new();
+		let mut root = H256::new();
+		{
+			let mut t = TrieDBMut::new(&mut source, &mut root);
+			for i in 0..100u64 {
+				t.insert(&H256::from(i).sha3(), &account(SHA3_NULL_RLP, SHA3_EMPTY)).unwrap();
+			}
+		}
+
+		let state = StateDownload::new(root, &|a, h| a.is_none() && *h == root);
+		assert!(state.is_complete());
+		assert_eq!(state.pending(), 0);
+	}
+}
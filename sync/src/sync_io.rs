@@ -14,12 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use network::{NetworkContext, PeerId, PacketId, NetworkError};
+use network::{NetworkContext, PeerId, PacketId, NetworkError, PeerReport};
 use ethcore::client::BlockChainClient;
 
 /// IO interface for the syning handler.
 /// Provides peer connection management and an interface to the blockchain client.
-// TODO: ratings
 pub trait SyncIo {
 	/// Disable a peer
 	fn disable_peer(&mut self, peer_id: PeerId);
@@ -35,6 +34,13 @@ pub trait SyncIo {
 	fn peer_info(&self, peer_id: PeerId) -> String {
 		peer_id.to_string()
 	}
+	/// Report peer behaviour affecting its reputation
+	fn report_peer(&mut self, _peer_id: PeerId, _report: PeerReport) {
+	}
+	/// Returns peer reputation score
+	fn peer_reputation(&self, _peer_id: PeerId) -> i32 {
+		0
+	}
 	/// Returns if the chain block queue empty
 	fn is_chain_queue_empty(&self) -> bool {
 		self.chain().queue_info().is_empty()
@@ -84,6 +90,14 @@ impl<'s, 'h> SyncIo for NetSyncIo<'s, 'h> {
 		self.network.peer_info(peer_id)
 	}
 
+	fn report_peer(&mut self, peer_id: PeerId, report: PeerReport) {
+		self.network.report_peer(peer_id, report);
+	}
+
+	fn peer_reputation(&self, peer_id: PeerId) -> i32 {
+		self.network.peer_reputation(peer_id)
+	}
+
 	fn is_expired(&self) -> bool {
 		self.network.is_expired()
 	}
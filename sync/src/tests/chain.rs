@@ -15,9 +15,11 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use util::*;
+use rlp::*;
 use ethcore::client::{TestBlockChainClient, BlockChainClient, BlockID, EachBlockWith};
-use chain::{SyncState};
+use chain::{SyncState, ChainSync};
 use super::helpers::*;
+use SyncConfig;
 
 #[test]
 fn two_peers() {
@@ -101,19 +103,83 @@ fn net_hard_fork() {
 	let ref_client = TestBlockChainClient::new();
 	ref_client.add_blocks(50, EachBlockWith::Uncle);
 	{
-		let mut net = TestNet::new_with_fork(2, Some((50, ref_client.block_hash(BlockID::Number(50)).unwrap())));
+		let mut net = TestNet::new_with_fork(2, vec![(50, ref_client.block_hash(BlockID::Number(50)).unwrap())]);
 		net.peer_mut(0).chain.add_blocks(100, EachBlockWith::Uncle);
 		net.sync();
 		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 100);
 	}
 	{
-		let mut net = TestNet::new_with_fork(2, Some((50, ref_client.block_hash(BlockID::Number(50)).unwrap())));
+		let mut net = TestNet::new_with_fork(2, vec![(50, ref_client.block_hash(BlockID::Number(50)).unwrap())]);
 		net.peer_mut(0).chain.add_blocks(100, EachBlockWith::Nothing);
 		net.sync();
 		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 0);
 	}
 }
 
+#[test]
+fn net_hard_fork_checkpoints() {
+	::env_logger::init().ok();
+	let ref_client = TestBlockChainClient::new();
+	ref_client.add_blocks(80, EachBlockWith::Uncle);
+	let checkpoints = vec![
+		(30, ref_client.block_hash(BlockID::Number(30)).unwrap()),
+		(80, ref_client.block_hash(BlockID::Number(80)).unwrap()),
+	];
+	{
+		let mut net = TestNet::new_with_fork(2, checkpoints.clone());
+		net.peer_mut(0).chain.add_blocks(100, EachBlockWith::Uncle);
+		net.sync();
+		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 100);
+	}
+	{
+		// same chain up to the first checkpoint only
+		let mut net = TestNet::new_with_fork(2, checkpoints.clone());
+		net.peer_mut(0).chain.add_blocks(50, EachBlockWith::Uncle);
+		net.peer_mut(0).chain.add_blocks(50, EachBlockWith::Nothing);
+		net.sync();
+		assert_eq!(net.peer(1).chain.chain_info().best_block_number, 0);
+	}
+}
+
+#[test]
+fn fast_sync() {
+	::env_logger::init().ok();
+	let mut db = MemoryDB::new();
+	let code = vec![0x60u8, 0x00, 0x60, 0x00, 0xf3];
+	let code_hash = db.insert(&code);
+	let mut state_root = H256::new();
+	{
+		let mut t = TrieDBMut::new(&mut db, &mut state_root);
+		for i in 0..100u64 {
+			let mut account = RlpStream::new_list(4);
+			account.append(&U256::from(i)).append(&U256::from(1000)).append(&SHA3_NULL_RLP);
+			account.append(if i == 7 { &code_hash } else { &SHA3_EMPTY });
+			t.insert(&H256::from(i).sha3(), &account.out()).unwrap();
+		}
+	}
+	let nodes: Vec<Bytes> = db.drain().into_iter().filter(|&(_, (_, rc))| rc > 0).map(|(_, (node, _))| node).collect();
+
+	let mut net = TestNet::new(4);
+	for i in 1..4 {
+		net.peer_mut(i).chain.set_state(state_root, nodes.clone());
+		net.peer_mut(i).chain.add_blocks(100, EachBlockWith::Uncle);
+	}
+	let mut config = SyncConfig::default();
+	config.fast_sync = true;
+	let sync = ChainSync::new(config, &net.peer(0).chain);
+	net.peer_mut(0).sync = RwLock::new(sync);
+	net.sync();
+
+	let chain = &net.peer(0).chain;
+	assert_eq!(chain.chain_info().best_block_number, 100);
+	assert_eq!(*chain.blocks.read(), *net.peer(1).chain.blocks.read());
+	assert_eq!(*chain.state_nodes.read(), *net.peer(1).chain.state_nodes.read());
+	// blocks up to the pivot are imported along with their receipts, the rest are executed.
+	let receipts = chain.block_receipts.read();
+	assert!(receipts.contains_key(&chain.block_hash(BlockID::Number(68)).unwrap()));
+	assert!(!receipts.contains_key(&chain.block_hash(BlockID::Number(69)).unwrap()));
+}
+
 #[test]
 fn restart() {
 	let mut net = TestNet::new(3);
@@ -20,7 +20,7 @@ use ethcore::client::{TestBlockChainClient, BlockChainClient};
 use ethcore::header::BlockNumber;
 use sync_io::SyncIo;
 use chain::ChainSync;
-use ::SyncConfig;
+use ::{SyncConfig, ForkCheckpoint};
 
 pub struct TestIo<'p> {
 	pub chain: &'p mut TestBlockChainClient,
@@ -91,19 +91,23 @@ pub struct TestNet {
 
 impl TestNet {
 	pub fn new(n: usize) -> TestNet {
-		Self::new_with_fork(n, None)
+		Self::new_with_fork(n, Vec::new())
 	}
 
-	pub fn new_with_fork(n: usize, fork: Option<(BlockNumber, H256)>) -> TestNet {
+	pub fn new_with_fork(n: usize, fork: Vec<(BlockNumber, H256)>) -> TestNet {
+		let mut config = SyncConfig::default();
+		config.fork_checkpoints = fork.into_iter().map(|(number, hash)| ForkCheckpoint { number: number, hash: hash }).collect();
+		Self::new_with_config(n, config)
+	}
+
+	pub fn new_with_config(n: usize, config: SyncConfig) -> TestNet {
 		let mut net = TestNet {
 			peers: Vec::new(),
 			started: false,
 		};
 		for _ in 0..n {
 			let chain = TestBlockChainClient::new();
-			let mut config = SyncConfig::default();
-			config.fork_block = fork;
-			let sync = ChainSync::new(config, &chain);
+			let sync = ChainSync::new(config.clone(), &chain);
 			net.peers.push(TestPeer {
 				sync: RwLock::new(sync),
 				chain: chain,
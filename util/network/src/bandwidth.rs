@@ -0,0 +1,205 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Upload and download rate limiting.
+
+use std::cmp::min;
+use std::sync::Arc;
+use std::usize;
+use parking_lot::Mutex;
+use time;
+
+const NS_PER_SEC: u64 = 1_000_000_000;
+
+/// Token bucket rate limiter. Allows bursts of up to one second worth of traffic.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+	/// Bytes per second. Zero means unlimited.
+	rate: u64,
+	/// Bytes that can be transferred right now.
+	allowance: u64,
+	/// Time of the last allowance update.
+	last_update_ns: u64,
+}
+
+impl RateLimiter {
+	/// Create a new limiter for the given rate in bytes per second. Zero means unlimited.
+	pub fn new(rate: u64) -> RateLimiter {
+		RateLimiter {
+			rate: rate,
+			allowance: rate,
+			last_update_ns: time::precise_time_ns(),
+		}
+	}
+
+	/// Check if there is a limit.
+	pub fn is_limited(&self) -> bool {
+		self.rate != 0
+	}
+
+	/// Number of bytes that can be transferred now.
+	pub fn available(&mut self) -> usize {
+		self.available_at(time::precise_time_ns())
+	}
+
+	/// Account for transferred bytes.
+	pub fn consume(&mut self, size: usize) {
+		if self.is_limited() {
+			self.allowance = self.allowance.saturating_sub(size as u64);
+		}
+	}
+
+	fn available_at(&mut self, now_ns: u64) -> usize {
+		if !self.is_limited() {
+			return usize::MAX;
+		}
+		let elapsed = min(now_ns.saturating_sub(self.last_update_ns), NS_PER_SEC);
+		let added = elapsed * self.rate / NS_PER_SEC;
+		// only move the clock forward when some bytes were added so that frequent polling does not lose them
+		if added > 0 {
+			self.allowance = min(self.rate, self.allowance + added);
+			self.last_update_ns = now_ns;
+		}
+		min(self.allowance, usize::MAX as u64) as usize
+	}
+}
+
+/// Global limits shared by all connections, plus the configuration for per-connection limits.
+pub struct BandwidthLimits {
+	upload: Mutex<RateLimiter>,
+	download: Mutex<RateLimiter>,
+	peer_upload: u64,
+	peer_download: u64,
+}
+
+impl BandwidthLimits {
+	/// Create new limits. All rates are in bytes per second, zero means unlimited.
+	pub fn new(upload: u64, download: u64, peer_upload: u64, peer_download: u64) -> BandwidthLimits {
+		BandwidthLimits {
+			upload: Mutex::new(RateLimiter::new(upload)),
+			download: Mutex::new(RateLimiter::new(download)),
+			peer_upload: peer_upload,
+			peer_download: peer_download,
+		}
+	}
+
+	/// No limits at all.
+	pub fn unlimited() -> BandwidthLimits {
+		BandwidthLimits::new(0, 0, 0, 0)
+	}
+
+	/// Check if any of the limits is set.
+	pub fn is_limited(&self) -> bool {
+		self.peer_upload != 0 || self.peer_download != 0 || self.upload.lock().is_limited() || self.download.lock().is_limited()
+	}
+}
+
+/// Rate limiter of a single connection. Enforces both the connection's own and the global limits.
+#[derive(Clone)]
+pub struct ConnectionLimiter {
+	limits: Arc<BandwidthLimits>,
+	upload: RateLimiter,
+	download: RateLimiter,
+}
+
+impl ConnectionLimiter {
+	/// Create a new limiter for a connection.
+	pub fn new(limits: Arc<BandwidthLimits>) -> ConnectionLimiter {
+		ConnectionLimiter {
+			upload: RateLimiter::new(limits.peer_upload),
+			download: RateLimiter::new(limits.peer_download),
+			limits: limits,
+		}
+	}
+
+	/// Number of bytes that can be read now.
+	pub fn read_allowance(&mut self) -> usize {
+		min(self.download.available(), self.limits.download.lock().available())
+	}
+
+	/// Account for read bytes.
+	pub fn on_read(&mut self, size: usize) {
+		self.download.consume(size);
+		self.limits.download.lock().consume(size);
+	}
+
+	/// Number of bytes that can be written now.
+	pub fn write_allowance(&mut self) -> usize {
+		min(self.upload.available(), self.limits.upload.lock().available())
+	}
+
+	/// Account for written bytes.
+	pub fn on_write(&mut self, size: usize) {
+		self.upload.consume(size);
+		self.limits.upload.lock().consume(size);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use std::usize;
+
+	#[test]
+	fn unlimited() {
+		let mut limiter = RateLimiter::new(0);
+		assert!(!limiter.is_limited());
+		limiter.consume(1_000_000);
+		assert_eq!(limiter.available(), usize::MAX);
+	}
+
+	#[test]
+	fn refills_over_time() {
+		let mut limiter = RateLimiter::new(1000);
+		let start = limiter.last_update_ns;
+		assert_eq!(limiter.available_at(start), 1000);
+		limiter.consume(1000);
+		assert_eq!(limiter.available_at(start), 0);
+		assert_eq!(limiter.available_at(start + NS_PER_SEC / 4), 250);
+		limiter.consume(200);
+		assert_eq!(limiter.available_at(start + NS_PER_SEC / 2), 300);
+		// burst is capped to one second worth of traffic
+		assert_eq!(limiter.available_at(start + 10 * NS_PER_SEC), 1000);
+	}
+
+	#[test]
+	fn does_not_lose_bytes_when_polled_often() {
+		let mut limiter = RateLimiter::new(10);
+		let start = limiter.last_update_ns;
+		limiter.consume(10);
+		for i in 1..100 {
+			limiter.available_at(start + i * NS_PER_SEC / 1000);
+		}
+		assert_eq!(limiter.available_at(start + NS_PER_SEC / 10), 1);
+	}
+
+	#[test]
+	fn connection_respects_global_limit() {
+		let limits = Arc::new(BandwidthLimits::new(100, 0, 1000, 50));
+		assert!(limits.is_limited());
+		let mut a = ConnectionLimiter::new(limits.clone());
+		let mut b = ConnectionLimiter::new(limits.clone());
+		assert_eq!(a.write_allowance(), 100);
+		a.on_write(80);
+		assert!(b.write_allowance() <= 20);
+		assert!(a.read_allowance() <= 50);
+		a.on_read(50);
+		assert_eq!(a.read_allowance(), 0);
+		assert!(b.read_allowance() > 0);
+		assert!(!BandwidthLimits::unlimited().is_limited());
+	}
+}
@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::sync::Arc;
+use std::cmp::min;
 use std::collections::VecDeque;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
@@ -29,6 +30,7 @@ use error::*;
 use io::{IoContext, StreamToken};
 use handshake::Handshake;
 use stats::NetworkStats;
+use bandwidth::{BandwidthLimits, ConnectionLimiter};
 use rcrypto::blockmodes::*;
 use rcrypto::aessafe::*;
 use rcrypto::symmetriccipher::*;
@@ -37,6 +39,8 @@ use tiny_keccak::Keccak;
 use crypto;
 
 const ENCRYPTED_HEADER_LEN: usize = 32;
+/// Maximum packet payload size. Frame size is encoded with 24 bits.
+pub const MAX_PAYLOAD_SIZE: usize = (1 << 24) - 1;
 const RECIEVE_PAYLOAD_TIMEOUT: u64 = 30000;
 
 pub trait GenericSocket : Read + Write {
@@ -62,6 +66,16 @@ pub struct GenericConnection<Socket: GenericSocket> {
 	stats: Arc<NetworkStats>,
 	/// Registered flag
 	registered: AtomicBool,
+	/// Upload and download rate limiter
+	limiter: ConnectionLimiter,
+	/// Reading stopped because the download limit was hit
+	read_throttled: bool,
+	/// Writing stopped because the upload limit was hit
+	write_throttled: bool,
+	/// Bytes sent over this connection
+	sent_bytes: u64,
+	/// Bytes received over this connection
+	recv_bytes: u64,
 }
 
 impl<Socket: GenericSocket> GenericConnection<Socket> {
@@ -75,15 +89,24 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 
 	/// Readable IO handler. Called when there is some data to be read.
 	pub fn readable(&mut self) -> io::Result<Option<Bytes>> {
+		self.read_throttled = false;
 		if self.rec_size == 0 || self.rec_buf.len() >= self.rec_size {
 			return Ok(None);
 		}
 		let sock_ref = <Socket as Read>::by_ref(&mut self.socket);
 		loop {
-			let max = self.rec_size - self.rec_buf.len();
+			let max = min(self.rec_size - self.rec_buf.len(), self.limiter.read_allowance());
+			if max == 0 {
+				// data is left in the socket, the host resumes reading once the limit allows.
+				trace!(target:"network", "{}: Read throttled", self.token);
+				self.read_throttled = true;
+				return Ok(None);
+			}
 			match sock_ref.take(max as u64).try_read_buf(&mut self.rec_buf) {
 				Ok(Some(size)) if size != 0  => {
 					self.stats.inc_recv(size);
+					self.limiter.on_read(size);
+					self.recv_bytes += size as u64;
 					trace!(target:"network", "{}: Read {} of {} bytes", self.token, self.rec_buf.len(), self.rec_size);
 					if self.rec_size != 0 && self.rec_buf.len() == self.rec_size {
 						self.rec_size = 0;
@@ -119,25 +142,56 @@ impl<Socket: GenericSocket> GenericConnection<Socket> {
 		self.interest.is_writable()
 	}
 
+	/// Check if reading or writing is paused by a rate limit.
+	pub fn is_throttled(&self) -> bool {
+		self.read_throttled || self.write_throttled
+	}
+
+	/// Bytes sent over this connection.
+	pub fn sent_bytes(&self) -> u64 {
+		self.sent_bytes
+	}
+
+	/// Bytes received over this connection.
+	pub fn recv_bytes(&self) -> u64 {
+		self.recv_bytes
+	}
+
 	/// Writable IO handler. Called when the socket is ready to send.
 	pub fn writable<Message>(&mut self, io: &IoContext<Message>) -> Result<WriteStatus, NetworkError> where Message: Send + Clone {
+		self.write_throttled = false;
 		if self.send_queue.is_empty() {
 			return Ok(WriteStatus::Complete)
 		}
+		let allowance = self.limiter.write_allowance();
+		if allowance == 0 {
+			// the host resumes writing once the limit allows.
+			trace!(target:"network", "{}: Write throttled", self.token);
+			self.write_throttled = true;
+			return Ok(WriteStatus::Ongoing)
+		}
 		{
 			let buf = self.send_queue.front_mut().unwrap();
 			let send_size = buf.get_ref().len();
-			if (buf.position() as usize) >= send_size {
+			let position = buf.position() as usize;
+			if position >= send_size {
 				warn!(target:"net", "Unexpected connection data");
 				return Ok(WriteStatus::Complete)
 			}
-			match self.socket.try_write_buf(buf) {
-				Ok(Some(size)) if (buf.position() as usize) < send_size => {
+			let end = position + min(allowance, send_size - position);
+			match self.socket.try_write(&buf.get_ref()[position..end]) {
+				Ok(Some(size)) if position + size < send_size => {
+					buf.set_position((position + size) as u64);
 					self.stats.inc_send(size);
+					self.limiter.on_write(size);
+					self.sent_bytes += size as u64;
 					Ok(WriteStatus::Ongoing)
 				},
-				Ok(Some(size)) if (buf.position() as usize) == send_size => {
+				Ok(Some(size)) if position + size == send_size => {
+					buf.set_position(send_size as u64);
 					self.stats.inc_send(size);
+					self.limiter.on_write(size);
+					self.sent_bytes += size as u64;
 					trace!(target:"network", "{}: Wrote {} bytes", self.token, send_size);
 					Ok(WriteStatus::Complete)
 				},
@@ -163,7 +217,7 @@ pub type Connection = GenericConnection<TcpStream>;
 
 impl Connection {
 	/// Create a new connection with given id and socket.
-	pub fn new(token: StreamToken, socket: TcpStream, stats: Arc<NetworkStats>) -> Connection {
+	pub fn new(token: StreamToken, socket: TcpStream, stats: Arc<NetworkStats>, limits: Arc<BandwidthLimits>) -> Connection {
 		Connection {
 			token: token,
 			socket: socket,
@@ -173,6 +227,11 @@ impl Connection {
 			interest: EventSet::hup() | EventSet::readable(),
 			stats: stats,
 			registered: AtomicBool::new(false),
+			limiter: ConnectionLimiter::new(limits),
+			read_throttled: false,
+			write_throttled: false,
+			sent_bytes: 0,
+			recv_bytes: 0,
 		}
 	}
 
@@ -202,6 +261,11 @@ impl Connection {
 			interest: EventSet::hup(),
 			stats: self.stats.clone(),
 			registered: AtomicBool::new(false),
+			limiter: self.limiter.clone(),
+			read_throttled: false,
+			write_throttled: false,
+			sent_bytes: self.sent_bytes,
+			recv_bytes: self.recv_bytes,
 		})
 	}
 
@@ -492,6 +556,7 @@ mod tests {
 	use std::sync::Arc;
 	use std::sync::atomic::AtomicBool;
 	use super::super::stats::*;
+	use bandwidth::{BandwidthLimits, ConnectionLimiter};
 	use std::io::{Read, Write, Error, Cursor, ErrorKind};
 	use mio::{EventSet};
 	use std::collections::VecDeque;
@@ -542,6 +607,11 @@ mod tests {
 				interest: EventSet::hup() | EventSet::readable(),
 				stats: Arc::<NetworkStats>::new(NetworkStats::new()),
 				registered: AtomicBool::new(false),
+				limiter: ConnectionLimiter::new(Arc::new(BandwidthLimits::unlimited())),
+				read_throttled: false,
+				write_throttled: false,
+				sent_bytes: 0,
+				recv_bytes: 0,
 			}
 		}
 	}
@@ -565,6 +635,11 @@ mod tests {
 				interest: EventSet::hup() | EventSet::readable(),
 				stats: Arc::<NetworkStats>::new(NetworkStats::new()),
 				registered: AtomicBool::new(false),
+				limiter: ConnectionLimiter::new(Arc::new(BandwidthLimits::unlimited())),
+				read_throttled: false,
+				write_throttled: false,
+				sent_bytes: 0,
+				recv_bytes: 0,
 			}
 		}
 	}
@@ -671,4 +746,35 @@ mod tests {
 		assert!(status.is_ok());
 		assert_eq!(0, connection.socket.cursor);
 	}
+
+	#[test]
+	fn connection_write_throttled() {
+		let mut connection = TestConnection::new();
+		connection.limiter = ConnectionLimiter::new(Arc::new(BandwidthLimits::new(0, 0, 1000, 0)));
+		connection.send_queue.push_back(Cursor::new(vec![0; 10240]));
+
+		let status = connection.writable(&test_io());
+		assert!(WriteStatus::Ongoing == status.unwrap());
+		assert_eq!(1000, connection.socket.write_buffer.len());
+		assert_eq!(1000, connection.sent_bytes());
+
+		let status = connection.writable(&test_io());
+		assert!(WriteStatus::Ongoing == status.unwrap());
+		assert!(connection.is_throttled());
+		assert!(connection.socket.write_buffer.len() < 1100);
+	}
+
+	#[test]
+	fn connection_read_throttled() {
+		let mut connection = TestConnection::new();
+		connection.limiter = ConnectionLimiter::new(Arc::new(BandwidthLimits::new(0, 512, 0, 0)));
+		connection.rec_size = 2048;
+		connection.socket.read_buffer = vec![99; 2048];
+
+		let status = connection.readable();
+		assert!(status.unwrap().is_none());
+		assert!(connection.is_throttled());
+		assert!(connection.socket.cursor < 600);
+		assert!(connection.recv_bytes() >= 512);
+	}
 }
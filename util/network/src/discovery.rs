@@ -26,12 +26,11 @@ use time;
 use util::hash::*;
 use rlp::*;
 use node_table::*;
+use ip_filter::{IpFilter, AllowIP};
 use error::NetworkError;
 use io::{StreamToken, IoContext};
 use ethkey::{Secret, KeyPair, sign, recover};
 
-use PROTOCOL_VERSION;
-
 const ADDRESS_BYTES_SIZE: u32 = 32;							// Size of address type in bytes.
 const ADDRESS_BITS: u32 = 8 * ADDRESS_BYTES_SIZE;			// Denoted by n in [Kademlia].
 const NODE_BINS: u32 = ADDRESS_BITS - 1;					// Size of m_state (excludes root, which is us).
@@ -39,6 +38,7 @@ const DISCOVERY_MAX_STEPS: u16 = 8;							// Max iterations of discovery. (disco
 const BUCKET_SIZE: usize = 16;		// Denoted by k in [Kademlia]. Number of nodes stored in each bucket.
 const ALPHA: usize = 3;				// Denoted by \alpha in [Kademlia]. Number of concurrent FindNode requests.
 const MAX_DATAGRAM_SIZE: usize = 1280;
+const DISCOVERY_VERSION: u32 = 4;
 
 const PACKET_PING: u8 = 1;
 const PACKET_PONG: u8 = 2;
@@ -95,15 +95,18 @@ pub struct Discovery {
 	send_queue: VecDeque<Datagramm>,
 	check_timestamps: bool,
 	adding_nodes: Vec<NodeEntry>,
+	ip_filter: IpFilter,
 }
 
 pub struct TableUpdates {
 	pub added: HashMap<NodeId, NodeEntry>,
 	pub removed: HashSet<NodeId>,
+	/// Nodes that have sent us a valid discovery packet.
+	pub seen: HashSet<NodeId>,
 }
 
 impl Discovery {
-	pub fn new(key: &KeyPair, listen: SocketAddr, public: NodeEndpoint, token: StreamToken) -> Discovery {
+	pub fn new(key: &KeyPair, listen: SocketAddr, public: NodeEndpoint, token: StreamToken, ip_filter: IpFilter) -> Discovery {
 		let socket = UdpSocket::bound(&listen).expect("Error binding UDP socket");
 		Discovery {
 			id: key.public().clone(),
@@ -118,6 +121,23 @@ impl Discovery {
 			send_queue: VecDeque::new(),
 			check_timestamps: true,
 			adding_nodes: Vec::new(),
+			ip_filter: ip_filter,
+		}
+	}
+
+	/// Replace the filter for addresses of discovered nodes.
+	pub fn set_ip_filter(&mut self, ip_filter: IpFilter) {
+		self.ip_filter = ip_filter;
+	}
+
+	/// Whether discovered nodes at the endpoint may be added to the table. Unless the filter
+	/// asks for a specific set of addresses, only globally routable ones and networks allowed
+	/// explicitly are accepted.
+	fn is_allowed(&self, endpoint: &NodeEndpoint) -> bool {
+		let explicitly_allowed = || self.ip_filter.custom_allow.iter().any(|n| n.contains(&endpoint.address.ip()));
+		endpoint.is_allowed(&self.ip_filter) && match self.ip_filter.predefined {
+			AllowIP::All => endpoint.is_global() || explicitly_allowed(),
+			_ => true,
 		}
 	}
 
@@ -128,15 +148,17 @@ impl Discovery {
 		self.ping(&endpoint);
 	}
 
-	/// Add a list of nodes. Pings a few nodes each round
-	pub fn add_node_list(&mut self, nodes: Vec<NodeEntry>) {
+	/// Add a list of nodes, most reliable first. Pings a few nodes each round, starting with the most reliable ones.
+	pub fn add_node_list(&mut self, mut nodes: Vec<NodeEntry>) {
+		nodes.reverse();
 		self.adding_nodes = nodes;
 		self.update_new_nodes();
 	}
 
-	/// Add a list of known nodes to the table.
-	pub fn init_node_list(&mut self, mut nodes: Vec<NodeEntry>) {
-		for n in nodes.drain(..) {
+	/// Seed the table with a list of known nodes, most reliable first.
+	/// The most reliable nodes end up at the front of their buckets and are kept when a bucket overflows.
+	pub fn init_node_list(&mut self, nodes: Vec<NodeEntry>) {
+		for n in nodes.into_iter().rev() {
 			self.update_node(n);
 		}
 	}
@@ -232,7 +254,7 @@ impl Discovery {
 
 	fn ping(&mut self, node: &NodeEndpoint) {
 		let mut rlp = RlpStream::new_list(3);
-		rlp.append(&PROTOCOL_VERSION);
+		rlp.append(&DISCOVERY_VERSION);
 		self.public_endpoint.to_rlp_list(&mut rlp);
 		node.to_rlp_list(&mut rlp);
 		trace!(target: "discovery", "Sent Ping to {:?}", &node);
@@ -394,7 +416,7 @@ impl Discovery {
 		try!(self.check_timestamp(timestamp));
 		let mut added_map = HashMap::new();
 		let entry = NodeEntry { id: node.clone(), endpoint: source.clone() };
-		if !entry.endpoint.is_valid() || !entry.endpoint.is_global() {
+		if !entry.endpoint.is_valid() || !self.is_allowed(&entry.endpoint) {
 			debug!(target: "discovery", "Got bad address: {:?}", entry);
 		}
 		else {
@@ -407,7 +429,9 @@ impl Discovery {
 		response.append(&hash);
 		self.send_packet(PACKET_PONG, from, &response.drain());
 
-		Ok(Some(TableUpdates { added: added_map, removed: HashSet::new() }))
+		let mut seen = HashSet::new();
+		seen.insert(node.clone());
+		Ok(Some(TableUpdates { added: added_map, removed: HashSet::new(), seen: seen }))
 	}
 
 	fn on_pong(&mut self, rlp: &UntrustedRlp, node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
@@ -416,15 +440,13 @@ impl Discovery {
 		let dest = try!(NodeEndpoint::from_rlp(&try!(rlp.at(0))));
 		let timestamp: u64 = try!(rlp.val_at(2));
 		try!(self.check_timestamp(timestamp));
-		let mut entry = NodeEntry { id: node.clone(), endpoint: dest };
-		if !entry.endpoint.is_valid() {
-			debug!(target: "discovery", "Bad address: {:?}", entry);
-			entry.endpoint.address = from.clone();
+		if !dest.is_valid() {
+			debug!(target: "discovery", "Bad address: {:?}", dest);
 		}
 		self.clear_ping(node);
-		let mut added_map = HashMap::new();
-		added_map.insert(node.clone(), entry);
-		Ok(None)
+		let mut seen = HashSet::new();
+		seen.insert(node.clone());
+		Ok(Some(TableUpdates { added: HashMap::new(), removed: HashSet::new(), seen: seen }))
 	}
 
 	fn on_find_node(&mut self, rlp: &UntrustedRlp, _node: &NodeId, from: &SocketAddr) -> Result<Option<TableUpdates>, NetworkError> {
@@ -470,6 +492,10 @@ impl Discovery {
 				debug!(target: "discovery", "Bad address: {:?}", endpoint);
 				continue;
 			}
+			if !self.is_allowed(&endpoint) {
+				debug!(target: "discovery", "Address not allowed: {:?}", endpoint);
+				continue;
+			}
 			let node_id: NodeId = try!(r.val_at(3));
 			if node_id == self.id {
 				continue;
@@ -479,7 +505,7 @@ impl Discovery {
 			self.ping(&entry.endpoint);
 			self.update_node(entry);
 		}
-		Ok(Some(TableUpdates { added: added, removed: HashSet::new() }))
+		Ok(Some(TableUpdates { added: added, removed: HashSet::new(), seen: HashSet::new() }))
 	}
 
 	fn check_expired(&mut self, force: bool) -> HashSet<NodeId> {
@@ -506,7 +532,7 @@ impl Discovery {
 		let removed = self.check_expired(false);
 		self.discover();
 		if !removed.is_empty() {
-			Some(TableUpdates { added: HashMap::new(), removed: removed })
+			Some(TableUpdates { added: HashMap::new(), removed: removed, seen: HashSet::new() })
 		} else { None }
 	}
 
@@ -536,6 +562,7 @@ mod tests {
 	use util::hash::*;
 	use std::net::*;
 	use node_table::*;
+	use ip_filter::{IpFilter, AllowIP};
 	use std::str::FromStr;
 	use rustc_serialize::hex::FromHex;
 	use ethkey::{Random, Generator};
@@ -563,8 +590,9 @@ mod tests {
 		let key2 = Random.generate().unwrap();
 		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40444").unwrap(), udp_port: 40444 };
 		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40445").unwrap(), udp_port: 40445 };
-		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0);
-		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0);
+		let filter = IpFilter { predefined: AllowIP::Private, ..IpFilter::default() };
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0, filter.clone());
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0, filter);
 
 		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@127.0.0.1:7770").unwrap();
 		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@127.0.0.1:7771").unwrap();
@@ -592,11 +620,27 @@ mod tests {
 		assert_eq!(Discovery::nearest_node_entries(&NodeId::new(), &discovery2.node_buckets).len(), 3)
 	}
 
+	#[test]
+	fn ignores_loopback_ping_source_by_default() {
+		let key1 = Random.generate().unwrap();
+		let key2 = Random.generate().unwrap();
+		let ep1 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40449").unwrap(), udp_port: 40449 };
+		let ep2 = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40450").unwrap(), udp_port: 40450 };
+		let mut discovery1 = Discovery::new(&key1, ep1.address.clone(), ep1.clone(), 0, IpFilter::default());
+		let mut discovery2 = Discovery::new(&key2, ep2.address.clone(), ep2.clone(), 0, IpFilter::default());
+
+		discovery1.ping(&ep2);
+		let datagramm = discovery1.send_queue.pop_front().unwrap();
+		let updates = discovery2.on_packet(&datagramm.payload, ep1.address.clone()).unwrap().unwrap();
+		assert!(updates.added.is_empty());
+		assert!(Discovery::nearest_node_entries(&NodeId::new(), &discovery2.node_buckets).is_empty());
+	}
+
 	#[test]
 	fn removes_expired() {
 		let key = Random.generate().unwrap();
 		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40446").unwrap(), udp_port: 40447 };
-		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0);
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, IpFilter::default());
 		for _ in 0..1200 {
 			discovery.add_node(NodeEntry { id: NodeId::random(), endpoint: ep.clone() });
 		}
@@ -605,6 +649,20 @@ mod tests {
 		assert!(removed > 0);
 	}
 
+	#[test]
+	fn pings_most_reliable_first() {
+		let key = Random.generate().unwrap();
+		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40448").unwrap(), udp_port: 40448 };
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, IpFilter::default());
+		let nodes: Vec<_> = (0..(MAX_NODES_PING + 8)).map(|_| NodeEntry { id: NodeId::random(), endpoint: ep.clone() }).collect();
+		discovery.add_node_list(nodes.clone());
+		let mut pending: Vec<_> = discovery.adding_nodes.iter().map(|n| n.id.clone()).collect();
+		pending.sort();
+		let mut least_reliable: Vec<_> = nodes[MAX_NODES_PING..].iter().map(|n| n.id.clone()).collect();
+		least_reliable.sort();
+		assert_eq!(pending, least_reliable);
+	}
+
 	#[test]
 	fn find_nearest_saturated() {
 		use super::*;
@@ -624,7 +682,7 @@ mod tests {
 	fn packets() {
 		let key = Random.generate().unwrap();
 		let ep = NodeEndpoint { address: SocketAddr::from_str("127.0.0.1:40447").unwrap(), udp_port: 40447 };
-		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0);
+		let mut discovery = Discovery::new(&key, ep.address.clone(), ep.clone(), 0, IpFilter::default());
 		discovery.check_timestamps = false;
 		let from = SocketAddr::from_str("99.99.99.99:40445").unwrap();
 
@@ -106,6 +106,8 @@ pub enum NetworkError {
 	AddressResolve(Option<::std::io::Error>),
 	/// Error concerning the Rust standard library's IO subsystem.
 	StdIo(::std::io::Error),
+	/// Invalid IP filter mode or network specification.
+	InvalidIpFilter(String),
 }
 
 impl fmt::Display for NetworkError {
@@ -124,6 +126,7 @@ impl fmt::Display for NetworkError {
 			AddressResolve(_) => "Failed to resolve network address.".into(),
 			StdIo(ref err) => format!("{}", err),
 			Util(ref err) => format!("{}", err),
+			InvalidIpFilter(ref filter) => format!("Invalid IP filter: {}", filter),
 		};
 
 		f.write_fmt(format_args!("Network error ({})", msg))
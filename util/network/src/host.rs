@@ -28,7 +28,7 @@ use ethkey::{KeyPair, Secret, Random, Generator};
 use mio::*;
 use mio::tcp::*;
 use util::hash::*;
-use util::Hashable;
+use util::{Hashable, UtilError};
 use util::version;
 use rlp::*;
 use session::{Session, SessionData};
@@ -36,9 +36,11 @@ use error::*;
 use io::*;
 use {NetworkProtocolHandler, NonReservedPeerMode, PROTOCOL_VERSION};
 use node_table::*;
-use stats::NetworkStats;
+use stats::{NetworkStats, PeerTraffic};
+use bandwidth::BandwidthLimits;
 use discovery::{Discovery, TableUpdates, NodeEntry};
 use ip_utils::{map_external_address, select_public_address};
+use ip_filter::IpFilter;
 use util::path::restrict_permissions_owner;
 use parking_lot::{Mutex, RwLock};
 
@@ -74,10 +76,27 @@ pub struct NetworkConfiguration {
 	pub min_peers: u32,
 	/// Maximum allowd number of peers
 	pub max_peers: u32,
+	/// Maximum number of peers connected to us. None means only `max_peers` applies.
+	pub max_inbound_peers: Option<u32>,
+	/// Maximum number of peers we connect to. None means only `max_peers` applies.
+	pub max_outbound_peers: Option<u32>,
+	/// Number of slots inbound peers can't take, so that there is room for peers chosen by discovery.
+	/// Capped to half of `max_peers`.
+	pub reserved_outbound_peers: u32,
 	/// List of reserved node addresses.
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub non_reserved_mode: NonReservedPeerMode,
+	/// Filter for addresses of non-reserved peers.
+	pub ip_filter: IpFilter,
+	/// Upload limit for all connections in bytes per second. None means unlimited.
+	pub max_upload_rate: Option<u64>,
+	/// Download limit for all connections in bytes per second. None means unlimited.
+	pub max_download_rate: Option<u64>,
+	/// Upload limit for a single connection in bytes per second. None means unlimited.
+	pub peer_max_upload_rate: Option<u64>,
+	/// Download limit for a single connection in bytes per second. None means unlimited.
+	pub peer_max_download_rate: Option<u64>,
 }
 
 impl Default for NetworkConfiguration {
@@ -101,8 +120,16 @@ impl NetworkConfiguration {
 			use_secret: None,
 			min_peers: 25,
 			max_peers: 50,
+			max_inbound_peers: None,
+			max_outbound_peers: None,
+			reserved_outbound_peers: 10,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
+			ip_filter: IpFilter::default(),
+			max_upload_rate: None,
+			max_download_rate: None,
+			peer_max_upload_rate: None,
+			peer_max_download_rate: None,
 		}
 	}
 
@@ -120,8 +147,32 @@ impl NetworkConfiguration {
 		config.nat_enabled = false;
 		config
 	}
+
+	/// Number of slots kept for outbound peers.
+	pub fn reserved_outbound(&self) -> u32 {
+		min(self.reserved_outbound_peers, self.max_peers / 2)
+	}
+
+	/// Effective limit of inbound peers.
+	pub fn max_inbound(&self) -> u32 {
+		min(self.max_inbound_peers.unwrap_or(self.max_peers), self.max_peers - self.reserved_outbound())
+	}
+
+	/// Effective limit of outbound peers.
+	pub fn max_outbound(&self) -> u32 {
+		min(self.max_outbound_peers.unwrap_or(self.max_peers), self.max_peers)
+	}
+
+	/// Check if a new inbound peer can be accepted with the given number of sessions.
+	/// Inbound peers never take the slots reserved for outbound ones.
+	fn accepts_inbound(&self, inbound: usize, outbound: usize) -> bool {
+		inbound < self.max_inbound() as usize && inbound + outbound < self.max_peers as usize
+	}
 }
 
+// Interval of resuming connections paused by bandwidth limits.
+const BANDWIDTH_TIMEOUT_MS: u64 = 100;
+
 // Tokens
 const TCP_ACCEPT: usize = SYS_TIMER + 1;
 const IDLE: usize = SYS_TIMER + 2;
@@ -129,6 +180,7 @@ const DISCOVERY: usize = SYS_TIMER + 3;
 const DISCOVERY_REFRESH: usize = SYS_TIMER + 4;
 const DISCOVERY_ROUND: usize = SYS_TIMER + 5;
 const NODE_TABLE: usize = SYS_TIMER + 6;
+const BANDWIDTH: usize = SYS_TIMER + 7;
 const FIRST_SESSION: usize = 0;
 const LAST_SESSION: usize = FIRST_SESSION + MAX_SESSIONS - 1;
 const USER_TIMER: usize = LAST_SESSION + 256;
@@ -148,6 +200,8 @@ pub enum NetworkIoMessage {
 		handler: Arc<NetworkProtocolHandler + Sync>,
 		/// Protocol Id.
 		protocol: ProtocolId,
+		/// Number of packet IDs the protocol uses.
+		packet_count: u8,
 		/// Supported protocol versions.
 		versions: Vec<u8>,
 	},
@@ -166,6 +220,8 @@ pub enum NetworkIoMessage {
 	Disconnect(PeerId),
 	/// Disconnect and temporary disable peer.
 	DisablePeer(PeerId),
+	/// Adjust peer reputation.
+	ReportPeer(PeerId, PeerReport),
 	/// Network has been started with the host as the given enode.
 	NetworkStarted(String),
 }
@@ -198,6 +254,7 @@ pub struct NetworkContext<'s> {
 	session: Option<SharedSession>,
 	session_id: Option<StreamToken>,
 	_reserved_peers: &'s HashSet<NodeId>,
+	nodes: &'s RwLock<NodeTable>,
 }
 
 impl<'s> NetworkContext<'s> {
@@ -205,7 +262,8 @@ impl<'s> NetworkContext<'s> {
 	fn new(io: &'s IoContext<NetworkIoMessage>,
 		protocol: ProtocolId,
 		session: Option<SharedSession>, sessions: Arc<RwLock<Slab<SharedSession>>>,
-		reserved_peers: &'s HashSet<NodeId>) -> NetworkContext<'s> {
+		reserved_peers: &'s HashSet<NodeId>,
+		nodes: &'s RwLock<NodeTable>) -> NetworkContext<'s> {
 		let id = session.as_ref().map(|s| s.lock().token());
 		NetworkContext {
 			io: io,
@@ -214,6 +272,7 @@ impl<'s> NetworkContext<'s> {
 			session: session,
 			sessions: sessions,
 			_reserved_peers: reserved_peers,
+			nodes: nodes,
 		}
 	}
 
@@ -259,6 +318,18 @@ impl<'s> NetworkContext<'s> {
 			.unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
 	}
 
+	/// Report peer behaviour. Peers with low reputation get disconnected and banned.
+	pub fn report_peer(&self, peer: PeerId, report: PeerReport) {
+		self.io.message(NetworkIoMessage::ReportPeer(peer, report))
+			.unwrap_or_else(|e| warn!("Error sending network IO message: {:?}", e));
+	}
+
+	/// Returns peer reputation score.
+	pub fn peer_reputation(&self, peer: PeerId) -> i32 {
+		let id = self.resolve_session(peer).and_then(|s| s.lock().id().cloned());
+		id.map_or(0, |id| self.nodes.read().reputation(&id).score)
+	}
+
 	/// Check if the session is still active.
 	pub fn is_expired(&self) -> bool {
 		self.session.as_ref().map_or(false, |s| s.lock().expired())
@@ -341,8 +412,10 @@ pub struct Host {
 	timers: RwLock<HashMap<TimerToken, ProtocolTimer>>,
 	timer_counter: RwLock<usize>,
 	stats: Arc<NetworkStats>,
+	bandwidth: Arc<BandwidthLimits>,
 	reserved_nodes: RwLock<HashSet<NodeId>>,
-	num_sessions: AtomicUsize,
+	num_inbound: AtomicUsize,
+	num_outbound: AtomicUsize,
 	stopping: AtomicBool,
 }
 
@@ -378,6 +451,11 @@ impl Host {
 
 		let boot_nodes = config.boot_nodes.clone();
 		let reserved_nodes = config.reserved_nodes.clone();
+		let bandwidth = BandwidthLimits::new(
+			config.max_upload_rate.unwrap_or(0),
+			config.max_download_rate.unwrap_or(0),
+			config.peer_max_upload_rate.unwrap_or(0),
+			config.peer_max_download_rate.unwrap_or(0));
 
 		let mut host = Host {
 			info: RwLock::new(HostInfo {
@@ -398,8 +476,10 @@ impl Host {
 			timers: RwLock::new(HashMap::new()),
 			timer_counter: RwLock::new(USER_TIMER),
 			stats: stats,
+			bandwidth: Arc::new(bandwidth),
 			reserved_nodes: RwLock::new(HashSet::new()),
-			num_sessions: AtomicUsize::new(0),
+			num_inbound: AtomicUsize::new(0),
+			num_outbound: AtomicUsize::new(0),
 			stopping: AtomicBool::new(false),
 		};
 
@@ -473,6 +553,37 @@ impl Host {
 		}
 	}
 
+	/// Replace the address filter. Disconnects non-reserved peers that are no longer allowed.
+	pub fn set_ip_filter(&self, ip_filter: IpFilter, io: &IoContext<NetworkIoMessage>) {
+		self.info.write().config.ip_filter = ip_filter.clone();
+		if let Some(ref mut discovery) = *self.discovery.lock() {
+			discovery.set_ip_filter(ip_filter);
+		}
+		let mut to_kill = Vec::new();
+		for e in self.sessions.read().iter() {
+			let mut s = e.lock();
+			if s.is_ready() && !self.is_address_allowed(&s) {
+				s.disconnect(io, DisconnectReason::UselessPeer);
+				to_kill.push(s.token());
+			}
+		}
+		for p in to_kill {
+			trace!(target: "network", "Disconnecting on IP filter change: {}", p);
+			self.kill_connection(p, io, false);
+		}
+	}
+
+	/// Check the session's remote address against the filter. Reserved peers are always allowed.
+	fn is_address_allowed(&self, s: &Session) -> bool {
+		if s.id().map_or(false, |id| self.reserved_nodes.read().contains(id)) {
+			return true;
+		}
+		match s.remote_addr() {
+			Ok(address) => self.info.read().config.ip_filter.is_allowed(&address.ip()),
+			Err(_) => true,
+		}
+	}
+
 	pub fn remove_reserved_node(&self, id: &str) -> Result<(), NetworkError> {
 		let n = try!(Node::from_str(id));
 		self.reserved_nodes.write().remove(&n.id);
@@ -480,6 +591,56 @@ impl Host {
 		Ok(())
 	}
 
+	/// Returns traffic of all connected peers.
+	pub fn peers_traffic(&self) -> Vec<PeerTraffic> {
+		self.sessions.read().iter().filter_map(|s| s.lock().traffic()).collect()
+	}
+
+	/// Returns reputation of all nodes that have one.
+	pub fn node_reputations(&self) -> Vec<(NodeId, NodeReputation)> {
+		self.nodes.read().reputations()
+	}
+
+	/// Set node reputation. Takes either a node id or an enode URL. Disconnects the node if it gets banned.
+	pub fn set_node_reputation(&self, node: &str, score: i32, io: &IoContext<NetworkIoMessage>) -> Result<(), NetworkError> {
+		let id = if node.starts_with("enode://") {
+			try!(Node::from_str(node)).id
+		} else {
+			try!(NodeId::from_str(node).map_err(UtilError::from))
+		};
+		if self.nodes.write().set_reputation(&id, score) {
+			self.disconnect_banned(&id, io);
+		}
+		Ok(())
+	}
+
+	fn report_node(&self, id: &NodeId, report: PeerReport) -> bool {
+		// reserved nodes are never banned
+		if self.reserved_nodes.read().contains(id) {
+			return false;
+		}
+		let banned = self.nodes.write().report(id, report);
+		if banned {
+			debug!(target: "network", "Banned node {}", id);
+		}
+		banned
+	}
+
+	fn disconnect_banned(&self, id: &NodeId, io: &IoContext<NetworkIoMessage>) {
+		let mut to_kill = Vec::new();
+		for e in self.sessions.read().iter() {
+			let mut s = e.lock();
+			if s.id() == Some(id) {
+				s.disconnect(io, DisconnectReason::UselessPeer);
+				to_kill.push(s.token());
+			}
+		}
+		for p in to_kill {
+			trace!(target: "network", "Disconnecting banned peer: {}", p);
+			self.kill_connection(p, io, false);
+		}
+	}
+
 	pub fn client_version() -> String {
 		version()
 	}
@@ -547,19 +708,23 @@ impl Host {
 			if info.config.discovery_enabled && info.config.non_reserved_mode == NonReservedPeerMode::Accept {
 				let mut udp_addr = local_endpoint.address.clone();
 				udp_addr.set_port(local_endpoint.udp_port);
-				Some(Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY))
+				Some(Discovery::new(&info.keys, udp_addr, public_endpoint, DISCOVERY, info.config.ip_filter.clone()))
 			} else { None }
 		};
 
 		if let Some(mut discovery) = discovery {
-			discovery.init_node_list(self.nodes.read().unordered_entries());
-			discovery.add_node_list(self.nodes.read().unordered_entries());
+			let entries = self.nodes.read().entries(&self.info.read().config.ip_filter);
+			discovery.init_node_list(entries.clone());
+			discovery.add_node_list(entries);
 			*self.discovery.lock() = Some(discovery);
 			io.register_stream(DISCOVERY).expect("Error registering UDP listener");
 			io.register_timer(DISCOVERY_REFRESH, 7200).expect("Error registering discovery timer");
 			io.register_timer(DISCOVERY_ROUND, 300).expect("Error registering discovery timer");
 		}
 		try!(io.register_timer(NODE_TABLE, 300_000));
+		if self.bandwidth.is_limited() {
+			try!(io.register_timer(BANDWIDTH, BANDWIDTH_TIMEOUT_MS));
+		}
 		try!(io.register_stream(TCP_ACCEPT));
 		Ok(())
 	}
@@ -574,7 +739,12 @@ impl Host {
 	}
 
 	fn session_count(&self) -> usize {
-		self.num_sessions.load(AtomicOrdering::Relaxed)
+		self.num_inbound.load(AtomicOrdering::Relaxed) + self.num_outbound.load(AtomicOrdering::Relaxed)
+	}
+
+	/// Returns number of inbound and outbound sessions.
+	pub fn session_counts(&self) -> (usize, usize) {
+		(self.num_inbound.load(AtomicOrdering::Relaxed), self.num_outbound.load(AtomicOrdering::Relaxed))
 	}
 
 	fn connecting_to(&self, id: &NodeId) -> bool {
@@ -587,33 +757,58 @@ impl Host {
 
 	fn keep_alive(&self, io: &IoContext<NetworkIoMessage>) {
 		let mut to_kill = Vec::new();
+		let mut timed_out = Vec::new();
 		for e in self.sessions.write().iter_mut() {
 			let mut s = e.lock();
 			if !s.keep_alive(io) {
 				s.disconnect(io, DisconnectReason::PingTimeout);
 				to_kill.push(s.token());
+				if let Some(id) = s.id() {
+					timed_out.push(id.clone());
+				}
 			}
 		}
 		for p in to_kill {
 			trace!(target: "network", "Ping timeout: {}", p);
 			self.kill_connection(p, io, true);
 		}
+		for id in timed_out {
+			self.report_node(&id, PeerReport::Timeout);
+		}
+	}
+
+	/// Continue reading and writing on connections paused by bandwidth limits.
+	fn resume_throttled(&self, io: &IoContext<NetworkIoMessage>) {
+		let throttled: Vec<StreamToken> = self.sessions.read().iter()
+			.map(|s| s.lock())
+			.filter(|s| s.is_throttled())
+			.map(|s| s.token())
+			.collect();
+		for token in throttled {
+			self.session_writable(token, io);
+			self.session_readable(token, io);
+		}
 	}
 
 	fn connect_peers(&self, io: &IoContext<NetworkIoMessage>) {
-		let (min_peers, mut pin) = {
+		let (min_peers, max_peers, max_outbound, reserved_outbound, mut pin, ip_filter) = {
 			let info = self.info.read();
 			if info.capabilities.is_empty() {
 				return;
 			}
 			let config = &info.config;
 
-			(config.min_peers, config.non_reserved_mode == NonReservedPeerMode::Deny)
+			(config.min_peers as usize, config.max_peers as usize, config.max_outbound() as usize, config.reserved_outbound() as usize,
+				config.non_reserved_mode == NonReservedPeerMode::Deny, config.ip_filter.clone())
 		};
 
 		let session_count = self.session_count();
+		let (_, outbound_count) = self.session_counts();
 		let reserved_nodes = self.reserved_nodes.read();
-		if session_count >= min_peers as usize + reserved_nodes.len() {
+		// keep connecting while the slots reserved for outbound peers are not filled, even if there are enough peers.
+		let enough_peers = session_count >= min_peers + reserved_nodes.len() && outbound_count >= reserved_outbound;
+		let outbound_full = outbound_count >= max_outbound || session_count >= max_peers + reserved_nodes.len();
+		if enough_peers || outbound_full {
 			// check if all pinned nodes are connected.
 			if reserved_nodes.iter().all(|n| self.have_session(n) && self.connecting_to(n)) {
 				return;
@@ -632,8 +827,9 @@ impl Host {
 
 		// iterate over all nodes, reserved ones coming first.
 		// if we are pinned to only reserved nodes, ignore all others.
+		// reserved nodes are not subject to the address filter.
 		let nodes = reserved_nodes.iter().cloned().chain(if !pin {
-			self.nodes.read().nodes()
+			self.nodes.read().nodes(&ip_filter)
 		} else {
 			Vec::new()
 		});
@@ -690,7 +886,7 @@ impl Host {
 		let mut sessions = self.sessions.write();
 
 		let token = sessions.insert_with_opt(|token| {
-			match Session::new(io, socket, token, id, &nonce, self.stats.clone(), &self.info.read()) {
+			match Session::new(io, socket, token, id, &nonce, self.stats.clone(), self.bandwidth.clone(), &self.info.read()) {
 				Ok(s) => Some(Arc::new(Mutex::new(s))),
 				Err(e) => {
 					debug!(target: "network", "Session create error: {:?}", e);
@@ -768,15 +964,37 @@ impl Host {
 						break;
 					},
 					Ok(SessionData::Ready) => {
-						self.num_sessions.fetch_add(1, AtomicOrdering::SeqCst);
+						let (inbound_count, outbound_count) = self.session_counts();
+						if s.info.originated {
+							self.num_outbound.fetch_add(1, AtomicOrdering::SeqCst);
+						} else {
+							self.num_inbound.fetch_add(1, AtomicOrdering::SeqCst);
+						}
+						if self.nodes.read().is_banned(s.id().unwrap()) && !self.reserved_nodes.read().contains(s.id().unwrap()) {
+							trace!(target: "network", "Rejecting banned peer: {}", token);
+							s.disconnect(io, DisconnectReason::UselessPeer);
+							return;
+						}
+						if !s.info.originated && !self.is_address_allowed(&s) {
+							trace!(target: "network", "Rejecting peer with filtered address: {}", token);
+							s.disconnect(io, DisconnectReason::UselessPeer);
+							return;
+						}
+						if s.info.originated {
+							let max_outbound = self.info.read().config.max_outbound() as usize;
+							if outbound_count >= max_outbound && !self.reserved_nodes.read().contains(s.id().unwrap()) {
+								trace!(target: "network", "Too many outbound peers: {}", token);
+								s.disconnect(io, DisconnectReason::TooManyPeers);
+								return;
+							}
+						}
 						if !s.info.originated {
-							let session_count = self.session_count();
-							let (max_peers, reserved_only) = {
+							let (accepts, reserved_only) = {
 								let info = self.info.read();
-								(info.config.max_peers, info.config.non_reserved_mode == NonReservedPeerMode::Deny)
+								(info.config.accepts_inbound(inbound_count, outbound_count), info.config.non_reserved_mode == NonReservedPeerMode::Deny)
 							};
 
-							if session_count >= max_peers as usize || reserved_only {
+							if !accepts || reserved_only {
 								// only proceed if the connecting peer is reserved.
 								if !self.reserved_nodes.read().contains(s.id().unwrap()) {
 									s.disconnect(io, DisconnectReason::TooManyPeers);
@@ -794,6 +1012,7 @@ impl Host {
 								}
 							}
 						}
+						self.nodes.write().note_connected(s.id().unwrap());
 						for (p, _) in self.handlers.read().iter() {
 							if s.have_capability(p)  {
 								ready_data.push(p);
@@ -823,12 +1042,12 @@ impl Host {
 			let h = handlers.get(p).unwrap().clone();
 			self.stats.inc_sessions();
 			let reserved = self.reserved_nodes.read();
-			h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token);
+			h.connected(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved, &self.nodes), &token);
 		}
 		for (p, packet_id, data) in packet_data {
 			let h = handlers.get(p).unwrap().clone();
 			let reserved = self.reserved_nodes.read();
-			h.read(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved), &token, packet_id, &data[1..]);
+			h.read(&NetworkContext::new(io, p, session.clone(), self.sessions.clone(), &reserved, &self.nodes), &token, packet_id, &data[1..]);
 		}
 	}
 
@@ -849,7 +1068,11 @@ impl Host {
 				let mut s = session.lock();
 				if !s.expired() {
 					if s.is_ready() {
-						self.num_sessions.fetch_sub(1, AtomicOrdering::SeqCst);
+						if s.info.originated {
+							self.num_outbound.fetch_sub(1, AtomicOrdering::SeqCst);
+						} else {
+							self.num_inbound.fetch_sub(1, AtomicOrdering::SeqCst);
+						}
 						for (p, _) in self.handlers.read().iter() {
 							if s.have_capability(p)  {
 								to_disconnect.push(p);
@@ -870,7 +1093,7 @@ impl Host {
 		for p in to_disconnect {
 			let h = self.handlers.read().get(p).unwrap().clone();
 			let reserved = self.reserved_nodes.read();
-			h.disconnected(&NetworkContext::new(io, p, expired_session.clone(), self.sessions.clone(), &reserved), &token);
+			h.disconnected(&NetworkContext::new(io, p, expired_session.clone(), self.sessions.clone(), &reserved, &self.nodes), &token);
 		}
 		if deregister {
 			io.deregister_stream(token).unwrap_or_else(|e| debug!("Error deregistering stream: {:?}", e));
@@ -900,7 +1123,7 @@ impl Host {
 	pub fn with_context<F>(&self, protocol: ProtocolId, io: &IoContext<NetworkIoMessage>, action: F) where F: Fn(&NetworkContext) {
 		let reserved = { self.reserved_nodes.read() };
 
-		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved);
+		let context = NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved, &self.nodes);
 		action(&context);
 	}
 }
@@ -971,14 +1194,22 @@ impl IoHandler<NetworkIoMessage> for Host {
 			},
 			NODE_TABLE => {
 				trace!(target: "network", "Refreshing node table");
-				self.nodes.write().clear_useless();
+				let mut nodes = self.nodes.write();
+				nodes.clear_useless();
+				nodes.clear_expired_bans();
+				let evicted = nodes.evict_stale(&*self.reserved_nodes.read());
+				if !evicted.is_empty() {
+					debug!(target: "network", "Evicted {} stale nodes", evicted.len());
+				}
+				nodes.save();
 			},
+			BANDWIDTH => self.resume_throttled(io),
 			_ => match self.timers.read().get(&token).cloned() {
 				Some(timer) => match self.handlers.read().get(timer.protocol).cloned() {
 					None => { warn!(target: "network", "No handler found for protocol: {:?}", timer.protocol) },
 					Some(h) => {
 						let reserved = self.reserved_nodes.read();
-						h.timeout(&NetworkContext::new(io, timer.protocol, None, self.sessions.clone(), &reserved), timer.token);
+						h.timeout(&NetworkContext::new(io, timer.protocol, None, self.sessions.clone(), &reserved, &self.nodes), timer.token);
 					}
 				},
 				None => { warn!("Unknown timer token: {}", token); } // timer is not registerd through us
@@ -994,15 +1225,16 @@ impl IoHandler<NetworkIoMessage> for Host {
 			NetworkIoMessage::AddHandler {
 				ref handler,
 				ref protocol,
+				ref packet_count,
 				ref versions
 			} => {
 				let h = handler.clone();
 				let reserved = self.reserved_nodes.read();
-				h.initialize(&NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved));
+				h.initialize(&NetworkContext::new(io, protocol, None, self.sessions.clone(), &reserved, &self.nodes));
 				self.handlers.write().insert(protocol, h);
 				let mut info = self.info.write();
 				for v in versions {
-					info.capabilities.push(CapabilityInfo { protocol: protocol, version: *v, packet_count:0 });
+					info.capabilities.push(CapabilityInfo { protocol: protocol, version: *v, packet_count: *packet_count });
 				}
 			},
 			NetworkIoMessage::AddTimer {
@@ -1032,13 +1264,23 @@ impl IoHandler<NetworkIoMessage> for Host {
 				let session = { self.sessions.read().get(*peer).cloned() };
 				if let Some(session) = session {
 					session.lock().disconnect(io, DisconnectReason::DisconnectRequested);
-					if let Some(id) = session.lock().id() {
-						self.nodes.write().mark_as_useless(id)
+					let id = session.lock().id().cloned();
+					if let Some(id) = id {
+						self.nodes.write().mark_as_useless(&id);
 					}
 				}
 				trace!(target: "network", "Disabling peer {}", peer);
 				self.kill_connection(*peer, io, false);
 			},
+			NetworkIoMessage::ReportPeer(ref peer, ref report) => {
+				let id = { self.sessions.read().get(*peer).and_then(|s| s.lock().id().cloned()) };
+				if let Some(id) = id {
+					trace!(target: "network", "Peer {} reported: {:?}", peer, report);
+					if self.report_node(&id, *report) {
+						self.disconnect_banned(&id, io);
+					}
+				}
+			},
 			NetworkIoMessage::InitPublicInterface =>
 				self.init_public_interface(io).unwrap_or_else(|e| warn!("Error initializing public interface: {:?}", e)),
 			_ => {}	// ignore others.
@@ -1157,3 +1399,29 @@ fn host_client_url() {
 	let host: Host = Host::new(config, Arc::new(NetworkStats::new())).unwrap();
 	assert!(host.local_url().starts_with("enode://101b3ef5a4ea7a1c7928e24c4c75fd053c235d7b80c22ae5c03d145d0ac7396e2a4ffff9adee3133a7b05044a5cee08115fd65145e5165d646bde371010d803c@"));
 }
+
+#[test]
+fn inbound_outbound_limits() {
+	let mut config = NetworkConfiguration::new();
+	config.max_peers = 10;
+	config.reserved_outbound_peers = 3;
+	assert_eq!(config.max_inbound(), 7);
+	assert_eq!(config.max_outbound(), 10);
+	assert!(config.accepts_inbound(6, 0));
+	assert!(!config.accepts_inbound(7, 0));
+	assert!(config.accepts_inbound(6, 3));
+	assert!(!config.accepts_inbound(6, 4));
+	assert!(!config.accepts_inbound(1, 9));
+
+	config.max_inbound_peers = Some(2);
+	config.max_outbound_peers = Some(20);
+	assert_eq!(config.max_inbound(), 2);
+	assert_eq!(config.max_outbound(), 10);
+	assert!(!config.accepts_inbound(2, 0));
+
+	// reserve is capped to half of the slots
+	config.reserved_outbound_peers = 100;
+	config.max_inbound_peers = None;
+	assert_eq!(config.reserved_outbound(), 5);
+	assert_eq!(config.max_inbound(), 5);
+}
@@ -0,0 +1,247 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Peer address filtering.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use error::NetworkError;
+use ip_utils::SocketAddrExt;
+
+/// Predefined sets of peer addresses to allow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllowIP {
+	/// Allow any address. This is the default.
+	All,
+	/// Allow globally routable addresses only.
+	Public,
+	/// Allow private, loopback and link-local addresses only.
+	Private,
+	/// Allow only the networks listed in `IpFilter::custom_allow`.
+	None,
+}
+
+impl AllowIP {
+	/// Attempt to parse the mode from a string.
+	pub fn parse(s: &str) -> Option<Self> {
+		match s {
+			"all" => Some(AllowIP::All),
+			"public" => Some(AllowIP::Public),
+			"private" => Some(AllowIP::Private),
+			"none" => Some(AllowIP::None),
+			_ => None,
+		}
+	}
+
+	/// Mode name as accepted by `parse`.
+	pub fn as_str(&self) -> &'static str {
+		match *self {
+			AllowIP::All => "all",
+			AllowIP::Public => "public",
+			AllowIP::Private => "private",
+			AllowIP::None => "none",
+		}
+	}
+}
+
+/// IP network in CIDR notation, e.g. `10.0.0.0/8`. A single address is a network with the full prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+	address: IpAddr,
+	prefix: u8,
+}
+
+impl IpNetwork {
+	/// Check if the address belongs to this network.
+	pub fn contains(&self, ip: &IpAddr) -> bool {
+		match (self.address, *ip) {
+			(IpAddr::V4(net), IpAddr::V4(ip)) => prefix_matches(&net.octets(), &ip.octets(), self.prefix),
+			(IpAddr::V6(net), IpAddr::V6(ip)) => prefix_matches(&net.octets(), &ip.octets(), self.prefix),
+			_ => false,
+		}
+	}
+}
+
+fn prefix_matches(network: &[u8], address: &[u8], prefix: u8) -> bool {
+	let full_bytes = prefix as usize / 8;
+	let remaining_bits = prefix % 8;
+	if network[..full_bytes] != address[..full_bytes] {
+		return false;
+	}
+	if remaining_bits == 0 {
+		return true;
+	}
+	let mask = 0xffu8 << (8 - remaining_bits);
+	network[full_bytes] & mask == address[full_bytes] & mask
+}
+
+impl FromStr for IpNetwork {
+	type Err = NetworkError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(2, '/');
+		let address: IpAddr = try!(parts.next().unwrap_or("").parse().map_err(|_| NetworkError::InvalidIpFilter(s.to_owned())));
+		let max_prefix = match address {
+			IpAddr::V4(_) => 32,
+			IpAddr::V6(_) => 128,
+		};
+		let prefix = match parts.next() {
+			Some(p) => try!(p.parse::<u8>().map_err(|_| NetworkError::InvalidIpFilter(s.to_owned()))),
+			None => max_prefix,
+		};
+		if prefix > max_prefix {
+			return Err(NetworkError::InvalidIpFilter(s.to_owned()));
+		}
+		Ok(IpNetwork {
+			address: address,
+			prefix: prefix,
+		})
+	}
+}
+
+impl fmt::Display for IpNetwork {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}/{}", self.address, self.prefix)
+	}
+}
+
+/// Filter for addresses of peers we connect to or accept connections from.
+/// Denied networks take precedence over allowed ones, which take precedence over the predefined mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpFilter {
+	/// Predefined set of allowed addresses.
+	pub predefined: AllowIP,
+	/// Networks allowed regardless of the predefined set.
+	pub custom_allow: Vec<IpNetwork>,
+	/// Networks that are never allowed.
+	pub custom_block: Vec<IpNetwork>,
+}
+
+impl Default for IpFilter {
+	fn default() -> Self {
+		IpFilter {
+			predefined: AllowIP::All,
+			custom_allow: Vec::new(),
+			custom_block: Vec::new(),
+		}
+	}
+}
+
+impl IpFilter {
+	/// Create a filter from mode name and lists of allowed and denied networks in CIDR notation.
+	pub fn parse(mode: &str, allow: &[String], deny: &[String]) -> Result<IpFilter, NetworkError> {
+		let predefined = try!(AllowIP::parse(mode).ok_or_else(|| NetworkError::InvalidIpFilter(mode.to_owned())));
+		let mut custom_allow = Vec::new();
+		for n in allow {
+			custom_allow.push(try!(IpNetwork::from_str(n)));
+		}
+		let mut custom_block = Vec::new();
+		for n in deny {
+			custom_block.push(try!(IpNetwork::from_str(n)));
+		}
+		Ok(IpFilter {
+			predefined: predefined,
+			custom_allow: custom_allow,
+			custom_block: custom_block,
+		})
+	}
+
+	/// Check if connections with the address are allowed.
+	pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+		if self.custom_block.iter().any(|n| n.contains(ip)) {
+			return false;
+		}
+		if self.custom_allow.iter().any(|n| n.contains(ip)) {
+			return true;
+		}
+		let is_global = match *ip {
+			IpAddr::V4(ref a) => a.is_global_s(),
+			IpAddr::V6(ref a) => a.is_global_s(),
+		};
+		match self.predefined {
+			AllowIP::All => true,
+			AllowIP::Public => is_global,
+			AllowIP::Private => !is_global,
+			AllowIP::None => false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::str::FromStr;
+	use std::net::IpAddr;
+
+	fn ip(s: &str) -> IpAddr {
+		IpAddr::from_str(s).unwrap()
+	}
+
+	#[test]
+	fn network_contains() {
+		let net = IpNetwork::from_str("10.1.0.0/16").unwrap();
+		assert!(net.contains(&ip("10.1.200.3")));
+		assert!(!net.contains(&ip("10.2.0.1")));
+		assert!(!net.contains(&ip("::1")));
+
+		let net = IpNetwork::from_str("192.168.1.128/25").unwrap();
+		assert!(net.contains(&ip("192.168.1.200")));
+		assert!(!net.contains(&ip("192.168.1.100")));
+
+		let net = IpNetwork::from_str("fc00::/7").unwrap();
+		assert!(net.contains(&ip("fd12::1")));
+		assert!(!net.contains(&ip("2001:db8::1")));
+
+		assert!(IpNetwork::from_str("1.2.3.4").unwrap().contains(&ip("1.2.3.4")));
+		assert!(IpNetwork::from_str("0.0.0.0/0").unwrap().contains(&ip("8.8.8.8")));
+	}
+
+	#[test]
+	fn network_parse_errors() {
+		assert!(IpNetwork::from_str("10.0.0.0/33").is_err());
+		assert!(IpNetwork::from_str("10.0.0/8").is_err());
+		assert!(IpNetwork::from_str("::/129").is_err());
+	}
+
+	#[test]
+	fn predefined_modes() {
+		let public = IpFilter::parse("public", &[], &[]).unwrap();
+		assert!(public.is_allowed(&ip("8.8.8.8")));
+		assert!(!public.is_allowed(&ip("192.168.0.1")));
+
+		let private = IpFilter::parse("private", &[], &[]).unwrap();
+		assert!(!private.is_allowed(&ip("8.8.8.8")));
+		assert!(private.is_allowed(&ip("10.0.0.1")));
+		assert!(private.is_allowed(&ip("127.0.0.1")));
+
+		assert!(IpFilter::default().is_allowed(&ip("8.8.8.8")));
+		assert!(IpFilter::parse("bogus", &[], &[]).is_err());
+	}
+
+	#[test]
+	fn custom_networks() {
+		let filter = IpFilter::parse("none", &["10.0.0.0/8".into()], &["10.0.1.0/24".into()]).unwrap();
+		assert!(filter.is_allowed(&ip("10.2.3.4")));
+		assert!(!filter.is_allowed(&ip("10.0.1.4")));
+		assert!(!filter.is_allowed(&ip("8.8.8.8")));
+
+		let filter = IpFilter::parse("public", &["192.168.0.0/16".into()], &["8.8.8.8".into()]).unwrap();
+		assert!(filter.is_allowed(&ip("192.168.3.4")));
+		assert!(!filter.is_allowed(&ip("8.8.8.8")));
+		assert!(filter.is_allowed(&ip("8.8.4.4")));
+	}
+}
@@ -45,7 +45,7 @@
 //!
 //! fn main () {
 //! 	let mut service = NetworkService::new(NetworkConfiguration::new_local()).expect("Error creating network service");
-//! 	service.register_protocol(Arc::new(MyHandler), "myproto", &[1u8]);
+//! 	service.register_protocol(Arc::new(MyHandler), "myproto", 1, &[1u8]);
 //! 	service.start().expect("Error starting service");
 //!
 //! 	// Wait for quit condition
@@ -87,23 +87,27 @@ mod error;
 mod node_table;
 mod stats;
 mod ip_utils;
+mod ip_filter;
+mod bandwidth;
 
 #[cfg(test)]
 mod tests;
 
 pub use host::PeerId;
 pub use host::PacketId;
+pub use host::ProtocolId;
 pub use host::NetworkContext;
 pub use service::NetworkService;
 pub use host::NetworkIoMessage;
 pub use error::NetworkError;
 pub use host::NetworkConfiguration;
-pub use stats::NetworkStats;
+pub use stats::{NetworkStats, PeerTraffic, PacketTraffic};
 
 use io::TimerToken;
-pub use node_table::is_valid_node_url;
+pub use node_table::{is_valid_node_url, NodeId, NodeReputation, PeerReport};
+pub use ip_filter::{IpFilter, IpNetwork, AllowIP};
 
-const PROTOCOL_VERSION: u32 = 4;
+const PROTOCOL_VERSION: u32 = 5;
 
 /// Network IO protocol handler. This needs to be implemented for each new subprotocol.
 /// All the handler function are called from within IO event loop.
@@ -24,6 +24,7 @@ use std::fmt::{Display, Formatter};
 use std::path::{PathBuf};
 use std::fmt;
 use std::fs;
+use std::cmp::{min, max};
 use std::io::{Read, Write};
 use util::hash::*;
 use util::UtilError;
@@ -32,11 +33,69 @@ use time::Tm;
 use error::NetworkError;
 use discovery::{TableUpdates, NodeEntry};
 use ip_utils::*;
+use ip_filter::IpFilter;
 pub use rustc_serialize::json::Json;
 
 /// Node public key
 pub type NodeId = H512;
 
+/// Lowest possible node reputation.
+pub const MIN_REPUTATION: i32 = -100;
+/// Highest possible node reputation.
+pub const MAX_REPUTATION: i32 = 100;
+/// Nodes with reputation at or below this value get banned.
+pub const BAN_THRESHOLD: i32 = -50;
+/// Ban duration in seconds.
+const BAN_DURATION_SEC: i64 = 60 * 60;
+/// Nodes not seen or connected to for this many seconds are evicted from the table.
+const STALE_NODE_SEC: i64 = 7 * 24 * 60 * 60;
+/// Nodes that were never seen are evicted after this many failed connection attempts.
+const MAX_UNSEEN_FAILURES: u32 = 5;
+
+/// Peer behaviour reported by the network layer or a protocol handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerReport {
+	/// Peer provided useful data.
+	Useful,
+	/// Peer failed to respond in time.
+	Timeout,
+	/// Peer sent malformed or invalid data, e.g. an invalid transaction.
+	InvalidData,
+	/// Peer sent a bad block.
+	BadBlock,
+}
+
+impl PeerReport {
+	fn reputation_change(&self) -> i32 {
+		match *self {
+			PeerReport::Useful => 1,
+			PeerReport::Timeout => -5,
+			PeerReport::InvalidData => -10,
+			PeerReport::BadBlock => -50,
+		}
+	}
+}
+
+/// Node reputation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NodeReputation {
+	/// Reputation score, between `MIN_REPUTATION` and `MAX_REPUTATION`.
+	pub score: i32,
+	/// Unix timestamp in seconds when the ban expires, if the node is banned.
+	pub banned_until: Option<i64>,
+}
+
+impl NodeReputation {
+	/// Check if the node is banned at given time.
+	pub fn is_banned(&self, now: i64) -> bool {
+		self.banned_until.map_or(false, |t| t > now)
+	}
+}
+
+fn unix_now() -> i64 {
+	::time::get_time().sec
+}
+
 #[derive(Debug, Clone)]
 /// Node address info
 pub struct NodeEndpoint {
@@ -105,6 +164,10 @@ impl NodeEndpoint {
 			SocketAddr::V6(a) => a.ip().is_global_s()
 		}
 	}
+
+	pub fn is_allowed(&self, filter: &IpFilter) -> bool {
+		filter.is_allowed(&self.address.ip())
+	}
 }
 
 impl FromStr for NodeEndpoint {
@@ -134,8 +197,13 @@ pub struct Node {
 	pub id: NodeId,
 	pub endpoint: NodeEndpoint,
 	pub peer_type: PeerType,
+	/// Number of failed connection attempts since the last successful one.
 	pub failures: u32,
 	pub last_attempted: Option<Tm>,
+	/// Unix timestamp in seconds of the last discovery packet received from the node.
+	pub last_seen: Option<i64>,
+	/// Unix timestamp in seconds of the last established session with the node.
+	pub last_connected: Option<i64>,
 }
 
 impl Node {
@@ -146,6 +214,21 @@ impl Node {
 			peer_type: PeerType::Optional,
 			failures: 0,
 			last_attempted: None,
+			last_seen: None,
+			last_connected: None,
+		}
+	}
+
+	/// Unix timestamp in seconds of the last sign of life from the node.
+	pub fn last_active(&self) -> Option<i64> {
+		max(self.last_seen, self.last_connected)
+	}
+
+	/// Check if the node should be removed from the table at given time.
+	fn is_stale(&self, now: i64) -> bool {
+		match self.last_active() {
+			Some(t) => now - t > STALE_NODE_SEC,
+			None => self.failures >= MAX_UNSEEN_FAILURES,
 		}
 	}
 }
@@ -177,6 +260,8 @@ impl FromStr for Node {
 			peer_type: PeerType::Optional,
 			last_attempted: None,
 			failures: 0,
+			last_seen: None,
+			last_connected: None,
 		})
 	}
 }
@@ -198,37 +283,59 @@ impl Hash for Node {
 pub struct NodeTable {
 	nodes: HashMap<NodeId, Node>,
 	useless_nodes: HashSet<NodeId>,
+	reputation: HashMap<NodeId, NodeReputation>,
 	path: Option<String>,
 }
 
 impl NodeTable {
 	pub fn new(path: Option<String>) -> NodeTable {
+		let (nodes, reputation) = NodeTable::load(path.clone());
 		NodeTable {
-			path: path.clone(),
-			nodes: NodeTable::load(path),
+			path: path,
+			nodes: nodes,
 			useless_nodes: HashSet::new(),
+			reputation: reputation,
 		}
 	}
 
 	/// Add a node to table
 	pub fn add_node(&mut self, mut node: Node) {
-		// preserve failure counter
-		let failures = self.nodes.get(&node.id).map_or(0, |n| n.failures);
-		node.failures = failures;
+		// preserve quality stats
+		if let Some(n) = self.nodes.get(&node.id) {
+			node.failures = n.failures;
+			node.last_seen = n.last_seen;
+			node.last_connected = n.last_connected;
+		}
 		self.nodes.insert(node.id.clone(), node);
 	}
 
-	/// Returns node ids sorted by number of failures
-	pub fn nodes(&self) -> Vec<NodeId> {
-		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains(&n.id)).collect();
-		refs.sort_by(|a, b| a.failures.cmp(&b.failures));
-		refs.iter().map(|n| n.id.clone()).collect()
+	/// Nodes that are not banned and pass the address filter, most reliable first.
+	/// Nodes are ordered by reputation, number of failures, last connection time and last discovery contact.
+	fn ordered(&self, filter: &IpFilter) -> Vec<&Node> {
+		let now = unix_now();
+		let mut refs: Vec<(i32, &Node)> = self.nodes.values()
+			.filter(|n| !self.useless_nodes.contains(&n.id))
+			.filter(|n| filter.is_allowed(&n.endpoint.address.ip()))
+			.map(|n| (self.reputation(&n.id), n))
+			.filter(|&(ref r, _)| !r.is_banned(now))
+			.map(|(r, n)| (r.score, n))
+			.collect();
+		refs.sort_by(|&(sa, a), &(sb, b)| {
+			let ka = (-sa, a.failures, -a.last_connected.unwrap_or(0), -a.last_seen.unwrap_or(0));
+			let kb = (-sb, b.failures, -b.last_connected.unwrap_or(0), -b.last_seen.unwrap_or(0));
+			ka.cmp(&kb)
+		});
+		refs.into_iter().map(|(_, n)| n).collect()
+	}
+
+	/// Returns ids of nodes that are not banned and pass the address filter, most reliable first.
+	pub fn nodes(&self, filter: &IpFilter) -> Vec<NodeId> {
+		self.ordered(filter).into_iter().map(|n| n.id.clone()).collect()
 	}
 
-	/// Unordered list of all entries
-	pub fn unordered_entries(&self) -> Vec<NodeEntry> {
-		// preserve failure counter
-		self.nodes.values().map(|n| NodeEntry { endpoint: n.endpoint.clone(), id: n.id.clone() }).collect()
+	/// Discovery entries for nodes that are not banned and pass the address filter, most reliable first.
+	pub fn entries(&self, filter: &IpFilter) -> Vec<NodeEntry> {
+		self.ordered(filter).into_iter().map(|n| NodeEntry { endpoint: n.endpoint.clone(), id: n.id.clone() }).collect()
 	}
 
 	/// Get particular node
@@ -242,6 +349,12 @@ impl NodeTable {
 			let mut entry = self.nodes.entry(node.id.clone()).or_insert_with(|| Node::new(node.id.clone(), node.endpoint.clone()));
 			entry.endpoint = node.endpoint;
 		}
+		let now = unix_now();
+		for id in &update.seen {
+			if let Some(node) = self.nodes.get_mut(id) {
+				node.last_seen = Some(now);
+			}
+		}
 		for r in update.removed {
 			if !reserved.contains(&r) {
 				self.nodes.remove(&r);
@@ -256,6 +369,30 @@ impl NodeTable {
 		}
 	}
 
+	/// Note an established session with the node. Resets the failure counter.
+	pub fn note_connected(&mut self, id: &NodeId) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			node.failures = 0;
+			node.last_connected = Some(unix_now());
+		}
+	}
+
+	/// Remove nodes that have not been seen for a long time or that were never reachable.
+	/// Returns ids of the removed nodes.
+	pub fn evict_stale(&mut self, reserved: &HashSet<NodeId>) -> Vec<NodeId> {
+		let now = unix_now();
+		let stale: Vec<NodeId> = self.nodes.values()
+			.filter(|n| !reserved.contains(&n.id) && n.is_stale(now))
+			.map(|n| n.id.clone())
+			.collect();
+		for id in &stale {
+			trace!(target: "network", "Evicting stale node {:?}", id);
+			self.nodes.remove(id);
+			self.useless_nodes.remove(id);
+		}
+		stale
+	}
+
 	/// Mark as useless, no furter attempts to connect until next call to `clear_useless`.
 	pub fn mark_as_useless(&mut self, id: &NodeId) {
 		self.useless_nodes.insert(id.clone());
@@ -266,7 +403,65 @@ impl NodeTable {
 		self.useless_nodes.clear();
 	}
 
-	fn save(&self) {
+	/// Get node reputation.
+	pub fn reputation(&self, id: &NodeId) -> NodeReputation {
+		self.reputation.get(id).cloned().unwrap_or_else(NodeReputation::default)
+	}
+
+	/// All nodes with non-zero reputation.
+	pub fn reputations(&self) -> Vec<(NodeId, NodeReputation)> {
+		self.reputation.iter().map(|(id, r)| (id.clone(), r.clone())).collect()
+	}
+
+	/// Check if the node is currently banned.
+	pub fn is_banned(&self, id: &NodeId) -> bool {
+		self.reputation(id).is_banned(unix_now())
+	}
+
+	/// Adjust node reputation according to the report. Returns `true` if the node has been banned as a result.
+	pub fn report(&mut self, id: &NodeId, report: PeerReport) -> bool {
+		let score = self.reputation(id).score + report.reputation_change();
+		self.set_reputation(id, score)
+	}
+
+	/// Set node reputation score. The node is banned if the score is at or below `BAN_THRESHOLD` and
+	/// unbanned otherwise. Returns `true` if the node has been banned as a result.
+	pub fn set_reputation(&mut self, id: &NodeId, score: i32) -> bool {
+		let now = unix_now();
+		let score = max(MIN_REPUTATION, min(MAX_REPUTATION, score));
+		let banned = {
+			let entry = self.reputation.entry(id.clone()).or_insert_with(NodeReputation::default);
+			let was_banned = entry.is_banned(now);
+			entry.score = score;
+			if score <= BAN_THRESHOLD {
+				if !was_banned {
+					entry.banned_until = Some(now + BAN_DURATION_SEC);
+				}
+			} else {
+				entry.banned_until = None;
+			}
+			!was_banned && entry.banned_until.is_some()
+		};
+		if score == 0 {
+			self.reputation.remove(id);
+		}
+		banned
+	}
+
+	/// Lift expired bans. Nodes are given a reputation just above the ban threshold,
+	/// so that a single misbehaviour gets them banned again.
+	pub fn clear_expired_bans(&mut self) {
+		let now = unix_now();
+		for r in self.reputation.values_mut() {
+			if r.banned_until.map_or(false, |t| t <= now) {
+				r.banned_until = None;
+				r.score = BAN_THRESHOLD / 2;
+			}
+		}
+	}
+
+	/// Write the table to `nodes.json`.
+	pub fn save(&self) {
 		if let Some(ref path) = self.path {
 			let mut path_buf = PathBuf::from(path);
 			if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
@@ -277,10 +472,19 @@ impl NodeTable {
 			let mut json = String::new();
 			json.push_str("{\n");
 			json.push_str("\"nodes\": [\n");
-			let node_ids = self.nodes();
-			for i in 0 .. node_ids.len() {
-				let node = self.nodes.get(&node_ids[i]).unwrap();
-				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {} }}{}\n", node, node.failures, if i == node_ids.len() - 1 {""} else {","}))
+			let nodes = self.ordered(&IpFilter::default());
+			for i in 0 .. nodes.len() {
+				let node = nodes[i];
+				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {}, \"last_seen\": {}, \"last_connected\": {} }}{}\n",
+					node, node.failures, node.last_seen.unwrap_or(0), node.last_connected.unwrap_or(0), if i == nodes.len() - 1 {""} else {","}))
+			}
+			json.push_str("],\n");
+			json.push_str("\"reputation\": [\n");
+			let reputation = self.reputations();
+			for i in 0 .. reputation.len() {
+				let (ref id, ref r) = reputation[i];
+				json.push_str(&format!("\t{{ \"id\": \"{}\", \"score\": {}, \"banned_until\": {} }}{}\n",
+					id.hex(), r.score, r.banned_until.unwrap_or(0), if i == reputation.len() - 1 {""} else {","}))
 			}
 			json.push_str("]\n");
 			json.push_str("}");
@@ -297,8 +501,9 @@ impl NodeTable {
 		}
 	}
 
-	fn load(path: Option<String>) -> HashMap<NodeId, Node> {
+	fn load(path: Option<String>) -> (HashMap<NodeId, Node>, HashMap<NodeId, NodeReputation>) {
 		let mut nodes: HashMap<NodeId, Node> = HashMap::new();
+		let mut reputation: HashMap<NodeId, NodeReputation> = HashMap::new();
 		if let Some(path) = path {
 			let mut path_buf = PathBuf::from(path);
 			path_buf.push("nodes.json");
@@ -306,7 +511,7 @@ impl NodeTable {
 				Ok(file) => file,
 				Err(e) => {
 					debug!("Error opening node table file: {:?}", e);
-					return nodes;
+					return (nodes, reputation);
 				}
 			};
 			let mut buf = String::new();
@@ -314,14 +519,14 @@ impl NodeTable {
 				Ok(_) => {},
 				Err(e) => {
 					warn!("Error reading node table file: {:?}", e);
-					return nodes;
+					return (nodes, reputation);
 				}
 			}
 			let json = match Json::from_str(&buf) {
 				Ok(json) => json,
 				Err(e) => {
 					warn!("Error parsing node table file: {:?}", e);
-					return nodes;
+					return (nodes, reputation);
 				}
 			};
 			if let Some(list) = json.as_object().and_then(|o| o.get("nodes")).and_then(|n| n.as_array()) {
@@ -331,13 +536,27 @@ impl NodeTable {
 							if let Some(failures) = n.get("failures").and_then(|f| f.as_u64()) {
 								node.failures = failures as u32;
 							}
+							node.last_seen = n.get("last_seen").and_then(|t| t.as_i64()).and_then(|t| if t == 0 { None } else { Some(t) });
+							node.last_connected = n.get("last_connected").and_then(|t| t.as_i64()).and_then(|t| if t == 0 { None } else { Some(t) });
 							nodes.insert(node.id.clone(), node);
 						}
 					}
 				}
 			}
+			if let Some(list) = json.as_object().and_then(|o| o.get("reputation")).and_then(|n| n.as_array()) {
+				for r in list.iter().filter_map(|r| r.as_object()) {
+					if let Some(Ok(id)) = r.get("id").and_then(|id| id.as_string()).map(NodeId::from_str) {
+						let score = r.get("score").and_then(|s| s.as_i64()).unwrap_or(0) as i32;
+						let banned_until = r.get("banned_until").and_then(|t| t.as_i64()).and_then(|t| if t == 0 { None } else { Some(t) });
+						reputation.insert(id, NodeReputation {
+							score: max(MIN_REPUTATION, min(MAX_REPUTATION, score)),
+							banned_until: banned_until,
+						});
+					}
+				}
+			}
 		}
-		nodes
+		(nodes, reputation)
 	}
 }
 
@@ -360,6 +579,9 @@ mod tests {
 	use std::net::*;
 	use util::hash::*;
 	use devtools::*;
+	use ip_filter::IpFilter;
+	use std::collections::{HashMap, HashSet};
+	use discovery::TableUpdates;
 
 	#[test]
 	fn endpoint_parse() {
@@ -405,7 +627,7 @@ mod tests {
 		table.note_failure(&id1);
 		table.note_failure(&id2);
 
-		let r = table.nodes();
+		let r = table.nodes(&IpFilter::default());
 		assert_eq!(r[0][..], id3[..]);
 		assert_eq!(r[1][..], id2[..]);
 		assert_eq!(r[2][..], id1[..]);
@@ -423,13 +645,155 @@ mod tests {
 			table.add_node(node1);
 			table.add_node(node2);
 			table.note_failure(&id2);
+			table.note_connected(&id1);
 		}
 
 		{
-			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
-			let r = table.nodes();
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			let r = table.nodes(&IpFilter::default());
 			assert_eq!(r[0][..], id1[..]);
 			assert_eq!(r[1][..], id2[..]);
+			assert!(table.get_mut(&id1).unwrap().last_connected.is_some());
+			assert_eq!(table.get_mut(&id2).unwrap().failures, 1);
 		}
 	}
+
+	#[test]
+	fn table_reliability_order() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = node1.id.clone();
+		let id2 = node2.id.clone();
+		let id3 = node3.id.clone();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.add_node(node3);
+
+		table.note_failure(&id1);
+		table.note_failure(&id1);
+		table.note_connected(&id1);
+		let mut seen = HashSet::new();
+		seen.insert(id2.clone());
+		table.update(TableUpdates { added: HashMap::new(), removed: HashSet::new(), seen: seen }, &HashSet::new());
+
+		assert_eq!(table.get_mut(&id1).unwrap().failures, 0);
+		assert!(table.get_mut(&id2).unwrap().last_seen.is_some());
+		assert_eq!(table.nodes(&IpFilter::default()), vec![id1.clone(), id2.clone(), id3.clone()]);
+		let entries: Vec<_> = table.entries(&IpFilter::default()).into_iter().map(|e| e.id).collect();
+		assert_eq!(entries, vec![id1, id2, id3]);
+	}
+
+	#[test]
+	fn table_evicts_stale() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node3 = Node::from_str("enode://c979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node4 = Node::from_str("enode://d979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let (id1, id2, id3, id4) = (node1.id.clone(), node2.id.clone(), node3.id.clone(), node4.id.clone());
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+		table.add_node(node3);
+		table.add_node(node4);
+
+		let now = unix_now();
+		// seen recently
+		table.get_mut(&id1).unwrap().last_seen = Some(now);
+		// not seen for too long
+		table.get_mut(&id2).unwrap().last_connected = Some(now - STALE_NODE_SEC - 1);
+		// never seen and unreachable
+		table.get_mut(&id3).unwrap().failures = MAX_UNSEEN_FAILURES;
+		// reserved
+		table.get_mut(&id4).unwrap().failures = MAX_UNSEEN_FAILURES;
+		let mut reserved = HashSet::new();
+		reserved.insert(id4.clone());
+
+		let mut evicted = table.evict_stale(&reserved);
+		evicted.sort();
+		assert_eq!(evicted, vec![id2, id3]);
+		let mut left = table.nodes(&IpFilter::default());
+		left.sort();
+		assert_eq!(left, vec![id1, id4]);
+	}
+
+	#[test]
+	fn table_reputation_order() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+
+		table.note_failure(&id2);
+		table.report(&id2, PeerReport::Useful);
+		let r = table.nodes(&IpFilter::default());
+		assert_eq!(r[0][..], id2[..]);
+		assert_eq!(r[1][..], id1[..]);
+	}
+
+	#[test]
+	fn table_ban_and_expiry() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+
+		assert!(!table.report(&id1, PeerReport::InvalidData));
+		assert!(table.report(&id1, PeerReport::BadBlock));
+		assert!(table.is_banned(&id1));
+		assert!(table.nodes(&IpFilter::default()).is_empty());
+		// already banned
+		assert!(!table.report(&id1, PeerReport::BadBlock));
+		assert_eq!(table.reputation(&id1).score, MIN_REPUTATION);
+
+		table.reputation.get_mut(&id1).unwrap().banned_until = Some(unix_now() - 1);
+		table.clear_expired_bans();
+		assert!(!table.is_banned(&id1));
+		assert_eq!(table.reputation(&id1).score, BAN_THRESHOLD / 2);
+		assert_eq!(table.nodes(&IpFilter::default()).len(), 1);
+
+		assert!(table.set_reputation(&id1, BAN_THRESHOLD));
+		assert!(!table.set_reputation(&id1, 0));
+		assert!(!table.is_banned(&id1));
+		assert!(table.reputations().is_empty());
+	}
+
+	#[test]
+	fn table_reputation_save_load() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.set_reputation(&id1, 10);
+			table.report(&id2, PeerReport::BadBlock);
+		}
+
+		{
+			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			assert_eq!(table.reputation(&id1), NodeReputation { score: 10, banned_until: None });
+			assert!(table.is_banned(&id2));
+		}
+	}
+
+	#[test]
+	fn table_ip_filter() {
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@10.0.0.1:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let mut table = NodeTable::new(None);
+		table.add_node(node1);
+		table.add_node(node2);
+
+		assert_eq!(table.nodes(&IpFilter::parse("public", &[], &[]).unwrap()), vec![id1.clone()]);
+		assert_eq!(table.nodes(&IpFilter::parse("private", &[], &[]).unwrap()), vec![id2.clone()]);
+		assert_eq!(table.nodes(&IpFilter::parse("all", &[], &["22.99.0.0/16".into()]).unwrap()), vec![id2]);
+	}
 }
@@ -17,7 +17,9 @@
 use {NetworkProtocolHandler, NetworkConfiguration, NonReservedPeerMode};
 use error::NetworkError;
 use host::{Host, NetworkContext, NetworkIoMessage, ProtocolId};
-use stats::NetworkStats;
+use stats::{NetworkStats, PeerTraffic};
+use node_table::{NodeId, NodeReputation};
+use ip_filter::IpFilter;
 use io::*;
 use parking_lot::RwLock;
 use std::sync::Arc;
@@ -72,11 +74,12 @@ impl NetworkService {
 		})
 	}
 
-	/// Regiter a new protocol handler with the event loop.
-	pub fn register_protocol(&self, handler: Arc<NetworkProtocolHandler + Send + Sync>, protocol: ProtocolId, versions: &[u8]) -> Result<(), NetworkError> {
+	/// Regiter a new protocol handler with the event loop. `packet_count` is the number of packet IDs the protocol uses.
+	pub fn register_protocol(&self, handler: Arc<NetworkProtocolHandler + Send + Sync>, protocol: ProtocolId, packet_count: u8, versions: &[u8]) -> Result<(), NetworkError> {
 		try!(self.io_service.send_message(NetworkIoMessage::AddHandler {
 			handler: handler,
 			protocol: protocol,
+			packet_count: packet_count,
 			versions: versions.to_vec(),
 		}));
 		Ok(())
@@ -170,6 +173,44 @@ impl NetworkService {
 		}
 	}
 
+	/// Set the filter for addresses of non-reserved peers.
+	pub fn set_ip_filter(&self, ip_filter: IpFilter) {
+		let host = self.host.read();
+		if let Some(ref host) = *host {
+			let io_ctxt = IoContext::new(self.io_service.channel(), 0);
+			host.set_ip_filter(ip_filter, &io_ctxt);
+		}
+	}
+
+	/// Returns traffic of all connected peers.
+	pub fn peers_traffic(&self) -> Vec<PeerTraffic> {
+		let host = self.host.read();
+		host.as_ref().map_or_else(Vec::new, |h| h.peers_traffic())
+	}
+
+	/// Returns number of inbound and outbound sessions.
+	pub fn session_counts(&self) -> (usize, usize) {
+		let host = self.host.read();
+		host.as_ref().map_or((0, 0), |h| h.session_counts())
+	}
+
+	/// Returns reputation of all nodes that have one.
+	pub fn node_reputations(&self) -> Vec<(NodeId, NodeReputation)> {
+		let host = self.host.read();
+		host.as_ref().map_or_else(Vec::new, |h| h.node_reputations())
+	}
+
+	/// Set node reputation. Nodes with reputation at or below the ban threshold get banned.
+	pub fn set_node_reputation(&self, node: &str, score: i32) -> Result<(), NetworkError> {
+		let host = self.host.read();
+		if let Some(ref host) = *host {
+			let io_ctxt = IoContext::new(self.io_service.channel(), 0);
+			host.set_node_reputation(node, score, &io_ctxt)
+		} else {
+			Ok(())
+		}
+	}
+
 	/// Executes action in the network context
 	pub fn with_context<F>(&self, protocol: ProtocolId, action: F) where F: Fn(&NetworkContext) {
 		let io = IoContext::new(self.io_service.channel(), 0);
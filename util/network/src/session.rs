@@ -20,18 +20,26 @@ use std::sync::*;
 use mio::*;
 use mio::tcp::*;
 use util::hash::*;
+use util::snappy;
 use rlp::*;
-use connection::{EncryptedConnection, Packet, Connection};
+use connection::{EncryptedConnection, Packet, Connection, MAX_PAYLOAD_SIZE};
 use handshake::Handshake;
 use io::{IoContext, StreamToken};
 use error::{NetworkError, DisconnectReason};
 use host::*;
 use node_table::NodeId;
-use stats::NetworkStats;
+use stats::{NetworkStats, SessionTraffic, PeerTraffic};
+use bandwidth::BandwidthLimits;
 use time;
 
 const PING_TIMEOUT_SEC: u64 = 30;
 const PING_INTERVAL_SEC: u64 = 30;
+/// Minimal peer RLPx protocol version we can talk to.
+const MIN_PROTOCOL_VERSION: u32 = 4;
+/// First RLPx protocol version that supports snappy compression.
+const SNAPPY_PROTOCOL_VERSION: u32 = 5;
+/// Protocol name used to account for base protocol packets.
+const BASE_PROTOCOL: &'static str = "p2p";
 
 /// Peer session over encrypted connection.
 /// When created waits for Hello packet exchange and signals ready state.
@@ -46,6 +54,11 @@ pub struct Session {
 	ping_time_ns: u64,
 	pong_time_ns: Option<u64>,
 	state: State,
+	/// Snappy compression is enabled. Set after Hello packet exchange when both sides support it.
+	compression: bool,
+	stats: Arc<NetworkStats>,
+	/// Per protocol and packet id traffic.
+	traffic: SessionTraffic,
 }
 
 enum State {
@@ -124,10 +137,10 @@ impl Session {
 	/// Create a new session out of comepleted handshake. This clones the handshake connection object
 	/// and leaves the handhsake in limbo to be deregistered from the event loop.
 	pub fn new<Message>(io: &IoContext<Message>, socket: TcpStream, token: StreamToken, id: Option<&NodeId>,
-		nonce: &H256, stats: Arc<NetworkStats>, host: &HostInfo) -> Result<Session, NetworkError>
+		nonce: &H256, stats: Arc<NetworkStats>, limits: Arc<BandwidthLimits>, host: &HostInfo) -> Result<Session, NetworkError>
 		where Message: Send + Clone {
 		let originated = id.is_some();
-		let mut handshake = Handshake::new(token, id, socket, nonce, stats).expect("Can't create handshake");
+		let mut handshake = Handshake::new(token, id, socket, nonce, stats.clone(), limits).expect("Can't create handshake");
 		try!(handshake.start(io, host, originated));
 		Ok(Session {
 			state: State::Handshake(handshake),
@@ -143,6 +156,9 @@ impl Session {
 			ping_time_ns: 0,
 			pong_time_ns: None,
 			expired: false,
+			compression: false,
+			stats: stats,
+			traffic: SessionTraffic::default(),
 		})
 	}
 
@@ -155,7 +171,10 @@ impl Session {
 		};
 		self.state = State::Session(connection);
 		try!(self.write_hello(io, host));
-		try!(self.send_ping(io));
+		// The first ping is sent once Hello is received and compression is negotiated.
+		// Until then the ping timer limits the time we wait for the Hello.
+		self.ping_time_ns = time::precise_time_ns();
+		self.pong_time_ns = None;
 		Ok(())
 	}
 
@@ -199,6 +218,25 @@ impl Session {
 		self.connection().remote_addr()
 	}
 
+	/// Check if reading or writing is paused by a rate limit.
+	pub fn is_throttled(&self) -> bool {
+		self.connection().is_throttled()
+	}
+
+	/// Get traffic of this session. Returns `None` until the session is ready.
+	pub fn traffic(&self) -> Option<PeerTraffic> {
+		match self.info.id {
+			Some(ref id) if self.had_hello => Some(PeerTraffic {
+				id: id.clone(),
+				remote_address: self.connection().remote_addr_str(),
+				sent_bytes: self.connection().sent_bytes(),
+				recv_bytes: self.connection().recv_bytes(),
+				packets: self.traffic.packets().to_vec(),
+			}),
+			_ => None,
+		}
+	}
+
 	/// Readable IO handler. Returns packet data if available.
 	pub fn readable<Message>(&mut self, io: &IoContext<Message>, host: &HostInfo) -> Result<SessionData, NetworkError>  where Message: Send + Sync + Clone {
 		if self.expired() {
@@ -318,6 +356,15 @@ impl Session {
 			return Err(From::from(NetworkError::BadProtocol));
 		}
 		let packet_id = packet.data[0];
+		self.stats.inc_recv_compressed(packet.data.len());
+		let packet = if self.compression && packet_id != PACKET_HELLO {
+			try!(Session::decompress(packet))
+		} else {
+			packet
+		};
+		self.stats.inc_recv_raw(packet.data.len());
+		let (protocol, pid) = Session::map_packet_id(&self.info.capabilities, packet_id);
+		self.traffic.on_recv(protocol, pid, packet.data.len());
 		if packet_id != PACKET_HELLO && packet_id != PACKET_DISCONNECT && !self.had_hello {
 			return Err(From::from(NetworkError::BadProtocol));
 		}
@@ -347,18 +394,12 @@ impl Session {
 			PACKET_GET_PEERS => Ok(SessionData::None), //TODO;
 			PACKET_PEERS => Ok(SessionData::None),
 			PACKET_USER ... PACKET_LAST => {
-				let mut i = 0usize;
-				while packet_id < self.info.capabilities[i].id_offset {
-					i += 1;
-					if i == self.info.capabilities.len() {
-						debug!(target: "network", "Unknown packet: {:?}", packet_id);
-						return Ok(SessionData::Continue)
-					}
-				}
-
 				// map to protocol
-				let protocol = self.info.capabilities[i].protocol;
-				let pid = packet_id - self.info.capabilities[i].id_offset;
+				let (protocol, pid) = Session::map_packet_id(&self.info.capabilities, packet_id);
+				if protocol == BASE_PROTOCOL {
+					debug!(target: "network", "Unknown packet: {:?}", packet_id);
+					return Ok(SessionData::Continue)
+				}
 				Ok(SessionData::Packet { data: packet.data, protocol: protocol, packet_id: pid } )
 			},
 			_ => {
@@ -412,6 +453,8 @@ impl Session {
 			}
 		}
 
+		// Offsets are assigned in the alphabetical order of protocol names
+		caps.sort_by(|a, b| a.protocol.cmp(b.protocol));
 		i = 0;
 		let mut offset: u8 = PACKET_USER;
 		while i < caps.len() {
@@ -426,14 +469,48 @@ impl Session {
 			trace!(target: "network", "No common capabilities with peer.");
 			return Err(From::from(self.disconnect(io, DisconnectReason::UselessPeer)));
 		}
-		if protocol != host.protocol_version {
+		if protocol < MIN_PROTOCOL_VERSION {
 			trace!(target: "network", "Peer protocol version mismatch: {}", protocol);
 			return Err(From::from(self.disconnect(io, DisconnectReason::UselessPeer)));
 		}
+		self.info.protocol_version = protocol;
+		self.compression = protocol >= SNAPPY_PROTOCOL_VERSION && host.protocol_version >= SNAPPY_PROTOCOL_VERSION;
 		self.had_hello = true;
+		try!(self.send_ping(io));
 		Ok(())
 	}
 
+	/// Map a wire packet id to the protocol and the packet id within the protocol.
+	fn map_packet_id(capabilities: &[SessionCapabilityInfo], packet_id: u8) -> (&'static str, u8) {
+		if packet_id < PACKET_USER || packet_id > PACKET_LAST {
+			return (BASE_PROTOCOL, packet_id);
+		}
+		match capabilities.iter().filter(|c| packet_id >= c.id_offset).max_by_key(|c| c.id_offset) {
+			Some(c) => (c.protocol, packet_id - c.id_offset),
+			None => (BASE_PROTOCOL, packet_id),
+		}
+	}
+
+	/// Decompress packet payload. Packet id is never compressed.
+	fn decompress(packet: Packet) -> Result<Packet, NetworkError> {
+		let payload = &packet.data[1..];
+		match snappy::decompressed_len(payload) {
+			Ok(len) if len <= MAX_PAYLOAD_SIZE => (),
+			Ok(len) => {
+				debug!(target: "network", "Decompressed packet size too large: {}", len);
+				return Err(NetworkError::BadProtocol);
+			},
+			Err(_) => return Err(NetworkError::BadProtocol),
+		}
+		let mut data = vec![packet.data[0]];
+		let mut decompressed = try!(snappy::decompress(payload).map_err(|_| NetworkError::BadProtocol));
+		data.append(&mut decompressed);
+		Ok(Packet {
+			protocol: packet.protocol,
+			data: data,
+		})
+	}
+
 	/// Senf ping packet
 	pub fn send_ping<Message>(&mut self, io: &IoContext<Message>) -> Result<(), NetworkError> where Message: Send + Sync + Clone {
 		try!(self.send(io, try!(Session::prepare(PACKET_PING))));
@@ -471,10 +548,64 @@ impl Session {
 				warn!(target:"network", "Unexpected send request");
 			},
 			State::Session(ref mut s) => {
-				try!(s.send_packet(io, &rlp.out()))
+				let data = rlp.out();
+				self.stats.inc_send_raw(data.len());
+				let (protocol, pid) = Session::map_packet_id(&self.info.capabilities, data[0]);
+				self.traffic.on_send(protocol, pid, data.len());
+				let data = if self.compression && data[0] != PACKET_HELLO {
+					// packet id is a single byte, only the payload is compressed
+					let mut compressed = vec![data[0]];
+					compressed.extend_from_slice(&snappy::compress(&data[1..]));
+					compressed
+				} else {
+					data
+				};
+				self.stats.inc_send_compressed(data.len());
+				try!(s.send_packet(io, &data))
 			},
 		}
 		Ok(())
 	}
 }
 
+
+#[cfg(test)]
+mod tests {
+	use super::{Session, SessionCapabilityInfo};
+	use connection::{Packet, MAX_PAYLOAD_SIZE};
+	use util::snappy;
+
+	fn compressed_packet(packet_id: u8, payload: &[u8]) -> Packet {
+		let mut data = vec![packet_id];
+		data.extend_from_slice(&snappy::compress(payload));
+		Packet { protocol: 0, data: data }
+	}
+
+	#[test]
+	fn decompresses_payload() {
+		let payload = vec![0x42u8; 1024];
+		let packet = Session::decompress(compressed_packet(0x10, &payload)).unwrap();
+		assert_eq!(packet.data[0], 0x10);
+		assert_eq!(&packet.data[1..], &payload[..]);
+	}
+
+	#[test]
+	fn rejects_invalid_and_oversized_payload() {
+		assert!(Session::decompress(Packet { protocol: 0, data: vec![0x10, 0xff, 0xff, 0xff] }).is_err());
+		let payload = vec![0u8; MAX_PAYLOAD_SIZE + 1];
+		assert!(Session::decompress(compressed_packet(0x10, &payload)).is_err());
+	}
+
+	#[test]
+	fn maps_packet_ids_to_protocols() {
+		let caps = vec![
+			SessionCapabilityInfo { protocol: "eth", version: 63, packet_count: 17, id_offset: 0x10 },
+			SessionCapabilityInfo { protocol: "shh", version: 2, packet_count: 8, id_offset: 0x21 },
+		];
+		assert_eq!(Session::map_packet_id(&caps, 0x02), ("p2p", 0x02));
+		assert_eq!(Session::map_packet_id(&caps, 0x80), ("p2p", 0x80));
+		assert_eq!(Session::map_packet_id(&caps, 0x13), ("eth", 0x03));
+		assert_eq!(Session::map_packet_id(&caps, 0x22), ("shh", 0x01));
+		assert_eq!(Session::map_packet_id(&[], 0x13), ("p2p", 0x13));
+	}
+}
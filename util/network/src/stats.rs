@@ -16,6 +16,7 @@
 
 //! Network Statistics
 use std::sync::atomic::*;
+use node_table::NodeId;
 
 /// Network statistics structure
 #[derive(Default, Debug)]
@@ -24,6 +25,14 @@ pub struct NetworkStats {
 	recv: AtomicUsize,
 	/// Bytes sent
 	send: AtomicUsize,
+	/// Packet bytes received before decompression
+	recv_compressed: AtomicUsize,
+	/// Packet bytes received after decompression
+	recv_raw: AtomicUsize,
+	/// Packet bytes sent after compression
+	send_compressed: AtomicUsize,
+	/// Packet bytes sent before compression
+	send_raw: AtomicUsize,
 	/// Total number of sessions created
 	sessions: AtomicUsize,
 }
@@ -41,6 +50,30 @@ impl NetworkStats {
 		self.send.fetch_add(size, Ordering::Relaxed);
 	}
 
+	/// Increase received packet bytes before decompression.
+	#[inline]
+	pub fn inc_recv_compressed(&self, size: usize) {
+		self.recv_compressed.fetch_add(size, Ordering::Relaxed);
+	}
+
+	/// Increase received packet bytes after decompression.
+	#[inline]
+	pub fn inc_recv_raw(&self, size: usize) {
+		self.recv_raw.fetch_add(size, Ordering::Relaxed);
+	}
+
+	/// Increase sent packet bytes after compression.
+	#[inline]
+	pub fn inc_send_compressed(&self, size: usize) {
+		self.send_compressed.fetch_add(size, Ordering::Relaxed);
+	}
+
+	/// Increase sent packet bytes before compression.
+	#[inline]
+	pub fn inc_send_raw(&self, size: usize) {
+		self.send_raw.fetch_add(size, Ordering::Relaxed);
+	}
+
 	/// Increase number of sessions.
 	#[inline]
 	pub fn inc_sessions(&self) {
@@ -59,6 +92,30 @@ impl NetworkStats {
 		self.recv.load(Ordering::Relaxed)
 	}
 
+	/// Get packet bytes received, as transmitted.
+	#[inline]
+	pub fn recv_compressed(&self) -> usize {
+		self.recv_compressed.load(Ordering::Relaxed)
+	}
+
+	/// Get packet bytes received, decompressed.
+	#[inline]
+	pub fn recv_raw(&self) -> usize {
+		self.recv_raw.load(Ordering::Relaxed)
+	}
+
+	/// Get packet bytes sent, as transmitted.
+	#[inline]
+	pub fn send_compressed(&self) -> usize {
+		self.send_compressed.load(Ordering::Relaxed)
+	}
+
+	/// Get packet bytes sent, before compression.
+	#[inline]
+	pub fn send_raw(&self) -> usize {
+		self.send_raw.load(Ordering::Relaxed)
+	}
+
 	/// Get total number of sessions created.
 	#[inline]
 	pub fn sessions(&self) -> usize {
@@ -70,7 +127,100 @@ impl NetworkStats {
 		NetworkStats {
 			recv: AtomicUsize::new(0),
 			send: AtomicUsize::new(0),
+			recv_compressed: AtomicUsize::new(0),
+			recv_raw: AtomicUsize::new(0),
+			send_compressed: AtomicUsize::new(0),
+			send_raw: AtomicUsize::new(0),
 			sessions: AtomicUsize::new(0),
 		}
 	}
 }
+
+/// Traffic of a single packet type within a session.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PacketTraffic {
+	/// Protocol id. Base protocol packets are reported as `p2p`.
+	pub protocol: &'static str,
+	/// Packet id within the protocol.
+	pub packet_id: u8,
+	/// Number of packets sent.
+	pub sent_packets: u64,
+	/// Packet bytes sent, before compression.
+	pub sent_bytes: u64,
+	/// Number of packets received.
+	pub recv_packets: u64,
+	/// Packet bytes received, after decompression.
+	pub recv_bytes: u64,
+}
+
+/// Per protocol and packet id traffic counters of a session.
+#[derive(Debug, Default, Clone)]
+pub struct SessionTraffic {
+	packets: Vec<PacketTraffic>,
+}
+
+impl SessionTraffic {
+	/// Account for a sent packet.
+	pub fn on_send(&mut self, protocol: &'static str, packet_id: u8, size: usize) {
+		let p = self.entry(protocol, packet_id);
+		p.sent_packets += 1;
+		p.sent_bytes += size as u64;
+	}
+
+	/// Account for a received packet.
+	pub fn on_recv(&mut self, protocol: &'static str, packet_id: u8, size: usize) {
+		let p = self.entry(protocol, packet_id);
+		p.recv_packets += 1;
+		p.recv_bytes += size as u64;
+	}
+
+	/// Counters of all packet types seen so far.
+	pub fn packets(&self) -> &[PacketTraffic] {
+		&self.packets
+	}
+
+	fn entry(&mut self, protocol: &'static str, packet_id: u8) -> &mut PacketTraffic {
+		let index = match self.packets.iter().position(|p| p.protocol == protocol && p.packet_id == packet_id) {
+			Some(index) => index,
+			None => {
+				self.packets.push(PacketTraffic { protocol: protocol, packet_id: packet_id, ..Default::default() });
+				self.packets.len() - 1
+			}
+		};
+		&mut self.packets[index]
+	}
+}
+
+/// Traffic of a connected peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerTraffic {
+	/// Peer node id.
+	pub id: NodeId,
+	/// Remote socket address.
+	pub remote_address: String,
+	/// Bytes sent over the connection, including framing and encryption.
+	pub sent_bytes: u64,
+	/// Bytes received over the connection, including framing and encryption.
+	pub recv_bytes: u64,
+	/// Per protocol and packet id counters.
+	pub packets: Vec<PacketTraffic>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn session_traffic_per_packet() {
+		let mut traffic = SessionTraffic::default();
+		traffic.on_send("eth", 1, 100);
+		traffic.on_send("eth", 1, 50);
+		traffic.on_recv("eth", 1, 10);
+		traffic.on_recv("p2p", 2, 1);
+
+		let packets = traffic.packets();
+		assert_eq!(packets.len(), 2);
+		assert_eq!(packets[0], PacketTraffic { protocol: "eth", packet_id: 1, sent_packets: 2, sent_bytes: 150, recv_packets: 1, recv_bytes: 10 });
+		assert_eq!(packets[1], PacketTraffic { protocol: "p2p", packet_id: 2, sent_packets: 0, sent_bytes: 0, recv_packets: 1, recv_bytes: 1 });
+	}
+}
@@ -104,6 +104,8 @@ impl From<::std::io::Error> for Error {
 
 /// A generalized migration from the given db to a destination db.
 pub trait Migration: 'static {
+	/// Number of columns in database before the migration.
+	fn pre_columns(&self) -> Option<u32> { self.columns() }
 	/// Number of columns in database after the migration.
 	fn columns(&self) -> Option<u32>;
 	/// Version of the database after the migration.
@@ -141,6 +143,34 @@ impl<T: SimpleMigration> Migration for T {
 	}
 }
 
+/// A migration which only changes the number of columns. All existing data is copied over unchanged.
+pub struct ChangeColumns {
+	/// Number of columns before the migration.
+	pub pre_columns: Option<u32>,
+	/// Number of columns after the migration.
+	pub post_columns: Option<u32>,
+	/// Version of database after the migration.
+	pub version: u32,
+}
+
+impl Migration for ChangeColumns {
+	fn pre_columns(&self) -> Option<u32> { self.pre_columns }
+
+	fn columns(&self) -> Option<u32> { self.post_columns }
+
+	fn version(&self) -> u32 { self.version }
+
+	fn migrate(&mut self, source: &Database, config: &Config, dest: &mut Database, col: Option<u32>) -> Result<(), Error> {
+		let mut batch = Batch::new(config, col);
+
+		for (key, value) in source.iter(col) {
+			try!(batch.insert(key.to_vec(), value.to_vec(), dest));
+		}
+
+		batch.commit(dest)
+	}
+}
+
 /// Get the path where all databases reside.
 fn database_path(path: &Path) -> PathBuf {
 	let mut temp_path = path.to_owned();
@@ -272,7 +302,7 @@ impl Manager {
 		let migration = self.migrations.iter().find(|m| m.version() == version);
 		match migration {
 			Some(m) => m.columns(),
-			None => None
+			None => self.migrations.iter().find(|m| m.version() > version).and_then(|m| m.pre_columns()),
 		}
 	}
 }
@@ -19,8 +19,8 @@
 //! are performed in temp sub-directories.
 
 use common::*;
-use migration::{Config, SimpleMigration, Manager};
-use kvdb::Database;
+use migration::{Config, SimpleMigration, Manager, ChangeColumns};
+use kvdb::{Database, DatabaseConfig};
 
 use devtools::RandomTempPath;
 use std::path::PathBuf;
@@ -189,3 +189,27 @@ fn is_migration_needed() {
 	assert!(manager.is_needed(1));
 	assert!(!manager.is_needed(2));
 }
+
+#[test]
+fn change_columns() {
+	let dir = RandomTempPath::create_dir();
+	let db_path = db_path(dir.as_path());
+	let mut manager = Manager::new(Config::default());
+	{
+		let db = Database::open(&DatabaseConfig::with_columns(Some(1)), db_path.to_str().unwrap()).unwrap();
+		let mut transaction = db.transaction();
+		transaction.put(Some(0), &[1], &[2]);
+		db.write(transaction).unwrap();
+	}
+
+	manager.add_migration(ChangeColumns {
+		pre_columns: Some(1),
+		post_columns: Some(2),
+		version: 1,
+	}).unwrap();
+	let end_path = manager.execute(&db_path, 0).unwrap();
+
+	let db = Database::open(&DatabaseConfig::with_columns(Some(2)), end_path.to_str().unwrap()).unwrap();
+	assert_eq!(&db.get(Some(0), &[1]).unwrap().unwrap()[..], &[2u8][..]);
+	assert!(db.get(Some(1), &[1]).unwrap().is_none());
+}
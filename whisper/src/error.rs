@@ -0,0 +1,63 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use ethkey::Error as EthKeyError;
+use ethcrypto::Error as CryptoError;
+
+/// Whisper error.
+#[derive(Debug)]
+pub enum Error {
+	/// Key generation or signing failed.
+	EthKey(EthKeyError),
+	/// Message encryption failed.
+	Crypto(CryptoError),
+	/// No identity with the given public key.
+	UnknownIdentity,
+	/// Time to live is zero or too large.
+	InvalidTtl,
+	/// Too many filters installed.
+	TooManyFilters,
+	/// Pool refused the envelope.
+	Rejected,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+		let s = match *self {
+			Error::EthKey(ref err) => err.to_string(),
+			Error::Crypto(ref err) => format!("Crypto error: {:?}", err),
+			Error::UnknownIdentity => "Unknown identity".into(),
+			Error::InvalidTtl => "Invalid time to live".into(),
+			Error::TooManyFilters => "Too many filters".into(),
+			Error::Rejected => "Message pool is full".into(),
+		};
+
+		write!(f, "{}", s)
+	}
+}
+
+impl From<EthKeyError> for Error {
+	fn from(err: EthKeyError) -> Self {
+		Error::EthKey(err)
+	}
+}
+
+impl From<CryptoError> for Error {
+	fn from(err: CryptoError) -> Self {
+		Error::Crypto(err)
+	}
+}
@@ -0,0 +1,47 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Whisper v2 messaging protocol.
+//!
+//! Envelopes carry a proof of work, a time to live and a set of abridged topics. They are relayed
+//! between peers running the `shh` subprotocol and kept in a bounded pool until they expire.
+//! Messages sent to an identity are encrypted with its public key.
+
+#![warn(missing_docs)]
+#![cfg_attr(feature="dev", feature(plugin))]
+#![cfg_attr(feature="dev", plugin(clippy))]
+
+extern crate ethcore_util as util;
+extern crate ethcore_io as io;
+extern crate ethcore_network as network;
+extern crate ethkey;
+extern crate ethcrypto;
+extern crate parking_lot;
+extern crate time;
+extern crate rlp;
+
+#[macro_use]
+extern crate log;
+
+mod error;
+mod message;
+mod pool;
+mod net;
+
+pub use error::Error;
+pub use message::{Topic, Envelope, Message, MessageParams, TOPIC_LEN, MAX_TTL};
+pub use pool::MessagePool;
+pub use net::{Whisper, WhisperConfig, Filter, PROTOCOL_ID, PROTOCOL_VERSION, PROTOCOL_VERSIONS, PACKET_COUNT};
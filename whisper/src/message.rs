@@ -0,0 +1,412 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Whisper envelopes and messages.
+
+use time;
+use util::{H256, Bytes, Hashable};
+use rlp::*;
+use ethkey::{self, Secret, Public, Signature};
+use ethcrypto::ecies;
+use error::Error;
+
+/// Length of an abridged topic.
+pub const TOPIC_LEN: usize = 4;
+/// Max time to live of an envelope in seconds.
+pub const MAX_TTL: u32 = 60 * 60 * 24 * 2;
+/// Max difference between the sender's clock and ours in seconds.
+const MAX_CLOCK_DRIFT: u64 = 2;
+/// Set in the flags byte of a message that carries a signature.
+const SIGNATURE_FLAG: u8 = 0x80;
+const SIGNATURE_LEN: usize = 65;
+/// Nonces tried between deadline checks when sealing.
+const SEAL_BATCH: u32 = 1024;
+
+/// Abridged topic: first four bytes of the Keccak hash of a full topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Topic(pub [u8; TOPIC_LEN]);
+
+impl Topic {
+	/// Abridge a full topic.
+	pub fn from_full(topic: &[u8]) -> Topic {
+		let hash = topic.sha3();
+		let mut t = [0u8; TOPIC_LEN];
+		t.copy_from_slice(&hash[0..TOPIC_LEN]);
+		Topic(t)
+	}
+}
+
+impl Encodable for Topic {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.append(&&self.0[..]);
+	}
+}
+
+impl Decodable for Topic {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let bytes: Vec<u8> = try!(decoder.as_rlp().as_val());
+		if bytes.len() != TOPIC_LEN {
+			return Err(DecoderError::Custom("Invalid topic length"));
+		}
+		let mut t = [0u8; TOPIC_LEN];
+		t.copy_from_slice(&bytes);
+		Ok(Topic(t))
+	}
+}
+
+/// Envelope as sent over the wire.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+	/// Unix time after which the envelope is dropped.
+	pub expiry: u32,
+	/// Time to live in seconds.
+	pub ttl: u32,
+	/// Abridged topics.
+	pub topics: Vec<Topic>,
+	/// Message, encrypted if it has a recipient.
+	pub data: Bytes,
+	/// Proof-of-work nonce.
+	pub nonce: u32,
+}
+
+impl Envelope {
+	/// Envelope hash. Identifies the envelope in the pool and on the wire.
+	pub fn hash(&self) -> H256 {
+		encode(self).to_vec().sha3()
+	}
+
+	/// Proof of work: number of trailing zero bits of the work hash.
+	pub fn work(&self) -> u32 {
+		work(&self.seed(), self.nonce)
+	}
+
+	/// Look for the nonce with the most work for about `work_ms` milliseconds.
+	pub fn seal(&mut self, work_ms: u64) {
+		let seed = self.seed();
+		let deadline = time::precise_time_ns() + work_ms * 1_000_000;
+		let mut best = work(&seed, self.nonce);
+		let mut nonce = self.nonce;
+		loop {
+			for _ in 0..SEAL_BATCH {
+				nonce = nonce.wrapping_add(1);
+				let w = work(&seed, nonce);
+				if w > best {
+					best = w;
+					self.nonce = nonce;
+				}
+			}
+			if time::precise_time_ns() >= deadline {
+				break;
+			}
+		}
+	}
+
+	/// Check if the envelope is expired at the given unix time.
+	pub fn is_expired(&self, now: u64) -> bool {
+		self.expiry as u64 <= now
+	}
+
+	/// Check if the envelope has an acceptable time to live.
+	pub fn is_well_formed(&self) -> bool {
+		self.ttl != 0 && self.ttl <= MAX_TTL
+	}
+
+	/// Check if the envelope is alive and was not sent from the future.
+	pub fn is_timely(&self, now: u64) -> bool {
+		let sent = (self.expiry as u64).saturating_sub(self.ttl as u64);
+		!self.is_expired(now) && sent <= now + MAX_CLOCK_DRIFT
+	}
+
+	/// Check if the envelope is well formed and timely.
+	pub fn is_valid(&self, now: u64) -> bool {
+		self.is_well_formed() && self.is_timely(now)
+	}
+
+	/// Check if the envelope has all of the given topics.
+	pub fn matches(&self, topics: &[Topic]) -> bool {
+		topics.iter().all(|t| self.topics.contains(t))
+	}
+
+	/// Approximate size in memory.
+	pub fn size(&self) -> usize {
+		self.data.len() + self.topics.len() * TOPIC_LEN + 12
+	}
+
+	fn seed(&self) -> H256 {
+		let mut s = RlpStream::new_list(4);
+		s.append(&self.expiry).append(&self.ttl).append(&self.topics).append(&self.data);
+		s.out().sha3()
+	}
+}
+
+fn work(seed: &H256, nonce: u32) -> u32 {
+	let mut d = [0u8; 64];
+	d[0..32].copy_from_slice(seed);
+	d[60] = (nonce >> 24) as u8;
+	d[61] = (nonce >> 16) as u8;
+	d[62] = (nonce >> 8) as u8;
+	d[63] = nonce as u8;
+	let hash = (&d[..]).sha3();
+	let mut bits = 0;
+	for byte in hash.iter().rev() {
+		if *byte != 0 {
+			return bits + byte.trailing_zeros();
+		}
+		bits += 8;
+	}
+	bits
+}
+
+impl Encodable for Envelope {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(5);
+		s.append(&self.expiry);
+		s.append(&self.ttl);
+		s.append(&self.topics);
+		s.append(&self.data);
+		s.append(&self.nonce);
+	}
+}
+
+impl Decodable for Envelope {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		if d.item_count() != 5 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+		Ok(Envelope {
+			expiry: try!(d.val_at(0)),
+			ttl: try!(d.val_at(1)),
+			topics: try!(d.val_at(2)),
+			data: try!(d.val_at(3)),
+			nonce: try!(d.val_at(4)),
+		})
+	}
+}
+
+/// Message to post.
+#[derive(Debug, Clone)]
+pub struct MessageParams {
+	/// Secret of the sender identity. The message is signed if present.
+	pub from: Option<Secret>,
+	/// Recipient public key. The message is encrypted if present.
+	pub to: Option<Public>,
+	/// Full topics.
+	pub topics: Vec<Bytes>,
+	/// Message payload.
+	pub payload: Bytes,
+	/// Time to live in seconds.
+	pub ttl: u32,
+	/// Time to spend on the proof of work in milliseconds.
+	pub work_ms: u64,
+}
+
+impl MessageParams {
+	/// Create a sealed envelope for the message.
+	pub fn seal(&self, now: u64) -> Result<Envelope, Error> {
+		if self.ttl == 0 || self.ttl > MAX_TTL {
+			return Err(Error::InvalidTtl);
+		}
+		let mut plain = vec![0u8];
+		if let Some(ref secret) = self.from {
+			plain[0] |= SIGNATURE_FLAG;
+			let signature = try!(ethkey::sign(secret, &signature_hash(plain[0], &self.payload)));
+			plain.extend_from_slice(&*signature);
+		}
+		plain.extend_from_slice(&self.payload);
+		let data = match self.to {
+			Some(ref to) => try!(ecies::encrypt(to, &[], &plain)),
+			None => plain,
+		};
+		let mut envelope = Envelope {
+			expiry: (now + self.ttl as u64) as u32,
+			ttl: self.ttl,
+			topics: self.topics.iter().map(|t| Topic::from_full(t)).collect(),
+			data: data,
+			nonce: 0,
+		};
+		envelope.seal(self.work_ms);
+		Ok(envelope)
+	}
+}
+
+fn signature_hash(flags: u8, payload: &[u8]) -> H256 {
+	let mut data = Vec::with_capacity(payload.len() + 1);
+	data.push(flags);
+	data.extend_from_slice(payload);
+	data.sha3()
+}
+
+/// Message opened from an envelope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+	/// Envelope hash.
+	pub hash: H256,
+	/// Sender public key if the message is signed.
+	pub from: Option<Public>,
+	/// Recipient public key if the message is encrypted.
+	pub to: Option<Public>,
+	/// Unix time the message expires at.
+	pub expiry: u32,
+	/// Time to live in seconds.
+	pub ttl: u32,
+	/// Unix time the message was sent at.
+	pub sent: u32,
+	/// Abridged topics.
+	pub topics: Vec<Topic>,
+	/// Message payload.
+	pub payload: Bytes,
+	/// Proof of work of the envelope.
+	pub work: u32,
+}
+
+impl Message {
+	/// Open an envelope. Envelopes sent to an identity are decrypted with its secret.
+	/// Returns `None` if the envelope can't be decrypted or the signature is invalid.
+	pub fn open(envelope: &Envelope, to: Option<(&Public, &Secret)>) -> Option<Message> {
+		let plain = match to {
+			Some((_, secret)) => match ecies::decrypt(secret, &[], &envelope.data) {
+				Ok(plain) => plain,
+				Err(_) => return None,
+			},
+			None => envelope.data.clone(),
+		};
+		if plain.is_empty() {
+			return None;
+		}
+		let flags = plain[0];
+		let (from, payload) = if flags & SIGNATURE_FLAG != 0 {
+			if plain.len() < 1 + SIGNATURE_LEN {
+				return None;
+			}
+			let mut signature = [0u8; SIGNATURE_LEN];
+			signature.copy_from_slice(&plain[1..1 + SIGNATURE_LEN]);
+			let payload = &plain[1 + SIGNATURE_LEN..];
+			match ethkey::recover(&Signature::from(signature), &signature_hash(flags, payload)) {
+				Ok(public) => (Some(public), payload),
+				Err(_) => return None,
+			}
+		} else {
+			(None, &plain[1..])
+		};
+		Some(Message {
+			hash: envelope.hash(),
+			from: from,
+			to: to.map(|(public, _)| public.clone()),
+			expiry: envelope.expiry,
+			ttl: envelope.ttl,
+			sent: envelope.expiry.saturating_sub(envelope.ttl),
+			topics: envelope.topics.clone(),
+			payload: payload.to_vec(),
+			work: envelope.work(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ethkey::{Random, Generator};
+	use rlp::{encode, decode};
+
+	fn params() -> MessageParams {
+		MessageParams {
+			from: None,
+			to: None,
+			topics: vec![b"chat".to_vec()],
+			payload: b"hello".to_vec(),
+			ttl: 50,
+			work_ms: 0,
+		}
+	}
+
+	#[test]
+	fn envelope_roundtrip() {
+		let envelope = params().seal(1000).unwrap();
+		assert_eq!(envelope.expiry, 1050);
+		assert_eq!(envelope.topics, vec![Topic::from_full(b"chat")]);
+		let decoded: Envelope = decode(&encode(&envelope));
+		assert_eq!(decoded, envelope);
+		assert_eq!(decoded.hash(), envelope.hash());
+	}
+
+	#[test]
+	fn sealing_does_work() {
+		let mut p = params();
+		p.work_ms = 10;
+		let envelope = p.seal(1000).unwrap();
+		// the best of at least 1024 nonces almost surely has a few zero bits
+		assert!(envelope.work() > 0);
+		let mut other = envelope.clone();
+		other.nonce = envelope.nonce.wrapping_add(1);
+		assert!(other.hash() != envelope.hash());
+	}
+
+	#[test]
+	fn validity() {
+		let envelope = params().seal(1000).unwrap();
+		assert!(envelope.is_valid(1000));
+		assert!(envelope.is_valid(1049));
+		assert!(!envelope.is_valid(1050));
+		// sent from the future
+		assert!(!envelope.is_valid(990));
+		assert!(envelope.is_well_formed());
+		assert!(!envelope.is_timely(1050));
+		let mut zero_ttl = envelope.clone();
+		zero_ttl.ttl = 0;
+		assert!(!zero_ttl.is_well_formed());
+		let mut p = params();
+		p.ttl = MAX_TTL + 1;
+		assert!(p.seal(1000).is_err());
+	}
+
+	#[test]
+	fn plain_message() {
+		let envelope = params().seal(1000).unwrap();
+		let message = Message::open(&envelope, None).unwrap();
+		assert_eq!(message.payload, b"hello".to_vec());
+		assert_eq!(message.from, None);
+		assert_eq!(message.sent, 1000);
+	}
+
+	#[test]
+	fn signed_and_encrypted_message() {
+		let sender = Random.generate().unwrap();
+		let recipient = Random.generate().unwrap();
+		let other = Random.generate().unwrap();
+		let mut p = params();
+		p.from = Some(sender.secret().clone());
+		p.to = Some(recipient.public().clone());
+		let envelope = p.seal(1000).unwrap();
+		assert!(&envelope.data[..] != &b"hello"[..]);
+
+		assert!(Message::open(&envelope, Some((other.public(), other.secret()))).is_none());
+		let message = Message::open(&envelope, Some((recipient.public(), recipient.secret()))).unwrap();
+		assert_eq!(message.payload, b"hello".to_vec());
+		assert_eq!(message.from, Some(sender.public().clone()));
+		assert_eq!(message.to, Some(recipient.public().clone()));
+	}
+
+	#[test]
+	fn topic_matching() {
+		let mut p = params();
+		p.topics = vec![b"a".to_vec(), b"b".to_vec()];
+		let envelope = p.seal(1000).unwrap();
+		assert!(envelope.matches(&[]));
+		assert!(envelope.matches(&[Topic::from_full(b"a"), Topic::from_full(b"b")]));
+		assert!(!envelope.matches(&[Topic::from_full(b"a"), Topic::from_full(b"c")]));
+	}
+}
@@ -0,0 +1,425 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Whisper protocol handler and local node API.
+
+use std::collections::{HashMap, HashSet};
+use time;
+use util::{H256, Bytes};
+use rlp::*;
+use io::TimerToken;
+use network::{NetworkProtocolHandler, NetworkContext, PeerId, ProtocolId, PeerReport};
+use ethkey::{Random, Generator, KeyPair, Public};
+use parking_lot::{Mutex, RwLock};
+use message::{Envelope, Message, MessageParams, Topic};
+use pool::MessagePool;
+use error::Error;
+
+/// Whisper subprotocol name.
+pub const PROTOCOL_ID: ProtocolId = "shh";
+/// Supported protocol version.
+pub const PROTOCOL_VERSION: u8 = 2;
+/// All supported protocol versions.
+pub const PROTOCOL_VERSIONS: &'static [u8] = &[PROTOCOL_VERSION];
+/// Number of packet IDs used by the protocol.
+pub const PACKET_COUNT: u8 = 2;
+
+const STATUS_PACKET: u8 = 0x00;
+const MESSAGES_PACKET: u8 = 0x01;
+
+const RELAY_TIMER: TimerToken = 0;
+const EXPIRE_TIMER: TimerToken = 1;
+const RELAY_INTERVAL_MS: u64 = 300;
+const EXPIRE_INTERVAL_MS: u64 = 800;
+
+/// Max envelopes sent to a peer in a single packet.
+const MAX_RELAY_ENVELOPES: usize = 256;
+/// Max number of installed filters.
+const MAX_FILTERS: usize = 256;
+/// Max messages waiting in a filter to be polled. Older messages are dropped.
+const MAX_FILTER_MESSAGES: usize = 1024;
+
+/// Whisper configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhisperConfig {
+	/// Max total size of pooled envelopes in bytes.
+	pub pool_size: usize,
+	/// Min proof of work of envelopes accepted from peers.
+	pub min_work: u32,
+}
+
+impl Default for WhisperConfig {
+	fn default() -> Self {
+		WhisperConfig {
+			pool_size: 10 * 1024 * 1024,
+			min_work: 0,
+		}
+	}
+}
+
+/// Filter for incoming messages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+	/// Abridged topics the envelope must have, all of them.
+	pub topics: Vec<Topic>,
+	/// Identity the messages must be sent to. Only unencrypted messages match if not set.
+	pub to: Option<Public>,
+}
+
+impl Filter {
+	/// Create a filter for full topics.
+	pub fn new(topics: &[Bytes], to: Option<Public>) -> Filter {
+		Filter {
+			topics: topics.iter().map(|t| Topic::from_full(t)).collect(),
+			to: to,
+		}
+	}
+}
+
+struct InstalledFilter {
+	filter: Filter,
+	messages: Vec<Message>,
+}
+
+struct Filters {
+	filters: HashMap<usize, InstalledFilter>,
+	next_id: usize,
+}
+
+#[derive(Default)]
+struct Peer {
+	/// Status packet received.
+	confirmed: bool,
+	/// Envelopes the peer has seen.
+	known: HashSet<H256>,
+}
+
+/// Whisper protocol handler.
+pub struct Whisper {
+	config: WhisperConfig,
+	pool: RwLock<MessagePool>,
+	peers: RwLock<HashMap<PeerId, Peer>>,
+	filters: Mutex<Filters>,
+	identities: RwLock<HashMap<Public, KeyPair>>,
+}
+
+fn unix_time() -> u64 {
+	time::get_time().sec as u64
+}
+
+impl Whisper {
+	/// Create a new handler.
+	pub fn new(config: WhisperConfig) -> Whisper {
+		Whisper {
+			pool: RwLock::new(MessagePool::new(config.pool_size)),
+			config: config,
+			peers: RwLock::new(HashMap::new()),
+			filters: Mutex::new(Filters { filters: HashMap::new(), next_id: 0 }),
+			identities: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Seal and post a new message. `from` must be one of our identities.
+	/// The message is relayed to peers on the next relay round. Returns the envelope hash.
+	pub fn post(&self, from: Option<Public>, mut params: MessageParams) -> Result<H256, Error> {
+		if let Some(ref from) = from {
+			let identities = self.identities.read();
+			let pair = try!(identities.get(from).ok_or(Error::UnknownIdentity));
+			params.from = Some(pair.secret().clone());
+		}
+		let envelope = try!(params.seal(unix_time()));
+		let hash = envelope.hash();
+		if !self.add_envelope(envelope) {
+			return Err(Error::Rejected);
+		}
+		Ok(hash)
+	}
+
+	/// Generate a new identity. Returns its public key.
+	pub fn new_identity(&self) -> Result<Public, Error> {
+		let pair = try!(Random.generate());
+		let public = pair.public().clone();
+		self.identities.write().insert(public.clone(), pair);
+		Ok(public)
+	}
+
+	/// Check if we have the identity.
+	pub fn has_identity(&self, public: &Public) -> bool {
+		self.identities.read().contains_key(public)
+	}
+
+	/// Install a new filter. Returns its id.
+	pub fn new_filter(&self, filter: Filter) -> Result<usize, Error> {
+		let mut filters = self.filters.lock();
+		if filters.filters.len() >= MAX_FILTERS {
+			return Err(Error::TooManyFilters);
+		}
+		let id = filters.next_id;
+		filters.next_id += 1;
+		filters.filters.insert(id, InstalledFilter { filter: filter, messages: Vec::new() });
+		Ok(id)
+	}
+
+	/// Take messages received by the filter since the last call. `None` if there is no such filter.
+	pub fn filter_changes(&self, id: usize) -> Option<Vec<Message>> {
+		self.filters.lock().filters.get_mut(&id).map(|f| ::std::mem::replace(&mut f.messages, Vec::new()))
+	}
+
+	/// Remove a filter. Returns false if there is no such filter.
+	pub fn uninstall_filter(&self, id: usize) -> bool {
+		self.filters.lock().filters.remove(&id).is_some()
+	}
+
+	/// Number of envelopes in the pool.
+	pub fn pool_len(&self) -> usize {
+		self.pool.read().len()
+	}
+
+	/// Add an envelope to the pool and deliver it to matching filters. Returns false if it was not added.
+	fn add_envelope(&self, envelope: Envelope) -> bool {
+		if !self.pool.write().insert(envelope.clone()) {
+			return false;
+		}
+		let identities = self.identities.read();
+		let mut filters = self.filters.lock();
+		for installed in filters.filters.values_mut() {
+			if !envelope.matches(&installed.filter.topics) {
+				continue;
+			}
+			let message = match installed.filter.to {
+				Some(ref to) => match identities.get(to) {
+					Some(pair) => Message::open(&envelope, Some((pair.public(), pair.secret()))),
+					None => None,
+				},
+				None => Message::open(&envelope, None),
+			};
+			if let Some(message) = message {
+				if installed.messages.len() >= MAX_FILTER_MESSAGES {
+					installed.messages.remove(0);
+				}
+				installed.messages.push(message);
+			}
+		}
+		true
+	}
+
+	fn on_status(&self, io: &NetworkContext, peer: &PeerId, r: &UntrustedRlp) -> Result<(), DecoderError> {
+		let version: u8 = try!(r.val_at(0));
+		if version != PROTOCOL_VERSION {
+			trace!(target: "whisper", "Peer {} has unsupported version {}", peer, version);
+			io.disable_peer(*peer);
+			return Ok(());
+		}
+		if let Some(p) = self.peers.write().get_mut(peer) {
+			p.confirmed = true;
+		}
+		Ok(())
+	}
+
+	fn on_messages(&self, io: &NetworkContext, peer: &PeerId, r: &UntrustedRlp) -> Result<(), DecoderError> {
+		let now = unix_time();
+		let mut hashes = Vec::new();
+		let mut invalid = false;
+		for item in r.iter() {
+			let envelope: Envelope = try!(item.as_val());
+			if !envelope.is_well_formed() || envelope.work() < self.config.min_work {
+				trace!(target: "whisper", "Invalid envelope from peer {}", peer);
+				invalid = true;
+				continue;
+			}
+			// envelopes may expire in transit or come from a peer whose clock is off.
+			if !envelope.is_timely(now) {
+				trace!(target: "whisper", "Ignored expired or future envelope from peer {}", peer);
+				continue;
+			}
+			hashes.push(envelope.hash());
+			self.add_envelope(envelope);
+		}
+		if invalid {
+			io.report_peer(*peer, PeerReport::InvalidData);
+		}
+		if let Some(p) = self.peers.write().get_mut(peer) {
+			p.known.extend(hashes);
+		}
+		Ok(())
+	}
+
+	/// Send each peer the pooled envelopes it has not seen yet.
+	fn relay(&self, io: &NetworkContext) {
+		let packets: Vec<(PeerId, Bytes)> = {
+			let pool = self.pool.read();
+			let hashes = pool.hashes();
+			let mut peers = self.peers.write();
+			peers.iter_mut().filter(|&(_, ref p)| p.confirmed).filter_map(|(id, p)| {
+				let new: Vec<&Envelope> = hashes.iter()
+					.filter(|h| !p.known.contains(*h))
+					.take(MAX_RELAY_ENVELOPES)
+					.filter_map(|h| pool.get(h))
+					.collect();
+				if new.is_empty() {
+					return None;
+				}
+				let mut s = RlpStream::new_list(new.len());
+				for envelope in &new {
+					s.append(*envelope);
+					p.known.insert(envelope.hash());
+				}
+				Some((*id, s.out()))
+			}).collect()
+		};
+		for (peer, packet) in packets {
+			if let Err(e) = io.send(peer, MESSAGES_PACKET, packet) {
+				debug!(target: "whisper", "Error sending messages to peer {}: {:?}", peer, e);
+			}
+		}
+	}
+
+	/// Drop expired envelopes and forget them in peer records.
+	fn expire(&self) {
+		let mut pool = self.pool.write();
+		if pool.prune(unix_time()) == 0 {
+			return;
+		}
+		for p in self.peers.write().values_mut() {
+			p.known.retain(|h| pool.contains(h));
+		}
+	}
+}
+
+impl NetworkProtocolHandler for Whisper {
+	fn initialize(&self, io: &NetworkContext) {
+		io.register_timer(RELAY_TIMER, RELAY_INTERVAL_MS).expect("Error registering whisper relay timer");
+		io.register_timer(EXPIRE_TIMER, EXPIRE_INTERVAL_MS).expect("Error registering whisper expiry timer");
+	}
+
+	fn read(&self, io: &NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
+		let r = UntrustedRlp::new(data);
+		let result = match packet_id {
+			STATUS_PACKET => self.on_status(io, peer, &r),
+			MESSAGES_PACKET => {
+				let confirmed = self.peers.read().get(peer).map_or(false, |p| p.confirmed);
+				if confirmed {
+					self.on_messages(io, peer, &r)
+				} else {
+					trace!(target: "whisper", "Messages from unconfirmed peer {}", peer);
+					io.disable_peer(*peer);
+					Ok(())
+				}
+			},
+			_ => {
+				debug!(target: "whisper", "Unknown packet {} from peer {}", packet_id, peer);
+				Ok(())
+			},
+		};
+		if let Err(e) = result {
+			debug!(target: "whisper", "Malformed packet from peer {}: {:?}", peer, e);
+			io.report_peer(*peer, PeerReport::InvalidData);
+		}
+	}
+
+	fn connected(&self, io: &NetworkContext, peer: &PeerId) {
+		self.peers.write().insert(*peer, Peer::default());
+		let mut s = RlpStream::new_list(1);
+		s.append(&PROTOCOL_VERSION);
+		if let Err(e) = io.send(*peer, STATUS_PACKET, s.out()) {
+			debug!(target: "whisper", "Error sending status to peer {}: {:?}", peer, e);
+		}
+	}
+
+	fn disconnected(&self, _io: &NetworkContext, peer: &PeerId) {
+		self.peers.write().remove(peer);
+	}
+
+	fn timeout(&self, io: &NetworkContext, timer: TimerToken) {
+		match timer {
+			RELAY_TIMER => self.relay(io),
+			EXPIRE_TIMER => self.expire(),
+			_ => warn!(target: "whisper", "Unknown timer {}", timer),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ethkey::{Random, Generator};
+	use message::MessageParams;
+
+	fn params(topic: &[u8], payload: &[u8]) -> MessageParams {
+		MessageParams {
+			from: None,
+			to: None,
+			topics: vec![topic.to_vec()],
+			payload: payload.to_vec(),
+			ttl: 50,
+			work_ms: 0,
+		}
+	}
+
+	#[test]
+	fn delivers_plain_messages_to_filters() {
+		let whisper = Whisper::new(WhisperConfig::default());
+		let chat = whisper.new_filter(Filter::new(&[b"chat".to_vec()], None)).unwrap();
+		let other = whisper.new_filter(Filter::new(&[b"other".to_vec()], None)).unwrap();
+
+		let hash = whisper.post(None, params(b"chat", b"hi")).unwrap();
+		let messages = whisper.filter_changes(chat).unwrap();
+		assert_eq!(messages.len(), 1);
+		assert_eq!(messages[0].hash, hash);
+		assert_eq!(messages[0].payload, b"hi".to_vec());
+		assert!(whisper.filter_changes(chat).unwrap().is_empty());
+		assert!(whisper.filter_changes(other).unwrap().is_empty());
+
+		assert!(whisper.uninstall_filter(chat));
+		assert!(whisper.filter_changes(chat).is_none());
+		assert_eq!(whisper.pool_len(), 1);
+	}
+
+	#[test]
+	fn delivers_encrypted_messages_to_identity() {
+		let whisper = Whisper::new(WhisperConfig::default());
+		let me = whisper.new_identity().unwrap();
+		let sender = whisper.new_identity().unwrap();
+		assert!(whisper.has_identity(&me));
+		let mine = whisper.new_filter(Filter::new(&[b"chat".to_vec()], Some(me.clone()))).unwrap();
+
+		let mut p = params(b"chat", b"secret");
+		p.to = Some(me.clone());
+		whisper.post(Some(sender.clone()), p).unwrap();
+		let messages = whisper.filter_changes(mine).unwrap();
+		assert_eq!(messages.len(), 1);
+		assert_eq!(messages[0].payload, b"secret".to_vec());
+		assert_eq!(messages[0].from, Some(sender));
+		assert_eq!(messages[0].to, Some(me));
+	}
+
+	#[test]
+	fn rejects_unknown_identity() {
+		let whisper = Whisper::new(WhisperConfig::default());
+		let stranger = Random.generate().unwrap();
+		assert!(!whisper.has_identity(stranger.public()));
+		assert!(whisper.post(Some(stranger.public().clone()), params(b"chat", b"hi")).is_err());
+	}
+
+	#[test]
+	fn bounds_filter_count() {
+		let whisper = Whisper::new(WhisperConfig::default());
+		for _ in 0..MAX_FILTERS {
+			whisper.new_filter(Filter::new(&[], None)).unwrap();
+		}
+		assert!(whisper.new_filter(Filter::new(&[], None)).is_err());
+	}
+}
@@ -0,0 +1,167 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bounded pool of envelopes to relay.
+
+use std::collections::HashMap;
+use util::H256;
+use message::Envelope;
+
+struct PoolEntry {
+	envelope: Envelope,
+	work: u32,
+}
+
+/// Pool of live envelopes bounded by total size.
+/// When full, envelopes with the least proof of work are evicted first.
+pub struct MessagePool {
+	entries: HashMap<H256, PoolEntry>,
+	size: usize,
+	max_size: usize,
+}
+
+impl MessagePool {
+	/// Create a new pool holding up to `max_size` bytes of envelopes.
+	pub fn new(max_size: usize) -> MessagePool {
+		MessagePool {
+			entries: HashMap::new(),
+			size: 0,
+			max_size: max_size,
+		}
+	}
+
+	/// Insert a new envelope. Returns false if the envelope is already known or has
+	/// less work than everything that would have to be evicted to make room for it.
+	pub fn insert(&mut self, envelope: Envelope) -> bool {
+		let hash = envelope.hash();
+		let size = envelope.size();
+		if self.entries.contains_key(&hash) || size > self.max_size {
+			return false;
+		}
+		let work = envelope.work();
+		while self.size + size > self.max_size {
+			let worst = self.entries.iter()
+				.min_by_key(|&(_, e)| (e.work, e.envelope.expiry))
+				.map(|(h, e)| (h.clone(), e.work));
+			match worst {
+				Some((worst_hash, worst_work)) if worst_work < work => {
+					self.remove(&worst_hash);
+				},
+				_ => return false,
+			}
+		}
+		self.size += size;
+		self.entries.insert(hash, PoolEntry { envelope: envelope, work: work });
+		true
+	}
+
+	/// Remove all envelopes expired at the given unix time. Returns the number of removed envelopes.
+	pub fn prune(&mut self, now: u64) -> usize {
+		let expired: Vec<H256> = self.entries.iter()
+			.filter(|&(_, e)| e.envelope.is_expired(now))
+			.map(|(h, _)| h.clone())
+			.collect();
+		for hash in &expired {
+			self.remove(hash);
+		}
+		expired.len()
+	}
+
+	/// Check if the envelope is in the pool.
+	pub fn contains(&self, hash: &H256) -> bool {
+		self.entries.contains_key(hash)
+	}
+
+	/// Get an envelope by hash.
+	pub fn get(&self, hash: &H256) -> Option<&Envelope> {
+		self.entries.get(hash).map(|e| &e.envelope)
+	}
+
+	/// Hashes of all envelopes in the pool.
+	pub fn hashes(&self) -> Vec<H256> {
+		self.entries.keys().cloned().collect()
+	}
+
+	/// Number of envelopes in the pool.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Total size of envelopes in the pool.
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	fn remove(&mut self, hash: &H256) {
+		if let Some(entry) = self.entries.remove(hash) {
+			self.size -= entry.envelope.size();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use message::{Envelope, Topic};
+
+	fn envelope(expiry: u32, len: usize, nonce: u32) -> Envelope {
+		Envelope {
+			expiry: expiry,
+			ttl: 10,
+			topics: vec![Topic([1, 2, 3, 4])],
+			data: vec![0u8; len],
+			nonce: nonce,
+		}
+	}
+
+	#[test]
+	fn inserts_and_prunes() {
+		let mut pool = MessagePool::new(1000);
+		let a = envelope(100, 10, 0);
+		let b = envelope(200, 10, 0);
+		assert!(pool.insert(a.clone()));
+		assert!(!pool.insert(a.clone()));
+		assert!(pool.insert(b.clone()));
+		assert_eq!(pool.len(), 2);
+		assert_eq!(pool.size(), a.size() + b.size());
+
+		assert_eq!(pool.prune(100), 1);
+		assert!(!pool.contains(&a.hash()));
+		assert!(pool.contains(&b.hash()));
+		assert_eq!(pool.size(), b.size());
+	}
+
+	#[test]
+	fn evicts_least_work() {
+		let candidates: Vec<Envelope> = (0..64).map(|n| envelope(100, 100, n)).collect();
+		let weakest = candidates.iter().min_by_key(|e| e.work()).unwrap().clone();
+		let strongest = candidates.iter().max_by_key(|e| e.work()).unwrap().clone();
+		assert!(weakest.work() < strongest.work());
+
+		let mut pool = MessagePool::new(weakest.size() + 10);
+		assert!(pool.insert(weakest.clone()));
+		assert!(pool.insert(strongest.clone()));
+		assert!(!pool.contains(&weakest.hash()));
+		// no room and not more work than the pooled one
+		let mut same = strongest.clone();
+		same.expiry += 1;
+		if same.work() <= strongest.work() {
+			assert!(!pool.insert(same));
+		}
+		assert!(!pool.insert(envelope(100, 1000, 0)));
+		assert_eq!(pool.len(), 1);
+	}
+}